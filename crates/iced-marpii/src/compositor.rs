@@ -1,5 +1,5 @@
 use iced_graphics::{compositor, error::Reason};
-use marpii::{ash::vk, resources::ImgDesc, OoS};
+use marpii::{ash::vk, resources::ImgDesc, swapchain::TransferFunction, OoS};
 use marpii_rmg::{ImageHandle, Rmg};
 use marpii_rmg_tasks::SwapchainPresent;
 
@@ -9,6 +9,8 @@ use crate::{
 };
 
 mod rendering;
+mod surface;
+pub use surface::WindowSurface;
 
 pub struct Compositor {
     rmg: Rmg,
@@ -16,9 +18,14 @@ pub struct Compositor {
     _shell: iced_graphics::Shell,
 
     //the color buffer we use for rendering. Note that we _blit_ to the swapchain.
+    //NOTE: Each window has its own buffers. These are the ones of the window that is rendered, see `bind_surface`.
     color_buffer: ImageHandle,
     //the depth buffer we use for ordering _everything_
     depth_buffer: ImageHandle,
+    //transfer function of the swapchain we blit the color buffer to.
+    transfer_function: TransferFunction,
+    //format of all depth buffers
+    depth_format: vk::Format,
 
     //quad renderer
     quads: QuadRenderer,
@@ -38,6 +45,11 @@ impl Compositor {
             | vk::ImageUsageFlags::TRANSFER_SRC.as_raw()
             | vk::ImageUsageFlags::SAMPLED.as_raw(),
     );
+    ///Color spaces the swapchain may use. Our shaders can only apply the sRGB encoding, therefore HDR color spaces are not used.
+    pub const COLOR_SPACES: &'static [vk::ColorSpaceKHR] = &[
+        vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+    ];
     pub const DEPTH_USAGE: vk::ImageUsageFlags = vk::ImageUsageFlags::from_raw(
         vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT.as_raw()
             | vk::ImageUsageFlags::TRANSFER_DST.as_raw()
@@ -48,7 +60,7 @@ impl Compositor {
 
 impl iced_graphics::compositor::Compositor for Compositor {
     type Renderer = Renderer;
-    type Surface = SwapchainPresent;
+    type Surface = WindowSurface;
 
     async fn with_backend(
         settings: iced_graphics::Settings,
@@ -84,11 +96,13 @@ impl iced_graphics::compositor::Compositor for Compositor {
         })?;
 
         //and build swapchain handler
-        let swapchain = SwapchainPresent::new(&mut rmg, surface).map_err(|e| {
-            iced_graphics::Error::BackendError(format!(
-                "Failed to greate present surface for window: {e}"
-            ))
-        })?;
+        let swapchain =
+            SwapchainPresent::new_with_color_spaces(&mut rmg, surface, Self::COLOR_SPACES)
+                .map_err(|e| {
+                    iced_graphics::Error::BackendError(format!(
+                        "Failed to greate present surface for window: {e}"
+                    ))
+                })?;
 
         let width = swapchain.image_desc().extent.width;
         let height = swapchain.image_desc().extent.height;
@@ -111,7 +125,8 @@ impl iced_graphics::compositor::Compositor for Compositor {
             return Err(iced_graphics::Error::NoAvailablePixelFormat);
         }
 
-        log::warn!("Selected: {:#?}", color_format);
+        let transfer_function = swapchain.transfer_function();
+        log::info!("Selected: {:#?} with {:?}", color_format, transfer_function);
 
         let color_buffer = rmg
             .new_image_uninitialized(
@@ -173,6 +188,8 @@ impl iced_graphics::compositor::Compositor for Compositor {
             rmg,
             color_buffer,
             depth_buffer,
+            transfer_function,
+            depth_format,
             settings,
             quads,
             mesh,
@@ -213,6 +230,8 @@ impl iced_graphics::compositor::Compositor for Compositor {
         background_color: iced::Color,
         on_pre_present: impl FnOnce(),
     ) -> Result<(), iced_graphics::compositor::SurfaceError> {
+        //render into the window's buffers
+        self.bind_surface(surface);
         //prepare all the renderer data. This is where
         //we upload anything that is needed to the gpu.
         self.prepare(renderer, viewport);
@@ -238,14 +257,29 @@ impl iced_graphics::compositor::Compositor for Compositor {
         let surface = marpii::surface::Surface::new(&self.rmg.ctx.instance, &window)
             .expect("Failed to create surface");
 
-        let swapchain = SwapchainPresent::new(&mut self.rmg, OoS::new(surface))
-            .expect("Could not create swapchain for surface!");
-        self.notify_resize(width, height);
-        swapchain
+        let swapchain = SwapchainPresent::new_with_color_spaces(
+            &mut self.rmg,
+            OoS::new(surface),
+            Self::COLOR_SPACES,
+        )
+        .expect("Could not create swapchain for surface!");
+        if !self.rmg.ctx.device.is_format_supported(
+            Self::COLOR_USAGE,
+            vk::ImageTiling::OPTIMAL,
+            swapchain.format(),
+        ) {
+            log::error!(
+                "Swapchain format {:#?} does not support our usage: {:#?}",
+                swapchain.format(),
+                Self::COLOR_USAGE
+            );
+        }
+        WindowSurface::new(&mut self.rmg, swapchain, self.depth_format, width, height)
     }
 
-    fn configure_surface(&mut self, _surface: &mut Self::Surface, width: u32, height: u32) {
-        self.notify_resize(width, height);
+    fn configure_surface(&mut self, surface: &mut Self::Surface, width: u32, height: u32) {
+        //NOTE: the swapchain itself is recreated on the next present.
+        surface.resize(&mut self.rmg, width, height);
     }
 
     fn screenshot(
@@ -1,8 +1,8 @@
 use crate::{Compositor, renderer::Renderer};
 use iced::Transformation;
 use iced_graphics::text::font_system;
-use marpii::ash::vk;
-use marpii_rmg_tasks::SwapchainPresent;
+
+use super::WindowSurface;
 
 struct LayerDepth {
     layer_count: usize,
@@ -51,9 +51,11 @@ impl LayerDepth {
 }
 
 impl Compositor {
-    //Returns true, if the colors need to be gamma corrected
-    pub(crate) fn must_gamma_correct_color(format: vk::Format) -> bool {
-        !marpii::util::is_srgb(&format)
+    //Returns true, if the colors need to be gamma corrected. That is the case if the swapchain expects sRGB encoded colors,
+    // but the color buffer's format doesn't encode them.
+    pub(crate) fn must_gamma_correct_color(&self) -> bool {
+        self.transfer_function
+            .needs_srgb_encoding(*self.color_buffer.format())
     }
 
     ///Data setup step before actually rendering something
@@ -70,7 +72,7 @@ impl Compositor {
             layer_count: renderer.layers.iter().count(),
         };
 
-        let gamma_correct = Self::must_gamma_correct_color(*self.color_buffer.format());
+        let gamma_correct = self.must_gamma_correct_color();
         self.quads.set_gamma_correct(gamma_correct);
         self.shape.set_gamma_correct(gamma_correct);
         self.mesh.set_gamma_correct(gamma_correct);
@@ -155,7 +157,7 @@ impl Compositor {
     pub fn render_to_surface(
         &mut self,
         renderer: &mut Renderer,
-        surface: &mut SwapchainPresent,
+        surface: &mut WindowSurface,
         _viewport: &iced_graphics::Viewport,
         background_color: iced::Color,
         on_pre_present: impl FnOnce(),
@@ -163,7 +165,7 @@ impl Compositor {
         //NOTE: while all colors used when rendering _stuff_ are corrected on the GPU,
         // the clear color is directed from the host-site, so we have to make that
         // decission here.
-        let bg_color = if Self::must_gamma_correct_color(*self.color_buffer.format()) {
+        let bg_color = if self.must_gamma_correct_color() {
             crate::util::gamma_correct(background_color.into_linear())
         } else {
             background_color.into_linear()
@@ -171,7 +173,9 @@ impl Compositor {
 
         self.quads.set_clear_color(Some(bg_color));
         //setup new push-image
-        surface.push_image(self.color_buffer.clone(), self.color_buffer.extent_2d());
+        surface
+            .present
+            .push_image(self.color_buffer.clone(), self.color_buffer.extent_2d());
 
         let mut recorder = self.rmg.record();
 
@@ -223,7 +227,7 @@ impl Compositor {
             .unwrap()
             .add_task(&mut self.text.renderpass)
             .unwrap()
-            .add_task(surface)
+            .add_task(&mut surface.present)
             .unwrap()
             .execute()
             .unwrap();
@@ -237,23 +241,14 @@ impl Compositor {
         self.mesh.end_frame();
     }
 
-    pub fn notify_resize(&mut self, width: u32, height: u32) {
-        //re-create color buffer
-        let mut color_desc = self.color_buffer.image_desc().clone();
-        color_desc.extent.width = width;
-        color_desc.extent.height = height;
-        self.color_buffer = self
-            .rmg
-            .new_image_uninitialized(color_desc, Some("color-buffer"))
-            .unwrap();
-        //re-create depth buffer
-        let mut depth_desc = self.depth_buffer.image_desc().clone();
-        depth_desc.extent.width = width;
-        depth_desc.extent.height = height;
-        self.depth_buffer = self
-            .rmg
-            .new_image_uninitialized(depth_desc, Some("depth-buffer"))
-            .unwrap();
+    ///Renders to the buffers of `surface` from now on. Does nothing if they are already used.
+    pub(crate) fn bind_surface(&mut self, surface: &WindowSurface) {
+        self.transfer_function = surface.transfer_function();
+        if self.color_buffer == surface.color_buffer && self.depth_buffer == surface.depth_buffer {
+            return;
+        }
+        self.color_buffer = surface.color_buffer.clone();
+        self.depth_buffer = surface.depth_buffer.clone();
 
         //now notify all passes
         self.quads
@@ -0,0 +1,69 @@
+use marpii::{ash::vk, resources::ImgDesc, swapchain::TransferFunction};
+use marpii_rmg::{ImageHandle, Rmg};
+use marpii_rmg_tasks::SwapchainPresent;
+
+use crate::Compositor;
+
+///Per-window state of the [Compositor]. Each window renders into its own color and depth buffer, which is blitted to the
+/// window's swapchain. That way windows of different sizes and formats can be presented by the same compositor.
+pub struct WindowSurface {
+    pub(crate) present: SwapchainPresent,
+    pub(crate) color_buffer: ImageHandle,
+    pub(crate) depth_buffer: ImageHandle,
+}
+
+impl WindowSurface {
+    pub(crate) fn new(
+        rmg: &mut Rmg,
+        present: SwapchainPresent,
+        depth_format: vk::Format,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let color_buffer = rmg
+            .new_image_uninitialized(
+                ImgDesc::color_attachment_2d(width, height, present.format())
+                    .add_usage(Compositor::COLOR_USAGE),
+                Some("color-buffer"),
+            )
+            .unwrap();
+        let depth_buffer = rmg
+            .new_image_uninitialized(
+                ImgDesc::depth_attachment_2d(width, height, depth_format)
+                    .add_usage(Compositor::DEPTH_USAGE),
+                Some("depth-buffer"),
+            )
+            .unwrap();
+        WindowSurface {
+            present,
+            color_buffer,
+            depth_buffer,
+        }
+    }
+
+    ///Re-creates the color and depth buffer with the new size.
+    pub(crate) fn resize(&mut self, rmg: &mut Rmg, width: u32, height: u32) {
+        let mut color_desc = self.color_buffer.image_desc().clone();
+        color_desc.extent.width = width;
+        color_desc.extent.height = height;
+        self.color_buffer = rmg
+            .new_image_uninitialized(color_desc, Some("color-buffer"))
+            .unwrap();
+        let mut depth_desc = self.depth_buffer.image_desc().clone();
+        depth_desc.extent.width = width;
+        depth_desc.extent.height = height;
+        self.depth_buffer = rmg
+            .new_image_uninitialized(depth_desc, Some("depth-buffer"))
+            .unwrap();
+    }
+
+    ///Returns the transfer function of the window's swapchain.
+    pub fn transfer_function(&self) -> TransferFunction {
+        self.present.transfer_function()
+    }
+
+    ///Returns the swapchain task of the window.
+    pub fn swapchain(&self) -> &SwapchainPresent {
+        &self.present
+    }
+}
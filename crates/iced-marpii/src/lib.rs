@@ -13,7 +13,7 @@ pub mod shape;
 pub(crate) mod text;
 pub(crate) mod util;
 
-pub use compositor::Compositor;
+pub use compositor::{Compositor, WindowSurface};
 pub use custom::{marpii_surface, Event, MarpiiSurface, Persistent, Primitive, Program};
 pub use renderer::Renderer;
 
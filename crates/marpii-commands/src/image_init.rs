@@ -4,6 +4,7 @@ use marpii::{
     ash::vk::{self, BufferImageCopy},
     context::{Device, Queue},
     resources::{Buffer, CommandBufferAllocator, CommandPool, Image, ImgDesc, SharingMode},
+    util::{FormatInfo, mip_extent},
     CommandBufferError, MarpiiError, OoS,
 };
 use std::sync::{Arc, Mutex};
@@ -12,6 +13,9 @@ use crate::ManagedCommands;
 
 ///Creates a Gpu exclusive image from `data`. Assumes that `data` is in the same format as described in `image_description`.
 ///
+/// `data` starts with the tightly packed base level of all layers. If it contains the following mip levels as well, tightly packed
+/// one after another, those are uploaded too. Sizes are calculated via [FormatInfo], which includes block compressed formats.
+///
 ///Returns when the image has finished uploading.
 /// Since this can potentially be a long operation you can either use a dedicated
 /// uploading pass in a graph if the upload should be scheduled better, or use something like [poll-promise](https://crates.io/crates/poll-promise) to do the upload on another thread.
@@ -28,12 +32,51 @@ pub fn image_from_data<A: Allocator + Send + Sync + 'static>(
     //make sure image usage transfer DST is actiavted
     description.usage |= vk::ImageUsageFlags::TRANSFER_DST;
 
+    //collect one copy per mip level that is contained in `data`.
     let image_extent = description.extent;
+    let layers = description.img_type.layer_count();
+    let mut level_offsets = Vec::with_capacity(description.mip_levels as usize);
+    match FormatInfo::of(description.format) {
+        Some(info) => {
+            let mut offset = 0;
+            for level in 0..description.mip_levels {
+                let size = info.region_size(mip_extent(image_extent, level)) * u64::from(layers);
+                if offset + size > data.len() as u64 {
+                    break;
+                }
+                level_offsets.push((level, offset));
+                offset += size;
+            }
+            if level_offsets.is_empty() {
+                return Err(MarpiiError::Other(format!(
+                    "Image data has {} bytes, but the base level needs {}",
+                    data.len(),
+                    info.region_size(image_extent) * u64::from(layers)
+                )));
+            }
+        }
+        //Unknown format, assume the data is only the base level.
+        None => level_offsets.push((0, 0)),
+    }
+
     let staging_buffer =
         Buffer::new_staging_for_data(device, allocator, Some("ImageStagingBuffer"), data)?;
     //init image
     let image = Image::new(device, allocator, description, MemoryUsage::GpuOnly, name)?;
-    let image_subresource = image.subresource_layers_all();
+    let regions = level_offsets
+        .into_iter()
+        .map(|(mip_level, buffer_offset)| BufferImageCopy {
+            buffer_offset,
+            buffer_row_length: 0,
+            buffer_image_height: 0, //always copying tightly packed.
+            image_extent: mip_extent(image_extent, mip_level),
+            image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+            image_subresource: vk::ImageSubresourceLayers {
+                mip_level,
+                ..image.subresource_layers_all()
+            },
+        })
+        .collect::<Vec<_>>();
     //now schedule CB that uploads the image
     let command_pool = OoS::new(CommandPool::new(
         device,
@@ -81,14 +124,7 @@ pub fn image_from_data<A: Allocator + Send + Sync + 'static>(
                 staging_buffer.inner,
                 image_hdl,
                 vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                &[BufferImageCopy {
-                    buffer_offset: 0,
-                    buffer_row_length: 0,
-                    buffer_image_height: 0, //always copying tightly packed.
-                    image_extent,
-                    image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
-                    image_subresource,
-                }],
+                &regions,
             );
         }
     });
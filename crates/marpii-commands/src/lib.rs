@@ -21,5 +21,8 @@ pub use image_init::image_from_data;
 #[cfg(feature = "image_loading")]
 pub use image_init::{image_from_file, image_from_image};
 
+mod texture;
+pub use texture::{TextureData, TextureError, TextureLevel, image_from_texture};
+
 mod barrier_builder;
 pub use barrier_builder::BarrierBuilder;
@@ -0,0 +1,282 @@
+use marpii::{
+    MarpiiError,
+    allocator::Allocator,
+    ash::vk,
+    context::{Device, Queue},
+    resources::{Image, ImageType, ImgDesc, SharingMode},
+    util::{FormatInfo, mip_extent},
+};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+use crate::image_from_data;
+
+mod dds;
+mod ktx2;
+
+///Mip chains of 32-bit extents can't be longer than this.
+const MAX_MIP_LEVELS: u32 = 32;
+
+#[derive(Debug, Error)]
+pub enum TextureError {
+    #[error("Data is neither a KTX2 nor a DDS file")]
+    UnknownContainer,
+    #[error("Texture data ends early, expected at least {0} bytes")]
+    Truncated(usize),
+    #[error("Unsupported texture format: {0}")]
+    UnsupportedFormat(String),
+    #[error("Unsupported KTX2 supercompression scheme {0}")]
+    UnsupportedSupercompression(u32),
+    #[error("Unsupported texture layout: {0}")]
+    UnsupportedLayout(String),
+    #[error("Mip level {level} has {len} bytes, but {expected} are needed")]
+    LevelSize { level: u32, len: u64, expected: u64 },
+    #[error("Failed to decompress mip level {level}: {error}")]
+    Decompression { level: u32, error: String },
+    #[error("Failed to read texture file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<TextureError> for MarpiiError {
+    fn from(value: TextureError) -> Self {
+        MarpiiError::Other(value.to_string())
+    }
+}
+
+///Region of a single mip level within [TextureData::data].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextureLevel {
+    pub mip_level: u32,
+    ///Offset of the level within [TextureData::data].
+    pub offset: u64,
+    ///Size of the level, including all layers.
+    pub size: u64,
+    pub extent: vk::Extent3D,
+    ///Number of layers of the level. Cube maps have 6 layers per cube.
+    pub layer_count: u32,
+}
+
+///Texture loaded from a KTX2 or DDS container. Block compressed formats, mip chains, array layers and cube faces are preserved.
+///
+/// `data` contains all mip levels tightly packed, starting with the base level. Each level contains all layers, for cube maps the
+/// faces are ordered +X, -X, +Y, -Y, +Z, -Z. This is the layout [image_from_data] expects, so
+/// `image_from_data(.., texture.desc, .., &texture.data)` uploads the whole texture. For RMG's `UploadImage`, convert
+/// [levels](Self::levels) into `MipOffset`s, or use `MipOffset::tightly_packed`.
+#[derive(Clone, Debug)]
+pub struct TextureData {
+    ///Description of the texture. The usage is `SAMPLED | TRANSFER_DST`, change it if needed.
+    pub desc: ImgDesc,
+    pub data: Vec<u8>,
+    pub levels: Vec<TextureLevel>,
+}
+
+impl TextureData {
+    ///Parses a KTX2 or DDS container, depending on the file's magic bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TextureError> {
+        if bytes.starts_with(&ktx2::IDENTIFIER) {
+            Self::from_ktx2(bytes)
+        } else if bytes.starts_with(&dds::MAGIC) {
+            Self::from_dds(bytes)
+        } else {
+            Err(TextureError::UnknownContainer)
+        }
+    }
+
+    ///Loads a KTX2 or DDS file.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, TextureError> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes)
+    }
+
+    ///Parses a KTX2 container. Zstandard supercompressed files need the `ktx2_zstd` feature. UASTC textures are transcoded
+    /// to BC7 if the `ktx2_basis` feature is enabled. BasisLZ (ETC1S) textures are not transcoded, and return
+    /// [TextureError::UnsupportedFormat].
+    pub fn from_ktx2(bytes: &[u8]) -> Result<Self, TextureError> {
+        ktx2::parse(bytes)
+    }
+
+    ///Parses a DDS container, including the DX10 header extension.
+    pub fn from_dds(bytes: &[u8]) -> Result<Self, TextureError> {
+        dds::parse(bytes)
+    }
+
+    ///Buffer to image copies that upload all levels from [data](Self::data), if it is placed at the start of the source buffer.
+    pub fn copy_regions(&self) -> Vec<vk::BufferImageCopy> {
+        let aspect_mask = FormatInfo::of(self.desc.format)
+            .map(|info| info.aspect)
+            .unwrap_or(vk::ImageAspectFlags::COLOR);
+        self.levels
+            .iter()
+            .map(|level| vk::BufferImageCopy {
+                buffer_offset: level.offset,
+                buffer_row_length: 0,
+                buffer_image_height: 0,
+                image_extent: level.extent,
+                image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+                image_subresource: vk::ImageSubresourceLayers {
+                    aspect_mask,
+                    mip_level: level.mip_level,
+                    base_array_layer: 0,
+                    layer_count: level.layer_count,
+                },
+            })
+            .collect()
+    }
+}
+
+///Creates a Gpu exclusive image from `texture`, with the additional `usage`. See [image_from_data].
+pub fn image_from_texture<A: Allocator + Send + Sync + 'static>(
+    device: &Arc<Device>,
+    allocator: &Arc<Mutex<A>>,
+    upload_queue: &Queue,
+    usage: vk::ImageUsageFlags,
+    name: Option<&str>,
+    texture: &TextureData,
+) -> Result<Image, MarpiiError> {
+    let desc = texture.desc.clone().add_usage(usage);
+    image_from_data(device, allocator, upload_queue, desc, name, &texture.data)
+}
+
+///Shape of a texture, as described by the container header.
+struct TextureShape {
+    format: vk::Format,
+    extent: vk::Extent3D,
+    ///Number of array elements, `None` if the texture is no array.
+    array_len: Option<u32>,
+    ///True for 1D textures, which have a height of 1.
+    one_dimensional: bool,
+    cube: bool,
+    mip_levels: u32,
+}
+
+impl TextureShape {
+    fn image_type(&self) -> Result<ImageType, TextureError> {
+        let ty = match (self.cube, self.extent.depth > 1, self.array_len) {
+            (true, false, None) => ImageType::Cube,
+            (true, false, Some(n)) => ImageType::CubeArray(n),
+            (false, true, None) => ImageType::Tex3d,
+            (false, false, None) if self.one_dimensional => ImageType::Tex1d,
+            (false, false, Some(n)) if self.one_dimensional => ImageType::Tex1dArray(n),
+            (false, false, None) => ImageType::Tex2d,
+            (false, false, Some(n)) => ImageType::Tex2dArray(n),
+            _ => {
+                return Err(TextureError::UnsupportedLayout(format!(
+                    "cube: {}, depth: {}, array: {:?}",
+                    self.cube, self.extent.depth, self.array_len
+                )));
+            }
+        };
+        Ok(ty)
+    }
+
+    fn layer_count(&self) -> u32 {
+        self.array_len
+            .unwrap_or(1)
+            .saturating_mul(if self.cube { 6 } else { 1 })
+    }
+
+    ///Returns the info of the format. Also checks the mip count, which the size calculations rely on.
+    fn format_info(&self) -> Result<FormatInfo, TextureError> {
+        if self.mip_levels > MAX_MIP_LEVELS {
+            return Err(TextureError::UnsupportedLayout(format!(
+                "{} mip levels",
+                self.mip_levels
+            )));
+        }
+        FormatInfo::of(self.format)
+            .filter(|info| !info.is_multi_planar())
+            .ok_or_else(|| TextureError::UnsupportedFormat(format!("{:?}", self.format)))
+    }
+
+    ///Size of a single layer of `mip_level`. Since the header is untrusted, fails if the size overflows.
+    fn layer_size(&self, info: &FormatInfo, mip_level: u32) -> Result<u64, TextureError> {
+        let blocks = info.block_count(mip_extent(self.extent, mip_level));
+        u64::from(blocks.width)
+            .checked_mul(u64::from(blocks.height))
+            .and_then(|size| size.checked_mul(u64::from(blocks.depth)))
+            .and_then(|size| size.checked_mul(u64::from(info.block_size)))
+            .ok_or_else(|| TextureError::UnsupportedLayout(format!("{:?} is too big", self.extent)))
+    }
+
+    ///Expected size of `mip_level`, including all layers.
+    fn level_size(&self, info: &FormatInfo, mip_level: u32) -> Result<u64, TextureError> {
+        self.layer_size(info, mip_level)?
+            .checked_mul(u64::from(self.layer_count()))
+            .ok_or_else(|| {
+                TextureError::UnsupportedLayout(format!(
+                    "{} layers are too many",
+                    self.layer_count()
+                ))
+            })
+    }
+
+    ///Builds the texture from the levels returned by `level_data`, starting with the base level.
+    fn build<'a>(
+        &self,
+        mut level_data: impl FnMut(u32) -> Result<std::borrow::Cow<'a, [u8]>, TextureError>,
+    ) -> Result<TextureData, TextureError> {
+        let info = self.format_info()?;
+        let img_type = self.image_type()?;
+        let mut data = Vec::new();
+        let mut levels = Vec::with_capacity(self.mip_levels as usize);
+        for mip_level in 0..self.mip_levels {
+            let level = level_data(mip_level)?;
+            let expected = self.level_size(&info, mip_level)?;
+            if level.len() as u64 != expected {
+                return Err(TextureError::LevelSize {
+                    level: mip_level,
+                    len: level.len() as u64,
+                    expected,
+                });
+            }
+            levels.push(TextureLevel {
+                mip_level,
+                offset: data.len() as u64,
+                size: expected,
+                extent: mip_extent(self.extent, mip_level),
+                layer_count: self.layer_count(),
+            });
+            data.extend_from_slice(&level);
+        }
+
+        let create_flags = if self.cube {
+            vk::ImageCreateFlags::CUBE_COMPATIBLE
+        } else {
+            vk::ImageCreateFlags::empty()
+        };
+        Ok(TextureData {
+            desc: ImgDesc {
+                img_type,
+                format: self.format,
+                extent: self.extent,
+                mip_levels: self.mip_levels,
+                samples: vk::SampleCountFlags::TYPE_1,
+                tiling: vk::ImageTiling::OPTIMAL,
+                usage: vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
+                sharing_mode: SharingMode::Exclusive,
+                create_flags,
+            },
+            data,
+            levels,
+        })
+    }
+}
+
+///Returns `bytes[offset..offset + len]`, or [TextureError::Truncated].
+fn slice(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8], TextureError> {
+    bytes
+        .get(offset..offset.saturating_add(len))
+        .ok_or(TextureError::Truncated(offset.saturating_add(len)))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, TextureError> {
+    Ok(u32::from_le_bytes(
+        slice(bytes, offset, 4)?.try_into().unwrap(),
+    ))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, TextureError> {
+    Ok(u64::from_le_bytes(
+        slice(bytes, offset, 8)?.try_into().unwrap(),
+    ))
+}
@@ -0,0 +1,347 @@
+use marpii::ash::vk;
+use std::borrow::Cow;
+
+use super::{TextureData, TextureError, TextureShape, read_u32, slice};
+
+pub(super) const MAGIC: [u8; 4] = *b"DDS ";
+
+///Size of the magic and the `DDS_HEADER`.
+const HEADER_SIZE: usize = 128;
+const DX10_HEADER_SIZE: usize = 20;
+
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDSD_DEPTH: u32 = 0x800000;
+
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x20000;
+
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_CUBEMAP_ALL_FACES: u32 = 0xFC00;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+
+const D3D10_RESOURCE_DIMENSION_TEXTURE1D: u32 = 2;
+const D3D10_RESOURCE_DIMENSION_TEXTURE3D: u32 = 4;
+const D3D10_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+pub(super) fn parse(bytes: &[u8]) -> Result<TextureData, TextureError> {
+    if !bytes.starts_with(&MAGIC) {
+        return Err(TextureError::UnknownContainer);
+    }
+    let flags = read_u32(bytes, 8)?;
+    let height = read_u32(bytes, 12)?;
+    let width = read_u32(bytes, 16)?;
+    let depth = read_u32(bytes, 24)?;
+    let mip_count = read_u32(bytes, 28)?;
+    let pf_flags = read_u32(bytes, 80)?;
+    let four_cc = read_u32(bytes, 84)?;
+    let caps2 = read_u32(bytes, 112)?;
+
+    let mip_levels = if flags & DDSD_MIPMAPCOUNT != 0 {
+        mip_count.max(1)
+    } else {
+        1
+    };
+    let mut extent = vk::Extent3D {
+        width,
+        height: height.max(1),
+        depth: if flags & DDSD_DEPTH != 0 || caps2 & DDSCAPS2_VOLUME != 0 {
+            depth.max(1)
+        } else {
+            1
+        },
+    };
+
+    let (shape, data_offset) = if pf_flags & DDPF_FOURCC != 0
+        && four_cc == u32::from_le_bytes(*b"DX10")
+    {
+        let dxgi_format = read_u32(bytes, HEADER_SIZE)?;
+        let dimension = read_u32(bytes, HEADER_SIZE + 4)?;
+        let misc_flag = read_u32(bytes, HEADER_SIZE + 8)?;
+        let array_size = read_u32(bytes, HEADER_SIZE + 12)?.max(1);
+
+        let format = dxgi_to_vk(dxgi_format)
+            .ok_or_else(|| TextureError::UnsupportedFormat(format!("DXGI_FORMAT {dxgi_format}")))?;
+        if dimension == D3D10_RESOURCE_DIMENSION_TEXTURE1D {
+            extent.height = 1;
+        }
+        if dimension != D3D10_RESOURCE_DIMENSION_TEXTURE3D {
+            extent.depth = 1;
+        }
+        let shape = TextureShape {
+            format,
+            extent,
+            array_len: (array_size > 1).then_some(array_size),
+            one_dimensional: dimension == D3D10_RESOURCE_DIMENSION_TEXTURE1D,
+            cube: misc_flag & D3D10_RESOURCE_MISC_TEXTURECUBE != 0,
+            mip_levels,
+        };
+        (shape, HEADER_SIZE + DX10_HEADER_SIZE)
+    } else {
+        let cube = caps2 & DDSCAPS2_CUBEMAP != 0;
+        if cube && caps2 & DDSCAPS2_CUBEMAP_ALL_FACES != DDSCAPS2_CUBEMAP_ALL_FACES {
+            return Err(TextureError::UnsupportedLayout(
+                "cube map with missing faces".to_owned(),
+            ));
+        }
+        let shape = TextureShape {
+            format: legacy_format(bytes, pf_flags, four_cc)?,
+            extent,
+            array_len: None,
+            one_dimensional: false,
+            cube,
+            mip_levels,
+        };
+        (shape, HEADER_SIZE)
+    };
+
+    //DDS stores the full mip chain of each layer after another, but levels are stored with all their
+    // layers in `TextureData`.
+    let info = shape.format_info()?;
+    let layer_sizes = (0..shape.mip_levels)
+        .map(|level| shape.layer_size(&info, level))
+        .collect::<Result<Vec<_>, _>>()?;
+    //The header is untrusted, so make sure all layers are actually present before anything is allocated. All sizes
+    // fit into `bytes` afterwards.
+    let needed = layer_sizes
+        .iter()
+        .try_fold(0u64, |sum, size| sum.checked_add(*size))
+        .and_then(|chain| chain.checked_mul(u64::from(shape.layer_count())))
+        .and_then(|size| size.checked_add(data_offset as u64))
+        .ok_or_else(|| TextureError::UnsupportedLayout("texture is too big".to_owned()))?;
+    if needed > bytes.len() as u64 {
+        return Err(TextureError::Truncated(
+            usize::try_from(needed).unwrap_or(usize::MAX),
+        ));
+    }
+    let layer_sizes = layer_sizes
+        .into_iter()
+        .map(|size| size as usize)
+        .collect::<Vec<_>>();
+    let chain_size: usize = layer_sizes.iter().sum();
+    shape.build(|mip_level| {
+        let level_offset: usize = layer_sizes[..mip_level as usize].iter().sum();
+        let size = layer_sizes[mip_level as usize];
+        let mut level = Vec::with_capacity(size * shape.layer_count() as usize);
+        for layer in 0..shape.layer_count() as usize {
+            level.extend_from_slice(slice(
+                bytes,
+                data_offset + layer * chain_size + level_offset,
+                size,
+            )?);
+        }
+        Ok(Cow::Owned(level))
+    })
+}
+
+///Maps the `DXGI_FORMAT` of a DX10 header to the Vulkan format.
+fn dxgi_to_vk(format: u32) -> Option<vk::Format> {
+    let format = match format {
+        2 => vk::Format::R32G32B32A32_SFLOAT,
+        10 => vk::Format::R16G16B16A16_SFLOAT,
+        11 => vk::Format::R16G16B16A16_UNORM,
+        16 => vk::Format::R32G32_SFLOAT,
+        24 => vk::Format::A2B10G10R10_UNORM_PACK32,
+        26 => vk::Format::B10G11R11_UFLOAT_PACK32,
+        28 => vk::Format::R8G8B8A8_UNORM,
+        29 => vk::Format::R8G8B8A8_SRGB,
+        30 => vk::Format::R8G8B8A8_UINT,
+        31 => vk::Format::R8G8B8A8_SNORM,
+        34 => vk::Format::R16G16_SFLOAT,
+        35 => vk::Format::R16G16_UNORM,
+        40 => vk::Format::D32_SFLOAT,
+        41 => vk::Format::R32_SFLOAT,
+        42 => vk::Format::R32_UINT,
+        49 => vk::Format::R8G8_UNORM,
+        54 => vk::Format::R16_SFLOAT,
+        55 => vk::Format::D16_UNORM,
+        56 => vk::Format::R16_UNORM,
+        61 => vk::Format::R8_UNORM,
+        67 => vk::Format::E5B9G9R9_UFLOAT_PACK32,
+        71 => vk::Format::BC1_RGBA_UNORM_BLOCK,
+        72 => vk::Format::BC1_RGBA_SRGB_BLOCK,
+        74 => vk::Format::BC2_UNORM_BLOCK,
+        75 => vk::Format::BC2_SRGB_BLOCK,
+        77 => vk::Format::BC3_UNORM_BLOCK,
+        78 => vk::Format::BC3_SRGB_BLOCK,
+        80 => vk::Format::BC4_UNORM_BLOCK,
+        81 => vk::Format::BC4_SNORM_BLOCK,
+        83 => vk::Format::BC5_UNORM_BLOCK,
+        84 => vk::Format::BC5_SNORM_BLOCK,
+        85 => vk::Format::R5G6B5_UNORM_PACK16,
+        87 => vk::Format::B8G8R8A8_UNORM,
+        91 => vk::Format::B8G8R8A8_SRGB,
+        95 => vk::Format::BC6H_UFLOAT_BLOCK,
+        96 => vk::Format::BC6H_SFLOAT_BLOCK,
+        98 => vk::Format::BC7_UNORM_BLOCK,
+        99 => vk::Format::BC7_SRGB_BLOCK,
+        _ => return None,
+    };
+    Some(format)
+}
+
+///Maps the pixel format of a DDS file without DX10 header.
+fn legacy_format(bytes: &[u8], pf_flags: u32, four_cc: u32) -> Result<vk::Format, TextureError> {
+    if pf_flags & DDPF_FOURCC != 0 {
+        let format = match &four_cc.to_le_bytes() {
+            b"DXT1" => vk::Format::BC1_RGBA_UNORM_BLOCK,
+            b"DXT2" | b"DXT3" => vk::Format::BC2_UNORM_BLOCK,
+            b"DXT4" | b"DXT5" => vk::Format::BC3_UNORM_BLOCK,
+            b"ATI1" | b"BC4U" => vk::Format::BC4_UNORM_BLOCK,
+            b"BC4S" => vk::Format::BC4_SNORM_BLOCK,
+            b"ATI2" | b"BC5U" => vk::Format::BC5_UNORM_BLOCK,
+            b"BC5S" => vk::Format::BC5_SNORM_BLOCK,
+            //D3DFORMAT values stored directly in the FourCC.
+            _ => match four_cc {
+                36 => vk::Format::R16G16B16A16_UNORM,
+                111 => vk::Format::R16_SFLOAT,
+                112 => vk::Format::R16G16_SFLOAT,
+                113 => vk::Format::R16G16B16A16_SFLOAT,
+                114 => vk::Format::R32_SFLOAT,
+                115 => vk::Format::R32G32_SFLOAT,
+                116 => vk::Format::R32G32B32A32_SFLOAT,
+                _ => {
+                    return Err(TextureError::UnsupportedFormat(format!(
+                        "DDS FourCC {:?}",
+                        String::from_utf8_lossy(&four_cc.to_le_bytes())
+                    )));
+                }
+            },
+        };
+        return Ok(format);
+    }
+
+    let bit_count = read_u32(bytes, 88)?;
+    let masks = [
+        read_u32(bytes, 92)?,
+        read_u32(bytes, 96)?,
+        read_u32(bytes, 100)?,
+    ];
+    let format = match (
+        pf_flags & (DDPF_RGB | DDPF_LUMINANCE) != 0,
+        bit_count,
+        masks,
+    ) {
+        (true, 32, [0xff0000, 0xff00, 0xff]) => vk::Format::B8G8R8A8_UNORM,
+        (true, 32, [0xff, 0xff00, 0xff0000]) => vk::Format::R8G8B8A8_UNORM,
+        (true, 16, [0xf800, 0x7e0, 0x1f]) => vk::Format::R5G6B5_UNORM_PACK16,
+        (true, 8, [0xff, _, _]) => vk::Format::R8_UNORM,
+        _ => {
+            return Err(TextureError::UnsupportedFormat(format!(
+                "DDS pixel format with {bit_count} bits and masks {masks:x?}"
+            )));
+        }
+    };
+    Ok(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use marpii::resources::ImageType;
+
+    fn header(
+        flags: u32,
+        width: u32,
+        height: u32,
+        mips: u32,
+        four_cc: &[u8; 4],
+        caps2: u32,
+    ) -> Vec<u8> {
+        let mut file = vec![0; HEADER_SIZE];
+        let mut write =
+            |offset: usize, v: u32| file[offset..offset + 4].copy_from_slice(&v.to_le_bytes());
+        write(0, u32::from_le_bytes(MAGIC));
+        write(4, 124);
+        write(8, flags);
+        write(12, height);
+        write(16, width);
+        write(28, mips);
+        write(76, 32);
+        write(80, DDPF_FOURCC);
+        write(84, u32::from_le_bytes(*four_cc));
+        write(112, caps2);
+        file
+    }
+
+    #[test]
+    fn dxt1_mips() {
+        let mut file = header(DDSD_MIPMAPCOUNT, 8, 8, 4, b"DXT1", 0);
+        //8x8, 4x4, 2x2 and 1x1 all round up to whole 4x4 blocks.
+        file.extend(std::iter::repeat_n(0u8, (4 + 1 + 1 + 1) * 8));
+        let texture = TextureData::from_bytes(&file).unwrap();
+        assert_eq!(texture.desc.img_type, ImageType::Tex2d);
+        assert_eq!(texture.desc.format, vk::Format::BC1_RGBA_UNORM_BLOCK);
+        assert_eq!(texture.levels.len(), 4);
+        assert_eq!(texture.levels[3].offset, 6 * 8);
+        assert_eq!(texture.copy_regions()[2].image_extent.width, 2);
+    }
+
+    #[test]
+    fn dx10_array_reorder() {
+        let mut file = header(DDSD_MIPMAPCOUNT, 2, 2, 2, b"DX10", 0);
+        for v in [28, 3, 0, 2, 0] {
+            file.extend_from_slice(&u32::to_le_bytes(v));
+        }
+        //Two layers with two mips each, stored layer after layer.
+        for layer in 0..2u8 {
+            file.extend(std::iter::repeat_n(layer * 2, 16));
+            file.extend(std::iter::repeat_n(layer * 2 + 1, 4));
+        }
+        let texture = TextureData::from_bytes(&file).unwrap();
+        assert_eq!(texture.desc.img_type, ImageType::Tex2dArray(2));
+        assert_eq!(texture.levels[1].offset, 32);
+        assert_eq!(&texture.data[..32], &[[0u8; 16], [2; 16]].concat());
+        assert_eq!(&texture.data[32..], &[[1u8; 4], [3; 4]].concat());
+    }
+
+    #[test]
+    fn untrusted_header() {
+        //huge extents with almost no data must neither overflow, nor allocate
+        let mut file = header(0, u32::MAX, u32::MAX, 0, b"DXT1", 0);
+        file.extend_from_slice(&[0; 8]);
+        assert!(matches!(
+            TextureData::from_bytes(&file),
+            Err(TextureError::Truncated(_) | TextureError::UnsupportedLayout(_))
+        ));
+
+        let file = header(0, 1 << 16, 1 << 16, 0, b"DXT1", 0);
+        assert!(matches!(
+            TextureData::from_bytes(&file),
+            Err(TextureError::Truncated(_))
+        ));
+
+        let file = header(DDSD_MIPMAPCOUNT, 4, 4, 1000, b"DXT1", 0);
+        assert!(matches!(
+            TextureData::from_bytes(&file),
+            Err(TextureError::UnsupportedLayout(_))
+        ));
+    }
+
+    #[test]
+    fn cube_faces() {
+        let file = header(0, 4, 4, 0, b"DXT5", DDSCAPS2_CUBEMAP | 0x400);
+        assert!(matches!(
+            TextureData::from_bytes(&file),
+            Err(TextureError::UnsupportedLayout(_))
+        ));
+
+        let mut file = header(
+            0,
+            4,
+            4,
+            0,
+            b"DXT5",
+            DDSCAPS2_CUBEMAP | DDSCAPS2_CUBEMAP_ALL_FACES,
+        );
+        file.extend(std::iter::repeat_n(0u8, 6 * 16));
+        let texture = TextureData::from_bytes(&file).unwrap();
+        assert_eq!(texture.desc.img_type, ImageType::Cube);
+        assert!(
+            texture
+                .desc
+                .create_flags
+                .contains(vk::ImageCreateFlags::CUBE_COMPATIBLE)
+        );
+    }
+}
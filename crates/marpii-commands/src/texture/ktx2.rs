@@ -0,0 +1,379 @@
+use marpii::{ash::vk, util::mip_extent};
+use std::borrow::Cow;
+
+use super::{TextureData, TextureError, TextureShape, read_u32, read_u64, slice};
+
+pub(super) const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+///Byte size of the header and index, after which the level index starts.
+const LEVEL_INDEX_OFFSET: usize = 80;
+const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+
+const SUPERCOMPRESSION_NONE: u32 = 0;
+const SUPERCOMPRESSION_BASIS_LZ: u32 = 1;
+const SUPERCOMPRESSION_ZSTD: u32 = 2;
+
+///Color model of UASTC textures in the data format descriptor.
+const DFD_MODEL_UASTC: u8 = 166;
+const DFD_TRANSFER_SRGB: u8 = 2;
+const DFD_CHANNEL_UASTC_RGBA: u8 = 3;
+const DFD_CHANNEL_UASTC_RRRG: u8 = 5;
+
+///Properties of a UASTC texture, read from the data format descriptor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Uastc {
+    srgb: bool,
+    has_alpha: bool,
+}
+
+impl Uastc {
+    fn read(bytes: &[u8]) -> Result<Option<Self>, TextureError> {
+        let dfd = read_u32(bytes, 48)? as usize;
+        //skip the total size and the first two words of the basic descriptor block
+        let block = slice(bytes, dfd.saturating_add(4), 28)?;
+        if block[8] != DFD_MODEL_UASTC {
+            return Ok(None);
+        }
+        let channel = block[24 + 3] & 0x0f;
+        Ok(Some(Uastc {
+            srgb: block[10] == DFD_TRANSFER_SRGB,
+            has_alpha: channel == DFD_CHANNEL_UASTC_RGBA || channel == DFD_CHANNEL_UASTC_RRRG,
+        }))
+    }
+
+    ///Format UASTC is transcoded to.
+    fn format(&self) -> vk::Format {
+        if self.srgb {
+            vk::Format::BC7_SRGB_BLOCK
+        } else {
+            vk::Format::BC7_UNORM_BLOCK
+        }
+    }
+}
+
+pub(super) fn parse(bytes: &[u8]) -> Result<TextureData, TextureError> {
+    if !bytes.starts_with(&IDENTIFIER) {
+        return Err(TextureError::UnknownContainer);
+    }
+    let format = vk::Format::from_raw(read_u32(bytes, 12)? as i32);
+    let width = read_u32(bytes, 20)?;
+    let height = read_u32(bytes, 24)?;
+    let depth = read_u32(bytes, 28)?;
+    let layer_count = read_u32(bytes, 32)?;
+    let face_count = read_u32(bytes, 36)?;
+    let level_count = read_u32(bytes, 40)?;
+    let supercompression = read_u32(bytes, 44)?;
+
+    //BasisLZ and UASTC textures have no Vulkan format, since they have to be transcoded first. UASTC is transcoded to BC7,
+    // which has the same block size, so the level sizes are those of the BC7 texture.
+    let uastc = match (format, supercompression) {
+        //NOTE: ETC1S slices need the codebooks of the supercompression global data. basis-universal only exposes a
+        //      low-level transcoder for UASTC, and a transcoder for `.basis` files, so BasisLZ is out of scope for now.
+        (_, SUPERCOMPRESSION_BASIS_LZ) => {
+            return Err(TextureError::UnsupportedFormat(
+                "BasisLZ (ETC1S) textures can not be transcoded, only UASTC is supported"
+                    .to_owned(),
+            ));
+        }
+        (vk::Format::UNDEFINED, _) => Some(Uastc::read(bytes)?.ok_or_else(|| {
+            TextureError::UnsupportedFormat("KTX2 texture without a Vulkan format".to_owned())
+        })?),
+        _ => None,
+    };
+    let format = uastc.map(|uastc| uastc.format()).unwrap_or(format);
+    if face_count != 1 && face_count != 6 {
+        return Err(TextureError::UnsupportedLayout(format!(
+            "{face_count} cube faces"
+        )));
+    }
+
+    let shape = TextureShape {
+        format,
+        extent: vk::Extent3D {
+            width,
+            height: height.max(1),
+            depth: depth.max(1),
+        },
+        array_len: (layer_count > 0).then_some(layer_count),
+        one_dimensional: height == 0,
+        cube: face_count == 6,
+        //0 requests mip generation at load time, which we don't do. Only the base level is present in that case.
+        mip_levels: level_count.max(1),
+    };
+
+    let info = shape.format_info()?;
+    shape.build(|mip_level| {
+        let entry = LEVEL_INDEX_OFFSET + mip_level as usize * LEVEL_INDEX_ENTRY_SIZE;
+        let offset = usize::try_from(read_u64(bytes, entry)?).unwrap_or(usize::MAX);
+        let len = usize::try_from(read_u64(bytes, entry + 8)?).unwrap_or(usize::MAX);
+        let uncompressed_len = read_u64(bytes, entry + 16)?;
+        let level = slice(bytes, offset, len)?;
+        //Don't trust the index, the level must have exactly the size of the described image.
+        let expected = shape.level_size(&info, mip_level)?;
+        let level = match supercompression {
+            SUPERCOMPRESSION_NONE => Cow::Borrowed(level),
+            SUPERCOMPRESSION_ZSTD => {
+                if uncompressed_len != expected {
+                    return Err(TextureError::LevelSize {
+                        level: mip_level,
+                        len: uncompressed_len,
+                        expected,
+                    });
+                }
+                decompress_zstd(level, expected, mip_level)?
+            }
+            other => return Err(TextureError::UnsupportedSupercompression(other)),
+        };
+        match uastc {
+            Some(uastc) if level.len() as u64 == expected => transcode_uastc(
+                &level,
+                mip_extent(shape.extent, mip_level),
+                uastc,
+                mip_level,
+            ),
+            _ => Ok(level),
+        }
+    })
+}
+
+///Transcodes all images (layers, faces and depth slices) of a UASTC `level` to BC7.
+#[cfg(feature = "ktx2_basis")]
+fn transcode_uastc<'a>(
+    level: &[u8],
+    extent: vk::Extent3D,
+    uastc: Uastc,
+    mip_level: u32,
+) -> Result<Cow<'a, [u8]>, TextureError> {
+    use basis_universal::{
+        DecodeFlags, LowLevelUastcTranscoder, SliceParametersUastc, TranscoderBlockFormat,
+    };
+
+    basis_universal::transcoder_init();
+    let num_blocks_x = extent.width.div_ceil(4);
+    let num_blocks_y = extent.height.div_ceil(4);
+    //UASTC blocks are 16 bytes, just like BC7 blocks.
+    let image_size = num_blocks_x as usize * num_blocks_y as usize * 16;
+    let transcoder = LowLevelUastcTranscoder::new();
+    let mut data = Vec::with_capacity(level.len());
+    for image in level.chunks(image_size) {
+        //NOTE: the slice parameters are consumed per call.
+        let parameters = SliceParametersUastc {
+            num_blocks_x,
+            num_blocks_y,
+            has_alpha: uastc.has_alpha,
+            original_width: extent.width,
+            original_height: extent.height,
+        };
+        let transcoded = transcoder
+            .transcode_slice(
+                image,
+                parameters,
+                DecodeFlags::empty(),
+                TranscoderBlockFormat::BC7,
+            )
+            .map_err(|_| TextureError::Decompression {
+                level: mip_level,
+                error: "UASTC transcoding failed".to_owned(),
+            })?;
+        data.extend_from_slice(&transcoded);
+    }
+    Ok(Cow::Owned(data))
+}
+
+#[cfg(not(feature = "ktx2_basis"))]
+fn transcode_uastc<'a>(
+    _level: &[u8],
+    _extent: vk::Extent3D,
+    _uastc: Uastc,
+    _mip_level: u32,
+) -> Result<Cow<'a, [u8]>, TextureError> {
+    Err(TextureError::UnsupportedFormat(
+        "UASTC textures need the `ktx2_basis` feature".to_owned(),
+    ))
+}
+
+#[cfg(feature = "ktx2_zstd")]
+fn decompress_zstd<'a>(
+    level: &[u8],
+    uncompressed_len: u64,
+    mip_level: u32,
+) -> Result<Cow<'a, [u8]>, TextureError> {
+    use std::io::Read;
+
+    //NOTE: decode as a stream, so memory is only allocated for data that is actually decompressed, not for the size
+    // the file claims.
+    let mut data = Vec::new();
+    zstd::stream::read::Decoder::with_buffer(level)
+        .and_then(|decoder| decoder.take(uncompressed_len).read_to_end(&mut data))
+        .map_err(|e| TextureError::Decompression {
+            level: mip_level,
+            error: e.to_string(),
+        })?;
+    Ok(Cow::Owned(data))
+}
+
+#[cfg(not(feature = "ktx2_zstd"))]
+fn decompress_zstd<'a>(
+    _level: &[u8],
+    _uncompressed_len: u64,
+    _mip_level: u32,
+) -> Result<Cow<'a, [u8]>, TextureError> {
+    Err(TextureError::UnsupportedSupercompression(
+        SUPERCOMPRESSION_ZSTD,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///Builds a KTX2 file of a BC1 cube map with two mip levels.
+    fn bc1_cube() -> Vec<u8> {
+        let levels: [Vec<u8>; 2] = [vec![1; 6 * 4 * 8], vec![2; 6 * 8]];
+        let mut file = IDENTIFIER.to_vec();
+        for v in [
+            vk::Format::BC1_RGBA_SRGB_BLOCK.as_raw() as u32,
+            1,
+            8,
+            8,
+            0,
+            0,
+            6,
+            2,
+            SUPERCOMPRESSION_NONE,
+        ] {
+            file.extend_from_slice(&v.to_le_bytes());
+        }
+        //dfd, kvd and sgd are not read.
+        file.resize(LEVEL_INDEX_OFFSET, 0);
+        let mut offset = (LEVEL_INDEX_OFFSET + 2 * LEVEL_INDEX_ENTRY_SIZE) as u64;
+        for level in &levels {
+            for v in [offset, level.len() as u64, level.len() as u64] {
+                file.extend_from_slice(&v.to_le_bytes());
+            }
+            offset += level.len() as u64;
+        }
+        for level in &levels {
+            file.extend_from_slice(level);
+        }
+        file
+    }
+
+    #[test]
+    fn parse_cube() {
+        let texture = TextureData::from_bytes(&bc1_cube()).unwrap();
+        assert_eq!(texture.desc.img_type, marpii::resources::ImageType::Cube);
+        assert_eq!(texture.desc.format, vk::Format::BC1_RGBA_SRGB_BLOCK);
+        assert_eq!(texture.desc.mip_levels, 2);
+        assert_eq!(texture.levels[1].offset, 6 * 4 * 8);
+        assert_eq!(texture.levels[1].layer_count, 6);
+        assert_eq!(texture.data.len(), 6 * 5 * 8);
+    }
+
+    #[test]
+    fn untrusted_header() {
+        let mut file = bc1_cube();
+        //level count
+        file[40..44].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(matches!(
+            TextureData::from_bytes(&file),
+            Err(TextureError::UnsupportedLayout(_))
+        ));
+
+        //huge extent, the levels don't match
+        let mut file = bc1_cube();
+        file[20..24].copy_from_slice(&u32::MAX.to_le_bytes());
+        file[24..28].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(TextureData::from_bytes(&file).is_err());
+
+        //level offset beyond the file
+        let mut file = bc1_cube();
+        file[LEVEL_INDEX_OFFSET..LEVEL_INDEX_OFFSET + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            TextureData::from_bytes(&file),
+            Err(TextureError::Truncated(_))
+        ));
+    }
+
+    ///Builds a KTX2 file of a single 4x4 UASTC block.
+    fn uastc_block(transfer: u8, channel: u8) -> Vec<u8> {
+        let mut file = IDENTIFIER.to_vec();
+        for v in [0, 1, 4, 4, 0, 0, 1, 1, SUPERCOMPRESSION_NONE] {
+            file.extend_from_slice(&v.to_le_bytes());
+        }
+        let dfd = LEVEL_INDEX_OFFSET + LEVEL_INDEX_ENTRY_SIZE;
+        for v in [dfd as u32, 44] {
+            file.extend_from_slice(&v.to_le_bytes());
+        }
+        file.resize(LEVEL_INDEX_OFFSET, 0);
+        for v in [dfd as u64 + 44, 16, 16] {
+            file.extend_from_slice(&v.to_le_bytes());
+        }
+        let mut descriptor = vec![0; 44];
+        descriptor[0..4].copy_from_slice(&44u32.to_le_bytes());
+        descriptor[4 + 8] = DFD_MODEL_UASTC;
+        descriptor[4 + 10] = transfer;
+        descriptor[4 + 24 + 3] = channel;
+        file.extend_from_slice(&descriptor);
+        file.extend_from_slice(&[0; 16]);
+        file
+    }
+
+    #[test]
+    fn uastc_descriptor() {
+        let file = uastc_block(DFD_TRANSFER_SRGB, DFD_CHANNEL_UASTC_RGBA);
+        let uastc = Uastc::read(&file).unwrap().unwrap();
+        assert_eq!(
+            uastc,
+            Uastc {
+                srgb: true,
+                has_alpha: true
+            }
+        );
+        assert_eq!(uastc.format(), vk::Format::BC7_SRGB_BLOCK);
+
+        let file = uastc_block(1, 0);
+        let uastc = Uastc::read(&file).unwrap().unwrap();
+        assert_eq!(uastc.format(), vk::Format::BC7_UNORM_BLOCK);
+        assert!(!uastc.has_alpha);
+
+        //not a UASTC descriptor
+        let mut file = bc1_cube();
+        file[12..16].copy_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(
+            TextureData::from_bytes(&file),
+            Err(TextureError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn basis_lz_unsupported() {
+        let mut file = bc1_cube();
+        file[44..48].copy_from_slice(&SUPERCOMPRESSION_BASIS_LZ.to_le_bytes());
+        assert!(matches!(
+            TextureData::from_bytes(&file),
+            Err(TextureError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[cfg(not(feature = "ktx2_basis"))]
+    #[test]
+    fn uastc_needs_feature() {
+        assert!(matches!(
+            TextureData::from_bytes(&uastc_block(DFD_TRANSFER_SRGB, 0)),
+            Err(TextureError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn truncated() {
+        let mut file = bc1_cube();
+        file.pop();
+        assert!(matches!(
+            TextureData::from_bytes(&file),
+            Err(TextureError::Truncated(_))
+        ));
+    }
+}
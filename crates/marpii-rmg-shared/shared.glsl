@@ -0,0 +1,23 @@
+//GLSL counterpart of the `ResourceHandle` defined in this crate. Available as `marpii_rmg_shared::SHARED_GLSL`.
+#define TYPE_STORAGE_BUFFER 0x1
+#define TYPE_STORAGE_IMAGE 0x2
+#define TYPE_SAMPLED_IMAGE 0x4
+#define TYPE_SAMPLER 0x8
+#define TYPE_ACCELERATION_STRUCTURE 0x10
+#define TYPE_INVALID 0xff
+
+struct ResHandle{
+    uint hdl;
+};
+
+uint get_index(ResHandle res){
+    return (res.hdl >> 8);
+}
+
+uint get_type(ResHandle res){
+    return res.hdl & 0x000000ff;
+}
+
+bool is_valid(ResHandle res){
+    return get_type(res) != TYPE_INVALID;
+}
@@ -7,6 +7,10 @@ use marpii::ash::vk;
 #[cfg(not(target_arch = "spirv"))]
 use bytemuck::{Pod, Zeroable};
 
+///GLSL definition of [ResourceHandle] (`ResHandle`) and its accessors. Include it as `shared.glsl` when compiling GLSL
+/// shaders at runtime.
+pub const SHARED_GLSL: &str = include_str!("../shared.glsl");
+
 ///By definition when interpreted as big endian the highest byte is the handle type and the lower bytes are the actual index.
 ///
 /// Note that the descriptor set index is the same as the type
@@ -1,11 +1,12 @@
 use marpii::{
+    allocator::RingAllocation,
     ash::vk,
-    resources::{BufDesc, Buffer, BufferMapError},
+    resources::{BufDesc, BufferMapError},
 };
-use marpii_rmg::{BufferHandle, ResourceRegistry, Resources, Rmg, Task};
-use std::sync::Arc;
+use marpii_rmg::{BufferHandle, CtxRmg, RecordError, ResourceRegistry, Resources, Rmg, Task};
+use std::{ops::Range, sync::Arc};
 
-use crate::{RmgTaskError, TaskError};
+use crate::RmgTaskError;
 
 ///Manages a buffer where the content can be changed from the CPU side
 /// efficiently at runtime.
@@ -16,14 +17,17 @@ use crate::{RmgTaskError, TaskError};
 /// the content is guaranteed to have changed.
 ///
 /// # Implementation
-/// A CPU local, map-abel buffer, and a GPU local clone are created.
-/// Whenever data changes it is written to the CPU local buffer immediately, and later, at execution time of this task the CPU buffer is written
-/// to the GPU local clone.
+/// A CPU side copy of the buffer's content, and a GPU local buffer are created.
+/// Whenever data changes it is written to the CPU side copy immediately. At recording time the changed range is
+/// staged on RMG's upload ring, and copied to the GPU local buffer when the task executes.
 ///
 pub struct DynamicBuffer<T: marpii::bytemuck::Pod> {
-    cpu_local: Buffer,
+    cpu_local: Vec<u8>,
     gpu_local: BufferHandle<T>,
-    has_changed: bool,
+    ///Byte range that changed since the last upload
+    changed: Option<Range<usize>>,
+    ///Staged upload, and the byte range it covers.
+    upload: Option<(RingAllocation, Range<usize>)>,
 }
 
 impl<T: marpii::bytemuck::Pod> DynamicBuffer<T> {
@@ -45,28 +49,27 @@ impl<T: marpii::bytemuck::Pod> DynamicBuffer<T> {
         );
 
         #[cfg(feature = "logging")]
-        log::trace!(
-            "Creating StagingBuffer & GpuBuffer of size={}",
-            description.size
-        );
+        log::trace!("Creating CPU copy & GpuBuffer of size={}", description.size);
 
-        let cpu_local =
-            Buffer::new_staging_buffer(&rmg.ctx.device, &rmg.ctx.allocator, None, description.size)
-                .map_err(|e| TaskError::Marpii(e.into()))?;
+        let mut cpu_local = vec![0u8; description.size as usize];
 
         //Initialize data only if there actualy is some
-        if initial_data.len() > 0 {
-            let initial_bytes = bytemuck::cast_slice(initial_data);
-            cpu_local
-                .write(0, initial_bytes)
-                .map_err(|e| TaskError::Marpii(e.into()))?;
+        if !initial_data.is_empty() {
+            let initial_bytes: &[u8] = bytemuck::cast_slice(initial_data);
+            cpu_local[0..initial_bytes.len()].copy_from_slice(initial_bytes);
         }
+        let changed = if cpu_local.is_empty() {
+            None
+        } else {
+            Some(0..cpu_local.len())
+        };
         let gpu_local = rmg.new_buffer_uninitialized(description, name)?;
 
         Ok(DynamicBuffer {
             cpu_local,
             gpu_local,
-            has_changed: true,
+            changed,
+            upload: None,
         })
     }
 
@@ -90,43 +93,45 @@ impl<T: marpii::bytemuck::Pod> DynamicBuffer<T> {
             return Err(BufferMapError::OffsetTooLarge);
         }
 
-        if ((size_of_element * data.len()) + (offset_elements * size_of_element))
-            > self.cpu_local.desc.size as usize
-        {
+        let start = offset_elements * size_of_element;
+        let end = start + (size_of_element * data.len());
+        if end > self.cpu_local.len() {
             return Err(BufferMapError::OffsetTooLarge);
         }
 
         #[cfg(feature = "logging")]
         log::trace!(
-            "Write to staging buffer {:?}(size={}byte) @{} ({} elements / {}byte)",
-            self.cpu_local.inner,
-            self.cpu_local.desc.size,
+            "Write to CPU copy (size={}byte) @{} ({} elements / {}byte)",
+            self.cpu_local.len(),
             offset_elements,
             data.len(),
             size_of_element * data.len()
         );
 
-        self.has_changed = true;
-        let data = bytemuck::cast_slice(data);
+        if start == end {
+            return Ok(());
+        }
 
-        self.cpu_local
-            .write(size_of_element * offset_elements, data)?;
+        self.cpu_local[start..end].copy_from_slice(bytemuck::cast_slice(data));
+        self.changed = Some(merge_ranges(self.changed.take(), start..end));
 
         Ok(())
     }
 
     ///Returns the buffer handle to the device local, dynamically updated buffer
     pub fn buffer_handle(&self) -> &BufferHandle<T> {
-        assert!(
-            self.cpu_local.desc.size >= self.gpu_local.size(),
-            "CPU(size={}) and GPU(size={}) buffers don't match...",
-            self.cpu_local.desc.size,
-            self.gpu_local.size()
-        );
         &self.gpu_local
     }
 }
 
+fn merge_ranges(a: Option<Range<usize>>, b: Range<usize>) -> Range<usize> {
+    if let Some(a) = a {
+        a.start.min(b.start)..a.end.max(b.end)
+    } else {
+        b
+    }
+}
+
 impl<T: marpii::bytemuck::Pod> Task for DynamicBuffer<T> {
     fn name(&self) -> &'static str {
         "DynamicBuffer"
@@ -134,10 +139,21 @@ impl<T: marpii::bytemuck::Pod> Task for DynamicBuffer<T> {
     fn queue_flags(&self) -> marpii::ash::vk::QueueFlags {
         vk::QueueFlags::TRANSFER
     }
+    fn pre_record(&mut self, resources: &mut Resources, ctx: &CtxRmg) -> Result<(), RecordError> {
+        let Some(changed) = self.changed.take() else {
+            return Ok(());
+        };
+
+        //If there is an upload that was not executed yet, re-stage both ranges
+        let range = merge_ranges(self.upload.take().map(|(_, range)| range), changed);
+        let allocation = resources.upload_data(ctx, &self.cpu_local[range.clone()], 4)?;
+        self.upload = Some((allocation, range));
+        Ok(())
+    }
     fn register(&self, registry: &mut ResourceRegistry) {
-        if !self.has_changed {
+        let Some((allocation, _)) = &self.upload else {
             return;
-        }
+        };
         registry
             .request_buffer(
                 &self.gpu_local,
@@ -145,6 +161,7 @@ impl<T: marpii::bytemuck::Pod> Task for DynamicBuffer<T> {
                 vk::AccessFlags2::TRANSFER_WRITE,
             )
             .unwrap();
+        registry.request_upload(allocation);
     }
     fn record(
         &mut self,
@@ -152,28 +169,22 @@ impl<T: marpii::bytemuck::Pod> Task for DynamicBuffer<T> {
         command_buffer: &vk::CommandBuffer,
         resources: &Resources,
     ) {
-        if !self.has_changed {
+        let Some((allocation, range)) = self.upload.take() else {
             return;
-        }
+        };
 
         let dst_access = resources.get_buffer_state(&self.gpu_local);
-        let copy_size = self.cpu_local.desc.size.min(dst_access.buffer.desc.size);
 
         //Move the src buffers to the correct layout state. Those are (not yet)
         // handled by RMG, so we have to do that by hand here.
         unsafe {
             device.inner.cmd_pipeline_barrier2(
                 *command_buffer,
-                &vk::DependencyInfo::default().buffer_memory_barriers(&[
-                    vk::BufferMemoryBarrier2::default()
-                        .buffer(self.cpu_local.inner)
-                        .offset(0)
-                        .size(vk::WHOLE_SIZE)
-                        .src_access_mask(vk::AccessFlags2::HOST_WRITE)
-                        .src_stage_mask(vk::PipelineStageFlags2::HOST)
-                        .dst_access_mask(vk::AccessFlags2::TRANSFER_READ)
-                        .dst_stage_mask(vk::PipelineStageFlags2::TRANSFER),
-                ]),
+                &vk::DependencyInfo::default().buffer_memory_barriers(&[allocation
+                    .host_write_barrier(
+                        vk::PipelineStageFlags2::TRANSFER,
+                        vk::AccessFlags2::TRANSFER_READ,
+                    )]),
             );
         }
 
@@ -181,15 +192,10 @@ impl<T: marpii::bytemuck::Pod> Task for DynamicBuffer<T> {
             device.inner.cmd_copy_buffer2(
                 *command_buffer,
                 &vk::CopyBufferInfo2::default()
-                    .src_buffer(self.cpu_local.inner)
+                    .src_buffer(allocation.buffer.inner)
                     .dst_buffer(dst_access.buffer.inner)
-                    .regions(&[vk::BufferCopy2::default()
-                        .src_offset(0)
-                        .dst_offset(0)
-                        .size(copy_size)]),
+                    .regions(&[allocation.as_buffer_copy(range.start as u64)]),
             );
         }
-
-        self.has_changed = false;
     }
 }
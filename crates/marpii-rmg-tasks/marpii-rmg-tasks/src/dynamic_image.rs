@@ -1,10 +1,11 @@
 use marpii::{
     DeviceError, MarpiiError,
+    allocator::RingAllocation,
     ash::vk,
-    resources::{Buffer, ImgDesc},
-    util::ImageRegion,
+    resources::ImgDesc,
+    util::{FormatInfo, ImageRegion},
 };
-use marpii_rmg::{ImageHandle, Rmg, RmgError, Task};
+use marpii_rmg::{CtxRmg, ImageHandle, RecordError, Resources, Rmg, RmgError, Task};
 use smallvec::SmallVec;
 use std::sync::Arc;
 
@@ -12,14 +13,16 @@ use crate::RmgTaskError;
 
 pub struct DynImgCmd {
     region: ImageRegion,
-    buffer: Arc<Buffer>,
+    ///Written bytes, until they are staged.
+    bytes: Vec<u8>,
+    alignment: u64,
+    upload: Option<RingAllocation>,
 }
 
 ///Helper task that lets you change a image rapidly between frames.
-/// This work by recording a set of copy commands for the image to buffers
-/// that are then applied whenever the task is executed.
-//TODO - use a ring buffer or something for the buffers.
-//     - allow image to image copy
+/// This work by keeping the written bytes until the task is recorded, staging them on RMG's upload ring,
+/// and recording a set of copy commands that are applied whenever the task is executed.
+//TODO - allow image to image copy
 pub struct DynamicImage {
     staging_copies: Vec<DynImgCmd>,
     pub image: ImageHandle,
@@ -48,6 +51,7 @@ impl DynamicImage {
     }
 
     ///Schedules write of `bytes` to the `region` of the image. All writes are executed in the order they are submitted to the task.
+    /// The bytes are staged on RMG's upload ring once the task is recorded.
     ///
     ///
     /// The `region` is clamped to the actual
@@ -58,20 +62,25 @@ impl DynamicImage {
     /// parts of `region` might become undefined.
     pub fn write_bytes(
         &mut self,
-        rmg: &mut Rmg,
+        _rmg: &mut Rmg,
         region: ImageRegion,
         bytes: &[u8],
     ) -> Result<(), RmgError> {
-        let buffer = Buffer::new_staging_for_data(
-            &rmg.ctx.device,
-            &rmg.ctx.allocator,
-            Some("DynamicImageSrcBuffer"),
-            bytes,
-        )
-        .map_err(MarpiiError::from)?;
+        //Copies need to be aligned to the texel block size, depth/stencil copies to 4 byte.
+        let alignment = FormatInfo::of(*self.image.format())
+            .map(|info| {
+                if info.is_depth() || info.is_stencil() {
+                    4
+                } else {
+                    u64::from(info.block_size)
+                }
+            })
+            .unwrap_or(4);
         self.staging_copies.push(DynImgCmd {
             region,
-            buffer: Arc::new(buffer),
+            bytes: bytes.to_vec(),
+            alignment,
+            upload: None,
         });
         Ok(())
     }
@@ -84,6 +93,19 @@ impl Task for DynamicImage {
     fn queue_flags(&self) -> vk::QueueFlags {
         vk::QueueFlags::TRANSFER
     }
+    fn pre_record(&mut self, resources: &mut Resources, ctx: &CtxRmg) -> Result<(), RecordError> {
+        //Stage at recording time, so ring ranges are only occupied until this execution finished.
+        for cp in self
+            .staging_copies
+            .iter_mut()
+            .filter(|cp| cp.upload.is_none())
+        {
+            cp.upload = Some(resources.upload_data(ctx, &cp.bytes, cp.alignment)?);
+            cp.bytes = Vec::new();
+        }
+        Ok(())
+    }
+
     fn register(&self, registry: &mut marpii_rmg::ResourceRegistry) {
         registry
             .request_image(
@@ -93,8 +115,12 @@ impl Task for DynamicImage {
                 vk::ImageLayout::TRANSFER_DST_OPTIMAL,
             )
             .unwrap();
-        for cp in self.staging_copies.iter() {
-            registry.register_asset(cp.buffer.clone());
+        for upload in self
+            .staging_copies
+            .iter()
+            .filter_map(|cp| cp.upload.as_ref())
+        {
+            registry.request_upload(upload);
         }
     }
 
@@ -106,21 +132,25 @@ impl Task for DynamicImage {
     ) {
         let image_access = resources.get_image_state(&self.image);
 
-        let staging = core::mem::take(&mut self.staging_copies);
+        //Only copies that were staged while recording are executed.
+        let (staging, pending): (Vec<_>, Vec<_>) = core::mem::take(&mut self.staging_copies)
+            .into_iter()
+            .partition(|cp| cp.upload.is_some());
+        self.staging_copies = pending;
+        let staging = staging
+            .into_iter()
+            .filter_map(|cp| cp.upload.map(|upload| (cp.region, upload)))
+            .collect::<Vec<_>>();
 
         //Move the src buffers to the correct layout state. Those are (not yet)
         // handled by RMG, so we have to do that by hand here.
         let buffer_barriers: SmallVec<[_; 4]> = staging
             .iter()
-            .map(|staging| {
-                vk::BufferMemoryBarrier2::default()
-                    .buffer(staging.buffer.inner)
-                    .offset(0)
-                    .size(vk::WHOLE_SIZE)
-                    .src_access_mask(vk::AccessFlags2::HOST_WRITE)
-                    .src_stage_mask(vk::PipelineStageFlags2::HOST)
-                    .dst_access_mask(vk::AccessFlags2::TRANSFER_READ)
-                    .dst_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+            .map(|(_, upload)| {
+                upload.host_write_barrier(
+                    vk::PipelineStageFlags2::TRANSFER,
+                    vk::AccessFlags2::TRANSFER_READ,
+                )
             })
             .collect();
 
@@ -131,19 +161,19 @@ impl Task for DynamicImage {
             );
         }
 
-        for cp in staging {
+        for (region, upload) in staging {
             let copy_cmd = vk::BufferImageCopy2::default()
                 .buffer_image_height(0)
-                .buffer_offset(0)
+                .buffer_offset(upload.offset)
                 .buffer_row_length(0)
-                .image_extent(cp.region.extent)
-                .image_offset(cp.region.offset)
+                .image_extent(region.extent)
+                .image_offset(region.offset)
                 .image_subresource(image_access.image.subresource_layers_all());
             unsafe {
                 device.inner.cmd_copy_buffer_to_image2(
                     *command_buffer,
                     &vk::CopyBufferToImageInfo2::default()
-                        .src_buffer(cp.buffer.inner)
+                        .src_buffer(upload.buffer.inner)
                         .regions(&[copy_cmd])
                         .dst_image(image_access.image.inner)
                         .dst_image_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL),
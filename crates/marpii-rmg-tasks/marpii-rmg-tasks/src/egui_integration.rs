@@ -15,6 +15,7 @@ use egui_winit::winit::window::Window;
 use marpii::MarpiiError;
 use marpii::ash::vk::{ImageUsageFlags, Rect2D};
 use marpii::resources::SharingMode;
+use marpii::swapchain::TransferFunction;
 use marpii::util::ImageRegion;
 use marpii::{
     OoS,
@@ -131,11 +132,20 @@ impl EGuiWinitIntegration {
     ///Sets a gamma value for the output.
     ///
     /// Should be 1.0 if you are using the pass in a linear-context (before tone mapping and gamma correction).
-    /// Otherwise it should be 2.2.
+    /// Otherwise it should be 2.2. Use [set_output_transfer](Self::set_output_transfer) to derive it from the swapchain.
     pub fn set_gamma(&mut self, gamma: f32) {
         self.task.renderer.gamma = gamma;
     }
 
+    ///See [EGuiTask::set_output_transfer].
+    pub fn set_output_transfer(
+        &mut self,
+        transfer: TransferFunction,
+        format: vk::Format,
+    ) -> Result<(), RmgTaskError> {
+        self.task.set_output_transfer(transfer, format)
+    }
+
     ///runs `run_ui` on this context. Use the closure to encode the next "to be rendered" egui.
     pub fn run(
         &mut self,
@@ -411,6 +421,12 @@ impl EGuiTask {
     ///Default vertex buffer size (in vertices).
     pub const DEFAULT_BUF_SIZE: usize = 1024;
     pub const MAX_TEXTURE_SIDE: u32 = 2048;
+    ///Color spaces the pass can write output for. The shader can only apply the sRGB encoding, therefore HDR color spaces
+    /// are not supported. Use them for [SwapchainPresent::new_with_color_spaces](crate::SwapchainPresent::new_with_color_spaces).
+    pub const COLOR_SPACES: &'static [vk::ColorSpaceKHR] = &[
+        vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+    ];
     pub fn texture_atlas(&self) -> Values<'_, TextureId, DynamicImage> {
         self.data.atlas.values()
     }
@@ -913,6 +929,31 @@ impl EGuiTask {
         Ok(())
     }
 
+    ///Sets the output transform for an image that is finally presented in `format` with the `transfer` function. For instance
+    /// `set_output_transfer(swapchain.transfer_function(), swapchain.format())`.
+    ///
+    /// Egui's colors are sRGB encoded. They are kept if the sRGB encoding is not applied in hardware, and linearized otherwise.
+    /// HDR transfer functions can not be encoded by the pass and return an error, see [COLOR_SPACES](Self::COLOR_SPACES).
+    pub fn set_output_transfer(
+        &mut self,
+        transfer: TransferFunction,
+        format: vk::Format,
+    ) -> Result<(), RmgTaskError> {
+        if transfer.is_hdr() {
+            return Err(MarpiiError::Other(format!(
+                "EGui can not encode output for the {:?} transfer function",
+                transfer
+            ))
+            .into());
+        }
+        self.renderer.gamma = if transfer.needs_srgb_encoding(format) {
+            1.0
+        } else {
+            2.2
+        };
+        Ok(())
+    }
+
     ///The image the egui output is written to.
     pub fn target_image(&self) -> &ImageHandle {
         &self.renderer.target_image
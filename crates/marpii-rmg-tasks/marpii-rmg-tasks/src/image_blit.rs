@@ -1,5 +1,5 @@
 use marpii::{ash::vk, util::ImageRegion};
-use marpii_rmg::{ImageHandle, Task};
+use marpii_rmg::{CommandEncoder, ImageHandle, Task};
 
 ///Blits `N` a regions of one image to another. Always blits all subresource layers.
 pub struct ImageBlit<const N: usize> {
@@ -64,42 +64,31 @@ impl<const N: usize> Task for ImageBlit<N> {
             .unwrap();
     }
 
-    fn record(
-        &mut self,
-        device: &std::sync::Arc<marpii::context::Device>,
-        command_buffer: &marpii::ash::vk::CommandBuffer,
-        resources: &marpii_rmg::Resources,
-    ) {
-        let src_image = resources.get_image_state(&self.src);
-        let dst_image = resources.get_image_state(&self.dst);
+    fn encode(&mut self, encoder: &mut CommandEncoder<'_>) {
+        let src_subresource = encoder
+            .resources()
+            .get_image_state(&self.src)
+            .image
+            .subresource_layers_all();
+        let dst_subresource = encoder
+            .resources()
+            .get_image_state(&self.dst)
+            .image
+            .subresource_layers_all();
 
-        let mut regions = [vk::ImageBlit2::default(); N];
-        let src_subresource = src_image.image.subresource_layers_all();
-        let dst_subresource = dst_image.image.subresource_layers_all();
+        let mut regions = [vk::ImageBlit::default(); N];
         for (idx, blit) in self.blits.iter_mut().enumerate() {
             //blit.0.clamp_to(&blit.1);
             //blit.1.clamp_to(&blit.0);
 
-            regions[idx] = vk::ImageBlit2::default()
+            regions[idx] = vk::ImageBlit::default()
                 .src_offsets(blit.0.to_blit_offsets())
                 .dst_offsets(blit.1.to_blit_offsets())
                 .src_subresource(src_subresource)
                 .dst_subresource(dst_subresource);
         }
 
-        let blit_image_info = vk::BlitImageInfo2::default()
-            .src_image(src_image.image.inner)
-            .src_image_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
-            .dst_image(dst_image.image.inner)
-            .dst_image_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
-            .filter(self.filter)
-            .regions(&regions);
-
-        unsafe {
-            device
-                .inner
-                .cmd_blit_image2(*command_buffer, &blit_image_info)
-        }
+        encoder.blit(&self.src, &self.dst, &regions, self.filter);
     }
 
     fn queue_flags(&self) -> vk::QueueFlags {
@@ -15,7 +15,11 @@ use thiserror::Error;
 mod dynamic_buffer;
 pub use dynamic_buffer::DynamicBuffer;
 mod swapchain_present;
-pub use swapchain_present::SwapchainPresent;
+pub use swapchain_present::{PresentTarget, SwapchainPresent};
+mod multi_swapchain_present;
+pub use multi_swapchain_present::MultiSwapchainPresent;
+mod virtual_swapchain;
+pub use virtual_swapchain::{FrameSink, VirtualFrame, VirtualSwapchain, VirtualSwapchainError};
 mod upload_buffer;
 pub use upload_buffer::UploadBuffer;
 mod upload_image;
@@ -0,0 +1,167 @@
+use marpii::{ash::vk, swapchain::Swapchain};
+use marpii_rmg::{ImageHandle, RecordError, Task};
+use std::sync::Arc;
+
+use crate::{RmgTaskError, SwapchainPresent};
+
+///Presents several surfaces, for instance all windows of an application, from one task graph.
+///
+/// Each surface is handled by its own [SwapchainPresent], identified by a key `K`. All surfaces that got an image
+/// [pushed](Self::push_image) are blitted in the same task, and presented with a single present call after the graph was executed.
+/// Surfaces without a pushed image are skipped.
+pub struct MultiSwapchainPresent<K: PartialEq = usize> {
+    surfaces: Vec<(K, SwapchainPresent)>,
+}
+
+impl<K: PartialEq> Default for MultiSwapchainPresent<K> {
+    fn default() -> Self {
+        MultiSwapchainPresent {
+            surfaces: Vec::new(),
+        }
+    }
+}
+
+impl<K: PartialEq> MultiSwapchainPresent<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///Adds the surface presented by `present` for `key`. Returns the surface that was registered for `key` before, if any.
+    pub fn insert(&mut self, key: K, present: SwapchainPresent) -> Option<SwapchainPresent> {
+        if let Some(old) = self.get_mut(&key) {
+            Some(std::mem::replace(old, present))
+        } else {
+            self.surfaces.push((key, present));
+            None
+        }
+    }
+
+    ///Removes the surface of `key`, for instance when its window was closed.
+    ///
+    /// Note that the swapchain might still be in use by the GPU. Keep it alive until the next frame finished, or wait for the device to be idle.
+    pub fn remove(&mut self, key: &K) -> Option<SwapchainPresent> {
+        let idx = self.surfaces.iter().position(|(k, _)| k == key)?;
+        Some(self.surfaces.remove(idx).1)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&SwapchainPresent> {
+        self.surfaces
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, present)| present)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut SwapchainPresent> {
+        self.surfaces
+            .iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, present)| present)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &SwapchainPresent)> {
+        self.surfaces.iter().map(|(k, present)| (k, present))
+    }
+
+    pub fn len(&self) -> usize {
+        self.surfaces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.surfaces.is_empty()
+    }
+
+    ///Pushes `image` to be presented on the surface of `key` whenever the task is scheduled next. Returns false if there is
+    /// no such surface. See [SwapchainPresent::push_image].
+    pub fn push_image(&mut self, key: &K, image: ImageHandle, extent: vk::Extent2D) -> bool {
+        if let Some(present) = self.get_mut(key) {
+            present.push_image(image, extent);
+            true
+        } else {
+            false
+        }
+    }
+
+    ///Resizes the surface of `key`, for instance after its window was resized. Does nothing if there is no such surface.
+    pub fn resize(&mut self, key: &K, extent: vk::Extent2D) -> Result<(), RmgTaskError> {
+        if let Some(present) = self.get_mut(key) {
+            present.recreate(extent)?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: PartialEq> Task for MultiSwapchainPresent<K> {
+    fn name(&self) -> &'static str {
+        "MultiSwapchainPresent"
+    }
+
+    fn pre_record(
+        &mut self,
+        resources: &mut marpii_rmg::Resources,
+        ctx: &marpii_rmg::CtxRmg,
+    ) -> Result<(), RecordError> {
+        for (_key, present) in self.surfaces.iter_mut() {
+            if present.is_scheduled() {
+                present.pre_record(resources, ctx)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn post_execution(
+        &mut self,
+        _resources: &mut marpii_rmg::Resources,
+        _ctx: &marpii_rmg::CtxRmg,
+    ) -> Result<(), RecordError> {
+        let in_flight = self
+            .surfaces
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(idx, (_key, present))| present.take_in_flight().map(|img| (idx, img)))
+            .collect::<Vec<_>>();
+        let Some((first, _)) = in_flight.first() else {
+            return Ok(());
+        };
+
+        let device = self.surfaces[*first].1.swapchain().device.clone();
+        let queue = device
+            .first_queue_for_attribute(true, false, false)
+            .unwrap(); //FIXME use track instead
+        let indices = in_flight.iter().map(|(idx, _)| *idx).collect::<Vec<_>>();
+        let results = {
+            let batch = in_flight
+                .into_iter()
+                .map(|(idx, img)| (self.surfaces[idx].1.swapchain(), img))
+                .collect::<Vec<(&Swapchain, _)>>();
+            Swapchain::present_batch(&queue.inner(), batch)
+        };
+        for (idx, result) in indices.into_iter().zip(results) {
+            self.surfaces[idx].1.handle_present_result(result);
+        }
+
+        Ok(())
+    }
+
+    fn register(&self, registry: &mut marpii_rmg::ResourceRegistry) {
+        for (_key, present) in self.surfaces.iter() {
+            present.register(registry);
+        }
+    }
+
+    fn queue_flags(&self) -> vk::QueueFlags {
+        vk::QueueFlags::GRAPHICS
+    }
+
+    fn record(
+        &mut self,
+        device: &Arc<marpii::context::Device>,
+        command_buffer: &vk::CommandBuffer,
+        resources: &marpii_rmg::Resources,
+    ) {
+        for (_key, present) in self.surfaces.iter_mut() {
+            if present.is_paired() {
+                present.record(device, command_buffer, resources);
+            }
+        }
+    }
+}
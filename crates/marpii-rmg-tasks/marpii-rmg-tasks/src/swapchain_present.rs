@@ -1,12 +1,12 @@
 use marpii::{
     MarpiiError, OoS,
-    ash::vk,
+    ash::{prelude::VkResult, vk},
     resources::ImgDesc,
     surface::Surface,
-    swapchain::{Swapchain, SwapchainImage},
+    swapchain::{FrameLimiter, HdrMetadata, Swapchain, SwapchainImage, TransferFunction},
 };
 use marpii_rmg::{ImageHandle, RecordError, ResourceError, Rmg, Task};
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::RmgTaskError;
 
@@ -28,18 +28,50 @@ impl PresentOp {
     }
 }
 
+///Common interface of tasks that present images, like [SwapchainPresent] and the off-screen [VirtualSwapchain](crate::VirtualSwapchain).
+/// Render loops that are generic over it run with and without a window.
+pub trait PresentTarget: Task {
+    ///Pushes `image` to be presented whenever the task is scheduled next.
+    fn push_image(&mut self, image: ImageHandle, extent: vk::Extent2D);
+    ///Returns the current extent of the presented images, if known.
+    fn extent(&self) -> Option<vk::Extent2D>;
+    fn format(&self) -> vk::Format;
+    ///Returns the transfer function the pushed images must be encoded with.
+    fn transfer_function(&self) -> TransferFunction;
+    fn image_desc(&self) -> &ImgDesc;
+    ///Recreates the presented images with `extent`.
+    fn recreate(&mut self, extent: vk::Extent2D) -> Result<(), RmgTaskError>;
+    ///Blocks until the next frame should be recorded. Returns the time that was spent waiting.
+    fn wait_for_frame(&mut self) -> Duration;
+}
+
 ///Task that handles a swapchain as well as present operation. Lets you blit any image to the swapchain.
 pub struct SwapchainPresent {
     swapchain: Swapchain,
     last_known_extent: vk::Extent2D,
     last_was_suboptimal: bool,
     ///the image that is going to be presented next.
-    //TODO: Do we want to implement more fancy double buffering? Usually done by the driver tho.
+    //NOTE: Buffering is left to the driver. The number of swapchain images can be controlled via `set_image_count`.
     next: PresentOp,
+    frame_limiter: Option<FrameLimiter>,
 }
 
 impl SwapchainPresent {
+    ///Creates the swapchain for `surface`, using the highest bit-depth format with sRGB nonlinear color space.
     pub fn new(rmg: &mut Rmg, surface: impl Into<OoS<Surface>>) -> Result<Self, RmgTaskError> {
+        Self::new_with_color_spaces(rmg, surface, &[vk::ColorSpaceKHR::SRGB_NONLINEAR])
+    }
+
+    ///Creates the swapchain for `surface`, using the first supported color space of `color_spaces`. Within that color space the
+    /// format with the highest bit-depth is used. Check [transfer_function](Self::transfer_function) for the chosen output encoding.
+    ///
+    /// Note that the image pushed via [push_image](Self::push_image) is blitted as is. It must already be encoded for the chosen
+    /// transfer function.
+    pub fn new_with_color_spaces(
+        rmg: &mut Rmg,
+        surface: impl Into<OoS<Surface>>,
+        color_spaces: &[vk::ColorSpaceKHR],
+    ) -> Result<Self, RmgTaskError> {
         let surface = surface.into();
         //Check for the creation extent.
         let create_extent = surface
@@ -53,11 +85,22 @@ impl SwapchainPresent {
                 }
             });
 
+        let color_spaces = color_spaces.to_vec();
         let swapchain = Swapchain::builder(&rmg.ctx.device, surface)?
             .with(move |b| {
+                //Use the first supported color space, if none is supported, fall back to whatever is reported first.
+                let color_space = color_spaces
+                    .iter()
+                    .find(|cs| b.format_preference.iter().any(|f| f.color_space == **cs))
+                    .copied()
+                    .unwrap_or(b.format_preference[0].color_space);
                 //try to use the highest bit format format
-                let mut best_format = b.format_preference.remove(0);
-                for next_format in b.format_preference.iter() {
+                let mut candidates = b
+                    .format_preference
+                    .iter()
+                    .filter(|f| f.color_space == color_space);
+                let mut best_format = *candidates.next().unwrap();
+                for next_format in candidates {
                     if marpii::util::byte_per_pixel(&next_format.format).unwrap_or(8)
                         > marpii::util::byte_per_pixel(&best_format.format).unwrap_or(8)
                     {
@@ -65,6 +108,7 @@ impl SwapchainPresent {
                     }
                 }
                 b.format_preference = vec![best_format];
+                b.color_space_preference = vec![color_space];
                 //Flag for color attachment and transfer dst, which are mostly used to interact with the image
                 b.create_info.usage =
                     vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST;
@@ -74,6 +118,13 @@ impl SwapchainPresent {
             .build()
             .map_err(MarpiiError::from)?;
 
+        #[cfg(feature = "logging")]
+        log::info!(
+            "Presenting {:?} with {:?}",
+            swapchain.surface_format(),
+            swapchain.transfer_function()
+        );
+
         Ok(SwapchainPresent {
             swapchain,
             last_known_extent: vk::Extent2D {
@@ -82,6 +133,7 @@ impl SwapchainPresent {
             },
             last_was_suboptimal: false,
             next: PresentOp::None,
+            frame_limiter: None,
         })
     }
 
@@ -109,6 +161,77 @@ impl SwapchainPresent {
         self.swapchain.images[0].desc.format
     }
 
+    ///Returns the swapchain's color space.
+    pub fn color_space(&self) -> vk::ColorSpaceKHR {
+        self.swapchain.surface_format().color_space
+    }
+
+    ///Returns the transfer function the pushed images must be encoded with.
+    pub fn transfer_function(&self) -> TransferFunction {
+        self.swapchain.transfer_function()
+    }
+
+    ///Submits HDR metadata for the swapchain. Fails if `VK_EXT_hdr_metadata` is not enabled, see `Config::hdr_metadata_support`.
+    pub fn set_hdr_metadata(&mut self, metadata: HdrMetadata) -> Result<(), RmgTaskError> {
+        self.swapchain
+            .set_hdr_metadata(metadata)
+            .map_err(MarpiiError::from)?;
+        Ok(())
+    }
+
+    ///Returns the present mode that is currently used.
+    pub fn present_mode(&self) -> vk::PresentModeKHR {
+        self.swapchain.present_mode()
+    }
+
+    ///Switches the present mode at runtime, for instance to toggle vsync. Fails if the surface does not support `present_mode`.
+    pub fn set_present_mode(
+        &mut self,
+        present_mode: vk::PresentModeKHR,
+    ) -> Result<(), RmgTaskError> {
+        self.swapchain
+            .set_present_mode(present_mode)
+            .map_err(MarpiiError::from)?;
+        self.last_was_suboptimal = false;
+        Ok(())
+    }
+
+    ///Sets the minimum number of swapchain images, for instance 2 for double, or 3 for triple buffering. Returns the count
+    /// that is used after clamping to the surface's capabilities.
+    pub fn set_image_count(&mut self, image_count: u32) -> Result<u32, RmgTaskError> {
+        let count = self
+            .swapchain
+            .set_image_count(image_count)
+            .map_err(MarpiiError::from)?;
+        self.last_was_suboptimal = false;
+        Ok(count)
+    }
+
+    ///Sets the limiter that is used by [wait_for_frame](Self::wait_for_frame). `None` disables frame pacing.
+    pub fn set_frame_limiter(&mut self, limiter: Option<FrameLimiter>) {
+        self.frame_limiter = limiter;
+    }
+
+    pub fn frame_limiter_mut(&mut self) -> Option<&mut FrameLimiter> {
+        self.frame_limiter.as_mut()
+    }
+
+    ///Blocks until the next frame should be recorded, based on the [frame limiter](Self::set_frame_limiter) and the
+    /// swapchain's present progress. Call this before recording the frame's tasks. Returns the time that was spent waiting,
+    /// which is zero if no limiter is set.
+    pub fn wait_for_frame(&mut self) -> Duration {
+        if let Some(limiter) = &mut self.frame_limiter {
+            limiter.wait(&self.swapchain)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    ///Returns the underlying swapchain, for instance to query [present timings](Swapchain::past_presentation_timing).
+    pub fn swapchain(&self) -> &Swapchain {
+        &self.swapchain
+    }
+
     ///Returns the description all current swapchain images are created with.
     pub fn image_desc(&self) -> &ImgDesc {
         &self.swapchain.images[0].desc
@@ -155,14 +278,62 @@ impl SwapchainPresent {
             .device
             .first_queue_for_attribute(true, false, false)
             .unwrap(); //FIXME use track instead
+        let result = self.swapchain.present_image(image, &queue.inner());
+        self.handle_present_result(result);
+    }
+
+    ///Flags the swapchain for recreation if the present failed.
+    pub(crate) fn handle_present_result(&mut self, result: VkResult<()>) {
         #[allow(unused_variables)]
-        if let Err(e) = self.swapchain.present_image(image, &queue.inner()) {
+        if let Err(e) = result {
             #[cfg(feature = "logging")]
             log::error!("present failed with: {}, recreating swapchain", e);
+            self.last_was_suboptimal = true;
+        }
+    }
+
+    pub(crate) fn is_scheduled(&self) -> bool {
+        matches!(self.next, PresentOp::Scheduled(_))
+    }
+
+    pub(crate) fn is_paired(&self) -> bool {
+        matches!(self.next, PresentOp::Paired { .. })
+    }
+
+    ///Takes the image that was recorded, and has to be presented next.
+    pub(crate) fn take_in_flight(&mut self) -> Option<SwapchainImage> {
+        if let PresentOp::InFilght(swimg) = self.next.take() {
+            Some(swimg)
+        } else {
+            None
         }
     }
 }
 
+impl PresentTarget for SwapchainPresent {
+    fn push_image(&mut self, image: ImageHandle, extent: vk::Extent2D) {
+        SwapchainPresent::push_image(self, image, extent)
+    }
+    fn extent(&self) -> Option<vk::Extent2D> {
+        SwapchainPresent::extent(self)
+    }
+    fn format(&self) -> vk::Format {
+        SwapchainPresent::format(self)
+    }
+    fn transfer_function(&self) -> TransferFunction {
+        SwapchainPresent::transfer_function(self)
+    }
+    fn image_desc(&self) -> &ImgDesc {
+        SwapchainPresent::image_desc(self)
+    }
+    fn recreate(&mut self, extent: vk::Extent2D) -> Result<(), RmgTaskError> {
+        SwapchainPresent::recreate(self, extent)
+    }
+    fn wait_for_frame(&mut self) -> Duration {
+        SwapchainPresent::wait_for_frame(self)
+    }
+}
+
 impl Task for SwapchainPresent {
     fn name(&self) -> &'static str {
         "SwapchainPresent"
@@ -194,10 +365,8 @@ impl Task for SwapchainPresent {
         _ctx: &marpii_rmg::CtxRmg,
     ) -> Result<(), RecordError> {
         //schedule image for present if there is any
-        if let PresentOp::InFilght(swimg) = self.next.take() {
+        if let Some(swimg) = self.take_in_flight() {
             self.present_image(swimg);
-            //reset state
-            self.next = PresentOp::None;
         } else {
             #[cfg(feature = "logging")]
             log::warn!("Swapchain queued, but no image infilght after execution.");
@@ -1,18 +1,18 @@
-use marpii::{
-    ash::vk,
-    resources::{BufDesc, Buffer, BufferMapError},
-    MarpiiError,
+use marpii::{allocator::RingAllocation, ash::vk, resources::BufDesc};
+use marpii_rmg::{
+    BufferHandle, CtxRmg, RecordError, ResourceRegistry, Resources, Rmg, RmgError, Task,
 };
-use marpii_rmg::{BufferHandle, ResourceRegistry, Resources, Rmg, RmgError, Task};
 use std::sync::Arc;
 
 ///Uploads a number of elements of type `T`.
 ///
 /// A fitting buffer (`self.buffer`) is created. Note that the buffer is uninitialised
-/// until the task is scheduled.
+/// until the task is scheduled. A CPU side copy of the data is kept, which is staged on RMG's upload ring
+/// whenever the task is recorded. So each execution uploads the data again.
 pub struct UploadBuffer<T: marpii::bytemuck::Pod> {
     pub buffer: BufferHandle<T>,
-    src_buffer: BufferHandle<T>,
+    data: Vec<T>,
+    upload: Option<RingAllocation>,
 }
 
 impl<T: marpii::bytemuck::Pod> UploadBuffer<T> {
@@ -26,39 +26,19 @@ impl<T: marpii::bytemuck::Pod> UploadBuffer<T> {
         )
     }
 
-    pub fn new_with_buffer(
-        rmg: &mut Rmg,
-        data: &[T],
-        mut desc: BufDesc,
-    ) -> Result<Self, RmgError> {
-        let staging = Buffer::new_staging_for_data(
-            &rmg.ctx.device,
-            &rmg.ctx.allocator,
-            Some("Staging buffer upload"),
-            data,
-        )
-        .map_err(MarpiiError::from)?;
-
-        staging
-            .flush_range()
-            .map_err(|#[allow(unused_variables)] e| {
-                #[cfg(feature = "logging")]
-                log::error!("Flushing upload buffer failed: {}", e);
-                MarpiiError::from(BufferMapError::FailedToFlush)
-            })?;
-
+    pub fn new_with_buffer(rmg: &mut Rmg, data: &[T], mut desc: BufDesc) -> Result<Self, RmgError> {
         if !desc.usage.contains(vk::BufferUsageFlags::TRANSFER_DST) {
             #[cfg(feature = "logging")]
             log::warn!("Upload buffer had TRANSEFER_DST not set, adding to usage...");
             desc.usage |= vk::BufferUsageFlags::TRANSFER_DST;
         }
 
-        let staging = rmg.import_buffer(Arc::new(staging), None, None)?;
         let dst_buffer = rmg.new_buffer_uninitialized(desc, None)?;
 
         Ok(UploadBuffer {
             buffer: dst_buffer,
-            src_buffer: staging,
+            data: data.to_vec(),
+            upload: None,
         })
     }
 }
@@ -68,7 +48,16 @@ impl<T: marpii::bytemuck::Pod> Task for UploadBuffer<T> {
         "BufferUpload"
     }
 
+    fn pre_record(&mut self, resources: &mut Resources, ctx: &CtxRmg) -> Result<(), RecordError> {
+        //Stage at recording time, so the ring range is only occupied until this execution finished.
+        self.upload = Some(resources.upload_data(ctx, &self.data, 4)?);
+        Ok(())
+    }
+
     fn register(&self, registry: &mut ResourceRegistry) {
+        let Some(upload) = &self.upload else {
+            return;
+        };
         registry
             .request_buffer(
                 &self.buffer,
@@ -76,13 +65,7 @@ impl<T: marpii::bytemuck::Pod> Task for UploadBuffer<T> {
                 vk::AccessFlags2::TRANSFER_WRITE,
             )
             .unwrap();
-        registry
-            .request_buffer(
-                &self.src_buffer,
-                vk::PipelineStageFlags2::TRANSFER,
-                vk::AccessFlags2::TRANSFER_READ,
-            )
-            .unwrap();
+        registry.request_upload(upload);
     }
 
     fn queue_flags(&self) -> marpii::ash::vk::QueueFlags {
@@ -95,23 +78,29 @@ impl<T: marpii::bytemuck::Pod> Task for UploadBuffer<T> {
         command_buffer: &vk::CommandBuffer,
         resources: &Resources,
     ) {
-        //NOTE: buffer barrier is done by scheduler
-
-        let src_access = resources.get_buffer_state(&self.src_buffer);
+        let Some(upload) = self.upload.take() else {
+            return;
+        };
+        //NOTE: dst buffer barrier is done by scheduler, the upload's host barrier is not.
         let dst_access = resources.get_buffer_state(&self.buffer);
 
-        let copy_size = src_access.buffer.desc.size.min(dst_access.buffer.desc.size);
+        let copy_size = upload.size.min(dst_access.buffer.desc.size);
+        let host_barrier = upload.host_write_barrier(
+            vk::PipelineStageFlags2::TRANSFER,
+            vk::AccessFlags2::TRANSFER_READ,
+        );
 
         unsafe {
+            device.inner.cmd_pipeline_barrier2(
+                *command_buffer,
+                &vk::DependencyInfo::default().buffer_memory_barriers(&[host_barrier]),
+            );
             device.inner.cmd_copy_buffer2(
                 *command_buffer,
                 &vk::CopyBufferInfo2::default()
-                    .src_buffer(src_access.buffer.inner)
+                    .src_buffer(upload.buffer.inner)
                     .dst_buffer(dst_access.buffer.inner)
-                    .regions(&[vk::BufferCopy2::default()
-                        .src_offset(0)
-                        .dst_offset(0)
-                        .size(copy_size)]),
+                    .regions(&[upload.as_buffer_copy(0).size(copy_size)]),
             );
         }
     }
@@ -1,10 +1,13 @@
 use marpii::{
+    allocator::RingAllocation,
     ash::vk,
-    resources::{Buffer, BufferMapError, Image, ImgDesc},
+    resources::{Image, ImgDesc},
+    util::{FormatInfo, mip_extent},
     MarpiiError, OoS,
 };
-use marpii_rmg::{BufferHandle, ImageHandle, ResourceRegistry, Resources, Rmg, RmgError, Task};
-use std::sync::Arc;
+use marpii_rmg::{
+    CtxRmg, ImageHandle, RecordError, ResourceRegistry, Resources, Rmg, RmgError, Task,
+};
 
 use crate::RmgTaskError;
 
@@ -20,15 +23,49 @@ pub struct MipOffset {
     pub layer_count: u32,
 }
 
+impl MipOffset {
+    ///Returns the offsets of mip levels `1..mip_levels` of data that contains all mip levels tightly packed, starting with the base
+    /// level of `extent` at offset 0. Each level contains `layer_count` layers. The sizes are calculated via [FormatInfo], so this works
+    /// for block compressed formats as well.
+    ///
+    /// Returns `None` if `format` is unknown.
+    pub fn tightly_packed(
+        format: vk::Format,
+        extent: vk::Extent3D,
+        mip_levels: u32,
+        layer_count: u32,
+    ) -> Option<Vec<Self>> {
+        let info = FormatInfo::of(format)?;
+        let mut offset = 0;
+        let mut mips = Vec::with_capacity(mip_levels.saturating_sub(1) as usize);
+        for mip_level in 0..mip_levels {
+            let mip_extent = mip_extent(extent, mip_level);
+            if mip_level > 0 {
+                mips.push(MipOffset {
+                    mip_level,
+                    offset,
+                    extent: mip_extent,
+                    layer_count,
+                });
+            }
+            offset += info.region_size(mip_extent) * u64::from(layer_count);
+        }
+        Some(mips)
+    }
+}
+
 ///Transfer pass that copies data to an image on the GPU.
 /// perfect if you need to initialise textures for instance.
 /// Note that this only works reliable for 2D and 3D images.
 /// Does not work for cubemaps!
+///
+/// A CPU side copy of the data is kept, which is staged on RMG's upload ring whenever the task is recorded.
 pub struct UploadImage {
     ///The GPU-Local image, which will contain `new`'s `data` after this pass was submitted.
     pub image: ImageHandle,
     pub mip_maps: Option<Vec<MipOffset>>,
-    upload: BufferHandle<u8>,
+    data: Vec<u8>,
+    upload: Option<RingAllocation>,
 }
 
 impl UploadImage {
@@ -40,25 +77,18 @@ impl UploadImage {
         image: impl Into<OoS<Image>>,
     ) -> Result<Self, RmgTaskError> {
         let image = image.into();
-        let staging = Buffer::new_staging_for_data(
-            &rmg.ctx.device,
-            &rmg.ctx.allocator,
-            Some("StagingBuffer"),
-            data,
-        )
-        .map_err(MarpiiError::from)?;
-
-        staging
-            .flush_range()
-            .map_err(|#[allow(unused_variables)] e| {
-                #[cfg(feature = "logging")]
-                log::error!("Flushing upload image failed: {}", e);
-                MarpiiError::from(BufferMapError::FailedToFlush)
-            })?;
-        let staging = rmg
-            .import_buffer(Arc::new(staging), None, None)
-            .map_err(RmgError::from)?;
-
+        #[cfg(feature = "logging")]
+        if let Some(info) = FormatInfo::of(image.desc.format) {
+            let base_size =
+                info.region_size(image.desc.extent) * u64::from(image.desc.img_type.layer_count());
+            if (data.len() as u64) < base_size {
+                log::warn!(
+                    "Upload data has {} bytes, but the base level of the image needs {}",
+                    data.len(),
+                    base_size
+                );
+            }
+        }
         //register image in rmg
         let image = rmg
             .resources
@@ -68,7 +98,8 @@ impl UploadImage {
         Ok(UploadImage {
             image,
             mip_maps: None,
-            upload: staging,
+            data: data.to_vec(),
+            upload: None,
         })
     }
 
@@ -111,7 +142,25 @@ impl Task for UploadImage {
     fn queue_flags(&self) -> vk::QueueFlags {
         vk::QueueFlags::TRANSFER
     }
+    fn pre_record(&mut self, resources: &mut Resources, ctx: &CtxRmg) -> Result<(), RecordError> {
+        //Copies need to be aligned to the texel block size, depth/stencil copies to 4 byte.
+        let alignment = FormatInfo::of(*self.image.format())
+            .map(|info| {
+                if info.is_depth() || info.is_stencil() {
+                    4
+                } else {
+                    u64::from(info.block_size)
+                }
+            })
+            .unwrap_or(4);
+        //Stage at recording time, so the ring range is only occupied until this execution finished.
+        self.upload = Some(resources.upload_data(ctx, &self.data, alignment)?);
+        Ok(())
+    }
     fn register(&self, registry: &mut ResourceRegistry) {
+        let Some(upload) = &self.upload else {
+            return;
+        };
         registry
             .request_image(
                 &self.image,
@@ -120,13 +169,7 @@ impl Task for UploadImage {
                 vk::ImageLayout::TRANSFER_DST_OPTIMAL,
             )
             .unwrap();
-        registry
-            .request_buffer(
-                &self.upload,
-                vk::PipelineStageFlags2::TRANSFER,
-                vk::AccessFlags2::TRANSFER_READ,
-            )
-            .unwrap();
+        registry.request_upload(upload);
     }
     fn record(
         &mut self,
@@ -134,7 +177,10 @@ impl Task for UploadImage {
         command_buffer: &vk::CommandBuffer,
         resources: &Resources,
     ) {
-        let buffer = resources.get_buffer_state(&self.upload);
+        let Some(upload) = self.upload.take() else {
+            return;
+        };
+        //NOTE: the image barrier is done by scheduler, the upload's host barrier is not.
         let img = resources.get_image_state(&self.image);
 
         let mut copies =
@@ -143,7 +189,7 @@ impl Task for UploadImage {
         //This is the first copy, it will just copy 0..image_memory_size to the first mip.
         copies.push(
             vk::BufferImageCopy2::default()
-                .buffer_offset(0)
+                .buffer_offset(upload.offset)
                 .buffer_row_length(0)
                 .buffer_image_height(0)
                 .image_extent(img.image.desc.extent)
@@ -151,8 +197,6 @@ impl Task for UploadImage {
                 .image_subresource(img.image.subresource_layers_all()),
         );
 
-        let mut offset = 0;
-
         //If we have mip copies, copy those as well.
         if let Some(mips) = &self.mip_maps {
             for mip in mips {
@@ -162,23 +206,31 @@ impl Task for UploadImage {
                 subres.layer_count = mip.layer_count;
                 copies.push(
                     vk::BufferImageCopy2::default()
-                        .buffer_offset(offset)
+                        .buffer_offset(upload.offset + mip.offset)
                         .buffer_row_length(0)
                         .buffer_image_height(0)
                         .image_extent(mip.extent)
                         .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
                         .image_subresource(subres),
                 );
-                offset += mip.offset * u64::from(mip.layer_count);
             }
         }
 
+        let host_barrier = upload.host_write_barrier(
+            vk::PipelineStageFlags2::TRANSFER,
+            vk::AccessFlags2::TRANSFER_READ,
+        );
+
         //Finally execute copies for all defined regions
         unsafe {
+            device.inner.cmd_pipeline_barrier2(
+                *command_buffer,
+                &vk::DependencyInfo::default().buffer_memory_barriers(&[host_barrier]),
+            );
             device.inner.cmd_copy_buffer_to_image2(
                 *command_buffer,
                 &vk::CopyBufferToImageInfo2::default()
-                    .src_buffer(buffer.buffer.inner)
+                    .src_buffer(upload.buffer.inner)
                     .dst_image(img.image.inner)
                     .regions(&copies)
                     .dst_image_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL),
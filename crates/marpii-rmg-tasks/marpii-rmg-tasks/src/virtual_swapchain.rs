@@ -0,0 +1,673 @@
+use marpii::{
+    DeviceError, MarpiiError,
+    allocator::MemoryUsage,
+    ash::vk,
+    context::Device,
+    gpu_allocator::vulkan::Allocator,
+    resources::{BufDesc, Buffer, Image, ImgDesc},
+    swapchain::TransferFunction,
+    sync::{BinarySemaphore, Semaphore},
+    util::FormatInfo,
+};
+use marpii_rmg::{ImageHandle, RecordError, ResourceError, Rmg, Task};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+use crate::{PresentTarget, RmgTaskError, TaskError};
+
+#[derive(Error, Debug)]
+pub enum VirtualSwapchainError {
+    #[error("Format {0:?} can not be read back")]
+    UnsupportedFormat(vk::Format),
+    #[error("Format {0:?} can not be converted to RGBA8")]
+    NotRgba8(vk::Format),
+    #[error("Virtual swapchain needs at least one image")]
+    NoImages,
+    #[error("No queue can execute the virtual swapchain")]
+    NoQueue,
+    #[error("Failed to write frame: {0}")]
+    Write(String),
+}
+
+///A frame that was presented to a [VirtualSwapchain] and read back to the CPU.
+#[derive(Clone, Debug)]
+pub struct VirtualFrame {
+    ///Index of the frame, starting at 0 for the first presented frame.
+    pub index: u64,
+    pub extent: vk::Extent2D,
+    pub format: vk::Format,
+    ///Tightly packed texel data of the frame.
+    pub data: Vec<u8>,
+}
+
+impl VirtualFrame {
+    ///Returns the frame as tightly packed RGBA8 texels. Only 8-bit RGBA and BGRA formats can be converted.
+    pub fn to_rgba8(&self) -> Result<Vec<u8>, VirtualSwapchainError> {
+        match self.format {
+            vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => Ok(self.data.clone()),
+            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => Ok(self
+                .data
+                .chunks_exact(4)
+                .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+                .collect()),
+            other => Err(VirtualSwapchainError::NotRgba8(other)),
+        }
+    }
+
+    ///Writes the frame to a PNG file at `path`. See [to_rgba8](Self::to_rgba8) for the supported formats.
+    #[cfg(feature = "png-output")]
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> Result<(), VirtualSwapchainError> {
+        image::save_buffer(
+            path,
+            &self.to_rgba8()?,
+            self.extent.width,
+            self.extent.height,
+            image::ExtendedColorType::Rgba8,
+        )
+        .map_err(|e| VirtualSwapchainError::Write(e.to_string()))
+    }
+}
+
+///Decides what happens with frames that are presented to a [VirtualSwapchain].
+pub enum FrameSink {
+    ///Only keeps the latest frame, see [VirtualSwapchain::latest_frame].
+    Latest,
+    ///Calls the closure for each presented frame. The latest frame is kept as well.
+    Callback(Box<dyn FnMut(&VirtualFrame) + Send>),
+    ///Writes each frame as `frame_<index>.png` into the directory.
+    #[cfg(feature = "png-output")]
+    PngSequence(std::path::PathBuf),
+}
+
+impl FrameSink {
+    fn deliver(&mut self, frame: &VirtualFrame) {
+        match self {
+            FrameSink::Latest => {}
+            FrameSink::Callback(f) => f(frame),
+            #[cfg(feature = "png-output")]
+            FrameSink::PngSequence(dir) => {
+                #[allow(unused_variables)]
+                if let Err(e) = frame.save_png(dir.join(format!("frame_{:06}.png", frame.index))) {
+                    #[cfg(feature = "logging")]
+                    log::error!("Failed to write frame {}: {}", frame.index, e);
+                }
+            }
+        }
+    }
+}
+
+struct VirtualImage {
+    image: Arc<Image>,
+    readback: Arc<Buffer>,
+    sem_present: Arc<BinarySemaphore>,
+    ///Index of the frame that was last presented from this image, and not yet read back.
+    pending: Option<u64>,
+}
+
+enum PresentOp {
+    None,
+    Scheduled(ImageHandle),
+    Paired { src_image: ImageHandle, slot: usize },
+    InFlight(usize),
+}
+
+impl PresentOp {
+    fn take(&mut self) -> PresentOp {
+        std::mem::replace(self, PresentOp::None)
+    }
+}
+
+///Off-screen replacement for [SwapchainPresent](crate::SwapchainPresent). Presented images are blitted into a ring of images,
+/// and read back to the CPU. Use it for headless rendering, for instance on CI machines without a window system.
+///
+/// Both implement [PresentTarget], so render loops that are generic over it run unchanged. Read back frames are handed to the
+/// [FrameSink]. Frames are collected whenever the next frame is presented, or [wait_for_frame](Self::wait_for_frame) is called.
+/// Call [flush](Self::flush) before reading the final frame.
+pub struct VirtualSwapchain {
+    device: Arc<Device>,
+    ///Family of the track the task is executed on, which also emulates the present.
+    queue_family: u32,
+    allocator: Arc<Mutex<Allocator>>,
+    desc: ImgDesc,
+    images: Vec<VirtualImage>,
+    next_slot: usize,
+    ///Signaled with `frame_index + 1` once a frame was presented.
+    timeline: Arc<Semaphore>,
+    next_frame_index: u64,
+    next: PresentOp,
+    sink: FrameSink,
+    latest: Option<VirtualFrame>,
+}
+
+impl VirtualSwapchain {
+    ///Default format of the swapchain images.
+    pub const DEFAULT_FORMAT: vk::Format = vk::Format::B8G8R8A8_SRGB;
+    ///Default number of images in the ring.
+    pub const DEFAULT_IMAGE_COUNT: usize = 3;
+
+    ///Creates a virtual swapchain of `extent`, with [DEFAULT_FORMAT](Self::DEFAULT_FORMAT) and
+    /// [DEFAULT_IMAGE_COUNT](Self::DEFAULT_IMAGE_COUNT) images.
+    pub fn new(
+        rmg: &mut Rmg,
+        extent: vk::Extent2D,
+    ) -> Result<Self, TaskError<VirtualSwapchainError>> {
+        Self::new_with_format(rmg, extent, Self::DEFAULT_FORMAT, Self::DEFAULT_IMAGE_COUNT)
+    }
+
+    ///Creates a virtual swapchain with `image_count` images of `format`. The format must support being blitted to.
+    pub fn new_with_format(
+        rmg: &mut Rmg,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        image_count: usize,
+    ) -> Result<Self, TaskError<VirtualSwapchainError>> {
+        if FormatInfo::of(format).is_none() {
+            return Err(TaskError::Task(VirtualSwapchainError::UnsupportedFormat(
+                format,
+            )));
+        }
+        if image_count == 0 {
+            return Err(TaskError::Task(VirtualSwapchainError::NoImages));
+        }
+        let queue_family = rmg
+            .queue_family_for_usage(vk::QueueFlags::GRAPHICS)
+            .ok_or(TaskError::Task(VirtualSwapchainError::NoQueue))?;
+
+        let timeline = Semaphore::new(&rmg.ctx.device, 0)
+            .map_err(|e| TaskError::Marpii(DeviceError::from(e).into()))?;
+        let mut swapchain = VirtualSwapchain {
+            device: rmg.ctx.device.clone(),
+            queue_family,
+            allocator: rmg.ctx.allocator.clone(),
+            desc: ImgDesc::color_attachment_2d(extent.width, extent.height, format)
+                .add_usage(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::TRANSFER_SRC),
+            images: Vec::with_capacity(image_count),
+            next_slot: 0,
+            timeline,
+            next_frame_index: 0,
+            next: PresentOp::None,
+            sink: FrameSink::Latest,
+            latest: None,
+        };
+        for _ in 0..image_count {
+            let image = swapchain.create_image().map_err(TaskError::Marpii)?;
+            swapchain.images.push(image);
+        }
+
+        Ok(swapchain)
+    }
+
+    pub fn with_sink(mut self, sink: FrameSink) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    pub fn set_sink(&mut self, sink: FrameSink) {
+        self.sink = sink;
+    }
+
+    fn create_image(&self) -> Result<VirtualImage, MarpiiError> {
+        let image = Image::new(
+            &self.device,
+            &self.allocator,
+            self.desc.clone(),
+            MemoryUsage::GpuOnly,
+            Some("VirtualSwapchainImage"),
+        )?;
+        let size = FormatInfo::of(self.desc.format)
+            .map(|info| info.region_size(self.desc.extent))
+            .unwrap_or(0);
+        let readback = Buffer::new(
+            &self.device,
+            &self.allocator,
+            BufDesc::for_data::<u8>(size as usize)
+                .with(|b| b.usage = vk::BufferUsageFlags::TRANSFER_DST),
+            MemoryUsage::GpuToCpu,
+            Some("VirtualSwapchainReadback"),
+        )?;
+        Ok(VirtualImage {
+            image: Arc::new(image),
+            readback: Arc::new(readback),
+            sem_present: Arc::new(BinarySemaphore::new(&self.device).map_err(DeviceError::from)?),
+            pending: None,
+        })
+    }
+
+    ///Pushes `image` to be presented whenever the task is scheduled next.
+    /// Might overwrite any already pushed frame that is waiting for execution.
+    ///
+    /// `image` is scaled to the swapchain's extent.
+    pub fn push_image(&mut self, image: ImageHandle, _extent: vk::Extent2D) {
+        self.next = PresentOp::Scheduled(image);
+    }
+
+    ///Returns the swapchain's extent.
+    pub fn extent(&self) -> Option<vk::Extent2D> {
+        Some(self.desc.extent_2d())
+    }
+
+    ///Returns the swapchain image's format.
+    pub fn format(&self) -> vk::Format {
+        self.desc.format
+    }
+
+    ///Returns the transfer function the pushed images must be encoded with.
+    pub fn transfer_function(&self) -> TransferFunction {
+        TransferFunction::from_color_space(vk::ColorSpaceKHR::SRGB_NONLINEAR)
+    }
+
+    ///Returns the description all swapchain images are created with.
+    pub fn image_desc(&self) -> &ImgDesc {
+        &self.desc
+    }
+
+    ///Returns the ring of swapchain images. After a frame was presented, its image is in `TRANSFER_SRC_OPTIMAL` layout.
+    pub fn images(&self) -> impl Iterator<Item = &Arc<Image>> {
+        self.images.iter().map(|img| &img.image)
+    }
+
+    ///Returns the number of frames that were presented so far.
+    pub fn presented_frames(&self) -> u64 {
+        self.next_frame_index
+    }
+
+    ///Returns the latest frame that was read back.
+    pub fn latest_frame(&self) -> Option<&VirtualFrame> {
+        self.latest.as_ref()
+    }
+
+    ///Takes the latest frame that was read back.
+    pub fn take_latest_frame(&mut self) -> Option<VirtualFrame> {
+        self.latest.take()
+    }
+
+    ///Recreates all images with `extent`. Frames that are still in flight are read back first.
+    pub fn recreate(&mut self, extent: vk::Extent2D) -> Result<(), RmgTaskError> {
+        self.flush(Duration::MAX)?;
+        self.desc.extent = vk::Extent3D {
+            width: extent.width,
+            height: extent.height,
+            depth: 1,
+        };
+        for slot in 0..self.images.len() {
+            self.images[slot] = self.create_image()?;
+        }
+        Ok(())
+    }
+
+    ///Waits until the next image of the ring is free, and collects all frames that finished so far. Returns the time that was spent waiting.
+    pub fn wait_for_frame(&mut self) -> Duration {
+        let start = Instant::now();
+        #[allow(unused_variables)]
+        if let Err(e) = self
+            .wait_slot(self.next_slot, Duration::MAX)
+            .and_then(|_| self.collect())
+        {
+            #[cfg(feature = "logging")]
+            log::error!("Failed to wait for virtual swapchain frame: {}", e);
+        }
+        start.elapsed()
+    }
+
+    ///Reads back all frames that finished presenting, without blocking. Returns the number of collected frames.
+    pub fn collect(&mut self) -> Result<usize, MarpiiError> {
+        let finished = finished_slots(
+            self.images.iter().map(|img| img.pending),
+            self.timeline.get_value(),
+        );
+        for slot in &finished {
+            self.read_back(*slot)?;
+        }
+        Ok(finished.len())
+    }
+
+    ///Waits until all frames in flight are presented, and reads them back. Fails if that takes longer than `timeout`.
+    pub fn flush(&mut self, timeout: Duration) -> Result<(), MarpiiError> {
+        if let Some(last) = self.images.iter().filter_map(|img| img.pending).max() {
+            self.wait_frame(last, timeout)?;
+        }
+        self.collect()?;
+        Ok(())
+    }
+
+    fn wait_frame(&self, frame: u64, timeout: Duration) -> Result<(), MarpiiError> {
+        let timeout = u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX);
+        self.timeline
+            .wait(frame + 1, timeout)
+            .map_err(|e| MarpiiError::from(DeviceError::from(e)))
+    }
+
+    ///Waits for the frame in flight on `slot`, if any, and reads it back.
+    fn wait_slot(&mut self, slot: usize, timeout: Duration) -> Result<(), MarpiiError> {
+        if let Some(frame) = self.images[slot].pending {
+            self.wait_frame(frame, timeout)?;
+            //deliver older frames first
+            self.collect()?;
+        }
+        Ok(())
+    }
+
+    fn read_back(&mut self, slot: usize) -> Result<(), MarpiiError> {
+        let Some(index) = self.images[slot].pending.take() else {
+            return Ok(());
+        };
+        let mapped = self.images[slot]
+            .readback
+            .read()
+            .map_err(MarpiiError::from)?;
+        let Some(data) = mapped.as_slice_ref() else {
+            return Err(MarpiiError::Other(
+                "Virtual swapchain readback buffer is not mappable".to_owned(),
+            ));
+        };
+        let frame = VirtualFrame {
+            index,
+            extent: self.images[slot].image.extent_2d(),
+            format: self.desc.format,
+            data: data.to_vec(),
+        };
+        drop(mapped);
+        self.sink.deliver(&frame);
+        self.latest = Some(frame);
+        Ok(())
+    }
+
+    ///Emulates the present operation. Waits for the task's submission and signals the timeline with the frame.
+    fn present(&mut self, slot: usize) -> Result<(), MarpiiError> {
+        let frame = self.next_frame_index;
+        let Some(queue) = self.device.get_first_queue_for_family(self.queue_family) else {
+            return Err(MarpiiError::Other(format!(
+                "Virtual swapchain has no queue of family {}",
+                self.queue_family
+            )));
+        };
+        let wait = vk::SemaphoreSubmitInfo::default()
+            .semaphore(self.images[slot].sem_present.inner)
+            .stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS);
+        let signal = vk::SemaphoreSubmitInfo::default()
+            .semaphore(self.timeline.inner)
+            .value(frame + 1)
+            .stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS);
+        let submit = vk::SubmitInfo2::default()
+            .wait_semaphore_infos(core::slice::from_ref(&wait))
+            .signal_semaphore_infos(core::slice::from_ref(&signal));
+        if let Err(e) = unsafe {
+            self.device.inner.queue_submit2(
+                *queue.inner(),
+                core::slice::from_ref(&submit),
+                vk::Fence::null(),
+            )
+        } {
+            //Nothing waits for the present semaphore now, so it stays signaled. Replace it, the old one is kept alive by
+            // the execution that signals it.
+            self.images[slot].sem_present =
+                Arc::new(BinarySemaphore::new(&self.device).map_err(DeviceError::from)?);
+            return Err(DeviceError::from(e).into());
+        }
+        self.images[slot].pending = Some(frame);
+        self.next_frame_index += 1;
+        Ok(())
+    }
+}
+
+///Returns the slots whose pending frame is below `presented`, in present order.
+fn finished_slots(pending: impl Iterator<Item = Option<u64>>, presented: u64) -> Vec<usize> {
+    let mut finished = pending
+        .enumerate()
+        .filter_map(|(slot, frame)| frame.map(|frame| (frame, slot)))
+        .filter(|(frame, _slot)| *frame < presented)
+        .collect::<Vec<_>>();
+    //deliver in present order
+    finished.sort_unstable();
+    finished.into_iter().map(|(_frame, slot)| slot).collect()
+}
+
+impl Drop for VirtualSwapchain {
+    fn drop(&mut self) {
+        //The timeline must outlive the pending present submissions.
+        if self.next_frame_index > 0 {
+            let _ = self.timeline.wait(self.next_frame_index, u64::MAX);
+        }
+    }
+}
+
+impl PresentTarget for VirtualSwapchain {
+    fn push_image(&mut self, image: ImageHandle, extent: vk::Extent2D) {
+        VirtualSwapchain::push_image(self, image, extent)
+    }
+    fn extent(&self) -> Option<vk::Extent2D> {
+        VirtualSwapchain::extent(self)
+    }
+    fn format(&self) -> vk::Format {
+        VirtualSwapchain::format(self)
+    }
+    fn transfer_function(&self) -> TransferFunction {
+        VirtualSwapchain::transfer_function(self)
+    }
+    fn image_desc(&self) -> &ImgDesc {
+        VirtualSwapchain::image_desc(self)
+    }
+    fn recreate(&mut self, extent: vk::Extent2D) -> Result<(), RmgTaskError> {
+        VirtualSwapchain::recreate(self, extent)
+    }
+    fn wait_for_frame(&mut self) -> Duration {
+        VirtualSwapchain::wait_for_frame(self)
+    }
+}
+
+impl Task for VirtualSwapchain {
+    fn name(&self) -> &'static str {
+        "VirtualSwapchain"
+    }
+
+    fn pre_record(
+        &mut self,
+        _resources: &mut marpii_rmg::Resources,
+        _ctx: &marpii_rmg::CtxRmg,
+    ) -> Result<(), RecordError> {
+        if let PresentOp::Scheduled(src_image) = self.next.take() {
+            //"acquire" the next image, which needs its last frame to be read back.
+            let slot = self.next_slot;
+            self.wait_slot(slot, Duration::MAX)
+                .map_err(|_e| ResourceError::SwapchainError)?;
+            self.next_slot = (slot + 1) % self.images.len();
+            self.next = PresentOp::Paired { src_image, slot };
+        } else {
+            #[cfg(feature = "logging")]
+            log::warn!("Virtual swapchain queued, but no present image set.");
+        }
+        Ok(())
+    }
+
+    fn post_execution(
+        &mut self,
+        _resources: &mut marpii_rmg::Resources,
+        _ctx: &marpii_rmg::CtxRmg,
+    ) -> Result<(), RecordError> {
+        if let PresentOp::InFlight(slot) = self.next.take() {
+            self.present(slot)?;
+        } else {
+            #[cfg(feature = "logging")]
+            log::warn!("Virtual swapchain queued, but no image inflight after execution.");
+        }
+        Ok(())
+    }
+
+    fn register(&self, registry: &mut marpii_rmg::ResourceRegistry) {
+        if let PresentOp::Paired { src_image, slot } = &self.next {
+            registry
+                .request_image(
+                    src_image,
+                    vk::PipelineStageFlags2::TRANSFER,
+                    vk::AccessFlags2::TRANSFER_READ,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                )
+                .unwrap();
+            let img = &self.images[*slot];
+            registry.register_binary_signal_semaphore(img.sem_present.clone());
+            registry.register_asset(img.image.clone());
+            registry.register_asset(img.readback.clone());
+        }
+    }
+
+    fn queue_flags(&self) -> vk::QueueFlags {
+        vk::QueueFlags::GRAPHICS
+    }
+
+    fn record(
+        &mut self,
+        device: &Arc<Device>,
+        command_buffer: &vk::CommandBuffer,
+        resources: &marpii_rmg::Resources,
+    ) {
+        let PresentOp::Paired { src_image, slot } = self.next.take() else {
+            #[cfg(feature = "logging")]
+            log::warn!("Present scheduled, but no virtual swapchain image present on record.");
+            return;
+        };
+        let dst = &self.images[slot];
+
+        //NOTE: Like the real swapchain, we take care of the image ourself and discard the old content.
+        unsafe {
+            device.inner.cmd_pipeline_barrier2(
+                *command_buffer,
+                &vk::DependencyInfo::default().image_memory_barriers(&[
+                    vk::ImageMemoryBarrier2::default()
+                        .image(dst.image.inner)
+                        .src_stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+                        .dst_stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+                        .dst_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+                        .subresource_range(dst.image.subresource_all())
+                        .old_layout(vk::ImageLayout::UNDEFINED)
+                        .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL),
+                ]),
+            );
+        }
+
+        let img = resources.get_image_state(&src_image);
+        let filter = if src_image.image_desc().is_depth_stencil() {
+            vk::Filter::NEAREST
+        } else {
+            vk::Filter::LINEAR
+        };
+        unsafe {
+            device.inner.cmd_blit_image2(
+                *command_buffer,
+                &vk::BlitImageInfo2::default()
+                    .src_image(img.image.inner)
+                    .dst_image(dst.image.inner)
+                    .src_image_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                    .dst_image_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                    .filter(filter)
+                    .regions(&[vk::ImageBlit2::default()
+                        .src_subresource(img.image.subresource_layers_all())
+                        .dst_subresource(dst.image.subresource_layers_all())
+                        .src_offsets(img.image.image_region().to_blit_offsets())
+                        .dst_offsets(dst.image.image_region().to_blit_offsets())]),
+            );
+
+            device.inner.cmd_pipeline_barrier2(
+                *command_buffer,
+                &vk::DependencyInfo::default().image_memory_barriers(&[
+                    vk::ImageMemoryBarrier2::default()
+                        .image(dst.image.inner)
+                        .src_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+                        .dst_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+                        .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+                        .dst_access_mask(vk::AccessFlags2::TRANSFER_READ)
+                        .subresource_range(dst.image.subresource_all())
+                        .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                        .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL),
+                ]),
+            );
+
+            device.inner.cmd_copy_image_to_buffer2(
+                *command_buffer,
+                &vk::CopyImageToBufferInfo2::default()
+                    .src_image(dst.image.inner)
+                    .src_image_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                    .dst_buffer(dst.readback.inner)
+                    .regions(&[vk::BufferImageCopy2::default()
+                        .image_subresource(dst.image.subresource_layers_all())
+                        .image_extent(dst.image.extent_3d())]),
+            );
+
+            //make the copy visible to the host
+            device.inner.cmd_pipeline_barrier2(
+                *command_buffer,
+                &vk::DependencyInfo::default().buffer_memory_barriers(&[
+                    vk::BufferMemoryBarrier2::default()
+                        .buffer(dst.readback.inner)
+                        .src_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+                        .dst_stage_mask(vk::PipelineStageFlags2::HOST)
+                        .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+                        .dst_access_mask(vk::AccessFlags2::HOST_READ)
+                        .size(vk::WHOLE_SIZE),
+                ]),
+            );
+        }
+
+        self.next = PresentOp::InFlight(slot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(index: u64, format: vk::Format) -> VirtualFrame {
+        VirtualFrame {
+            index,
+            extent: vk::Extent2D {
+                width: 2,
+                height: 1,
+            },
+            format,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8],
+        }
+    }
+
+    #[test]
+    fn to_rgba8() {
+        assert_eq!(
+            frame(0, vk::Format::R8G8B8A8_SRGB).to_rgba8().unwrap(),
+            vec![1, 2, 3, 4, 5, 6, 7, 8]
+        );
+        assert_eq!(
+            frame(0, vk::Format::B8G8R8A8_UNORM).to_rgba8().unwrap(),
+            vec![3, 2, 1, 4, 7, 6, 5, 8]
+        );
+        assert!(matches!(
+            frame(0, vk::Format::R16G16B16A16_SFLOAT).to_rgba8(),
+            Err(VirtualSwapchainError::NotRgba8(
+                vk::Format::R16G16B16A16_SFLOAT
+            ))
+        ));
+    }
+
+    #[test]
+    fn collect_in_present_order() {
+        //slot 1 holds the oldest frame, slot 3 is not yet presented and slot 2 is free
+        let pending = [Some(5), Some(3), None, Some(6), Some(4)];
+        assert_eq!(finished_slots(pending.into_iter(), 6), vec![1, 4, 0]);
+        assert_eq!(finished_slots(pending.into_iter(), 0), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn sink_dispatch() {
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let record = delivered.clone();
+        let mut sink = FrameSink::Callback(Box::new(move |frame| {
+            record.lock().unwrap().push(frame.index)
+        }));
+        sink.deliver(&frame(0, vk::Format::B8G8R8A8_SRGB));
+        sink.deliver(&frame(1, vk::Format::B8G8R8A8_SRGB));
+        assert_eq!(*delivered.lock().unwrap(), vec![0, 1]);
+
+        //only keeps the frame
+        FrameSink::Latest.deliver(&frame(2, vk::Format::B8G8R8A8_SRGB));
+    }
+}
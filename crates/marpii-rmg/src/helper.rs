@@ -1,13 +1,36 @@
 //! Helper that facilitate _common_ usage patterns in RMG. Many of those depend on the `marpii-rmg-macros` crate, to derive certain
 //! functionality.
 
-use marpii::ash::vk;
+use marpii::{PipelineError, ash::vk};
 
+pub mod bind_sparse;
 pub mod computepass;
+pub mod hot_reload;
+pub mod pipeline_variants;
 pub mod rasterpass;
 mod resource_register;
 pub use resource_register::ResourceRegister;
 
+///Checks that a push constant of type `PC` covers the push constant block of `expected` bytes a pipeline's shaders declare.
+pub(crate) fn check_push_constant<PC>(
+    expected: Option<u32>,
+    limit: u32,
+) -> Result<(), PipelineError> {
+    let provided = std::mem::size_of::<PC>() as u32;
+    if provided > limit {
+        return Err(PipelineError::PushConstantLimit {
+            size: provided,
+            limit,
+        });
+    }
+    match expected {
+        Some(expected) if expected > provided => {
+            Err(PipelineError::PushConstantSize { expected, provided })
+        }
+        _ => Ok(()),
+    }
+}
+
 ///Declares at a high level how the image is used in the pass.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ImageUsage {
@@ -108,3 +131,30 @@ pub trait DynamicRenderingInfo {
     fn depth_format(&self) -> Option<&vk::Format>;
     fn color_blend_attachments(&self) -> &[vk::PipelineColorBlendAttachmentState];
 }
+
+#[cfg(test)]
+mod tests {
+    use marpii::PipelineError;
+
+    use super::check_push_constant;
+
+    #[test]
+    fn push_constant_size_checks() {
+        assert!(check_push_constant::<[u32; 4]>(None, 128).is_ok());
+        assert!(check_push_constant::<[u32; 4]>(Some(16), 128).is_ok());
+        assert!(matches!(
+            check_push_constant::<[u32; 2]>(Some(16), 128),
+            Err(PipelineError::PushConstantSize {
+                expected: 16,
+                provided: 8
+            })
+        ));
+        assert!(matches!(
+            check_push_constant::<[u32; 64]>(Some(16), 128),
+            Err(PipelineError::PushConstantLimit {
+                size: 256,
+                limit: 128
+            })
+        ));
+    }
+}
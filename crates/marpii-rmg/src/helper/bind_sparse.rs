@@ -0,0 +1,105 @@
+//! Sparse memory binding as a task.
+
+use crate::{ImageHandle, ResourceRegistry, Resources, Task};
+use marpii::{ash::vk, context::Device, resources::SparseBindBatch};
+use std::sync::Arc;
+
+///Task that executes sparse memory binds (see [`SparseImageResidency`](marpii::resources::SparseImageResidency)) on a
+/// track that supports `SPARSE_BINDING`.
+///
+/// The binds are executed before any command of the track's frame that contains the task. All tasks that are scheduled
+/// after this task and use the image therefore see the new residency.
+///
+/// ```ignore
+/// let batch = residency.commit(residency.pages_for_region(0, 0, offset, extent))?;
+/// bind_task.push(batch);
+/// rmg.record()
+///    .add_task(&mut bind_task)?
+///    .add_task(&mut upload_pages)?
+///    .execute()?;
+/// ```
+pub struct BindSparse {
+    images: Vec<(ImageHandle, vk::ImageLayout)>,
+    pending: SparseBindBatch,
+}
+
+impl Default for BindSparse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BindSparse {
+    pub fn new() -> Self {
+        BindSparse {
+            images: Vec::new(),
+            pending: SparseBindBatch::new(),
+        }
+    }
+
+    ///Adds `image` to the task. The image is not transitioned, `layout` must be the layout the image is in before
+    /// the task, usually the one of the last task that used the image.
+    pub fn with_image(mut self, image: ImageHandle, layout: vk::ImageLayout) -> Self {
+        self.add_image(image, layout);
+        self
+    }
+
+    ///See [`with_image`](Self::with_image).
+    pub fn add_image(&mut self, image: ImageHandle, layout: vk::ImageLayout) {
+        if let Some(existing) = self.images.iter_mut().find(|(img, _)| *img == image) {
+            existing.1 = layout;
+        } else {
+            self.images.push((image, layout));
+        }
+    }
+
+    ///Queues `batch` for the next execution of this task.
+    pub fn push(&mut self, batch: SparseBindBatch) {
+        self.pending.append(batch);
+    }
+
+    ///True if there are binds waiting for execution.
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+}
+
+impl Task for BindSparse {
+    fn name(&self) -> &str {
+        "BindSparse"
+    }
+
+    fn queue_flags(&self) -> vk::QueueFlags {
+        vk::QueueFlags::SPARSE_BINDING
+    }
+
+    fn register(&self, registry: &mut ResourceRegistry) {
+        for (image, layout) in &self.images {
+            registry
+                .request_image(
+                    image,
+                    vk::PipelineStageFlags2::NONE,
+                    vk::AccessFlags2::NONE,
+                    *layout,
+                )
+                .unwrap();
+        }
+    }
+
+    fn take_sparse_binds(&mut self) -> Option<SparseBindBatch> {
+        if self.pending.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.pending))
+        }
+    }
+
+    fn record(
+        &mut self,
+        _device: &Arc<Device>,
+        _command_buffer: &vk::CommandBuffer,
+        _resources: &Resources,
+    ) {
+        //Nothing to record, the binds are executed by the executor.
+    }
+}
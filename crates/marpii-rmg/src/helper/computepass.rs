@@ -2,22 +2,57 @@
 
 use crate::{
     BufferHandle, ImageHandle, RecordError, Rmg, RmgError, SamplerHandle, Task,
-    helper::{BufferUsage, ImageUsage, ResourceRegister},
+    helper::{BufferUsage, ImageUsage, ResourceRegister, check_push_constant},
 };
 use marpii::{
     OoS,
     ash::vk::{self, DeviceSize, DispatchIndirectCommand},
-    resources::{ComputePipeline, PushConstant, ShaderModule},
+    resources::{
+        ComputePipeline, PushConstant, ShaderModule, ShaderObject, ShaderStage, Specialization,
+    },
 };
 use std::sync::Arc;
 
+///The program a [GenericComputePass] dispatches.
+#[derive(Clone)]
+pub enum ComputeProgram {
+    Pipeline(Arc<ComputePipeline>),
+    ///A compute [ShaderObject], usually created via [Rmg::shader_object]. It is bound directly, and uses the bindless layout.
+    ShaderObject(Arc<ShaderObject>),
+}
+
+impl ComputeProgram {
+    fn push_constant_size(&self) -> Option<u32> {
+        match self {
+            Self::Pipeline(pipeline) => pipeline.push_constant_size,
+            Self::ShaderObject(shader) => shader.push_constant_size,
+        }
+    }
+}
+
+impl From<Arc<ComputePipeline>> for ComputeProgram {
+    fn from(pipeline: Arc<ComputePipeline>) -> Self {
+        Self::Pipeline(pipeline)
+    }
+}
+
+impl From<Arc<ShaderObject>> for ComputeProgram {
+    fn from(shader: Arc<ShaderObject>) -> Self {
+        debug_assert!(
+            shader.stage == vk::ShaderStageFlags::COMPUTE,
+            "Shader object is no compute shader"
+        );
+        Self::ShaderObject(shader)
+    }
+}
+
 ///A generic compute pass that dispatches an amount of wavefronts for a given
 /// pipeline using a push-constant.
 ///
 /// This is designed to be used _once_ per frame. I.e. it does not allocate much
 /// and the pass is not expected to be mutated.
 pub struct GenericComputePass<P: 'static> {
-    pipeline: Arc<ComputePipeline>,
+    pipeline: ComputeProgram,
     push: PushConstant<P>,
     dispatch: DispatchType,
     name: Option<String>,
@@ -52,9 +87,9 @@ impl Default for DispatchType {
 
 impl<P: 'static> GenericComputePass<P> {
     ///Initializes the pass for `pipeline` with no other features set
-    pub fn init(pipeline: Arc<ComputePipeline>) -> GenericComputePass<()> {
+    pub fn init(pipeline: impl Into<ComputeProgram>) -> GenericComputePass<()> {
         GenericComputePass {
-            pipeline,
+            pipeline: pipeline.into(),
             push: PushConstant::new((), vk::ShaderStageFlags::COMPUTE),
             dispatch: DispatchType::default(),
             name: None,
@@ -79,13 +114,34 @@ impl<P: 'static> GenericComputePass<P> {
     ///
     /// # Safety: its your resonsibility to make sure that the pipeline object
     /// is compatible with the resources, push constant etc.
-    pub fn swap_pipeline(&mut self, pipeline: Arc<ComputePipeline>) {
-        self.pipeline = pipeline;
+    pub fn swap_pipeline(&mut self, pipeline: impl Into<ComputeProgram>) {
+        self.pipeline = pipeline.into();
     }
 
     ///Clones the inner, used pipeline. Nice if you want to setup yet another pass
     /// based on this pipeline without having to recreate the actual pipeline (which is expensive).
+    ///
+    /// Panics if the pass dispatches a [ShaderObject], see [shader_object](Self::shader_object) and [program](Self::program).
     pub fn pipeline(&self) -> Arc<ComputePipeline> {
+        match &self.pipeline {
+            ComputeProgram::Pipeline(pipeline) => pipeline.clone(),
+            ComputeProgram::ShaderObject(_) => {
+                panic!("Compute pass dispatches a shader object, not a pipeline")
+            }
+        }
+    }
+
+    ///Clones the inner shader object, or returns `None` if the pass dispatches a [ComputePipeline].
+    pub fn shader_object(&self) -> Option<Arc<ShaderObject>> {
+        if let ComputeProgram::ShaderObject(shader) = &self.pipeline {
+            Some(shader.clone())
+        } else {
+            None
+        }
+    }
+
+    ///Clones the inner program, regardless of whether it is a pipeline or a shader object.
+    pub fn program(&self) -> ComputeProgram {
         self.pipeline.clone()
     }
 
@@ -190,7 +246,7 @@ impl<P: 'static> GenericComputePass<P> {
 }
 
 impl<P: Default + 'static> GenericComputePass<P> {
-    fn init_empty(pipeline: Arc<ComputePipeline>) -> Self {
+    fn init_empty(pipeline: ComputeProgram) -> Self {
         GenericComputePass {
             pipeline,
             push: PushConstant::new(P::default(), vk::ShaderStageFlags::COMPUTE),
@@ -244,7 +300,10 @@ impl<P: 'static> Task for GenericComputePass<P> {
 
         self.storage.register_all(registry);
         //Always keep pipeline alive as long as possible
-        registry.register_asset(self.pipeline.clone());
+        match &self.pipeline {
+            ComputeProgram::Pipeline(pipeline) => registry.register_asset(pipeline.clone()),
+            ComputeProgram::ShaderObject(shader) => registry.register_asset(shader.clone()),
+        }
     }
 
     fn record(
@@ -255,14 +314,28 @@ impl<P: 'static> Task for GenericComputePass<P> {
     ) {
         //bind pipeline, setup push constant and execute
         unsafe {
-            device.inner.cmd_bind_pipeline(
-                *command_buffer,
-                vk::PipelineBindPoint::COMPUTE,
-                self.pipeline.pipeline,
-            );
+            let layout = match &self.pipeline {
+                ComputeProgram::Pipeline(pipeline) => {
+                    device.inner.cmd_bind_pipeline(
+                        *command_buffer,
+                        vk::PipelineBindPoint::COMPUTE,
+                        pipeline.pipeline,
+                    );
+                    pipeline.layout.layout
+                }
+                ComputeProgram::ShaderObject(shader) => {
+                    shader.loader.cmd_bind_shaders(
+                        *command_buffer,
+                        &[vk::ShaderStageFlags::COMPUTE],
+                        &[shader.inner],
+                    );
+                    //NOTE: shader objects are created with the bindless set layouts and push range
+                    resources.bindless_layout.layout
+                }
+            };
             device.inner.cmd_push_constants(
                 *command_buffer,
-                self.pipeline.layout.layout,
+                layout,
                 vk::ShaderStageFlags::ALL,
                 0,
                 self.push.content_as_bytes(),
@@ -292,17 +365,33 @@ pub struct ComputePassBuilder<'ctx, P: 'static> {
 }
 
 impl<'ctx, P: 'static> ComputePassBuilder<'ctx, P> {
-    ///Generates the _final_ push constant for the pass. I.e. use `configure` to fetch all
-    /// `ResourceHandle`
+    ///Same as [`try_with_push_constant`](Self::try_with_push_constant), but panics if `PC` does not fit the pipeline's
+    /// push constant block or the device limit.
+    #[deprecated(
+        note = "use `try_with_push_constant`, which reports push constant mismatches as error"
+    )]
     pub fn with_push_constant<PC: 'static>(
         self,
         configure: impl Fn(&mut Rmg) -> PC,
     ) -> ComputePassBuilder<'ctx, PC> {
-        assert!(
-            std::mem::size_of::<PC>()
-                <= self.rmg.config().limit.limits.max_push_constants_size as usize,
-            "Push constant size exceeds limit"
-        );
+        self.try_with_push_constant(configure)
+            .unwrap_or_else(|e| panic!("Invalid push constant: {}", e))
+    }
+
+    ///Generates the _final_ push constant for the pass. I.e. use `configure` to fetch all
+    /// `ResourceHandle`
+    ///
+    /// Fails if `PC` is smaller than the push constant block the pipeline's shader declares, or exceeds the
+    /// device's push constant limit.
+    pub fn try_with_push_constant<PC: 'static>(
+        self,
+        configure: impl Fn(&mut Rmg) -> PC,
+    ) -> Result<ComputePassBuilder<'ctx, PC>, RecordError> {
+        check_push_constant::<PC>(
+            self.task_setup.pipeline.push_constant_size(),
+            self.rmg.config().limit.limits.max_push_constants_size,
+        )
+        .map_err(|e| RecordError::MarpiiError(e.into()))?;
 
         let GenericComputePass {
             pipeline,
@@ -315,7 +404,7 @@ impl<'ctx, P: 'static> ComputePassBuilder<'ctx, P> {
         let push_constant = configure(self.rmg);
         let new_push_constant = PushConstant::new(push_constant, vk::ShaderStageFlags::COMPUTE);
 
-        ComputePassBuilder {
+        Ok(ComputePassBuilder {
             task_setup: GenericComputePass {
                 pipeline,
                 dispatch,
@@ -324,7 +413,7 @@ impl<'ctx, P: 'static> ComputePassBuilder<'ctx, P> {
                 storage,
             },
             rmg: self.rmg,
-        }
+        })
     }
 
     pub fn with_name(mut self, name: impl Into<String>) -> Self {
@@ -444,10 +533,11 @@ impl<'ctx, P: 'static> ComputePassBuilder<'ctx, P> {
 }
 
 impl Rmg {
-    ///Creates a new, configurable compute pass.
+    ///Creates a new, configurable compute pass. `pipeline` is either a [ComputePipeline], or a compute [ShaderObject] created via
+    /// [shader_object](Self::shader_object).
     pub fn new_compute_pass<'rmg>(
         &'rmg mut self,
-        pipeline: Arc<ComputePipeline>,
+        pipeline: impl Into<ComputeProgram>,
     ) -> ComputePassBuilder<'rmg, ()> {
         ComputePassBuilder {
             task_setup: GenericComputePass::<()>::init(pipeline),
@@ -457,21 +547,46 @@ impl Rmg {
 
     ///Creates a new generic compute-pipeline that matches the bindless
     /// pipeline-layout and enters `shader_code` at `entry_point`
+    ///
+    /// Fails with a [`PipelineError`](marpii::PipelineError) if the shader's descriptors or push constant block don't fit the bindless layout.
     pub fn compute_pipeline(
         &mut self,
         entry_point: &str,
         shader_code: &[u8],
+    ) -> Result<Arc<ComputePipeline>, RmgError> {
+        self.compute_pipeline_specialized(entry_point, shader_code, Specialization::new())
+    }
+
+    ///Same as [`compute_pipeline`](Self::compute_pipeline), but applies `specialization` to the shader.
+    pub fn compute_pipeline_specialized(
+        &mut self,
+        entry_point: &str,
+        shader_code: &[u8],
+        specialization: Specialization,
     ) -> Result<Arc<ComputePipeline>, RmgError> {
         let shader_module = ShaderModule::new_from_bytes(&self.ctx.device, shader_code)
             .map_err(|e| RecordError::MarpiiError(e.into()))?;
-        let shader_stage =
-            shader_module.into_shader_stage(vk::ShaderStageFlags::COMPUTE, entry_point);
+        let shader_stage = shader_module
+            .into_shader_stage(vk::ShaderStageFlags::COMPUTE, entry_point)
+            .with_specialization(specialization);
+        self.compute_pipeline_from_stage(&shader_stage)
+    }
+
+    ///Creates a compute pipeline for `shader_stage` that matches the bindless pipeline-layout. The stage's
+    /// specialization constants are applied.
+    pub fn compute_pipeline_from_stage(
+        &self,
+        shader_stage: &ShaderStage,
+    ) -> Result<Arc<ComputePipeline>, RmgError> {
+        self.resources
+            .validate_shader_stage(shader_stage)
+            .map_err(|e| RecordError::MarpiiError(e.into()))?;
 
         let layout = self.resources.bindless_layout();
         Ok(Arc::new(
             ComputePipeline::new(
                 &self.ctx.device,
-                &shader_stage,
+                shader_stage,
                 None,
                 OoS::new_shared(layout),
             )
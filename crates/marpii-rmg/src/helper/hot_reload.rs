@@ -0,0 +1,455 @@
+//! Hot-reloading of shader files.
+//!
+//! The [`PipelineRegistry`] watches the SPIR-V files pipelines are created from by polling their modification time.
+//! Whenever a file changes, the pipelines using it are rebuilt. If loading or [validation](crate::Resources::validate_shader_stage)
+//! fails, the old pipeline is kept and the error is reported.
+//!
+//! With the `shader_compiler` feature, GLSL (`.vert`, `.frag`, `.comp`) and WGSL (`.wgsl`) files are compiled using
+//! [`Rmg::shader_compiler`] instead. Note that only the file itself is watched, not the files it includes.
+//!
+//! ```ignore
+//! let mut registry = PipelineRegistry::new();
+//! let sim = registry.add_compute(&rmg, "shaders/simulation.spv", "main", Specialization::new())?;
+//! let mut pass = rmg.new_compute_pass(registry.compute_pipeline(sim).unwrap())...;
+//!
+//! loop {
+//!     for event in registry.poll(&rmg) {
+//!         if let ReloadEvent::Failed { error, .. } = event {
+//!             println!("Shader reload failed: {error}");
+//!         }
+//!     }
+//!     registry.update_compute_pass(sim, &mut pass);
+//!     rmg.record().add_task(&mut pass)?.execute()?;
+//! }
+//! ```
+
+use crate::{
+    RecordError, Rmg, RmgError,
+    helper::{
+        computepass::{ComputeProgram, GenericComputePass},
+        rasterpass::{GenericRasterPass, RasterPipeline, RasterProgram},
+    },
+};
+use marpii::{
+    OoS,
+    ash::vk,
+    resources::{ComputePipeline, ShaderModule, ShaderStage, Specialization},
+};
+use smallvec::SmallVec;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+///Identifies a pipeline of a [`PipelineRegistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineId(usize);
+
+///Result of a reload, see [`PipelineRegistry::poll`].
+#[derive(Debug)]
+pub enum ReloadEvent {
+    ///The pipeline was rebuilt.
+    Reloaded(PipelineId),
+    ///Rebuilding failed, the old pipeline is still in use.
+    Failed { id: PipelineId, error: RmgError },
+}
+
+struct WatchedFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl WatchedFile {
+    fn new(path: &Path) -> Self {
+        WatchedFile {
+            path: path.to_owned(),
+            modified: Self::modified_time(path),
+        }
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    ///Returns true if the file changed since the last call.
+    fn changed(&mut self) -> bool {
+        let modified = Self::modified_time(&self.path);
+        //NOTE: a file that is currently being replaced might not exist for a moment. Wait till it's back.
+        if modified.is_some() && modified != self.modified {
+            self.modified = modified;
+            true
+        } else {
+            false
+        }
+    }
+
+    ///Checks all `files`, returns true if any of them changed. Always checks every file, so that all timestamps are updated.
+    fn any_changed<'a>(files: impl IntoIterator<Item = &'a mut WatchedFile>) -> bool {
+        let mut any = false;
+        for file in files {
+            any |= file.changed();
+        }
+        any
+    }
+
+    fn load(&self, rmg: &Rmg) -> Result<ShaderModule, RmgError> {
+        #[cfg(feature = "shader_compiler")]
+        if marpii::resources::SourceLanguage::from_path(&self.path).is_some() {
+            return Ok(ShaderModule::new_from_source_file(
+                &rmg.ctx.device,
+                &Rmg::shader_compiler(),
+                &self.path,
+            )
+            .map_err(|e| RecordError::MarpiiError(e.into()))?);
+        }
+
+        Ok(ShaderModule::new_from_file(&rmg.ctx.device, &self.path)
+            .map_err(|e| RecordError::MarpiiError(e.into()))?)
+    }
+}
+
+enum Entry {
+    Compute {
+        shader: WatchedFile,
+        entry_point: String,
+        specialization: Specialization,
+        pipeline: Arc<ComputePipeline>,
+    },
+    Raster {
+        vertex: WatchedFile,
+        vertex_entry_point: String,
+        vertex_specialization: Specialization,
+        fragment: WatchedFile,
+        fragment_entry_point: String,
+        fragment_specialization: Specialization,
+        color_attachment_formats: SmallVec<[vk::Format; 4]>,
+        depth_attachment_format: Option<vk::Format>,
+        configure_pipeline:
+            fn(vk::GraphicsPipelineCreateInfo<'_>) -> vk::GraphicsPipelineCreateInfo<'_>,
+        pipeline: Arc<RasterPipeline>,
+    },
+}
+
+impl Entry {
+    ///Checks the watched files, and rebuilds the pipeline if any changed. Returns None if nothing changed.
+    fn reload_if_changed(&mut self, rmg: &Rmg) -> Option<Result<(), RmgError>> {
+        match self {
+            Entry::Compute {
+                shader,
+                entry_point,
+                specialization,
+                pipeline,
+            } => {
+                if !shader.changed() {
+                    return None;
+                }
+                Some(
+                    build_compute(rmg, shader, entry_point, specialization)
+                        .map(|new| *pipeline = new),
+                )
+            }
+            Entry::Raster {
+                vertex,
+                vertex_entry_point,
+                vertex_specialization,
+                fragment,
+                fragment_entry_point,
+                fragment_specialization,
+                color_attachment_formats,
+                depth_attachment_format,
+                configure_pipeline,
+                pipeline,
+            } => {
+                if !WatchedFile::any_changed([&mut *vertex, &mut *fragment]) {
+                    return None;
+                }
+                Some(
+                    build_raster(
+                        rmg,
+                        (vertex, vertex_entry_point, vertex_specialization),
+                        (fragment, fragment_entry_point, fragment_specialization),
+                        color_attachment_formats.clone(),
+                        *depth_attachment_format,
+                        *configure_pipeline,
+                    )
+                    .map(|new| *pipeline = new),
+                )
+            }
+        }
+    }
+}
+
+fn build_compute(
+    rmg: &Rmg,
+    shader: &WatchedFile,
+    entry_point: &str,
+    specialization: &Specialization,
+) -> Result<Arc<ComputePipeline>, RmgError> {
+    let stage = shader
+        .load(rmg)?
+        .into_shader_stage(vk::ShaderStageFlags::COMPUTE, entry_point)
+        .with_specialization(specialization.clone());
+    rmg.compute_pipeline_from_stage(&stage)
+}
+
+fn build_raster(
+    rmg: &Rmg,
+    vertex: (&WatchedFile, &str, &Specialization),
+    fragment: (&WatchedFile, &str, &Specialization),
+    color_attachment_formats: SmallVec<[vk::Format; 4]>,
+    depth_attachment_format: Option<vk::Format>,
+    configure_pipeline: fn(
+        vk::GraphicsPipelineCreateInfo<'_>,
+    ) -> vk::GraphicsPipelineCreateInfo<'_>,
+) -> Result<Arc<RasterPipeline>, RmgError> {
+    let vertex_stage = ShaderStage::from_module(
+        OoS::new(vertex.0.load(rmg)?),
+        vk::ShaderStageFlags::VERTEX,
+        vertex.1.to_owned(),
+    )
+    .with_specialization(vertex.2.clone());
+    let fragment_stage = ShaderStage::from_module(
+        OoS::new(fragment.0.load(rmg)?),
+        vk::ShaderStageFlags::FRAGMENT,
+        fragment.1.to_owned(),
+    )
+    .with_specialization(fragment.2.clone());
+    Ok(Arc::new(rmg.new_raster_pipeline_from_stages(
+        vertex_stage,
+        fragment_stage,
+        color_attachment_formats,
+        depth_attachment_format,
+        configure_pipeline,
+    )?))
+}
+
+///Registry of pipelines that are rebuilt whenever their shader files change. See the [module](self) documentation.
+#[derive(Default)]
+pub struct PipelineRegistry {
+    entries: Vec<Entry>,
+}
+
+impl PipelineRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///Creates a compute pipeline from the SPIR-V file at `path` and watches it for changes.
+    pub fn add_compute(
+        &mut self,
+        rmg: &Rmg,
+        path: impl AsRef<Path>,
+        entry_point: &str,
+        specialization: Specialization,
+    ) -> Result<PipelineId, RmgError> {
+        let shader = WatchedFile::new(path.as_ref());
+        let pipeline = build_compute(rmg, &shader, entry_point, &specialization)?;
+        self.entries.push(Entry::Compute {
+            shader,
+            entry_point: entry_point.to_owned(),
+            specialization,
+            pipeline,
+        });
+        Ok(PipelineId(self.entries.len() - 1))
+    }
+
+    ///Creates a raster pipeline (see [`Rmg::new_raster_pipeline`]) from the SPIR-V files at `vertex_path` and `fragment_path`,
+    /// and watches both for changes. Each stage is specialized with its own specialization, which is kept for reloads.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_raster(
+        &mut self,
+        rmg: &Rmg,
+        vertex_path: impl AsRef<Path>,
+        vertex_entry_point: &str,
+        vertex_specialization: Specialization,
+        fragment_path: impl AsRef<Path>,
+        fragment_entry_point: &str,
+        fragment_specialization: Specialization,
+        color_attachment_formats: impl Into<SmallVec<[vk::Format; 4]>>,
+        depth_attachment_format: Option<vk::Format>,
+        configure_pipeline: fn(
+            vk::GraphicsPipelineCreateInfo<'_>,
+        ) -> vk::GraphicsPipelineCreateInfo<'_>,
+    ) -> Result<PipelineId, RmgError> {
+        let vertex = WatchedFile::new(vertex_path.as_ref());
+        let fragment = WatchedFile::new(fragment_path.as_ref());
+        let color_attachment_formats = color_attachment_formats.into();
+        let pipeline = build_raster(
+            rmg,
+            (&vertex, vertex_entry_point, &vertex_specialization),
+            (&fragment, fragment_entry_point, &fragment_specialization),
+            color_attachment_formats.clone(),
+            depth_attachment_format,
+            configure_pipeline,
+        )?;
+        self.entries.push(Entry::Raster {
+            vertex,
+            vertex_entry_point: vertex_entry_point.to_owned(),
+            vertex_specialization,
+            fragment,
+            fragment_entry_point: fragment_entry_point.to_owned(),
+            fragment_specialization,
+            color_attachment_formats,
+            depth_attachment_format,
+            configure_pipeline,
+            pipeline,
+        });
+        Ok(PipelineId(self.entries.len() - 1))
+    }
+
+    ///Returns the current compute pipeline of `id`, or None if `id` is not a compute pipeline.
+    pub fn compute_pipeline(&self, id: PipelineId) -> Option<Arc<ComputePipeline>> {
+        if let Some(Entry::Compute { pipeline, .. }) = self.entries.get(id.0) {
+            Some(pipeline.clone())
+        } else {
+            None
+        }
+    }
+
+    ///Returns the current raster pipeline of `id`, or None if `id` is not a raster pipeline.
+    pub fn raster_pipeline(&self, id: PipelineId) -> Option<Arc<RasterPipeline>> {
+        if let Some(Entry::Raster { pipeline, .. }) = self.entries.get(id.0) {
+            Some(pipeline.clone())
+        } else {
+            None
+        }
+    }
+
+    ///Checks all watched files and rebuilds the pipelines of changed files. Should be called between frames.
+    ///
+    /// Pipelines that fail to rebuild keep their old version. Use [`update_compute_pass`](Self::update_compute_pass) and
+    /// [`update_raster_pass`](Self::update_raster_pass) to swap reloaded pipelines into passes.
+    pub fn poll(&mut self, rmg: &Rmg) -> Vec<ReloadEvent> {
+        let mut events = Vec::new();
+        for (idx, entry) in self.entries.iter_mut().enumerate() {
+            let id = PipelineId(idx);
+            match entry.reload_if_changed(rmg) {
+                None => {}
+                Some(Ok(())) => {
+                    #[cfg(feature = "logging")]
+                    log::info!("Reloaded pipeline {:?}", id);
+                    events.push(ReloadEvent::Reloaded(id));
+                }
+                Some(Err(error)) => {
+                    #[cfg(feature = "logging")]
+                    log::error!("Failed to reload pipeline {:?}: {}", id, error);
+                    events.push(ReloadEvent::Failed { id, error });
+                }
+            }
+        }
+        events
+    }
+
+    ///Swaps the current pipeline of `id` into `pass`, if the pass uses an outdated one. Returns true if the pipeline was swapped.
+    ///
+    /// Note that the push constant and resources of `pass` must still match the reloaded shader.
+    pub fn update_compute_pass<P: 'static>(
+        &self,
+        id: PipelineId,
+        pass: &mut GenericComputePass<P>,
+    ) -> bool {
+        let Some(Entry::Compute { pipeline, .. }) = self.entries.get(id.0) else {
+            return false;
+        };
+        if let ComputeProgram::Pipeline(current) = pass.program()
+            && Arc::ptr_eq(pipeline, &current)
+        {
+            return false;
+        }
+        pass.swap_pipeline(pipeline.clone());
+        true
+    }
+
+    ///Swaps the current pipeline of `id` into `pass`, if the pass uses an outdated one. Returns true if the pipeline was swapped.
+    ///
+    /// Note that the push constant and resources of `pass` must still match the reloaded shaders.
+    pub fn update_raster_pass<P: Default + Clone + 'static>(
+        &self,
+        id: PipelineId,
+        pass: &mut GenericRasterPass<P>,
+    ) -> bool {
+        let Some(Entry::Raster { pipeline, .. }) = self.entries.get(id.0) else {
+            return false;
+        };
+        if let RasterProgram::Pipeline(current) = pass.program()
+            && Arc::ptr_eq(&pipeline.inner, &current.inner)
+        {
+            return false;
+        }
+        pass.swap_pipeline(pipeline.clone());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WatchedFile;
+    use std::{
+        fs::File,
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    };
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "marpii-rmg-hot-reload-{}-{}",
+            std::process::id(),
+            name
+        ));
+        File::create(&path).unwrap();
+        path
+    }
+
+    fn touch(path: &PathBuf, secs: u64) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn watched_file_changed() {
+        let path = temp_file("changed");
+        let mut file = WatchedFile::new(&path);
+        assert!(!file.changed());
+
+        touch(&path, 1000);
+        assert!(file.changed());
+        assert!(!file.changed());
+
+        //a file that is being replaced is not reported until it is back
+        std::fs::remove_file(&path).unwrap();
+        assert!(!file.changed());
+        File::create(&path).unwrap();
+        touch(&path, 2000);
+        assert!(file.changed());
+        assert!(!file.changed());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn any_changed_updates_all_files() {
+        let vertex_path = temp_file("vertex");
+        let fragment_path = temp_file("fragment");
+        let mut vertex = WatchedFile::new(&vertex_path);
+        let mut fragment = WatchedFile::new(&fragment_path);
+        assert!(!WatchedFile::any_changed([&mut vertex, &mut fragment]));
+
+        //both changed, both timestamps must be taken, so the next poll doesn't reload again
+        touch(&vertex_path, 1000);
+        touch(&fragment_path, 1000);
+        assert!(WatchedFile::any_changed([&mut vertex, &mut fragment]));
+        assert!(!WatchedFile::any_changed([&mut vertex, &mut fragment]));
+
+        touch(&fragment_path, 2000);
+        assert!(WatchedFile::any_changed([&mut vertex, &mut fragment]));
+        assert!(!WatchedFile::any_changed([&mut vertex, &mut fragment]));
+
+        std::fs::remove_file(&vertex_path).unwrap();
+        std::fs::remove_file(&fragment_path).unwrap();
+    }
+}
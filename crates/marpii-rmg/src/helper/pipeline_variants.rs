@@ -0,0 +1,168 @@
+//! Caches for pipelines that only differ in their specialization constants.
+//!
+//! Instead of compiling one SPIR-V file per variant (workgroup size, feature toggles etc.), the shader module is loaded
+//! once and a pipeline is created for each [`Specialization`] on first use.
+
+use crate::{RecordError, Rmg, RmgError, helper::rasterpass::RasterPipeline};
+use ahash::AHashMap;
+use marpii::{
+    OoS,
+    ash::vk,
+    resources::{ComputePipeline, ShaderModule, ShaderStage, Specialization},
+};
+use smallvec::SmallVec;
+use std::sync::Arc;
+
+///Compute pipelines of a single shader entry point, keyed by their specialization.
+///
+/// ```ignore
+/// let mut variants = ComputePipelineVariants::new(&rmg, "main", SHADER)?;
+/// let pipeline = variants.get(&rmg, &Specialization::new().with(0, 64u32))?;
+/// ```
+pub struct ComputePipelineVariants {
+    module: Arc<ShaderModule>,
+    entry_point: String,
+    variants: AHashMap<Specialization, Arc<ComputePipeline>>,
+}
+
+impl ComputePipelineVariants {
+    pub fn new(rmg: &Rmg, entry_point: &str, shader_code: &[u8]) -> Result<Self, RmgError> {
+        let module = ShaderModule::new_from_bytes(&rmg.ctx.device, shader_code)
+            .map_err(|e| RecordError::MarpiiError(e.into()))?;
+        Ok(ComputePipelineVariants {
+            module: Arc::new(module),
+            entry_point: entry_point.to_owned(),
+            variants: AHashMap::default(),
+        })
+    }
+
+    ///Returns the pipeline for `specialization`. Creates it, if this variant wasn't requested before.
+    pub fn get(
+        &mut self,
+        rmg: &Rmg,
+        specialization: &Specialization,
+    ) -> Result<Arc<ComputePipeline>, RmgError> {
+        if let Some(pipeline) = self.variants.get(specialization) {
+            return Ok(pipeline.clone());
+        }
+
+        let stage = ShaderStage::from_module(
+            OoS::new_shared(self.module.clone()),
+            vk::ShaderStageFlags::COMPUTE,
+            self.entry_point.clone(),
+        )
+        .with_specialization(specialization.clone());
+        let pipeline = rmg.compute_pipeline_from_stage(&stage)?;
+        self.variants
+            .insert(specialization.clone(), pipeline.clone());
+        Ok(pipeline)
+    }
+
+    ///Number of cached variants.
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    ///Drops all cached variants. Passes that still use a variant keep it alive.
+    pub fn clear(&mut self) {
+        self.variants.clear();
+    }
+}
+
+///Raster pipelines of a vertex and fragment shader, keyed by the specialization of both stages. All variants share
+/// the attachment formats and the configuration of [`Rmg::new_raster_pipeline`].
+pub struct RasterPipelineVariants {
+    vertex: (Arc<ShaderModule>, String),
+    fragment: (Arc<ShaderModule>, String),
+    color_attachment_formats: SmallVec<[vk::Format; 4]>,
+    depth_attachment_format: Option<vk::Format>,
+    configure_pipeline:
+        fn(vk::GraphicsPipelineCreateInfo<'_>) -> vk::GraphicsPipelineCreateInfo<'_>,
+    variants: AHashMap<(Specialization, Specialization), Arc<RasterPipeline>>,
+}
+
+impl RasterPipelineVariants {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        rmg: &Rmg,
+        vertex_entry_point: &str,
+        vertex_shader_code: &[u8],
+        fragment_entry_point: &str,
+        fragment_shader_code: &[u8],
+        color_attachment_formats: impl Into<SmallVec<[vk::Format; 4]>>,
+        depth_attachment_format: Option<vk::Format>,
+        configure_pipeline: fn(
+            vk::GraphicsPipelineCreateInfo<'_>,
+        ) -> vk::GraphicsPipelineCreateInfo<'_>,
+    ) -> Result<Self, RmgError> {
+        let vertex = ShaderModule::new_from_bytes(&rmg.ctx.device, vertex_shader_code)
+            .map_err(|e| RecordError::MarpiiError(e.into()))?;
+        let fragment = ShaderModule::new_from_bytes(&rmg.ctx.device, fragment_shader_code)
+            .map_err(|e| RecordError::MarpiiError(e.into()))?;
+        Ok(RasterPipelineVariants {
+            vertex: (Arc::new(vertex), vertex_entry_point.to_owned()),
+            fragment: (Arc::new(fragment), fragment_entry_point.to_owned()),
+            color_attachment_formats: color_attachment_formats.into(),
+            depth_attachment_format,
+            configure_pipeline,
+            variants: AHashMap::default(),
+        })
+    }
+
+    ///Returns the pipeline for the given vertex and fragment specialization. Creates it, if this variant wasn't requested before.
+    pub fn get(
+        &mut self,
+        rmg: &Rmg,
+        vertex_specialization: &Specialization,
+        fragment_specialization: &Specialization,
+    ) -> Result<Arc<RasterPipeline>, RmgError> {
+        let key = (
+            vertex_specialization.clone(),
+            fragment_specialization.clone(),
+        );
+        if let Some(pipeline) = self.variants.get(&key) {
+            return Ok(pipeline.clone());
+        }
+
+        let vertex_stage = ShaderStage::from_module(
+            OoS::new_shared(self.vertex.0.clone()),
+            vk::ShaderStageFlags::VERTEX,
+            self.vertex.1.clone(),
+        )
+        .with_specialization(key.0.clone());
+        let fragment_stage = ShaderStage::from_module(
+            OoS::new_shared(self.fragment.0.clone()),
+            vk::ShaderStageFlags::FRAGMENT,
+            self.fragment.1.clone(),
+        )
+        .with_specialization(key.1.clone());
+
+        let pipeline = Arc::new(rmg.new_raster_pipeline_from_stages(
+            vertex_stage,
+            fragment_stage,
+            self.color_attachment_formats.clone(),
+            self.depth_attachment_format,
+            self.configure_pipeline,
+        )?);
+        self.variants.insert(key, pipeline.clone());
+        Ok(pipeline)
+    }
+
+    ///Number of cached variants.
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    ///Drops all cached variants. Passes that still use a variant keep it alive.
+    pub fn clear(&mut self) {
+        self.variants.clear();
+    }
+}
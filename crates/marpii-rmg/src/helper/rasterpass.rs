@@ -1,12 +1,15 @@
 use crate::{
-    BufferHandle, ImageHandle, ResourceError, ResourceRegistry, Resources, Rmg, RmgError,
-    SamplerHandle, Task,
-    helper::{BufferUsage, ImageUsage, ResourceRegister},
+    BufferHandle, CtxRmg, ImageHandle, RecordError, ResourceError, ResourceRegistry, Resources,
+    Rmg, RmgError, SamplerHandle, Task,
+    helper::{BufferUsage, ImageUsage, ResourceRegister, check_push_constant},
 };
 use marpii::{
     MarpiiError, OoS,
     ash::vk,
-    resources::{GraphicsPipeline, PushConstant, ShaderModule, ShaderStage},
+    resources::{
+        BlendPreset, GraphicsPipeline, GraphicsPipelineBuilder, ImageView, PushConstant,
+        ShaderModule, ShaderObject, ShaderStage,
+    },
     util::ImageRegion,
 };
 use smallvec::SmallVec;
@@ -21,6 +24,235 @@ pub struct RasterPipeline {
     depth_stencil_attachment: Option<vk::Format>,
 }
 
+///Vertex and fragment [ShaderObject]s, and the fixed function state that is set while drawing with them. This is the
+/// `VK_EXT_shader_object` counterpart to [RasterPipeline]. Since nothing is baked, changing the state does not compile anything.
+///
+/// By default triangle lists are drawn filled, without culling and with counter clockwise front faces. Color attachments use
+/// alpha blending, and the depth test (`LESS`, with writes) is enabled if a depth attachment is set. Just like a [RasterPipeline], no vertex input is used.
+pub struct RasterShaders {
+    pub vertex: Arc<ShaderObject>,
+    pub fragment: Arc<ShaderObject>,
+    color_attachments: SmallVec<[vk::Format; 4]>,
+    depth_stencil_attachment: Option<vk::Format>,
+    ///Blend state of each color attachment.
+    pub color_blend: SmallVec<[vk::PipelineColorBlendAttachmentState; 4]>,
+    pub topology: vk::PrimitiveTopology,
+    pub polygon_mode: vk::PolygonMode,
+    pub cull_mode: vk::CullModeFlags,
+    pub front_face: vk::FrontFace,
+    ///Compare operation and depth write, `None` if depth testing is disabled.
+    pub depth_test: Option<(vk::CompareOp, bool)>,
+}
+
+impl RasterShaders {
+    ///Uses `preset` for the color attachment at `index`.
+    pub fn with_blend(mut self, index: usize, preset: BlendPreset) -> Self {
+        self.color_blend[index] = preset.attachment_state();
+        self
+    }
+
+    pub fn with_topology(mut self, topology: vk::PrimitiveTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_polygon_mode(mut self, polygon_mode: vk::PolygonMode) -> Self {
+        self.polygon_mode = polygon_mode;
+        self
+    }
+
+    pub fn with_cull_mode(mut self, cull_mode: vk::CullModeFlags) -> Self {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn with_front_face(mut self, front_face: vk::FrontFace) -> Self {
+        self.front_face = front_face;
+        self
+    }
+
+    ///Sets the depth test. `None` disables it.
+    pub fn with_depth_test(mut self, depth_test: Option<(vk::CompareOp, bool)>) -> Self {
+        self.depth_test = depth_test;
+        self
+    }
+
+    ///Returns the graphics stages that are bound when drawing with shader objects on a device with the enabled `features`.
+    /// Tessellation and geometry stages may only be bound (to null) if their feature is enabled.
+    fn bound_stages(features: &vk::PhysicalDeviceFeatures) -> SmallVec<[vk::ShaderStageFlags; 5]> {
+        let mut stages = SmallVec::new();
+        stages.push(vk::ShaderStageFlags::VERTEX);
+        if features.tessellation_shader == vk::TRUE {
+            stages.push(vk::ShaderStageFlags::TESSELLATION_CONTROL);
+            stages.push(vk::ShaderStageFlags::TESSELLATION_EVALUATION);
+        }
+        if features.geometry_shader == vk::TRUE {
+            stages.push(vk::ShaderStageFlags::GEOMETRY);
+        }
+        stages.push(vk::ShaderStageFlags::FRAGMENT);
+        stages
+    }
+
+    ///Binds the shaders and sets all state drawing with shader objects requires. Stages the pass does not use are
+    /// bound to null, see [bound_stages](Self::bound_stages).
+    unsafe fn bind(
+        &self,
+        device: &marpii::context::Device,
+        command_buffer: vk::CommandBuffer,
+        area: &ImageRegion,
+    ) {
+        let loader = &self.vertex.loader;
+        let stages = Self::bound_stages(&device.enabled_features);
+        let shaders = stages
+            .iter()
+            .map(|stage| match *stage {
+                vk::ShaderStageFlags::VERTEX => self.vertex.inner,
+                vk::ShaderStageFlags::FRAGMENT => self.fragment.inner,
+                _ => vk::ShaderEXT::null(),
+            })
+            .collect::<SmallVec<[_; 5]>>();
+        unsafe {
+            loader.cmd_bind_shaders(command_buffer, &stages, &shaders);
+
+            loader.cmd_set_viewport_with_count(command_buffer, &[area.as_viewport()]);
+            loader.cmd_set_scissor_with_count(command_buffer, &[area.as_rect_2d()]);
+
+            //input assembly
+            loader.cmd_set_vertex_input(command_buffer, &[], &[]);
+            loader.cmd_set_primitive_topology(command_buffer, self.topology);
+            loader.cmd_set_primitive_restart_enable(command_buffer, false);
+
+            //rasterization
+            loader.cmd_set_rasterizer_discard_enable(command_buffer, false);
+            loader.cmd_set_polygon_mode(command_buffer, self.polygon_mode);
+            loader.cmd_set_cull_mode(command_buffer, self.cull_mode);
+            loader.cmd_set_front_face(command_buffer, self.front_face);
+            loader.cmd_set_depth_clamp_enable(command_buffer, false);
+            loader.cmd_set_depth_bias_enable(command_buffer, false);
+            device.inner.cmd_set_line_width(command_buffer, 1.0);
+
+            //multisampling
+            loader.cmd_set_rasterization_samples(command_buffer, vk::SampleCountFlags::TYPE_1);
+            loader.cmd_set_sample_mask(
+                command_buffer,
+                vk::SampleCountFlags::TYPE_1,
+                &[vk::SampleMask::MAX],
+            );
+            loader.cmd_set_alpha_to_coverage_enable(command_buffer, false);
+            loader.cmd_set_alpha_to_one_enable(command_buffer, false);
+
+            //depth / stencil
+            let depth_test = self.depth_stencil_attachment.and(self.depth_test);
+            loader.cmd_set_depth_test_enable(command_buffer, depth_test.is_some());
+            loader.cmd_set_depth_write_enable(
+                command_buffer,
+                depth_test.map(|(_, write)| write).unwrap_or(false),
+            );
+            if let Some((compare_op, _)) = depth_test {
+                loader.cmd_set_depth_compare_op(command_buffer, compare_op);
+            }
+            loader.cmd_set_depth_bounds_test_enable(command_buffer, false);
+            loader.cmd_set_stencil_test_enable(command_buffer, false);
+
+            //color blending
+            loader.cmd_set_logic_op_enable(command_buffer, false);
+            if !self.color_blend.is_empty() {
+                let enables = self
+                    .color_blend
+                    .iter()
+                    .map(|b| b.blend_enable)
+                    .collect::<SmallVec<[_; 4]>>();
+                let equations = self
+                    .color_blend
+                    .iter()
+                    .map(|b| {
+                        vk::ColorBlendEquationEXT::default()
+                            .src_color_blend_factor(b.src_color_blend_factor)
+                            .dst_color_blend_factor(b.dst_color_blend_factor)
+                            .color_blend_op(b.color_blend_op)
+                            .src_alpha_blend_factor(b.src_alpha_blend_factor)
+                            .dst_alpha_blend_factor(b.dst_alpha_blend_factor)
+                            .alpha_blend_op(b.alpha_blend_op)
+                    })
+                    .collect::<SmallVec<[_; 4]>>();
+                let write_masks = self
+                    .color_blend
+                    .iter()
+                    .map(|b| b.color_write_mask)
+                    .collect::<SmallVec<[_; 4]>>();
+                loader.cmd_set_color_blend_enable(command_buffer, 0, &enables);
+                loader.cmd_set_color_blend_equation(command_buffer, 0, &equations);
+                loader.cmd_set_color_write_mask(command_buffer, 0, &write_masks);
+            }
+        }
+    }
+}
+
+///What a [GenericRasterPass] draws with. Either a baked [RasterPipeline], or [RasterShaders] that are bound together with
+/// their state.
+pub enum RasterProgram {
+    Pipeline(OoS<RasterPipeline>),
+    Shaders(OoS<RasterShaders>),
+}
+
+impl RasterProgram {
+    fn color_attachments(&self) -> &[vk::Format] {
+        match self {
+            Self::Pipeline(pipeline) => &pipeline.color_attachments,
+            Self::Shaders(shaders) => &shaders.color_attachments,
+        }
+    }
+
+    fn depth_stencil_attachment(&self) -> Option<&vk::Format> {
+        match self {
+            Self::Pipeline(pipeline) => pipeline.depth_stencil_attachment.as_ref(),
+            Self::Shaders(shaders) => shaders.depth_stencil_attachment.as_ref(),
+        }
+    }
+
+    fn push_constant_size(&self) -> Option<u32> {
+        match self {
+            Self::Pipeline(pipeline) => pipeline.inner.push_constant_size,
+            Self::Shaders(shaders) => shaders
+                .vertex
+                .push_constant_size
+                .max(shaders.fragment.push_constant_size),
+        }
+    }
+
+    fn share(&mut self) -> Self {
+        match self {
+            Self::Pipeline(pipeline) => Self::Pipeline(pipeline.share()),
+            Self::Shaders(shaders) => Self::Shaders(shaders.share()),
+        }
+    }
+}
+
+macro_rules! impl_raster_program_from {
+    ($ty:ty, $variant:ident) => {
+        impl From<$ty> for RasterProgram {
+            fn from(value: $ty) -> Self {
+                Self::$variant(OoS::new(value))
+            }
+        }
+
+        impl From<Arc<$ty>> for RasterProgram {
+            fn from(value: Arc<$ty>) -> Self {
+                Self::$variant(OoS::new_shared(value))
+            }
+        }
+
+        impl From<OoS<$ty>> for RasterProgram {
+            fn from(value: OoS<$ty>) -> Self {
+                Self::$variant(value)
+            }
+        }
+    };
+}
+
+impl_raster_program_from!(RasterPipeline, Pipeline);
+impl_raster_program_from!(RasterShaders, Shaders);
+
 pub enum RasterDrawCall<P: 'static> {
     ///A simple draw call using the given index buffer
     Simple {
@@ -64,7 +296,7 @@ type AttachmentInfoAnd<T> = (
 ///A generic raster pass that uses a vertex+fragment shader
 /// to generate the content of a set of images.
 pub struct GenericRasterPass<P: Default + Clone + 'static> {
-    pipeline: OoS<RasterPipeline>,
+    pipeline: RasterProgram,
     push: PushConstant<P>,
     name: Option<String>,
 
@@ -74,12 +306,16 @@ pub struct GenericRasterPass<P: Default + Clone + 'static> {
     storage: ResourceRegister,
     framebuffer_area: ImageRegion,
     drawcalls: SmallVec<[(RasterDrawCall<P>, Option<ImageRegion>); 16]>,
+
+    ///Views of the color and depth attachments, resolved while recording.
+    color_views: SmallVec<[Arc<ImageView>; 4]>,
+    depth_view: Option<Arc<ImageView>>,
 }
 
 impl GenericRasterPass<()> {
-    pub fn init(pipeline: impl Into<OoS<RasterPipeline>>) -> Self {
+    pub fn init(pipeline: impl Into<RasterProgram>) -> Self {
         let pipeline = pipeline.into();
-        let color_attachment_count = pipeline.color_attachments.len();
+        let color_attachment_count = pipeline.color_attachments().len();
         GenericRasterPass {
             pipeline,
             push: PushConstant::new((), vk::ShaderStageFlags::ALL),
@@ -89,6 +325,8 @@ impl GenericRasterPass<()> {
             framebuffer_area: ImageRegion::ZERO,
             storage: ResourceRegister::new(),
             drawcalls: SmallVec::default(),
+            color_views: SmallVec::default(),
+            depth_view: None,
         }
     }
 }
@@ -103,10 +341,48 @@ impl<P: Default + Clone + 'static> GenericRasterPass<P> {
 
     ///Shares the internal raster-pipeline object. Good if you want to create another
     /// pass based on the same pipeline for instance
+    ///
+    /// Panics if the pass draws with [RasterShaders], see [clone_shaders](Self::clone_shaders) and [clone_program](Self::clone_program).
     pub fn clone_pipeline(&mut self) -> OoS<RasterPipeline> {
+        match &mut self.pipeline {
+            RasterProgram::Pipeline(pipeline) => pipeline.share(),
+            RasterProgram::Shaders(_) => {
+                panic!("Raster pass draws with shader objects, not a pipeline")
+            }
+        }
+    }
+
+    ///Shares the internal [RasterShaders], or returns `None` if the pass draws with a [RasterPipeline].
+    pub fn clone_shaders(&mut self) -> Option<OoS<RasterShaders>> {
+        if let RasterProgram::Shaders(shaders) = &mut self.pipeline {
+            Some(shaders.share())
+        } else {
+            None
+        }
+    }
+
+    ///Shares the internal program, regardless of whether it is a pipeline or a set of shader objects.
+    pub fn clone_program(&mut self) -> RasterProgram {
         self.pipeline.share()
     }
 
+    ///Returns the program the pass currently draws with.
+    pub fn program(&self) -> &RasterProgram {
+        &self.pipeline
+    }
+
+    ///Swaps out the pipeline used for drawing. This can also switch between a [RasterPipeline] and [RasterShaders].
+    ///
+    /// Its your resonsibility to make sure that the pipeline object is compatible with the attachments, push constant etc.
+    pub fn swap_pipeline(&mut self, pipeline: impl Into<RasterProgram>) {
+        let pipeline = pipeline.into();
+        debug_assert!(
+            pipeline.color_attachments().len() == self.color_attachments.len(),
+            "Swapped pipeline uses a different number of color attachments"
+        );
+        self.pipeline = pipeline;
+    }
+
     ///Allows the reconfiguration of the render-pass. If `keep_attachments` is true, it won't delete knowledge
     /// about used color/depth attachments, i.e. you don't have to re-record those.
     pub fn reconfigure<'rmg>(
@@ -134,8 +410,8 @@ impl<P: Default + Clone + 'static> GenericRasterPass<P> {
     }
 
     //Helper to create a temporary, empty copy of Self
-    fn init_empty(pipeline: OoS<RasterPipeline>) -> Self {
-        let color_attachment_count = pipeline.color_attachments.len();
+    fn init_empty(pipeline: RasterProgram) -> Self {
+        let color_attachment_count = pipeline.color_attachments().len();
         GenericRasterPass {
             pipeline,
             push: PushConstant::new(P::default(), vk::ShaderStageFlags::ALL),
@@ -145,6 +421,8 @@ impl<P: Default + Clone + 'static> GenericRasterPass<P> {
             framebuffer_area: ImageRegion::ZERO,
             storage: ResourceRegister::new(),
             drawcalls: SmallVec::default(),
+            color_views: SmallVec::default(),
+            depth_view: None,
         }
     }
 
@@ -177,6 +455,27 @@ impl<P: Default + Clone + 'static> Task for GenericRasterPass<P> {
         vk::QueueFlags::GRAPHICS
     }
 
+    fn pre_record(&mut self, resources: &mut Resources, _ctx: &CtxRmg) -> Result<(), RecordError> {
+        //resolve the attachment views. Views of derived attachment handles are created on first use.
+        self.color_views.clear();
+        for (idx, attachment) in self.color_attachments.iter().enumerate() {
+            let Some(attachment) = attachment else {
+                return Err(RecordError::GenericPassError(format!(
+                    "Color attachment {} of {} is not set",
+                    idx,
+                    self.name()
+                )));
+            };
+            self.color_views.push(resources.image_view(&attachment.0)?);
+        }
+        self.depth_view = self
+            .depth_attachment
+            .as_ref()
+            .map(|depth| resources.image_view(&depth.0))
+            .transpose()?;
+        Ok(())
+    }
+
     fn register(&self, registry: &mut ResourceRegistry) {
         for attachment in &self.color_attachments {
             let (image, usage, _, _, _) = attachment.as_ref().unwrap();
@@ -214,7 +513,13 @@ impl<P: Default + Clone + 'static> Task for GenericRasterPass<P> {
 
         //now enqueue all standard resources and the pipeline
         self.storage.register_all(registry);
-        registry.register_asset(self.pipeline.inner.clone());
+        match &self.pipeline {
+            RasterProgram::Pipeline(pipeline) => registry.register_asset(pipeline.inner.clone()),
+            RasterProgram::Shaders(shaders) => {
+                registry.register_asset(shaders.vertex.clone());
+                registry.register_asset(shaders.fragment.clone());
+            }
+        }
     }
 
     fn record(
@@ -225,10 +530,13 @@ impl<P: Default + Clone + 'static> Task for GenericRasterPass<P> {
     ) {
         //1. transform attachment images
         let mut color_attachments: SmallVec<[_; 4]> = SmallVec::default();
-        for color_attachment in &self.color_attachments {
-            let color_attachment = color_attachment.as_ref().unwrap();
-            let colorview = resources.get_image_state(&color_attachment.0).view.clone();
-
+        //NOTE: pre_record made sure all color attachments are set, and resolved their views.
+        for (color_attachment, colorview) in self
+            .color_attachments
+            .iter()
+            .flatten()
+            .zip(self.color_views.iter())
+        {
             let ca = vk::RenderingAttachmentInfo::default()
                 .clear_value(vk::ClearValue {
                     color: vk::ClearColorValue {
@@ -252,9 +560,7 @@ impl<P: Default + Clone + 'static> Task for GenericRasterPass<P> {
 
         //set a depth attchment, if it was defined
         let da;
-        render_info = if let Some(depth) = &self.depth_attachment {
-            let depthview = resources.get_image_state(&depth.0).view.clone();
-
+        render_info = if let Some(depthview) = &self.depth_view {
             da = vk::RenderingAttachmentInfo::default()
                 .clear_value(vk::ClearValue {
                     depth_stencil: vk::ClearDepthStencilValue {
@@ -281,20 +587,37 @@ impl<P: Default + Clone + 'static> Task for GenericRasterPass<P> {
                 .inner
                 .cmd_begin_rendering(*command_buffer, &render_info);
 
-            device.inner.cmd_bind_pipeline(
-                *command_buffer,
-                vk::PipelineBindPoint::GRAPHICS,
-                self.pipeline.inner.pipeline,
-            );
+            match &self.pipeline {
+                RasterProgram::Pipeline(pipeline) => {
+                    device.inner.cmd_bind_pipeline(
+                        *command_buffer,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        pipeline.inner.pipeline,
+                    );
 
-            device
-                .inner
-                .cmd_set_viewport(*command_buffer, 0, &[last_render_area.as_viewport()]);
-            device
-                .inner
-                .cmd_set_scissor(*command_buffer, 0, &[last_render_area.as_rect_2d()]);
+                    device.inner.cmd_set_viewport(
+                        *command_buffer,
+                        0,
+                        &[last_render_area.as_viewport()],
+                    );
+                    device.inner.cmd_set_scissor(
+                        *command_buffer,
+                        0,
+                        &[last_render_area.as_rect_2d()],
+                    );
+                }
+                RasterProgram::Shaders(shaders) => {
+                    shaders.bind(device, *command_buffer, &last_render_area);
+                }
+            }
         }
 
+        //NOTE: shader objects are created with the bindless set layouts and push range
+        let push_layout = match &self.pipeline {
+            RasterProgram::Pipeline(pipeline) => pipeline.inner.layout.layout,
+            RasterProgram::Shaders(_) => resources.bindless_layout.layout,
+        };
+
         for (draw, render_area) in &self.drawcalls {
             let area = render_area.unwrap_or(self.framebuffer_area);
             //change the render_area if it doesn't track
@@ -318,7 +641,7 @@ impl<P: Default + Clone + 'static> Task for GenericRasterPass<P> {
             unsafe {
                 device.inner.cmd_push_constants(
                     *command_buffer,
-                    self.pipeline.inner.layout.layout,
+                    push_layout,
                     vk::ShaderStageFlags::ALL,
                     0,
                     self.push.content_as_bytes(),
@@ -373,14 +696,29 @@ pub struct RasterPassBuilder<'rmg, P: Default + Clone + 'static> {
 }
 
 impl<'rmg, P: Default + Clone + 'static> RasterPassBuilder<'rmg, P> {
+    ///Same as [`try_with_push_constant`](Self::try_with_push_constant), but panics if `PC` does not fit the pipeline's
+    /// push constant block or the device limit.
+    #[deprecated(
+        note = "use `try_with_push_constant`, which reports push constant mismatches as error"
+    )]
+    pub fn with_push_constant<PC: Default + Clone + 'static>(self) -> RasterPassBuilder<'rmg, PC> {
+        self.try_with_push_constant()
+            .unwrap_or_else(|e| panic!("Invalid push constant: {}", e))
+    }
+
     /// Changes the push constant definition for the pass. Removes any already recorded draw-calls,
     /// since those depend on a correct push-constant definition.
-    pub fn with_push_constant<PC: Default + Clone + 'static>(self) -> RasterPassBuilder<'rmg, PC> {
-        assert!(
-            std::mem::size_of::<PC>()
-                <= self.rmg.config().limit.limits.max_push_constants_size as usize,
-            "Push constant size exceeds limit"
-        );
+    ///
+    /// Fails if `PC` is smaller than the push constant block the pipeline's shaders declare, or exceeds the
+    /// device's push constant limit.
+    pub fn try_with_push_constant<PC: Default + Clone + 'static>(
+        self,
+    ) -> Result<RasterPassBuilder<'rmg, PC>, RecordError> {
+        check_push_constant::<PC>(
+            self.task_setup.pipeline.push_constant_size(),
+            self.rmg.config().limit.limits.max_push_constants_size,
+        )
+        .map_err(|e| RecordError::MarpiiError(e.into()))?;
 
         let GenericRasterPass {
             pipeline,
@@ -395,7 +733,7 @@ impl<'rmg, P: Default + Clone + 'static> RasterPassBuilder<'rmg, P> {
 
         let new_push_constant = PushConstant::new(PC::default(), vk::ShaderStageFlags::ALL);
 
-        RasterPassBuilder {
+        Ok(RasterPassBuilder {
             rmg: self.rmg,
             task_setup: GenericRasterPass {
                 pipeline,
@@ -406,8 +744,10 @@ impl<'rmg, P: Default + Clone + 'static> RasterPassBuilder<'rmg, P> {
                 framebuffer_area,
                 storage,
                 drawcalls: SmallVec::default(),
+                color_views: SmallVec::default(),
+                depth_view: None,
             },
-        }
+        })
     }
 
     pub fn on_rmg<T>(&mut self, func: impl Fn(&mut Rmg) -> T) -> T {
@@ -433,7 +773,7 @@ impl<'rmg, P: Default + Clone + 'static> RasterPassBuilder<'rmg, P> {
                 if let Some(expected) = self
                     .task_setup
                     .pipeline
-                    .color_attachments
+                    .color_attachments()
                     .get(attachment_index)
                 {
                     if expected != image.format() {
@@ -456,7 +796,7 @@ impl<'rmg, P: Default + Clone + 'static> RasterPassBuilder<'rmg, P> {
                 store_op,
                 clear_depth,
             } => {
-                if let Some(expected) = &self.task_setup.pipeline.depth_stencil_attachment {
+                if let Some(expected) = self.task_setup.pipeline.depth_stencil_attachment() {
                     if expected != image.format() {
                         return Err(RmgError::ResourceError(ResourceError::FormatMissmatch(
                             *expected,
@@ -570,9 +910,9 @@ impl<'rmg, P: Default + Clone + 'static> RasterPassBuilder<'rmg, P> {
 
         assert_eq!(
             self.task_setup.color_attachments.len(),
-            self.task_setup.pipeline.color_attachments.len()
+            self.task_setup.pipeline.color_attachments().len()
         );
-        for idx in 0..self.task_setup.pipeline.color_attachments.len() {
+        for idx in 0..self.task_setup.pipeline.color_attachments().len() {
             if self.task_setup.color_attachments[idx].is_none() {
                 return Err(RmgError::ResourceError(
                     ResourceError::InvalidAttachmentIndex(idx),
@@ -583,7 +923,7 @@ impl<'rmg, P: Default + Clone + 'static> RasterPassBuilder<'rmg, P> {
         //Make sure either both are set, or unset
         match (
             &self.task_setup.depth_attachment,
-            &self.task_setup.pipeline.depth_stencil_attachment,
+            self.task_setup.pipeline.depth_stencil_attachment(),
         ) {
             (Some(_), Some(_)) | (None, None) => {}
             (None, Some(_)) => {
@@ -642,9 +982,11 @@ impl<'rmg, P: Default + Clone + 'static> RasterPassBuilder<'rmg, P> {
 }
 
 impl Rmg {
+    ///Creates a new raster pass that draws with `pipeline`. That is either a [RasterPipeline], or [RasterShaders]
+    /// created via [new_raster_shaders](Self::new_raster_shaders).
     pub fn new_raster_pass<'rmg>(
         &'rmg mut self,
-        pipeline: impl Into<OoS<RasterPipeline>>,
+        pipeline: impl Into<RasterProgram>,
     ) -> RasterPassBuilder<'rmg, ()> {
         RasterPassBuilder {
             rmg: self,
@@ -666,6 +1008,9 @@ impl Rmg {
     /// The [`GenericRasterPass`] assumes that the scissors and viewport are dynamic state, and that the pipeline uses no
     /// vertex-inputs (i.e. no vertex buffer is supplied to the draw command, only a index buffer). Everything else
     /// can be configured. When in doubt, use validation-layers as always.
+    ///
+    /// Both shader stages are checked against the bindless layout. Incompatible descriptors, missing entry points or a too
+    /// big push constant block are reported as [`PipelineError`](marpii::PipelineError).
     #[allow(clippy::too_many_arguments)]
     pub fn new_raster_pipeline<'a>(
         &self,
@@ -679,9 +1024,6 @@ impl Rmg {
             vk::GraphicsPipelineCreateInfo<'_>,
         ) -> vk::GraphicsPipelineCreateInfo<'_>,
     ) -> Result<RasterPipeline, RmgError> {
-        let color_attachments = color_attachment_formats.into();
-        let depth_stencil_attachment = depth_attachment_format;
-
         let vertex_shader_stage = ShaderStage::from_module(
             vertex_shader.into(),
             vk::ShaderStageFlags::VERTEX,
@@ -694,78 +1036,54 @@ impl Rmg {
             fragment_entry_point.to_owned(),
         );
 
-        let color_blend_attachments = vk::PipelineColorBlendAttachmentState::default()
-            .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
-            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
-            .color_blend_op(vk::BlendOp::ADD)
-            .src_alpha_blend_factor(vk::BlendFactor::ONE)
-            .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
-            .alpha_blend_op(vk::BlendOp::ADD)
-            .color_write_mask(vk::ColorComponentFlags::RGBA)
-            .blend_enable(true);
-
-        let color_blend_state = vk::PipelineColorBlendStateCreateInfo::default()
-            .blend_constants([0.0; 4])
-            .attachments(core::slice::from_ref(&color_blend_attachments)); //only the color target
-
-        let depth_stencil_state = if depth_stencil_attachment.is_some() {
-            vk::PipelineDepthStencilStateCreateInfo::default()
-                .depth_compare_op(vk::CompareOp::LESS)
-                .depth_write_enable(true)
-                .depth_test_enable(true)
-                .depth_bounds_test_enable(false)
-                .stencil_test_enable(false)
-        } else {
-            vk::PipelineDepthStencilStateCreateInfo::default()
-        };
-
-        let dynamic_state = vk::PipelineDynamicStateCreateInfo::default()
-            .dynamic_states(&[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR]);
-        //no other dynamic state
-
-        let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::default()
-            .primitive_restart_enable(false)
-            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
-
-        let multisample_state = vk::PipelineMultisampleStateCreateInfo::default()
-            .rasterization_samples(vk::SampleCountFlags::TYPE_1);
-
-        let rasterization_state = vk::PipelineRasterizationStateCreateInfo::default()
-            .cull_mode(vk::CullModeFlags::NONE)
-            .depth_bias_enable(false)
-            .depth_clamp_enable(false)
-            .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
-            .polygon_mode(vk::PolygonMode::FILL)
-            .line_width(1.0);
-
-        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
-            .viewport_count(1)
-            .scissor_count(1);
-
-        let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::default();
-
-        let create_info = vk::GraphicsPipelineCreateInfo::default()
-            .color_blend_state(&color_blend_state)
-            .depth_stencil_state(&depth_stencil_state)
-            .dynamic_state(&dynamic_state)
-            .input_assembly_state(&input_assembly_state)
-            .multisample_state(&multisample_state)
-            .rasterization_state(&rasterization_state)
-            .viewport_state(&viewport_state)
-            .vertex_input_state(&vertex_input_state);
-
-        //Call the handler, if set
-        let create_info = configure_pipeline(create_info);
-
-        let pipeline = GraphicsPipeline::new_dynamic_pipeline(
-            &self.ctx.device,
-            create_info,
-            self.resources.bindless_layout(),
-            &[vertex_shader_stage, fragment_shader_stage],
-            &color_attachments,
+        self.new_raster_pipeline_from_stages(
+            vertex_shader_stage,
+            fragment_shader_stage,
+            color_attachment_formats,
             depth_attachment_format,
+            configure_pipeline,
         )
-        .map_err(MarpiiError::from)?;
+    }
+
+    ///Same as [`new_raster_pipeline`](Self::new_raster_pipeline), but uses already created shader stages. Use this
+    /// to apply [specialization constants](ShaderStage::with_specialization) to the stages.
+    pub fn new_raster_pipeline_from_stages(
+        &self,
+        vertex_shader_stage: ShaderStage,
+        fragment_shader_stage: ShaderStage,
+        color_attachment_formats: impl Into<SmallVec<[vk::Format; 4]>>,
+        depth_attachment_format: Option<vk::Format>,
+        configure_pipeline: impl FnOnce(
+            vk::GraphicsPipelineCreateInfo<'_>,
+        ) -> vk::GraphicsPipelineCreateInfo<'_>,
+    ) -> Result<RasterPipeline, RmgError> {
+        let color_attachments = color_attachment_formats.into();
+        let depth_stencil_attachment = depth_attachment_format;
+
+        self.resources
+            .validate_shader_stage(&vertex_shader_stage)
+            .map_err(MarpiiError::from)?;
+        self.resources
+            .validate_shader_stage(&fragment_shader_stage)
+            .map_err(MarpiiError::from)?;
+
+        let mut builder = GraphicsPipelineBuilder::new()
+            .with_stage(vertex_shader_stage)
+            .with_stage(fragment_shader_stage);
+        for format in color_attachments.iter() {
+            builder = builder.with_color_attachment(*format, BlendPreset::AlphaBlend);
+        }
+        if let Some(format) = depth_attachment_format {
+            builder = builder.with_depth_attachment(format);
+        }
+
+        let pipeline = builder
+            .build_with(
+                &self.ctx.device,
+                self.resources.bindless_layout(),
+                configure_pipeline,
+            )
+            .map_err(MarpiiError::from)?;
 
         Ok(RasterPipeline {
             inner: Arc::new(pipeline),
@@ -773,4 +1091,79 @@ impl Rmg {
             depth_stencil_attachment,
         })
     }
+
+    ///Creates [RasterShaders] for the given vertex and fragment stages. This is the shader object based alternative to
+    /// [new_raster_pipeline_from_stages](Self::new_raster_pipeline_from_stages). Both stages are compiled independently, so
+    /// they can be shared with other [RasterShaders] without creating any pipelines. The fixed function state is configured on
+    /// the returned value.
+    ///
+    /// Fails if `VK_EXT_shader_object` is not supported, see [Config::shader_object_support](crate::Config::shader_object_support).
+    pub fn new_raster_shaders(
+        &self,
+        vertex_shader_stage: &ShaderStage,
+        fragment_shader_stage: &ShaderStage,
+        color_attachment_formats: impl Into<SmallVec<[vk::Format; 4]>>,
+        depth_attachment_format: Option<vk::Format>,
+    ) -> Result<RasterShaders, RmgError> {
+        let vertex = self.shader_object(vertex_shader_stage, vk::ShaderStageFlags::FRAGMENT)?;
+        let fragment = self.shader_object(fragment_shader_stage, vk::ShaderStageFlags::empty())?;
+
+        let color_attachments = color_attachment_formats.into();
+        let color_blend = color_attachments
+            .iter()
+            .map(|_| BlendPreset::AlphaBlend.attachment_state())
+            .collect();
+
+        Ok(RasterShaders {
+            vertex,
+            fragment,
+            color_attachments,
+            depth_stencil_attachment: depth_attachment_format,
+            color_blend,
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            polygon_mode: vk::PolygonMode::FILL,
+            cull_mode: vk::CullModeFlags::NONE,
+            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+            depth_test: Some((vk::CompareOp::LESS, true)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RasterShaders;
+    use marpii::ash::vk;
+
+    #[test]
+    fn bound_stages_follow_features() {
+        let features = vk::PhysicalDeviceFeatures::default();
+        assert_eq!(
+            RasterShaders::bound_stages(&features).as_slice(),
+            &[vk::ShaderStageFlags::VERTEX, vk::ShaderStageFlags::FRAGMENT]
+        );
+
+        let features = vk::PhysicalDeviceFeatures::default().geometry_shader(true);
+        assert_eq!(
+            RasterShaders::bound_stages(&features).as_slice(),
+            &[
+                vk::ShaderStageFlags::VERTEX,
+                vk::ShaderStageFlags::GEOMETRY,
+                vk::ShaderStageFlags::FRAGMENT
+            ]
+        );
+
+        let features = vk::PhysicalDeviceFeatures::default()
+            .tessellation_shader(true)
+            .geometry_shader(true);
+        assert_eq!(
+            RasterShaders::bound_stages(&features).as_slice(),
+            &[
+                vk::ShaderStageFlags::VERTEX,
+                vk::ShaderStageFlags::TESSELLATION_CONTROL,
+                vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+                vk::ShaderStageFlags::GEOMETRY,
+                vk::ShaderStageFlags::FRAGMENT
+            ]
+        );
+    }
 }
@@ -165,11 +165,13 @@ mod resources;
 pub use resources::{
     ResourceError, Resources,
     handle::{BufferHandle, ImageHandle, SamplerHandle},
-    res_states::{ResBuffer, ResImage, ResSampler},
+    res_states::{ResBuffer, ResImage, ResImageView, ResSampler},
 };
 pub mod recorder;
 pub use recorder::{
     RecordError, Recorder,
+    encoder::CommandEncoder,
+    queries::{TaskId, TaskQueryResult},
     task::{MetaTask, ResourceRegistry, Task},
 };
 
@@ -1,14 +1,17 @@
+pub mod encoder;
+pub(crate) mod queries;
 pub mod task;
 pub(crate) mod task_executor;
 pub(crate) mod task_scheduler;
 
 use self::{
+    queries::{QueryBatch, TaskId},
     task::{MetaTask, ResourceRegistry},
     task_executor::Executor,
     task_scheduler::TaskSchedule,
 };
 use crate::{resources::handle::AnyHandle, track::Guard, ResourceError, Rmg, Task};
-use marpii::{ash::vk, resources::CommandBuffer, MarpiiError};
+use marpii::{ash::vk, resources::RecycledCommandBuffer, MarpiiError};
 use std::any::Any;
 use std::fmt::Debug;
 use thiserror::Error;
@@ -60,14 +63,17 @@ pub struct Execution {
     pub(crate) resources: Vec<Box<dyn Any + Send>>,
     ///The command buffer that is executed
     #[allow(dead_code)]
-    pub(crate) command_buffer: CommandBuffer,
+    pub(crate) command_buffer: RecycledCommandBuffer,
     ///Until when it is guarded.
     pub(crate) guard: Guard,
+    ///Queries of the executed tasks, read once the execution finished.
+    pub(crate) queries: Option<QueryBatch>,
 }
 
 pub struct TaskRecord<'t> {
     task: &'t mut dyn Task,
     registry: ResourceRegistry,
+    id: TaskId,
 }
 
 impl<'t> Debug for TaskRecord<'t> {
@@ -80,16 +86,25 @@ impl<'t> Debug for TaskRecord<'t> {
 pub struct Recorder<'rmg> {
     pub rmg: &'rmg mut Rmg,
     pub records: Vec<TaskRecord<'rmg>>,
+    submission: u64,
 }
 
 impl<'rmg> Recorder<'rmg> {
     pub fn new(rmg: &'rmg mut Rmg) -> Self {
+        let submission = rmg.next_submission;
+        rmg.next_submission += 1;
         Recorder {
             rmg,
             records: Vec::new(),
+            submission,
         }
     }
 
+    ///Index of this recording, see [TaskId::submission].
+    pub fn submission(&self) -> u64 {
+        self.submission
+    }
+
     ///Adds `task` to the execution plan's timeline.
     pub fn add_task(mut self, task: &'rmg mut dyn Task) -> Result<Self, RecordError> {
         task.pre_record(&mut self.rmg.resources, &self.rmg.ctx)?;
@@ -97,7 +112,14 @@ impl<'rmg> Recorder<'rmg> {
         let mut registry = ResourceRegistry::new();
         task.register(&mut registry);
 
-        let record = TaskRecord { task, registry };
+        let record = TaskRecord {
+            task,
+            registry,
+            id: TaskId {
+                submission: self.submission,
+                index: self.records.len(),
+            },
+        };
 
         self.records.push(record);
 
@@ -117,6 +139,11 @@ impl<'rmg> Recorder<'rmg> {
             track.inflight_executions.push(ex);
         }
 
+        //surface validation errors of the submission, if requested.
+        if let Some(dbg) = self.rmg.ctx.device.get_debugger() {
+            dbg.check_errors();
+        }
+
         Ok(())
     }
 
@@ -132,6 +159,10 @@ impl<'rmg> Recorder<'rmg> {
             track.inflight_executions.push(ex);
         }
 
+        if let Some(dbg) = self.rmg.ctx.device.get_debugger() {
+            dbg.check_errors();
+        }
+
         Ok(())
     }
 }
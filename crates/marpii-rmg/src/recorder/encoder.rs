@@ -0,0 +1,379 @@
+//! Typed command recording for [Task](crate::Task)s.
+//!
+//! The [CommandEncoder] records commands on the task's command buffer and takes RMG handles instead of raw Vulkan handles.
+//! Images are used in the layout the task requested them in. In debug builds each command asserts that the handles it uses were
+//! registered by the task with a compatible access.
+
+use crate::{BufferHandle, ImageHandle, Resources, recorder::task::ResourceRegistry};
+use marpii::{
+    ash::vk,
+    context::Device,
+    resources::{ComputePipeline, GraphicsPipeline, PushConstant},
+};
+use std::sync::Arc;
+
+///Returns true if `registered` covers `required`. A registered `MEMORY_READ`/`MEMORY_WRITE` covers any read/write.
+fn covers_access(registered: vk::AccessFlags2, required: vk::AccessFlags2) -> bool {
+    const READS: vk::AccessFlags2 = vk::AccessFlags2::from_raw(
+        vk::AccessFlags2::TRANSFER_READ.as_raw()
+            | vk::AccessFlags2::INDIRECT_COMMAND_READ.as_raw()
+            | vk::AccessFlags2::INDEX_READ.as_raw(),
+    );
+    if registered.contains(required) {
+        return true;
+    }
+    if required.intersects(READS) {
+        registered.contains(vk::AccessFlags2::MEMORY_READ)
+    } else {
+        registered.contains(vk::AccessFlags2::MEMORY_WRITE)
+    }
+}
+
+///Records commands of a single task. Passed to [Task::encode](crate::Task::encode).
+pub struct CommandEncoder<'a> {
+    device: &'a Arc<Device>,
+    command_buffer: vk::CommandBuffer,
+    resources: &'a Resources,
+    registry: &'a ResourceRegistry,
+}
+
+impl<'a> CommandEncoder<'a> {
+    pub(crate) fn new(
+        device: &'a Arc<Device>,
+        command_buffer: vk::CommandBuffer,
+        resources: &'a Resources,
+        registry: &'a ResourceRegistry,
+    ) -> Self {
+        CommandEncoder {
+            device,
+            command_buffer,
+            resources,
+            registry,
+        }
+    }
+
+    pub fn device(&self) -> &'a Arc<Device> {
+        self.device
+    }
+
+    pub fn resources(&self) -> &'a Resources {
+        self.resources
+    }
+
+    ///The raw command buffer. Use this for commands the encoder does not wrap. Nothing recorded on it is checked.
+    pub fn raw(&self) -> vk::CommandBuffer {
+        self.command_buffer
+    }
+
+    fn check_buffer<T: 'static>(
+        &self,
+        _buffer: &BufferHandle<T>,
+        _access: vk::AccessFlags2,
+        _command: &str,
+    ) {
+        #[cfg(debug_assertions)]
+        match self.registry.buffer_access(&_buffer.key) {
+            Some(registered) => assert!(
+                covers_access(registered, _access),
+                "{_command}: buffer {:?} is registered with {registered:?}, but {_access:?} is needed",
+                _buffer.key
+            ),
+            None => panic!(
+                "{_command}: buffer {:?} was not registered by the task",
+                _buffer.key
+            ),
+        }
+    }
+
+    ///Returns the layout `image` was requested in. In release builds an unregistered image is used in its tracked layout.
+    fn image_layout(
+        &self,
+        image: &ImageHandle,
+        _access: vk::AccessFlags2,
+        _command: &str,
+    ) -> vk::ImageLayout {
+        let Some((_registered, layout)) = self.registry.image_access(&image.key) else {
+            #[cfg(debug_assertions)]
+            panic!(
+                "{_command}: image {:?} was not registered by the task",
+                image
+            );
+            #[cfg(not(debug_assertions))]
+            return self.resources.get_image_state(image).layout;
+        };
+        debug_assert!(
+            covers_access(_registered, _access),
+            "{_command}: image {:?} is registered with {_registered:?}, but {_access:?} is needed",
+            image
+        );
+        layout
+    }
+
+    ///Returns the layout of the source / destination image of a transfer command.
+    fn transfer_layout(
+        &self,
+        image: &ImageHandle,
+        access: vk::AccessFlags2,
+        command: &str,
+    ) -> vk::ImageLayout {
+        let layout = self.image_layout(image, access, command);
+        debug_assert!(
+            layout == vk::ImageLayout::GENERAL
+                || (access == vk::AccessFlags2::TRANSFER_READ
+                    && layout == vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                || (access == vk::AccessFlags2::TRANSFER_WRITE
+                    && layout == vk::ImageLayout::TRANSFER_DST_OPTIMAL),
+            "{command}: image {:?} is in layout {layout:?}, which can't be used for {access:?}",
+            image
+        );
+        layout
+    }
+
+    ///Copies `regions` from `src` to `dst`. `src` must be registered for `TRANSFER_READ`, `dst` for `TRANSFER_WRITE`.
+    pub fn copy_buffer<S: 'static, D: 'static>(
+        &mut self,
+        src: &BufferHandle<S>,
+        dst: &BufferHandle<D>,
+        regions: &[vk::BufferCopy],
+    ) {
+        self.check_buffer(src, vk::AccessFlags2::TRANSFER_READ, "copy_buffer");
+        self.check_buffer(dst, vk::AccessFlags2::TRANSFER_WRITE, "copy_buffer");
+        unsafe {
+            self.device.inner.cmd_copy_buffer(
+                self.command_buffer,
+                src.bufref.inner,
+                dst.bufref.inner,
+                regions,
+            );
+        }
+    }
+
+    ///Copies `regions` of `src` into `dst`. `dst` must be registered for `TRANSFER_WRITE` in either the `GENERAL` or `TRANSFER_DST_OPTIMAL` layout.
+    pub fn copy_buffer_to_image<T: 'static>(
+        &mut self,
+        src: &BufferHandle<T>,
+        dst: &ImageHandle,
+        regions: &[vk::BufferImageCopy],
+    ) {
+        self.check_buffer(src, vk::AccessFlags2::TRANSFER_READ, "copy_buffer_to_image");
+        let layout = self.transfer_layout(
+            dst,
+            vk::AccessFlags2::TRANSFER_WRITE,
+            "copy_buffer_to_image",
+        );
+        unsafe {
+            self.device.inner.cmd_copy_buffer_to_image(
+                self.command_buffer,
+                src.bufref.inner,
+                dst.imgref.inner,
+                layout,
+                regions,
+            );
+        }
+    }
+
+    ///Blits `regions` of `src` to `dst` using `filter`. `src` must be registered for `TRANSFER_READ`, `dst` for `TRANSFER_WRITE`.
+    pub fn blit(
+        &mut self,
+        src: &ImageHandle,
+        dst: &ImageHandle,
+        regions: &[vk::ImageBlit],
+        filter: vk::Filter,
+    ) {
+        let src_layout = self.transfer_layout(src, vk::AccessFlags2::TRANSFER_READ, "blit");
+        let dst_layout = self.transfer_layout(dst, vk::AccessFlags2::TRANSFER_WRITE, "blit");
+        unsafe {
+            self.device.inner.cmd_blit_image(
+                self.command_buffer,
+                src.imgref.inner,
+                src_layout,
+                dst.imgref.inner,
+                dst_layout,
+                regions,
+                filter,
+            );
+        }
+    }
+
+    ///Clears all mip levels and layers of `image` to `value`. `image` must be registered for `TRANSFER_WRITE`. Depth/stencil images
+    /// are cleared with `value.float32[0]` as depth and `value.uint32[1]` as stencil.
+    pub fn clear_image(&mut self, image: &ImageHandle, value: vk::ClearColorValue) {
+        let layout = self.transfer_layout(image, vk::AccessFlags2::TRANSFER_WRITE, "clear_image");
+        let desc = image.image_desc();
+        let range = desc.subresource_all();
+        unsafe {
+            if desc.is_depth_stencil() {
+                self.device.inner.cmd_clear_depth_stencil_image(
+                    self.command_buffer,
+                    image.imgref.inner,
+                    layout,
+                    &vk::ClearDepthStencilValue {
+                        depth: value.float32[0],
+                        stencil: value.uint32[1],
+                    },
+                    &[range],
+                );
+            } else {
+                self.device.inner.cmd_clear_color_image(
+                    self.command_buffer,
+                    image.imgref.inner,
+                    layout,
+                    &value,
+                    &[range],
+                );
+            }
+        }
+    }
+
+    ///Fills `size` bytes of `buffer`, starting at `offset`, with `data`. Use `vk::WHOLE_SIZE` to fill till the end of the buffer.
+    /// `buffer` must be registered for `TRANSFER_WRITE`.
+    pub fn fill_buffer<T: 'static>(
+        &mut self,
+        buffer: &BufferHandle<T>,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+        data: u32,
+    ) {
+        self.check_buffer(buffer, vk::AccessFlags2::TRANSFER_WRITE, "fill_buffer");
+        unsafe {
+            self.device.inner.cmd_fill_buffer(
+                self.command_buffer,
+                buffer.bufref.inner,
+                offset,
+                size,
+                data,
+            );
+        }
+    }
+
+    pub fn bind_compute_pipeline(&mut self, pipeline: &ComputePipeline) {
+        unsafe {
+            self.device.inner.cmd_bind_pipeline(
+                self.command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                pipeline.pipeline,
+            );
+        }
+    }
+
+    pub fn bind_graphics_pipeline(&mut self, pipeline: &GraphicsPipeline) {
+        unsafe {
+            self.device.inner.cmd_bind_pipeline(
+                self.command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.pipeline,
+            );
+        }
+    }
+
+    ///Pushes `push` via the bindless pipeline layout, which all RMG pipelines share.
+    pub fn push_constant<P: 'static>(&mut self, push: &PushConstant<P>) {
+        unsafe {
+            self.device.inner.cmd_push_constants(
+                self.command_buffer,
+                self.resources.bindless_layout.layout,
+                vk::ShaderStageFlags::ALL,
+                0,
+                push.content_as_bytes(),
+            );
+        }
+    }
+
+    pub fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        unsafe {
+            self.device.inner.cmd_dispatch(self.command_buffer, x, y, z);
+        }
+    }
+
+    ///Dispatches with the parameters stored in `buffer` at `offset`. `buffer` must be registered for `INDIRECT_COMMAND_READ`.
+    pub fn dispatch_indirect(
+        &mut self,
+        buffer: &BufferHandle<vk::DispatchIndirectCommand>,
+        offset: vk::DeviceSize,
+    ) {
+        self.check_buffer(
+            buffer,
+            vk::AccessFlags2::INDIRECT_COMMAND_READ,
+            "dispatch_indirect",
+        );
+        unsafe {
+            self.device.inner.cmd_dispatch_indirect(
+                self.command_buffer,
+                buffer.bufref.inner,
+                offset,
+            );
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        vertex_count: u32,
+        instance_count: u32,
+        first_vertex: u32,
+        first_instance: u32,
+    ) {
+        unsafe {
+            self.device.inner.cmd_draw(
+                self.command_buffer,
+                vertex_count,
+                instance_count,
+                first_vertex,
+                first_instance,
+            );
+        }
+    }
+
+    ///Binds `index_buffer` and draws all of its indices `instance_count` times. `index_buffer` must be registered for `INDEX_READ`.
+    pub fn draw_indexed(&mut self, index_buffer: &BufferHandle<u32>, instance_count: u32) {
+        self.check_buffer(index_buffer, vk::AccessFlags2::INDEX_READ, "draw_indexed");
+        let index_count = index_buffer
+            .count()
+            .try_into()
+            .expect("IndexBuffer size exceeds 32bit int");
+        unsafe {
+            self.device.inner.cmd_bind_index_buffer(
+                self.command_buffer,
+                index_buffer.bufref.inner,
+                0,
+                vk::IndexType::UINT32,
+            );
+            self.device.inner.cmd_draw_indexed(
+                self.command_buffer,
+                index_count,
+                instance_count,
+                0,
+                0,
+                0,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn access_coverage() {
+        assert!(covers_access(
+            vk::AccessFlags2::TRANSFER_READ | vk::AccessFlags2::TRANSFER_WRITE,
+            vk::AccessFlags2::TRANSFER_WRITE
+        ));
+        assert!(covers_access(
+            vk::AccessFlags2::MEMORY_READ,
+            vk::AccessFlags2::INDEX_READ
+        ));
+        assert!(covers_access(
+            vk::AccessFlags2::MEMORY_WRITE,
+            vk::AccessFlags2::TRANSFER_WRITE
+        ));
+        assert!(!covers_access(
+            vk::AccessFlags2::MEMORY_READ,
+            vk::AccessFlags2::TRANSFER_WRITE
+        ));
+        assert!(!covers_access(
+            vk::AccessFlags2::SHADER_STORAGE_READ,
+            vk::AccessFlags2::TRANSFER_READ
+        ));
+    }
+}
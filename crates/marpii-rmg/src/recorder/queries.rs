@@ -0,0 +1,425 @@
+use marpii::{
+    ash::vk,
+    context::Device,
+    util::{PipelineStatistics, QueryPool},
+};
+use std::sync::{Arc, Mutex};
+
+use crate::Config;
+
+///Statistics that can be counted on compute-only tracks. All others need a graphics track.
+const COMPUTE_STATISTICS: vk::QueryPipelineStatisticFlags =
+    vk::QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS;
+
+///Identifies a task of a single submission.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId {
+    ///Index of the recording the task was executed with. Counted per [Rmg](crate::Rmg), starting at 0, see
+    /// [Recorder::submission](crate::Recorder::submission).
+    pub submission: u64,
+    ///Position of the task within its recording, in the order the tasks were added.
+    pub index: usize,
+}
+
+///Results of the queries a task requested via [request_occlusion_query](crate::ResourceRegistry::request_occlusion_query)
+/// and [request_pipeline_statistics](crate::ResourceRegistry::request_pipeline_statistics).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TaskQueryResult {
+    ///The task the results belong to. Names are not unique, use this to tell the tasks apart.
+    pub task: TaskId,
+    ///Name of the task.
+    pub name: String,
+    ///Number of samples that passed the depth and stencil tests while recording the task. Without a precise query
+    /// this is only guaranteed to be zero if no sample passed.
+    pub occlusion: Option<u64>,
+    ///Requested pipeline statistics of the task.
+    pub pipeline_statistics: Option<PipelineStatistics>,
+}
+
+///Queries a single task requested.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct QueryRequest {
+    pub(crate) occlusion: Option<vk::QueryControlFlags>,
+    pub(crate) statistics: vk::QueryPipelineStatisticFlags,
+}
+
+impl QueryRequest {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.occlusion.is_none() && self.statistics.is_empty()
+    }
+
+    ///Removes everything from the request that can not be queried on a track with `track_flags`, or is not supported by the device.
+    pub(crate) fn supported(self, track_flags: vk::QueueFlags, config: &Config) -> Self {
+        let graphics = track_flags.contains(vk::QueueFlags::GRAPHICS);
+        let occlusion = if graphics {
+            self.occlusion.map(|flags| {
+                if !config.occlusion_query_precise_support
+                    && flags.contains(vk::QueryControlFlags::PRECISE)
+                {
+                    #[cfg(feature = "logging")]
+                    log::warn!(
+                        "Precise occlusion queries are not supported, using imprecise query"
+                    );
+                    flags & !vk::QueryControlFlags::PRECISE
+                } else {
+                    flags
+                }
+            })
+        } else {
+            None
+        };
+
+        let statistics = if !config.pipeline_statistics_query_support {
+            vk::QueryPipelineStatisticFlags::empty()
+        } else if graphics {
+            self.statistics
+        } else if track_flags.contains(vk::QueueFlags::COMPUTE) {
+            self.statistics & COMPUTE_STATISTICS
+        } else {
+            vk::QueryPipelineStatisticFlags::empty()
+        };
+
+        #[cfg(feature = "logging")]
+        if occlusion.is_none() != self.occlusion.is_none() || statistics != self.statistics {
+            log::warn!(
+                "Dropping unsupported queries on track {:?}: occlusion={:?} statistics={:?}",
+                track_flags,
+                self.occlusion.is_some() && occlusion.is_none(),
+                self.statistics & !statistics
+            );
+        }
+
+        QueryRequest {
+            occlusion,
+            statistics,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PoolKind {
+    Occlusion,
+    Statistics(vk::QueryPipelineStatisticFlags),
+}
+
+///Idle query pools of a track. [QueryBatch]es take a fitting pool, and return it once the execution finished.
+#[derive(Clone, Default)]
+pub(crate) struct QueryPoolCache {
+    idle: Arc<Mutex<Vec<(PoolKind, QueryPool)>>>,
+}
+
+impl QueryPoolCache {
+    ///Maximum number of idle pools that are kept.
+    const MAX_IDLE: usize = 8;
+
+    ///Returns an idle pool of `kind` with at least `size` queries, or creates one.
+    fn acquire(
+        &self,
+        device: &Arc<Device>,
+        kind: PoolKind,
+        size: u32,
+    ) -> Result<QueryPool, vk::Result> {
+        {
+            let mut idle = self.idle.lock().unwrap();
+            if let Some(idx) =
+                smallest_fitting(idle.iter().map(|(k, pool)| (*k, pool.size())), kind, size)
+            {
+                return Ok(idle.swap_remove(idx).1);
+            }
+        }
+        match kind {
+            PoolKind::Occlusion => QueryPool::new(device, size, vk::QueryType::OCCLUSION),
+            PoolKind::Statistics(flags) => QueryPool::new_pipeline_statistics(device, size, flags),
+        }
+    }
+
+    fn release(&self, kind: PoolKind, pool: QueryPool) {
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < Self::MAX_IDLE {
+            idle.push((kind, pool));
+        }
+    }
+}
+
+///Index of the smallest pool of `kind` with at least `size` queries.
+fn smallest_fitting(
+    pools: impl Iterator<Item = (PoolKind, u32)>,
+    kind: PoolKind,
+    size: u32,
+) -> Option<usize> {
+    pools
+        .enumerate()
+        .filter(|(_idx, (k, pool_size))| *k == kind && *pool_size >= size)
+        .min_by_key(|(_idx, (_k, pool_size))| *pool_size)
+        .map(|(idx, _)| idx)
+}
+
+struct QueryEntry {
+    node: usize,
+    task: TaskId,
+    name: String,
+    occlusion: Option<(u32, vk::QueryControlFlags)>,
+    statistics: Option<(u32, vk::QueryPipelineStatisticFlags)>,
+}
+
+///Query pools of a single execution. Each task's commands are wrapped in its requested queries, the results are read once the execution finished.
+///
+/// The pools are taken from the track's [QueryPoolCache] and might be bigger than needed, only the used queries are read.
+pub(crate) struct QueryBatch {
+    cache: QueryPoolCache,
+    occlusion: Option<(QueryPool, u32)>,
+    statistics: Option<(QueryPool, vk::QueryPipelineStatisticFlags, u32)>,
+    entries: Vec<QueryEntry>,
+}
+
+impl QueryBatch {
+    ///Takes the pools for all `requests`, which are `(node, task, task name, request)`, from `cache`. Returns `None` if nothing
+    /// was requested.
+    pub(crate) fn new<'a>(
+        device: &Arc<Device>,
+        cache: &QueryPoolCache,
+        requests: impl IntoIterator<Item = (usize, TaskId, &'a str, QueryRequest)>,
+    ) -> Result<Option<Self>, vk::Result> {
+        let mut occlusion_count = 0;
+        let mut statistics_count = 0;
+        //The pool counts all statistics any task requested, each task only reports its own.
+        let mut statistics_flags = vk::QueryPipelineStatisticFlags::empty();
+        let mut entries = Vec::new();
+        for (node, task, name, request) in requests {
+            if request.is_empty() {
+                continue;
+            }
+            let occlusion = request.occlusion.map(|flags| {
+                occlusion_count += 1;
+                (occlusion_count - 1, flags)
+            });
+            let statistics = if request.statistics.is_empty() {
+                None
+            } else {
+                statistics_count += 1;
+                statistics_flags |= request.statistics;
+                Some((statistics_count - 1, request.statistics))
+            };
+            entries.push(QueryEntry {
+                node,
+                task,
+                name: name.to_owned(),
+                occlusion,
+                statistics,
+            });
+        }
+
+        if entries.is_empty() {
+            return Ok(None);
+        }
+
+        let occlusion = if occlusion_count > 0 {
+            Some((
+                cache.acquire(device, PoolKind::Occlusion, occlusion_count)?,
+                occlusion_count,
+            ))
+        } else {
+            None
+        };
+        let statistics = if statistics_count > 0 {
+            Some((
+                cache.acquire(
+                    device,
+                    PoolKind::Statistics(statistics_flags),
+                    statistics_count,
+                )?,
+                statistics_flags,
+                statistics_count,
+            ))
+        } else {
+            None
+        };
+
+        Ok(Some(QueryBatch {
+            cache: cache.clone(),
+            occlusion,
+            statistics,
+            entries,
+        }))
+    }
+
+    ///Resets all queries. Must be recorded before any query is started.
+    pub(crate) fn reset(&mut self, command_buffer: &vk::CommandBuffer) -> Result<(), vk::Result> {
+        if let Some((pool, _)) = &mut self.occlusion {
+            pool.reset(command_buffer)?;
+        }
+        if let Some((pool, _, _)) = &mut self.statistics {
+            pool.reset(command_buffer)?;
+        }
+        Ok(())
+    }
+
+    ///Begins the queries of `node`, if there are any.
+    pub(crate) fn begin(&self, command_buffer: &vk::CommandBuffer, node: usize) {
+        let Some(entry) = self.entries.iter().find(|e| e.node == node) else {
+            return;
+        };
+        if let (Some((pool, _)), Some((query, flags))) = (&self.occlusion, entry.occlusion) {
+            pool.begin_query(command_buffer, query, flags);
+        }
+        if let (Some((pool, _, _)), Some((query, _))) = (&self.statistics, entry.statistics) {
+            pool.begin_query(command_buffer, query, vk::QueryControlFlags::empty());
+        }
+    }
+
+    ///Ends the queries of `node`, if there are any.
+    pub(crate) fn end(&self, command_buffer: &vk::CommandBuffer, node: usize) {
+        let Some(entry) = self.entries.iter().find(|e| e.node == node) else {
+            return;
+        };
+        if let (Some((pool, _)), Some((query, _))) = (&self.occlusion, entry.occlusion) {
+            pool.end_query(command_buffer, query);
+        }
+        if let (Some((pool, _, _)), Some((query, _))) = (&self.statistics, entry.statistics) {
+            pool.end_query(command_buffer, query);
+        }
+    }
+
+    ///Reads the results of all queries and appends them to `dst`. Must only be called once the execution finished.
+    pub(crate) fn read_results(&self, dst: &mut Vec<TaskQueryResult>) {
+        let occlusion_results = self.occlusion.as_ref().and_then(|(pool, count)| {
+            let mut results = vec![0; *count as usize];
+            pool.query_results_u64_strided(0, 1, &mut results, vk::QueryResultFlags::WAIT)
+                .inspect_err(|_e| {
+                    #[cfg(feature = "logging")]
+                    log::error!("Failed to read occlusion queries: {}", _e);
+                })
+                .ok()
+                .map(|_| results)
+        });
+        let statistics_results = self.statistics.as_ref().and_then(|(pool, flags, count)| {
+            let value_count = PipelineStatistics::value_count(*flags);
+            let mut results = vec![0; *count as usize * value_count];
+            pool.query_results_u64_strided(0, value_count, &mut results, vk::QueryResultFlags::WAIT)
+                .inspect_err(|_e| {
+                    #[cfg(feature = "logging")]
+                    log::error!("Failed to read pipeline statistics queries: {}", _e);
+                })
+                .ok()
+                .map(|_| (results, *flags, value_count))
+        });
+
+        for entry in &self.entries {
+            let occlusion = entry.occlusion.and_then(|(query, _)| {
+                occlusion_results
+                    .as_ref()
+                    .map(|results| results[query as usize])
+            });
+            let pipeline_statistics = entry.statistics.and_then(|(query, requested)| {
+                statistics_results
+                    .as_ref()
+                    .map(|(results, flags, value_count)| {
+                        let start = query as usize * value_count;
+                        PipelineStatistics::from_results(
+                            *flags,
+                            &results[start..start + value_count],
+                        )
+                        .restrict(requested)
+                    })
+            });
+            dst.push(TaskQueryResult {
+                task: entry.task,
+                name: entry.name.clone(),
+                occlusion,
+                pipeline_statistics,
+            });
+        }
+    }
+}
+
+impl Drop for QueryBatch {
+    fn drop(&mut self) {
+        if let Some((pool, _)) = self.occlusion.take() {
+            self.cache.release(PoolKind::Occlusion, pool);
+        }
+        if let Some((pool, flags, _)) = self.statistics.take() {
+            self.cache.release(PoolKind::Statistics(flags), pool);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(statistics: bool, precise: bool) -> Config {
+        Config {
+            pipeline_statistics_query_support: statistics,
+            occlusion_query_precise_support: precise,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn graphics_track_keeps_request() {
+        let request = QueryRequest {
+            occlusion: Some(vk::QueryControlFlags::PRECISE),
+            statistics: vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES,
+        };
+        assert_eq!(
+            request.supported(vk::QueueFlags::GRAPHICS, &config(true, true)),
+            request
+        );
+    }
+
+    #[test]
+    fn compute_track_only_counts_compute() {
+        let request = QueryRequest {
+            occlusion: Some(vk::QueryControlFlags::empty()),
+            statistics: vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES
+                | vk::QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS,
+        };
+        let supported = request.supported(vk::QueueFlags::COMPUTE, &config(true, true));
+        assert_eq!(supported.occlusion, None);
+        assert_eq!(
+            supported.statistics,
+            vk::QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS
+        );
+        assert!(
+            request
+                .supported(vk::QueueFlags::TRANSFER, &config(true, true))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn unsupported_features() {
+        let request = QueryRequest {
+            occlusion: Some(vk::QueryControlFlags::PRECISE),
+            statistics: vk::QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS,
+        };
+        let supported = request.supported(vk::QueueFlags::GRAPHICS, &config(false, false));
+        assert_eq!(supported.occlusion, Some(vk::QueryControlFlags::empty()));
+        assert!(supported.statistics.is_empty());
+    }
+
+    #[test]
+    fn reuse_smallest_fitting_pool() {
+        let clipping = PoolKind::Statistics(vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES);
+        let pools = [
+            (PoolKind::Occlusion, 8),
+            (clipping, 2),
+            (PoolKind::Occlusion, 4),
+            (PoolKind::Occlusion, 2),
+        ];
+        assert_eq!(
+            smallest_fitting(pools.into_iter(), PoolKind::Occlusion, 3),
+            Some(2)
+        );
+        assert_eq!(smallest_fitting(pools.into_iter(), clipping, 2), Some(1));
+        assert_eq!(smallest_fitting(pools.into_iter(), clipping, 3), None);
+        assert_eq!(
+            smallest_fitting(
+                pools.into_iter(),
+                PoolKind::Statistics(vk::QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS),
+                1
+            ),
+            None
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use crate::{
+    recorder::{encoder::CommandEncoder, queries::QueryRequest},
     resources::{
+        handle::AnyHandle,
         res_states::{AnyResKey, BufferKey, ImageKey, SamplerKey},
         Resources,
     },
@@ -7,8 +9,10 @@ use crate::{
 };
 use ahash::{AHashMap, AHashSet};
 use marpii::{
+    allocator::RingAllocation,
     ash::vk::{self, ImageLayout},
     context::Device,
+    resources::SparseBindBatch,
     sync::BinarySemaphore,
 };
 use marpii_commands::BarrierBuilder;
@@ -21,6 +25,12 @@ pub struct ResourceRegistry {
 
     binary_signal_sem: Vec<Arc<BinarySemaphore>>,
     binary_wait_sem: Vec<Arc<BinarySemaphore>>,
+    ///Upload ranges read by the task. Are guarded by the task's execution.
+    pub(crate) upload_allocations: Vec<RingAllocation>,
+    ///Resources that are released to `QUEUE_FAMILY_EXTERNAL` after the task.
+    pub(crate) external_releases: Vec<AnyResKey>,
+    ///Queries that wrap the task's commands.
+    pub(crate) queries: QueryRequest,
     ///Collects all resources handle used in the registry
     /// is later used to move them into an executions collector
     pub(crate) resource_collection: Vec<Box<dyn Any + Send>>,
@@ -40,6 +50,9 @@ impl ResourceRegistry {
             sampler: AHashSet::new(),
             binary_signal_sem: Vec::new(),
             binary_wait_sem: Vec::new(),
+            upload_allocations: Vec::new(),
+            external_releases: Vec::new(),
+            queries: QueryRequest::default(),
             resource_collection: Vec::new(),
         }
     }
@@ -186,6 +199,32 @@ impl ResourceRegistry {
         Ok(())
     }
 
+    ///Registers a staging range (usually allocated via [`upload_data`](Resources::upload_data)) that is read by this task.
+    /// The range won't be reused until the task has finished executing.
+    ///
+    /// Note that the host-write to transfer-read barrier is not handled by RMG.
+    pub fn request_upload(&mut self, allocation: &RingAllocation) {
+        self.upload_allocations.push(allocation.clone());
+        self.resource_collection.push(Box::new(allocation.clone()));
+    }
+
+    ///Releases `resource` to `vk::QUEUE_FAMILY_EXTERNAL` after the task's frame has executed. Use this to hand a shared image or buffer
+    /// back to an external party, for instance a video encoder. Images keep the layout they were requested in.
+    ///
+    /// The resource must be requested by this task as well. Synchronisation with the external party is usually done by
+    /// [signalling](Self::register_binary_signal_semaphore) an exported semaphore.
+    pub fn release_to_external(
+        &mut self,
+        resource: impl Into<AnyHandle>,
+    ) -> Result<(), ResourceError> {
+        let handle = resource.into();
+        if !self.contains_key(&handle.key) {
+            return Err(ResourceError::ResourceNotRequested(handle));
+        }
+        self.external_releases.push(handle.key);
+        Ok(())
+    }
+
     ///Registers *any*thing to be kept alive until the task finishes its execution.
     pub fn register_asset<T: Any + Send + 'static>(&mut self, asset: T) {
         self.resource_collection.push(Box::new(asset));
@@ -203,6 +242,41 @@ impl ResourceRegistry {
         self.resource_collection.push(Box::new(semaphore));
     }
 
+    ///Requests an occlusion query around the task's commands. The number of samples that passed the depth and stencil tests is
+    /// reported after the execution, see [Rmg::take_query_results](crate::Rmg::take_query_results).
+    ///
+    /// If `precise` is set, the exact sample count is reported. Otherwise only zero or non-zero is guaranteed, which is usually cheaper.
+    /// Falls back to an imprecise query if [precise queries](crate::Config::occlusion_query_precise_support) are not supported.
+    /// The query is ignored if the task is not executed on a graphics track.
+    pub fn request_occlusion_query(&mut self, precise: bool) {
+        self.queries.occlusion = Some(if precise {
+            vk::QueryControlFlags::PRECISE
+        } else {
+            vk::QueryControlFlags::empty()
+        });
+    }
+
+    ///Requests `statistics` to be counted for the task's commands, for instance shader invocations, or the primitives before and after clipping.
+    /// The counters are reported after the execution, see [Rmg::take_query_results](crate::Rmg::take_query_results).
+    ///
+    /// Needs [pipeline statistics support](crate::Config::pipeline_statistics_query_support). On compute-only tracks only
+    /// `COMPUTE_SHADER_INVOCATIONS` is counted, other statistics are ignored.
+    pub fn request_pipeline_statistics(&mut self, statistics: vk::QueryPipelineStatisticFlags) {
+        self.queries.statistics |= statistics;
+    }
+
+    ///Access and layout `image` was requested with, if it was requested.
+    pub(crate) fn image_access(&self, image: &ImageKey) -> Option<(vk::AccessFlags2, ImageLayout)> {
+        self.images
+            .get(image)
+            .map(|(_stage, access, layout)| (*access, *layout))
+    }
+
+    ///Access `buffer` was requested with, if it was requested.
+    pub(crate) fn buffer_access(&self, buffer: &BufferKey) -> Option<vk::AccessFlags2> {
+        self.buffers.get(buffer).map(|(_stage, access)| *access)
+    }
+
     pub(crate) fn any_res_iter<'a>(&'a self) -> impl Iterator<Item = AnyResKey> + 'a {
         self.images
             .keys()
@@ -254,7 +328,6 @@ impl ResourceRegistry {
         }
     }
 
-    #[allow(dead_code)]
     pub(crate) fn contains_key(&self, resource: &AnyResKey) -> bool {
         match resource {
             AnyResKey::Buffer(buf) => self.buffers.contains_key(buf),
@@ -378,12 +451,37 @@ pub trait Task {
     /// In principle you could register anything, that needs to stay alive, as long as it is used by the GPU to execute this task.
     fn register(&self, registry: &mut ResourceRegistry);
 
+    ///Records the task's commands on `command_buffer`. All resources are in the state they were [registered](Self::register) with.
+    ///
+    /// Implement either this, or [encode](Self::encode). The default implementation panics, since a task that records nothing
+    /// is most likely missing its implementation.
     fn record(
         &mut self,
-        device: &Arc<Device>,
-        command_buffer: &vk::CommandBuffer,
-        resources: &Resources,
-    );
+        _device: &Arc<Device>,
+        _command_buffer: &vk::CommandBuffer,
+        _resources: &Resources,
+    ) {
+        panic!(
+            "Task {} implements neither `record` nor `encode`",
+            self.name()
+        );
+    }
+
+    ///Records the task's commands via `encoder`, which wraps the command buffer in a typed [CommandEncoder]. By default this
+    /// calls [record](Self::record) with the raw command buffer.
+    ///
+    /// Tasks that override this are never asked to [record](Self::record).
+    fn encode(&mut self, encoder: &mut CommandEncoder<'_>) {
+        self.record(encoder.device(), &encoder.raw(), encoder.resources());
+    }
+
+    ///Sparse memory binds that are executed via `vkQueueBindSparse` before this task's track submits its commands
+    /// for the frame. Only called for tasks whose [`queue_flags`](Self::queue_flags) contain `SPARSE_BINDING`.
+    ///
+    /// See [`BindSparse`](crate::helper::bind_sparse::BindSparse) for the default implementation.
+    fn take_sparse_binds(&mut self) -> Option<SparseBindBatch> {
+        None
+    }
 
     ///Signals the task type to the recorder. By default this is compute only.
     fn queue_flags(&self) -> vk::QueueFlags {
@@ -1,22 +1,62 @@
-use ahash::AHashMap;
-#[cfg(feature = "timestamps")]
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
 
-use marpii::ash::vk;
+use marpii::{ash::vk, context::Queue, resources::SparseBindBatch, sync::Event};
 use marpii_commands::BarrierBuilder;
-use std::any::Any;
+use std::{any::Any, hash::Hash};
 
 use crate::{
     recorder::task_scheduler::DepPart,
     resources::res_states::{AnyResKey, QueueOwnership},
-    track::{Guard, TrackId},
+    track::{Guard, PooledEvent, TrackId},
     RecordError, Rmg,
 };
 
-use super::{task_scheduler::TaskSchedule, Execution};
+use super::{
+    encoder::CommandEncoder, queries::QueryBatch, task_scheduler::TaskSchedule, Execution,
+};
 
+///Label color of a track's frame.
+#[cfg(feature = "debug_marker")]
+const FRAME_LABEL_COLOR: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+///Label color of a single task.
 #[cfg(feature = "debug_marker")]
-use std::ffi::CString;
+const TASK_LABEL_COLOR: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+
+///A transition of `resource` that is split into an event signal after the `producer` node, and an event wait before the `consumer` node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct SplitTransition<K> {
+    producer: usize,
+    consumer: usize,
+    resource: K,
+}
+
+///Finds all transitions of a frame that are worth splitting. `nodes` yields each node's index and dependencies in recording order.
+///
+/// Splitting only pays off if the GPU has something else to do while the consumer waits. Therefore a transition is only split
+/// if at least one other node is recorded between producer and consumer. Those nodes never use the resource, otherwise they would
+/// be the producer.
+fn find_split_transitions<'a, K: Hash + Eq + Copy + 'a, D: IntoIterator<Item = &'a K>>(
+    nodes: impl IntoIterator<Item = (usize, D)>,
+) -> Vec<SplitTransition<K>> {
+    //maps each resource to the recording position and index of the node that used it last.
+    let mut last_use: AHashMap<K, (usize, usize)> = AHashMap::default();
+    let mut splits = Vec::new();
+    for (position, (node_idx, dependencies)) in nodes.into_iter().enumerate() {
+        for resource in dependencies {
+            if let Some((last_position, producer)) =
+                last_use.insert(*resource, (position, node_idx))
+                && position > last_position + 1
+            {
+                splits.push(SplitTransition {
+                    producer,
+                    consumer: node_idx,
+                    resource: *resource,
+                });
+            }
+        }
+    }
+    splits
+}
 
 ///Schedule executor. Takes Frames, dependencies and dependees to build an
 /// command buffer that is immediately pushed to the GPU.
@@ -500,6 +540,7 @@ impl<'t> Executor<'t> {
                     resources: released_resources,
                     command_buffer: cb,
                     guard: release_guard,
+                    queries: None,
                 });
             }
         }
@@ -552,6 +593,19 @@ impl<'t> Executor<'t> {
                     let bufstate = rmg.resources.buffer.get_mut(buf).unwrap();
                     //update ownership,  and if needed push acquire
                     match bufstate.ownership {
+                        QueueOwnership::Released { .. } if bufstate.ownership.is_external() => {
+                            //owned by an external party, acquire from there
+                            #[cfg(feature = "logging")]
+                            log::trace!("Acquire external {:?} to track {:?}", buf, trackid);
+                            bufstate.ownership = QueueOwnership::Owned(track_queue_family);
+                            barrier.buffer_queue_transition(
+                                bufstate.buffer.inner,
+                                0,
+                                vk::WHOLE_SIZE,
+                                vk::QUEUE_FAMILY_EXTERNAL,
+                                track_queue_family,
+                            );
+                        }
                         QueueOwnership::Released {
                             src_family,
                             dst_family,
@@ -607,6 +661,18 @@ impl<'t> Executor<'t> {
                     let imgstate = rmg.resources.images.get_mut(img).unwrap();
                     //update ownership,  and if needed push acquire
                     match imgstate.ownership {
+                        QueueOwnership::Released { .. } if imgstate.ownership.is_external() => {
+                            //owned by an external party, acquire from there
+                            #[cfg(feature = "logging")]
+                            log::trace!("Acquire external {:?} to track {:?}", img, trackid);
+                            imgstate.ownership = QueueOwnership::Owned(track_queue_family);
+                            barrier.image_queue_transition(
+                                imgstate.image.inner,
+                                imgstate.image.subresource_all(),
+                                vk::QUEUE_FAMILY_EXTERNAL,
+                                track_queue_family,
+                            );
+                        }
                         QueueOwnership::Released {
                             src_family,
                             dst_family,
@@ -790,6 +856,120 @@ impl<'t> Executor<'t> {
         Ok(barriers)
     }
 
+    ///Adds release barriers to `vk::QUEUE_FAMILY_EXTERNAL` for all resources of `frame` on `track` that a task
+    /// [released to external](crate::ResourceRegistry::release_to_external).
+    fn add_external_releases(
+        &mut self,
+        rmg: &mut Rmg,
+        trackid: TrackId,
+        frame_index: usize,
+        barriers: &mut BarrierBuilder,
+    ) -> Result<(), RecordError> {
+        let src_family = rmg.trackid_to_queue_idx(trackid);
+        let track = self.schedule.tracks.get(&trackid).unwrap();
+        for res in track.frames[frame_index]
+            .iter_indices()
+            .flat_map(|node_idx| track.nodes[node_idx].task.registry.external_releases.iter())
+        {
+            let ownership = match res {
+                AnyResKey::Buffer(buf) => {
+                    &mut rmg.resources.buffer.get_mut(*buf).unwrap().ownership
+                }
+                AnyResKey::Image(img) => &mut rmg.resources.images.get_mut(*img).unwrap().ownership,
+                AnyResKey::Sampler(_) => continue,
+            };
+            match *ownership {
+                QueueOwnership::Owned(owner) if owner == src_family => {
+                    *ownership = QueueOwnership::Released {
+                        src_family,
+                        dst_family: vk::QUEUE_FAMILY_EXTERNAL,
+                    };
+                }
+                QueueOwnership::Uninitialized => {
+                    return Err(RecordError::ReleaseUninitialised((*res).into()));
+                }
+                _ => {
+                    #[cfg(feature = "logging")]
+                    log::error!("{} can not be released to external from {}", res, trackid);
+                    return Err(RecordError::AlreadyReleased((*res).into()));
+                }
+            }
+
+            #[cfg(feature = "logging")]
+            log::trace!("Releasing {} {:#?} -> external", res, trackid.0);
+            match res {
+                AnyResKey::Buffer(buf) => {
+                    barriers.buffer_queue_transition(
+                        rmg.resources.buffer.get(*buf).unwrap().buffer.inner,
+                        0,
+                        vk::WHOLE_SIZE,
+                        src_family,
+                        vk::QUEUE_FAMILY_EXTERNAL,
+                    );
+                }
+                AnyResKey::Image(img) => {
+                    let imgstate = rmg.resources.images.get(*img).unwrap();
+                    barriers.image_queue_transition(
+                        imgstate.image.inner,
+                        imgstate.image.subresource_all(),
+                        src_family,
+                        vk::QUEUE_FAMILY_EXTERNAL,
+                    );
+                }
+                AnyResKey::Sampler(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    ///Executes `binds` on `queue`. Waits for all semaphores in the submit info cache and signals `guard` when finished.
+    fn submit_sparse_binds(
+        &self,
+        rmg: &Rmg,
+        queue: &Queue,
+        binds: &SparseBindBatch,
+        guard: Guard,
+    ) -> Result<(), RecordError> {
+        let wait_semaphores = self
+            .submit_info_cache
+            .iter()
+            .map(|info| info.semaphore)
+            .collect::<Vec<_>>();
+        //NOTE: values of binary semaphores are ignored
+        let wait_values = self
+            .submit_info_cache
+            .iter()
+            .map(|info| info.value)
+            .collect::<Vec<_>>();
+        let signal_semaphore = [rmg.tracks.0.get(guard.as_ref()).unwrap().sem.inner];
+        let signal_value = [guard.wait_value()];
+
+        let mut timeline_info = vk::TimelineSemaphoreSubmitInfo::default()
+            .wait_semaphore_values(&wait_values)
+            .signal_semaphore_values(&signal_value);
+
+        #[cfg(feature = "logging")]
+        log::trace!(
+            "Binding sparse memory on {:#?}, signalling {}",
+            queue.properties.queue_flags,
+            guard.wait_value()
+        );
+
+        binds.with_bind_info(|info| unsafe {
+            rmg.ctx.device.inner.queue_bind_sparse(
+                *queue.inner(),
+                &[info
+                    .wait_semaphores(&wait_semaphores)
+                    .signal_semaphores(&signal_semaphore)
+                    .push_next(&mut timeline_info)],
+                vk::Fence::null(),
+            )
+        })?;
+
+        Ok(())
+    }
+
     fn schedule_frame(
         &mut self,
         rmg: &mut Rmg,
@@ -850,6 +1030,16 @@ impl<'t> Executor<'t> {
             }
         }
 
+        //label the whole frame, so captures show which tasks were recorded together
+        #[cfg(feature = "debug_marker")]
+        let device = rmg.ctx.device.clone();
+        #[cfg(feature = "debug_marker")]
+        let frame_name = format!("{} frame {}", trackid, frame_index);
+        #[cfg(feature = "debug_marker")]
+        let frame_label = device
+            .get_debugger()
+            .map(|dbg| dbg.cmd_label(cb.inner, &frame_name, FRAME_LABEL_COLOR));
+
         //if this traks's timestamp was not yet reset, do it now
         #[cfg(feature = "timestamps")]
         {
@@ -870,6 +1060,47 @@ impl<'t> Executor<'t> {
             }
         }
 
+        //create the queries the frame's tasks requested, and reset them before any task starts.
+        let mut queries = {
+            let track = self.schedule.tracks.get(&trackid).unwrap();
+            QueryBatch::new(
+                &rmg.ctx.device,
+                &rmg.tracks.0.get(&trackid).unwrap().query_pools,
+                track.frames[frame_index].iter_indices().map(|node_idx| {
+                    let record = &track.nodes[node_idx].task;
+                    (
+                        node_idx,
+                        record.id,
+                        record.task.name(),
+                        record.registry.queries.supported(trackid.0, rmg.config()),
+                    )
+                }),
+            )?
+        };
+        if let Some(queries) = &mut queries {
+            queries.reset(&cb.inner)?;
+        }
+
+        //collect sparse binds of this frame. Those are executed before the command buffer, so they
+        // get their own guard.
+        let mut sparse_binds = SparseBindBatch::new();
+        {
+            let track = self.schedule.tracks.get_mut(&trackid).unwrap();
+            for node_idx in track.frames[frame_index].iter_indices() {
+                let task = &mut track.nodes[node_idx].task.task;
+                if task.queue_flags().contains(vk::QueueFlags::SPARSE_BINDING)
+                    && let Some(batch) = task.take_sparse_binds()
+                {
+                    sparse_binds.append(batch);
+                }
+            }
+        }
+        let bind_guard = if sparse_binds.is_empty() {
+            None
+        } else {
+            Some(rmg.tracks.0.get_mut(&trackid).unwrap().next_guard())
+        };
+
         let exec_guard = rmg.tracks.0.get_mut(&trackid).unwrap().next_guard();
         //pre-build signal semaphore. This allows us to
         // add all foreign semaphores while checking node dependencies.
@@ -937,6 +1168,35 @@ impl<'t> Executor<'t> {
         {
             let mut last_use: AHashMap<AnyResKey, usize> = AHashMap::default();
             let track = self.schedule.tracks.get_mut(&trackid).unwrap();
+
+            //If enabled, find the transitions that are split into an event signal and wait. Events can only be used on
+            // graphics and compute queues.
+            let split_transitions = if rmg.config().split_barriers
+                && trackid
+                    .0
+                    .intersects(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
+            {
+                find_split_transitions(track.frames[frame_index].iter_indices().map(|node_idx| {
+                    (
+                        node_idx,
+                        track.nodes[node_idx]
+                            .dependencies
+                            .iter()
+                            .map(|dep| &dep.dep),
+                    )
+                }))
+            } else {
+                Vec::new()
+            };
+            let split_consumers = split_transitions
+                .iter()
+                .map(|split| (split.consumer, split.resource))
+                .collect::<AHashSet<_>>();
+            //events (and their barriers) each consumer has to wait for before it is recorded.
+            let mut pending_waits: AHashMap<usize, Vec<(PooledEvent, BarrierBuilder)>> =
+                AHashMap::default();
+            let event_pool = rmg.tracks.0.get(&trackid).unwrap().event_pool.clone();
+
             for node_idx in track.frames[frame_index].iter_indices() {
                 #[cfg(feature = "logging")]
                 log::trace!("Recording Node [{} @ {:?}]", node_idx, trackid);
@@ -946,6 +1206,13 @@ impl<'t> Executor<'t> {
                 // If we find the dependency, check stage at which it was scheduled. Otherwise assume "none", since the acquire
                 // stage (if there was such a thing) would have waited already.
                 for dep in track.nodes[node_idx].dependencies.iter() {
+                    if split_consumers.contains(&(node_idx, dep.dep)) {
+                        #[cfg(feature = "logging")]
+                        log::trace!("    Res[{:?}] waits for split transition", dep.dep);
+                        //transitioned by the producer's event already
+                        last_use.insert(dep.dep, node_idx);
+                        continue;
+                    }
                     if let Some(last_use) = last_use.insert(dep.dep, node_idx) {
                         #[cfg(feature = "logging")]
                         log::trace!("    Res[{:?}] was already used in {}", dep.dep, last_use);
@@ -979,6 +1246,25 @@ impl<'t> Executor<'t> {
                         );
                     }
                 }
+                //wait for all split transitions that end at this node
+                if let Some(waits) = pending_waits.remove(&node_idx) {
+                    let events = waits.iter().map(|(ev, _)| ev.event).collect::<Vec<_>>();
+                    let infos = waits
+                        .iter()
+                        .map(|(_, barrier)| barrier.as_dependency_info())
+                        .collect::<Vec<_>>();
+                    Event::cmd_wait_all(&rmg.ctx.device, cb.inner, &events, &infos);
+                    //Reset once the wait finished, so the events are unsignaled when they return to the pool.
+                    for (event, barrier) in &waits {
+                        event.cmd_reset(cb.inner, dst_stages(barrier));
+                    }
+                    used_resources.extend(
+                        waits
+                            .into_iter()
+                            .map(|(ev, _)| Box::new(ev) as Box<dyn Any + Send>),
+                    );
+                }
+
                 //add barrier if there is anything
                 if trans_barrier.has_barrier() {
                     unsafe {
@@ -992,20 +1278,15 @@ impl<'t> Executor<'t> {
                 #[cfg(feature = "logging")]
                 log::trace!("Record task {}", track.nodes[node_idx].task.task.name());
 
+                //closed at the end of this node's iteration
                 #[cfg(feature = "debug_marker")]
-                {
-                    let string: CString = CString::new(track.nodes[node_idx].task.task.name())
-                        .unwrap_or(CString::new("INVALID TASK NAME").unwrap());
-                    let label = vk::DebugUtilsLabelEXT::default()
-                        .color([0.0, 1.0, 0.0, 1.0])
-                        .label_name(&string);
-                    if let Some(dbg) = rmg.ctx.device.get_debugger() {
-                        unsafe {
-                            dbg.debug_report_loader
-                                .cmd_begin_debug_utils_label(cb.inner, &label);
-                        };
-                    }
-                };
+                let _task_label = device.get_debugger().map(|dbg| {
+                    dbg.cmd_label(
+                        cb.inner,
+                        track.nodes[node_idx].task.task.name(),
+                        TASK_LABEL_COLOR,
+                    )
+                });
 
                 //if we are recording timestamps for tasks, start the region here and end it afterwards
                 #[cfg(feature = "timestamps")]
@@ -1020,11 +1301,25 @@ impl<'t> Executor<'t> {
                     None
                 };
 
+                if let Some(queries) = &queries {
+                    queries.begin(&cb.inner, node_idx);
+                }
+
                 //now let the node record itself
-                track.nodes[node_idx]
-                    .task
-                    .task
-                    .record(&rmg.ctx.device, &cb.inner, &rmg.resources);
+                {
+                    let record = &mut track.nodes[node_idx].task;
+                    let mut encoder = CommandEncoder::new(
+                        &rmg.ctx.device,
+                        cb.inner,
+                        &rmg.resources,
+                        &record.registry,
+                    );
+                    record.task.encode(&mut encoder);
+                }
+
+                if let Some(queries) = &queries {
+                    queries.end(&cb.inner, node_idx);
+                }
 
                 //end timestamp region if appropriate
                 #[cfg(feature = "timestamps")]
@@ -1036,15 +1331,59 @@ impl<'t> Executor<'t> {
                     }
                 }
 
-                #[cfg(feature = "debug_marker")]
-                if let Some(dbg) = rmg.ctx.device.get_debugger() {
-                    unsafe { dbg.debug_report_loader.cmd_end_debug_utils_label(cb.inner) };
-                };
+                //signal the split transitions that start at this node. One event per consumer.
+                let mut split_barriers: Vec<(usize, BarrierBuilder)> = Vec::new();
+                for split in split_transitions
+                    .iter()
+                    .filter(|split| split.producer == node_idx)
+                {
+                    //Must be some, since the producer uses the resource
+                    let Some(src_stage) = track.nodes[node_idx]
+                        .task
+                        .registry
+                        .get_stage_mask(&split.resource)
+                    else {
+                        continue;
+                    };
+                    let barrier_idx = if let Some(idx) = split_barriers
+                        .iter()
+                        .position(|(consumer, _)| *consumer == split.consumer)
+                    {
+                        idx
+                    } else {
+                        split_barriers.push((split.consumer, BarrierBuilder::new()));
+                        split_barriers.len() - 1
+                    };
+                    //NOTE: No node in between uses the resource, so the state right now is the state the consumer sees.
+                    track.nodes[split.consumer]
+                        .task
+                        .registry
+                        .add_diff_transition(
+                            rmg,
+                            &mut split_barriers[barrier_idx].1,
+                            split.resource,
+                            src_stage,
+                        );
+                }
+                for (consumer, barrier) in split_barriers {
+                    if !barrier.has_barrier() {
+                        continue;
+                    }
+                    #[cfg(feature = "logging")]
+                    log::trace!("Split transition {} -> {}", node_idx, consumer);
+                    let event = event_pool.acquire()?;
+                    event.cmd_set(cb.inner, &barrier.as_dependency_info());
+                    pending_waits
+                        .entry(consumer)
+                        .or_default()
+                        .push((event, barrier));
+                }
             }
         }
 
         //finished scheduling all nodes. We can now release to all dependees that are not on this track.
-        let release_barrier = self.build_release_barriers(rmg, trackid, frame_index)?;
+        let mut release_barrier = self.build_release_barriers(rmg, trackid, frame_index)?;
+        self.add_external_releases(rmg, trackid, frame_index, &mut release_barrier)?;
         if release_barrier.has_barrier() {
             unsafe {
                 rmg.ctx
@@ -1076,6 +1415,9 @@ impl<'t> Executor<'t> {
             }
         }
 
+        #[cfg(feature = "debug_marker")]
+        drop(frame_label);
+
         //finally, when finished recording, execute by
         unsafe {
             rmg.ctx.device.inner.end_command_buffer(cb.inner)?;
@@ -1103,20 +1445,25 @@ impl<'t> Executor<'t> {
             }
 
             #[cfg(feature = "debug_marker")]
-            {
-                if let Some(dbg) = rmg.ctx.device.get_debugger() {
-                    let string = CString::new(format!("{:#?}", queue.properties.queue_flags))
-                        .unwrap_or(CString::new("UNAMED_QUEUE").unwrap());
-                    let queue_label = vk::DebugUtilsLabelEXT::default()
-                        .color([0.0, 0.0, 1.0, 1.0])
-                        .label_name(&string);
-                    dbg.debug_report_loader
-                        .queue_begin_debug_utils_label(*queue.inner(), &queue_label);
-                }
-            }
+            let queue_label = device
+                .get_debugger()
+                .map(|dbg| dbg.queue_label(*queue.inner(), &frame_name, FRAME_LABEL_COLOR));
 
             assert!(queue.family_index == track_queue_family);
 
+            //If there are sparse binds, execute them first. The binds wait for everything the command buffer would wait for,
+            // the command buffer then only waits for the binds.
+            if let Some(bind_guard) = bind_guard {
+                self.submit_sparse_binds(rmg, queue, &sparse_binds, bind_guard)?;
+                self.submit_info_cache.clear();
+                self.submit_info_cache.push(
+                    vk::SemaphoreSubmitInfo::default()
+                        .semaphore(rmg.tracks.0.get(bind_guard.as_ref()).unwrap().sem.inner)
+                        .stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+                        .value(bind_guard.wait_value()),
+                );
+            }
+
             rmg.ctx.device.inner.queue_submit2(
                 *queue.inner(),
                 &[vk::SubmitInfo2::default()
@@ -1130,17 +1477,22 @@ impl<'t> Executor<'t> {
             )?;
 
             #[cfg(feature = "debug_marker")]
-            {
-                if let Some(dbg) = rmg.ctx.device.get_debugger() {
-                    dbg.debug_report_loader
-                        .queue_end_debug_utils_label(*queue.inner());
-                }
-            }
+            drop(queue_label);
         }
 
         //finally build execution struct which we give back to the resource manager for
         // tracking.
         for node in self.schedule.tracks.get(&trackid).unwrap().frames[frame_index].iter_indices() {
+            //guard all upload ranges by this execution
+            for allocation in self.schedule.tracks.get_mut(&trackid).unwrap().nodes[node]
+                .task
+                .registry
+                .upload_allocations
+                .drain(..)
+            {
+                rmg.resources.guard_upload(&allocation, exec_guard);
+            }
+
             used_resources.append(
                 &mut self.schedule.tracks.get_mut(&trackid).unwrap().nodes[node]
                     .task
@@ -1149,13 +1501,86 @@ impl<'t> Executor<'t> {
             );
         }
 
+        //keep decommitted sparse memory alive until the binds have executed.
+        used_resources.extend(
+            sparse_binds
+                .take_retired()
+                .into_iter()
+                .map(|res| res as Box<dyn Any + Send>),
+        );
+
         //add execution to exec cache
         self.execution_cache.push(Execution {
             resources: used_resources, //FIXME: collect
             command_buffer: cb,
             guard: exec_guard,
+            queries,
         });
 
         Ok(())
     }
 }
+
+///All stages the barriers of `barrier` wait in.
+fn dst_stages(barrier: &BarrierBuilder) -> vk::PipelineStageFlags2 {
+    barrier
+        .images
+        .iter()
+        .map(|b| b.dst_stage_mask)
+        .chain(barrier.buffers.iter().map(|b| b.dst_stage_mask))
+        .fold(vk::PipelineStageFlags2::empty(), |stages, mask| {
+            stages | mask
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_only_with_nodes_in_between() {
+        //0 writes a, 1 writes b, 2 reads a, 3 reads b
+        let nodes: [(usize, &[u32]); 4] = [(0, &[0]), (1, &[1]), (2, &[0]), (3, &[1])];
+        let splits = find_split_transitions(nodes.iter().map(|(idx, deps)| (*idx, deps.iter())));
+        assert_eq!(
+            splits,
+            vec![
+                SplitTransition {
+                    producer: 0,
+                    consumer: 2,
+                    resource: 0
+                },
+                SplitTransition {
+                    producer: 1,
+                    consumer: 3,
+                    resource: 1
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn no_split_for_adjacent_nodes() {
+        //chain a -> a -> a, there is nothing to overlap with
+        let nodes: [(usize, &[u32]); 3] = [(4, &[0]), (5, &[0, 1]), (6, &[0])];
+        let splits = find_split_transitions(nodes.iter().map(|(idx, deps)| (*idx, deps.iter())));
+        assert!(splits.is_empty());
+    }
+
+    #[test]
+    fn reset_stages_cover_all_waits() {
+        let mut barrier = BarrierBuilder::new();
+        assert_eq!(dst_stages(&barrier), vk::PipelineStageFlags2::empty());
+        barrier.image_custom_barrier(
+            vk::ImageMemoryBarrier2::default()
+                .dst_stage_mask(vk::PipelineStageFlags2::FRAGMENT_SHADER),
+        );
+        barrier.buffer_custom_barrier(
+            vk::BufferMemoryBarrier2::default().dst_stage_mask(vk::PipelineStageFlags2::TRANSFER),
+        );
+        assert_eq!(
+            dst_stages(&barrier),
+            vk::PipelineStageFlags2::FRAGMENT_SHADER | vk::PipelineStageFlags2::TRANSFER
+        );
+    }
+}
@@ -1,12 +1,16 @@
+use ahash::AHashMap;
 use marpii::{
+    allocator::{RingAllocation, RingBuffer},
     ash::vk,
     context::Device,
     resources::{
-        BufDesc, Buffer, BufferMapError, Image, ImgDesc, PipelineLayout, SafeImageView, Sampler,
+        BufDesc, Buffer, BufferMapError, Image, ImageView, ImgDesc, ImgViewDesc, PipelineLayout,
+        SafeImageView, Sampler, ShaderStage,
     },
-    MarpiiError, OoS,
+    MarpiiError, OoS, PipelineError,
 };
 use slotmap::SlotMap;
+use smallvec::SmallVec;
 use std::{marker::PhantomData, sync::Arc};
 use thiserror::Error;
 
@@ -14,11 +18,12 @@ use crate::{
     resources::{
         descriptor::{Bindless, ResourceHandle},
         res_states::{
-            BufferKey, ImageKey, QueueOwnership, ResBuffer, ResImage, ResSampler, SamplerKey,
+            BufferKey, ImageKey, QueueOwnership, ResBuffer, ResImage, ResImageView, ResSampler,
+            SamplerKey,
         },
     },
-    track::Tracks,
-    BufferHandle, Config, ImageHandle, SamplerHandle,
+    track::{Guard, Tracks},
+    BufferHandle, Config, CtxRmg, ImageHandle, SamplerHandle,
 };
 
 use self::{handle::AnyHandle, res_states::AnyResKey};
@@ -85,6 +90,12 @@ pub enum ResourceError {
 
     #[error("There are no attachments at all present, color nor depht.")]
     NoAttachments,
+
+    #[error("Resource {0} was not requested by the task")]
+    ResourceNotRequested(AnyHandle),
+
+    #[error("Invalid image view: {0}")]
+    InvalidImageView(String),
 }
 
 ///Rmg's resource management. This bundles all state that outlifes a single frame. Meaning Images, buffers and samplers.
@@ -96,6 +107,11 @@ pub struct Resources {
     pub(crate) images: SlotMap<ImageKey, ResImage>,
     pub(crate) buffer: SlotMap<BufferKey, ResBuffer>,
     pub(crate) sampler: SlotMap<SamplerKey, ResSampler>,
+
+    ///Staging ring buffer used for uploads. Created on first use.
+    pub(crate) upload_ring: Option<RingBuffer<Guard>>,
+    upload_ring_size: u64,
+    upload_alignment: u64,
 }
 
 impl Resources {
@@ -109,6 +125,13 @@ impl Resources {
             buffer: SlotMap::with_key(),
             images: SlotMap::with_key(),
             sampler: SlotMap::with_key(),
+            upload_ring: None,
+            upload_ring_size: config.upload_ring_size,
+            upload_alignment: config
+                .limit
+                .limits
+                .optimal_buffer_copy_offset_alignment
+                .max(16),
         })
     }
 
@@ -116,6 +139,170 @@ impl Resources {
         self.bindless_layout.clone()
     }
 
+    ///Checks that `stage` can be used with the [bindless layout](Self::bindless_layout). That is, all descriptors of the
+    /// stage's module match the bindless sets, the entry point exists, and its push constant block fits the layout's push constant range.
+    ///
+    /// Also checks that the stage's specialization constants are declared by the module.
+    pub fn validate_shader_stage(&self, stage: &ShaderStage) -> Result<(), PipelineError> {
+        stage
+            .module()
+            .reflection
+            .validate_specialization(&stage.specialization)?;
+
+        let bindings = self.bindless.set_layout_bindings();
+        let sets = bindings
+            .iter()
+            .map(core::slice::from_ref)
+            .collect::<SmallVec<[_; 5]>>();
+        stage.module().reflection.validate_layout(
+            stage.entry_name.to_str().unwrap_or_default(),
+            stage.stage,
+            &sets,
+            self.bindless.push_constant_size(),
+        )
+    }
+
+    ///Binds `view` of `image` to the bindless set(s) matching the image's usage.
+    fn bind_image_descriptor(
+        bindless: &mut Bindless,
+        image: &ResImage,
+        view: Arc<ImageView>,
+    ) -> Result<ResourceHandle, ResourceError> {
+        match (image.is_sampled_image(), image.is_storage_image()) {
+            (true, true) => {
+                #[cfg(feature = "logging")]
+                log::trace!("Binding image to both, sampled, and storage descriptor set");
+                bindless
+                    .bind_sampled_storage_image(view)
+                    .map_err(|_olderr| ResourceError::BindingFailed)
+            }
+            (true, false) => bindless
+                .bind_sampled_image(view)
+                .map_err(|_olderr| ResourceError::BindingFailed),
+            (false, true) => bindless
+                .bind_storage_image(view)
+                .map_err(|_olderr| ResourceError::BindingFailed),
+            (false, false) => {
+                #[cfg(feature = "logging")]
+                log::error!("Failed to bind image, neither STORAGE, nor SAMPLED bit set");
+                Err(ResourceError::BindingFailed)
+            }
+        }
+    }
+
+    ///Removes a descriptor of `image` that was bound via [`bind_image_descriptor`](Self::bind_image_descriptor).
+    fn unbind_image_descriptor(bindless: &mut Bindless, image: &ResImage, hdl: ResourceHandle) {
+        if image.is_sampled_image() {
+            bindless.remove_sampled_image(hdl);
+        }
+        if image.is_storage_image() {
+            bindless.remove_storage_image(hdl);
+        }
+    }
+
+    ///Checks that `desc` is a valid view of an image described by `image`.
+    fn validate_image_view(image: &ImgDesc, desc: &ImgViewDesc) -> Result<(), ResourceError> {
+        if desc.format != image.format
+            && !image
+                .create_flags
+                .contains(vk::ImageCreateFlags::MUTABLE_FORMAT)
+        {
+            return Err(ResourceError::InvalidImageView(format!(
+                "format {:?} differs from image format {:?}, but the image is not MUTABLE_FORMAT",
+                desc.format, image.format
+            )));
+        }
+        if matches!(
+            desc.view_type,
+            vk::ImageViewType::CUBE | vk::ImageViewType::CUBE_ARRAY
+        ) && !image
+            .create_flags
+            .contains(vk::ImageCreateFlags::CUBE_COMPATIBLE)
+        {
+            return Err(ResourceError::InvalidImageView(
+                "cube view of an image that is not CUBE_COMPATIBLE".to_owned(),
+            ));
+        }
+
+        let mip_end = if desc.range.level_count == vk::REMAINING_MIP_LEVELS {
+            desc.range.base_mip_level + 1
+        } else {
+            desc.range.base_mip_level + desc.range.level_count
+        };
+        let layer_end = if desc.range.layer_count == vk::REMAINING_ARRAY_LAYERS {
+            desc.range.base_array_layer + 1
+        } else {
+            desc.range.base_array_layer + desc.range.layer_count
+        };
+        if mip_end > image.mip_levels || layer_end > image.img_type.layer_count() {
+            return Err(ResourceError::InvalidImageView(format!(
+                "subresource range {:?} exceeds image with {} mip levels and {} layers",
+                desc.range,
+                image.mip_levels,
+                image.img_type.layer_count()
+            )));
+        }
+        Ok(())
+    }
+
+    ///Returns the view of `hdl`. For derived handles (see [`ImageHandle::view`]) the view is created on first use,
+    /// and cached with the image.
+    pub fn image_view(&mut self, hdl: &ImageHandle) -> Result<Arc<ImageView>, ResourceError> {
+        let image = self
+            .images
+            .get_mut(hdl.key)
+            .expect("Used invalid ImageHandle");
+        let Some(desc) = hdl.view else {
+            return Ok(image.view.clone());
+        };
+        if let Some(view) = image.views.get(&desc) {
+            return Ok(view.view.clone());
+        }
+
+        Self::validate_image_view(&image.image.desc, &desc)?;
+        let view = Arc::new(
+            OoS::<Image>::from(image.image.clone())
+                .view(desc)
+                .map_err(|e| ResourceError::MarpiiError(e.into()))?,
+        );
+        image.views.insert(
+            desc,
+            ResImageView {
+                view: view.clone(),
+                descriptor_handle: None,
+            },
+        );
+        Ok(view)
+    }
+
+    ///Returns the view of `hdl`, or `None` if `hdl` is a derived handle whose view was not created yet.
+    /// See [`image_view`](Self::image_view).
+    pub fn get_image_view(&self, hdl: &ImageHandle) -> Option<Arc<ImageView>> {
+        let image = self.images.get(hdl.key).expect("Used invalid ImageHandle");
+        match &hdl.view {
+            Some(desc) => image.views.get(desc).map(|v| v.view.clone()),
+            None => Some(image.view.clone()),
+        }
+    }
+
+    ///Binds the custom view `desc` of image `key`.
+    fn bind_view(
+        &mut self,
+        key: ImageKey,
+        desc: ImgViewDesc,
+    ) -> Result<ResourceHandle, ResourceError> {
+        let hdl = ImageHandle {
+            key,
+            imgref: self.images.get(key).unwrap().image.clone(),
+            view: Some(desc),
+        };
+        let view = self.image_view(&hdl)?;
+        let image = self.images.get_mut(key).unwrap();
+        let descriptor = Self::bind_image_descriptor(&mut self.bindless, image, view)?;
+        image.views.get_mut(&desc).unwrap().descriptor_handle = Some(descriptor);
+        Ok(descriptor)
+    }
+
     ///Binds the resource for use on the gpu.
     fn bind(&mut self, res: impl Into<AnyResKey>) -> Result<ResourceHandle, ResourceError> {
         let res = res.into();
@@ -138,36 +325,11 @@ impl Resources {
                     return Err(ResourceError::AlreadyBound(res.into(), *hdl));
                 }
 
-                match (image.is_sampled_image(), image.is_storage_image()) {
-                    (true, true) => {
-                        #[cfg(feature = "logging")]
-                        log::trace!("Binding image to both, sampled, and storage descriptor set");
-                        image.descriptor_handle = Some(
-                            self.bindless
-                                .bind_sampled_storage_image(image.view.clone())
-                                .map_err(|_olderr| ResourceError::BindingFailed)?,
-                        );
-                    }
-                    (true, false) => {
-                        image.descriptor_handle = Some(
-                            self.bindless
-                                .bind_sampled_image(image.view.clone())
-                                .map_err(|_olderr| ResourceError::BindingFailed)?,
-                        );
-                    }
-                    (false, true) => {
-                        image.descriptor_handle = Some(
-                            self.bindless
-                                .bind_storage_image(image.view.clone())
-                                .map_err(|_olderr| ResourceError::BindingFailed)?,
-                        );
-                    }
-                    (false, false) => {
-                        #[cfg(feature = "logging")]
-                        log::error!("Failed to bind image, neither STORAGE, nor SAMPLED bit set");
-                        return Err(ResourceError::BindingFailed);
-                    }
-                }
+                image.descriptor_handle = Some(Self::bind_image_descriptor(
+                    &mut self.bindless,
+                    image,
+                    image.view.clone(),
+                )?);
 
                 Ok(image.descriptor_handle.unwrap())
             }
@@ -211,11 +373,13 @@ impl Resources {
             layout: vk::ImageLayout::UNDEFINED,
             guard: None,
             descriptor_handle: None,
+            views: AHashMap::default(),
         });
 
         Ok(ImageHandle {
             key,
             imgref: image_arc,
+            view: None,
         })
     }
 
@@ -265,6 +429,8 @@ impl Resources {
     }
 
     ///Imports the buffer with the given state. Returns an error if a given `queue_family` index has no internal `TrackId`.
+    ///
+    /// If `queue_family` is `vk::QUEUE_FAMILY_EXTERNAL` the buffer is assumed to be released by an external party, and is acquired on first use.
     pub(crate) fn import_buffer<T: 'static>(
         &mut self,
         tracks: &Tracks,
@@ -272,7 +438,9 @@ impl Resources {
         queue_family: Option<u32>,
         access_flags: Option<vk::AccessFlags2>,
     ) -> Result<BufferHandle<T>, ResourceError> {
-        let owner = if let Some(fam) = queue_family {
+        let owner = if queue_family == Some(vk::QUEUE_FAMILY_EXTERNAL) {
+            QueueOwnership::EXTERNAL
+        } else if let Some(fam) = queue_family {
             let track = tracks.0.iter().find_map(|(track_id, track)| {
                 if track.queue_idx == fam {
                     Some(track_id)
@@ -319,6 +487,8 @@ impl Resources {
     }
 
     ///Imports the image with the given state. Returns an error if a given `queue_family` index has no internal `TrackId`.
+    ///
+    /// If `queue_family` is `vk::QUEUE_FAMILY_EXTERNAL` the image is assumed to be released by an external party, and is acquired on first use.
     pub(crate) fn import_image(
         &mut self,
         tracks: &Tracks,
@@ -327,7 +497,9 @@ impl Resources {
         layout: Option<vk::ImageLayout>,
         access_flags: Option<vk::AccessFlags2>,
     ) -> Result<ImageHandle, ResourceError> {
-        let owner = if let Some(fam) = queue_family {
+        let owner = if queue_family == Some(vk::QUEUE_FAMILY_EXTERNAL) {
+            QueueOwnership::EXTERNAL
+        } else if let Some(fam) = queue_family {
             let track = tracks.0.iter().find_map(|(track_id, track)| {
                 if track.queue_idx == fam {
                     Some(track_id)
@@ -364,14 +536,83 @@ impl Resources {
             guard: None,
             layout,
             descriptor_handle: None,
+            views: AHashMap::default(),
         });
 
         Ok(ImageHandle {
             key,
             imgref: image_arc,
+            view: None,
         })
     }
 
+    ///Allocates a host visible staging range for `data`, and writes `data` to it. The range is sub-allocated
+    /// from RMG's upload ring buffer. If the ring can't fit `data`, a dedicated staging buffer is created instead.
+    ///
+    /// The range is at least aligned to `alignment`, as well as the device's optimal buffer copy alignment.
+    /// Use [`request_upload`](crate::ResourceRegistry::request_upload) in the task that consumes the data, to keep the range valid
+    /// until the task has finished executing.
+    pub fn upload_data<T: marpii::bytemuck::Pod>(
+        &mut self,
+        ctx: &CtxRmg,
+        data: &[T],
+        alignment: u64,
+    ) -> Result<RingAllocation, ResourceError> {
+        let bytes: &[u8] = marpii::bytemuck::cast_slice(data);
+
+        if self.upload_ring.is_none() && self.upload_ring_size > 0 {
+            self.upload_ring = Some(
+                RingBuffer::new(
+                    &ctx.device,
+                    &ctx.allocator,
+                    self.upload_ring_size,
+                    vk::BufferUsageFlags::empty(),
+                    Some("RmgUploadRing"),
+                )
+                .map_err(MarpiiError::from)?,
+            );
+        }
+
+        if let Some(ring) = &mut self.upload_ring {
+            //lcm of both, so that odd texel sizes (like RGB32) are respected as well.
+            let alignment = alignment.max(1);
+            let alignment =
+                (alignment / gcd(alignment, self.upload_alignment)) * self.upload_alignment;
+            match ring.allocate(bytes.len() as u64, alignment) {
+                Ok(allocation) => {
+                    allocation
+                        .write(0, bytes)
+                        .map_err(ResourceError::BufferMapError)?;
+                    return Ok(allocation);
+                }
+                #[allow(unused_variables)]
+                Err(e) => {
+                    #[cfg(feature = "logging")]
+                    log::trace!(
+                        "Upload ring can't fit upload, using dedicated buffer: {}",
+                        e
+                    );
+                }
+            }
+        }
+
+        let buffer = Buffer::new_staging_for_data(
+            &ctx.device,
+            &ctx.allocator,
+            Some("RmgUploadStaging"),
+            bytes,
+        )
+        .map_err(MarpiiError::from)?;
+        Ok(RingAllocation::dedicated(Arc::new(buffer)))
+    }
+
+    ///Sets the guard of an upload allocation, if it is located on the upload ring.
+    pub(crate) fn guard_upload(&mut self, allocation: &RingAllocation, guard: Guard) {
+        if let Some(ring) = self.upload_ring.as_mut().filter(|r| r.owns(allocation)) {
+            ring.set_guard(allocation, guard);
+        }
+    }
+
     ///Tries to get the resource's bindless handle. If not already bound, tries to bind the resource
     pub fn resource_handle_or_bind(
         &mut self,
@@ -382,7 +623,11 @@ impl Resources {
         } else {
             let hdl = res.into();
             //try to bind, try that
-            Ok(self.bind(hdl.key)?)
+            if let (AnyResKey::Image(key), Some(desc)) = (hdl.key, hdl.view) {
+                self.bind_view(key, desc)
+            } else {
+                Ok(self.bind(hdl.key)?)
+            }
         }
     }
 
@@ -393,7 +638,14 @@ impl Resources {
         //      it is a bug.
         let hdl = match hdl.key {
             AnyResKey::Buffer(buf) => self.buffer.get(buf).unwrap().descriptor_handle,
-            AnyResKey::Image(img) => self.images.get(img).unwrap().descriptor_handle,
+            AnyResKey::Image(img) => {
+                let image = self.images.get(img).unwrap();
+                if let Some(desc) = &hdl.view {
+                    image.views.get(desc).and_then(|v| v.descriptor_handle)
+                } else {
+                    image.descriptor_handle
+                }
+            }
             AnyResKey::Sampler(sam) => self.sampler.get(sam).unwrap().descriptor_handle,
         };
         hdl
@@ -403,11 +655,20 @@ impl Resources {
     //TODO: Currently we use the rendering frame to do all the cleanup. In a perfect world we'd use
     //      another thread for that to not stall the recording process
     pub(crate) fn tick_record(&mut self, tracks: &Tracks) {
+        if let Some(ring) = &mut self.upload_ring {
+            ring.reclaim(|guard| guard.expired(tracks));
+        }
+
         self.images.retain(|#[allow(unused_variables)] key, img| {
             if img.is_orphaned() && img.guard.is_none_or(|g| g.expired(tracks)) {
                 #[cfg(feature = "logging")]
                 log::trace!("Dropping {:?}", key);
 
+                for view in img.views.values() {
+                    if let Some(hdl) = view.descriptor_handle {
+                        Self::unbind_image_descriptor(&mut self.bindless, img, hdl);
+                    }
+                }
                 if let Some(hdl) = img.descriptor_handle {
                     match (img.is_sampled_image(), img.is_storage_image()){
                         (true, true) => {
@@ -546,3 +807,72 @@ impl Resources {
         }
     }
 }
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use marpii::resources::ImageType;
+
+    fn view_of(image: &ImgDesc) -> ImgViewDesc {
+        ImgViewDesc {
+            view_type: ImgDesc::convert_imagety_to_image_viewty(image.img_type),
+            format: image.format,
+            component_mapping: vk::ComponentMapping::default(),
+            range: image.subresource_all(),
+        }
+    }
+
+    #[test]
+    fn full_view_is_valid() {
+        let image = ImgDesc::texture_2d(64, 64, vk::Format::R8G8B8A8_UNORM);
+        assert!(Resources::validate_image_view(&image, &view_of(&image)).is_ok());
+    }
+
+    #[test]
+    fn view_format_needs_mutable_format() {
+        let image = ImgDesc::texture_2d(64, 64, vk::Format::R8G8B8A8_UNORM);
+        let view = view_of(&image).with_format(vk::Format::R8G8B8A8_SRGB);
+        assert!(matches!(
+            Resources::validate_image_view(&image, &view),
+            Err(ResourceError::InvalidImageView(_))
+        ));
+
+        let image = image.add_create_flag(vk::ImageCreateFlags::MUTABLE_FORMAT);
+        assert!(Resources::validate_image_view(&image, &view).is_ok());
+    }
+
+    #[test]
+    fn cube_view_needs_cube_compatible() {
+        let mut image = ImgDesc::texture_2d(64, 64, vk::Format::R8G8B8A8_UNORM);
+        image.img_type = ImageType::Tex2dArray(6);
+        let view = view_of(&image).with_view_type(vk::ImageViewType::CUBE);
+        assert!(Resources::validate_image_view(&image, &view).is_err());
+
+        let image = image.add_create_flag(vk::ImageCreateFlags::CUBE_COMPATIBLE);
+        assert!(Resources::validate_image_view(&image, &view).is_ok());
+    }
+
+    #[test]
+    fn view_range_must_fit_image() {
+        let mut image = ImgDesc::texture_2d(64, 64, vk::Format::R8G8B8A8_UNORM);
+        image.mip_levels = 4;
+        image.img_type = ImageType::Tex2dArray(2);
+
+        let view = view_of(&image).with_mip_range(1, 3).with_layer_range(1, 1);
+        assert!(Resources::validate_image_view(&image, &view).is_ok());
+        let view = view_of(&image).with_mip_range(3, vk::REMAINING_MIP_LEVELS);
+        assert!(Resources::validate_image_view(&image, &view).is_ok());
+
+        let view = view_of(&image).with_mip_range(2, 3);
+        assert!(Resources::validate_image_view(&image, &view).is_err());
+        let view = view_of(&image).with_layer_range(2, vk::REMAINING_ARRAY_LAYERS);
+        assert!(Resources::validate_image_view(&image, &view).is_err());
+    }
+}
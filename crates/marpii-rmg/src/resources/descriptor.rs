@@ -562,27 +562,67 @@ impl Bindless {
         })
     }
 
-    ///Creates a new instance of the pipeline layout used for bindless descriptors.
-    pub fn new_pipeline_layout(&self) -> PipelineLayout {
-        //NOTE: This is the delicate part. We create a link between the descriptor set layouts and this pipeline layout. This is however *safe*
-        //      since we keep the sets in memory together with the pipeline layout. On drop the pipeline layout is destried before the descriptorset layouts
-        //      which is again *safe*
-        let mut descset_layouts = vec![
+    ///Returns the binding of each descriptor set in the order of [`new_pipeline_layout`](Self::new_pipeline_layout).
+    /// Each set contains a single (array) binding at index 0.
+    pub fn set_layout_bindings(
+        &self,
+    ) -> SmallVec<[vk::DescriptorSetLayoutBinding<'static>; Self::NUM_SETS as usize]> {
+        let mut bindings = SmallVec::new();
+        let mut push = |ty: vk::DescriptorType, max_idx: u32| {
+            bindings.push(vk::DescriptorSetLayoutBinding {
+                binding: 0,
+                descriptor_type: ty,
+                descriptor_count: max_idx,
+                stage_flags: vk::ShaderStageFlags::ALL,
+                ..Default::default()
+            });
+        };
+        push(self.stbuffer.ty, self.stbuffer.max_idx);
+        push(self.stimage.ty, self.stimage.max_idx);
+        push(self.saimage.ty, self.saimage.max_idx);
+        push(self.sampler.ty, self.sampler.max_idx);
+        if let Some(accel) = &self.accel {
+            push(accel.ty, accel.max_idx);
+        }
+        bindings
+    }
+
+    ///Size of the push constant range of the bindless pipeline layout.
+    pub fn push_constant_size(&self) -> u32 {
+        self.push_constant_size
+    }
+
+    ///Returns the descriptor set layouts in the order of [`new_pipeline_layout`](Self::new_pipeline_layout).
+    pub fn set_layouts(&self) -> SmallVec<[vk::DescriptorSetLayout; Self::NUM_SETS as usize]> {
+        let mut layouts = SmallVec::from_slice(&[
             self.stbuffer.layout.inner,
             self.stimage.layout.inner,
             self.saimage.layout.inner,
             self.sampler.layout.inner,
-        ];
+        ]);
 
         if let Some(accel_binding) = &self.accel {
-            descset_layouts.push(accel_binding.layout.inner);
+            layouts.push(accel_binding.layout.inner);
         }
+        layouts
+    }
 
-        let push_range = vk::PushConstantRange {
+    ///The push constant range of the bindless pipeline layout, visible to all stages.
+    pub fn push_constant_range(&self) -> vk::PushConstantRange {
+        vk::PushConstantRange {
             stage_flags: vk::ShaderStageFlags::ALL,
             offset: 0,
             size: self.push_constant_size,
-        };
+        }
+    }
+
+    ///Creates a new instance of the pipeline layout used for bindless descriptors.
+    pub fn new_pipeline_layout(&self) -> PipelineLayout {
+        //NOTE: This is the delicate part. We create a link between the descriptor set layouts and this pipeline layout. This is however *safe*
+        //      since we keep the sets in memory together with the pipeline layout. On drop the pipeline layout is destried before the descriptorset layouts
+        //      which is again *safe*
+        let descset_layouts = self.set_layouts();
+        let push_range = self.push_constant_range();
 
         let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo::default()
             .set_layouts(&descset_layouts)
@@ -13,7 +13,7 @@
 use crate::resources::res_states::{BufferKey, ImageKey, SamplerKey};
 use marpii::{
     ash::vk::{self, DeviceAddress},
-    resources::{BufDesc, Buffer, Image, ImageType, ImgDesc, Sampler},
+    resources::{BufDesc, Buffer, Image, ImageType, ImgDesc, ImgViewDesc, Sampler},
     util::ImageRegion,
 };
 use std::{
@@ -32,9 +32,44 @@ pub struct ImageHandle {
     // dropped
     pub(crate) key: ImageKey,
     pub(crate) imgref: Arc<Image>,
+    ///Custom view of the image, if this is a derived handle. `None` uses the full view.
+    pub(crate) view: Option<ImgViewDesc>,
 }
 
 impl ImageHandle {
+    ///Returns a derived handle that uses the view described by `desc` instead of the full image view. Can be used to bind a single mip level,
+    /// array layer, cube face, or a different format (for instance sRGB vs. UNORM) of the same image.
+    ///
+    /// The derived handle gets its own bindless `ResourceHandle` when bound. The view is created on first use and cached
+    /// with the image. All state (layout, ownership, access) is tracked on the parent image, so a derived handle and its
+    /// parent can't be requested by the same task.
+    pub fn view(&self, desc: ImgViewDesc) -> ImageHandle {
+        let view = if desc == self.imgref.view_all() {
+            None
+        } else {
+            Some(desc)
+        };
+        ImageHandle {
+            key: self.key,
+            imgref: self.imgref.clone(),
+            view,
+        }
+    }
+
+    ///Returns the handle to the full image, if this is a derived handle, see [`view`](Self::view).
+    pub fn parent(&self) -> ImageHandle {
+        ImageHandle {
+            key: self.key,
+            imgref: self.imgref.clone(),
+            view: None,
+        }
+    }
+
+    ///Returns the custom view description, if this is a derived handle.
+    pub fn view_desc(&self) -> Option<&ImgViewDesc> {
+        self.view.as_ref()
+    }
+
     pub fn format(&self) -> &vk::Format {
         &self.imgref.desc.format
     }
@@ -66,13 +101,17 @@ impl ImageHandle {
 
 impl Debug for ImageHandle {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "ImageHandle({:?})", self.key)
+        if let Some(view) = &self.view {
+            write!(f, "ImageHandle({:?}, {:?})", self.key, view)
+        } else {
+            write!(f, "ImageHandle({:?})", self.key)
+        }
     }
 }
 
 impl PartialEq for ImageHandle {
     fn eq(&self, other: &Self) -> bool {
-        self.key == other.key
+        self.key == other.key && self.view == other.view
     }
 }
 impl Eq for ImageHandle {}
@@ -177,6 +216,8 @@ pub struct AnyHandle {
     #[allow(dead_code)]
     pub(crate) atomic_ref: Option<Arc<dyn Any + Send + Sync + 'static>>,
     pub(crate) key: AnyResKey,
+    ///Custom image view, see [`ImageHandle::view`].
+    pub(crate) view: Option<ImgViewDesc>,
 }
 
 impl AnyHandle {
@@ -202,6 +243,7 @@ impl From<AnyResKey> for AnyHandle {
         AnyHandle {
             atomic_ref: None,
             key: res,
+            view: None,
         }
     }
 }
@@ -211,6 +253,7 @@ impl From<ImageHandle> for AnyHandle {
         AnyHandle {
             atomic_ref: Some(h.imgref),
             key: h.key.into(),
+            view: h.view,
         }
     }
 }
@@ -220,6 +263,7 @@ impl From<&ImageHandle> for AnyHandle {
         AnyHandle {
             atomic_ref: Some(h.imgref.clone()),
             key: h.key.into(),
+            view: h.view,
         }
     }
 }
@@ -229,6 +273,7 @@ impl<T: 'static> From<BufferHandle<T>> for AnyHandle {
         AnyHandle {
             atomic_ref: Some(h.bufref),
             key: h.key.into(),
+            view: None,
         }
     }
 }
@@ -237,6 +282,7 @@ impl<T: 'static> From<&BufferHandle<T>> for AnyHandle {
         AnyHandle {
             atomic_ref: Some(h.bufref.clone()),
             key: h.key.into(),
+            view: None,
         }
     }
 }
@@ -246,6 +292,7 @@ impl From<ImageKey> for AnyHandle {
         AnyHandle {
             atomic_ref: None,
             key: k.into(),
+            view: None,
         }
     }
 }
@@ -255,6 +302,7 @@ impl From<&ImageKey> for AnyHandle {
         AnyHandle {
             atomic_ref: None,
             key: (*k).into(),
+            view: None,
         }
     }
 }
@@ -264,6 +312,7 @@ impl From<BufferKey> for AnyHandle {
         AnyHandle {
             atomic_ref: None,
             key: k.into(),
+            view: None,
         }
     }
 }
@@ -273,6 +322,7 @@ impl From<&BufferKey> for AnyHandle {
         AnyHandle {
             atomic_ref: None,
             key: (*k).into(),
+            view: None,
         }
     }
 }
@@ -282,6 +332,7 @@ impl From<SamplerHandle> for AnyHandle {
         AnyHandle {
             atomic_ref: Some(h.samref),
             key: h.key.into(),
+            view: None,
         }
     }
 }
@@ -291,6 +342,7 @@ impl From<&SamplerHandle> for AnyHandle {
         AnyHandle {
             atomic_ref: Some(h.samref.clone()),
             key: h.key.into(),
+            view: None,
         }
     }
 }
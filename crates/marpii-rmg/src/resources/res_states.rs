@@ -1,8 +1,9 @@
 use std::{fmt::Display, sync::Arc};
 
+use ahash::AHashMap;
 use marpii::{
     ash::vk,
-    resources::{Buffer, Image, ImageView, Sampler},
+    resources::{Buffer, Image, ImageView, ImgViewDesc, Sampler},
 };
 
 use crate::track::Guard;
@@ -22,6 +23,8 @@ slotmap::new_key_type!(
     pub(crate) struct SamplerKey;
 );
 
+///Queue family ownership of a resource. A resource that is released to `vk::QUEUE_FAMILY_EXTERNAL` is owned by some
+/// external party (another process or API). It is acquired from `QUEUE_FAMILY_EXTERNAL` by whichever track uses it next.
 #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 pub enum QueueOwnership {
     Uninitialized,
@@ -30,6 +33,17 @@ pub enum QueueOwnership {
 }
 
 impl QueueOwnership {
+    ///State of a resource that is currently owned by an external party.
+    pub const EXTERNAL: Self = QueueOwnership::Released {
+        src_family: vk::QUEUE_FAMILY_EXTERNAL,
+        dst_family: vk::QUEUE_FAMILY_EXTERNAL,
+    };
+
+    ///Returns true if the resource is released to an external party.
+    pub fn is_external(&self) -> bool {
+        self.is_released_to(vk::QUEUE_FAMILY_EXTERNAL)
+    }
+
     pub fn is_initalised(&self) -> bool {
         self != &QueueOwnership::Uninitialized
     }
@@ -66,6 +80,16 @@ pub struct ResImage {
 
     ///Handle into bindless this is located at.
     pub descriptor_handle: Option<ResourceHandle>,
+
+    ///Custom views created via [`ImageHandle::view`](crate::ImageHandle::view).
+    pub views: AHashMap<ImgViewDesc, ResImageView>,
+}
+
+///Cached custom view of a [`ResImage`]. State tracking happens on the image itself.
+pub struct ResImageView {
+    pub view: Arc<ImageView>,
+    ///Handle into bindless this view is located at.
+    pub descriptor_handle: Option<ResourceHandle>,
 }
 
 impl ResImage {
@@ -84,8 +108,8 @@ impl ResImage {
         // 2. if bound: the descriptor set
         // 3. if in flight: in the execution guarded by self.guard
 
-        //the image itself is borrowed at least once in the image view as well
-        let image_strong = 2;
+        //the image itself is borrowed at least once in the image view as well, and once in each custom view
+        let image_strong = 2 + self.views.len();
 
         //if the strong count is higher, somewhere referenced
         Arc::strong_count(&self.image) <= image_strong
@@ -1,10 +1,13 @@
 use ahash::AHashMap;
 use marpii::{
-    allocator::MemoryUsage,
+    allocator::{AllocationDesc, MemoryUsage},
     ash::vk,
     context::Ctx,
     gpu_allocator::vulkan::Allocator,
-    resources::{BufDesc, Buffer, Image, ImgDesc, Sampler, SharingMode},
+    resources::{
+        BufDesc, Buffer, CommandPoolStatistics, Image, ImageType, ImgDesc, Sampler, ShaderObject,
+        ShaderStage, SharingMode, SparseImageResidency,
+    },
     MarpiiError,
 };
 use marpii_rmg_shared::ResourceHandle;
@@ -21,6 +24,7 @@ use crate::{
     resources::handle::AnyHandle,
     track::{Track, TrackId, Tracks},
     BufferHandle, Config, ImageHandle, RecordError, ResourceError, Resources, SamplerHandle,
+    TaskQueryResult,
 };
 
 #[cfg(feature = "debug_marker")]
@@ -53,6 +57,31 @@ pub enum RmgError {
 
 pub type CtxRmg = Ctx<Allocator>;
 
+///Returns the features that are missing to create a sparse, partially resident image of `img_type`.
+fn missing_sparse_image_features(config: &Config, img_type: &ImageType) -> Vec<String> {
+    let mut missing = Vec::new();
+    if !config.sparse_binding_support {
+        missing.push("PhysicalDeviceFeatures::sparse_binding".to_owned());
+    }
+    match img_type {
+        ImageType::Tex2d | ImageType::Tex2dArray(_) | ImageType::Cube | ImageType::CubeArray(_) => {
+            if !config.sparse_residency_image2d_support {
+                missing.push("PhysicalDeviceFeatures::sparse_residency_image2_d".to_owned());
+            }
+        }
+        ImageType::Tex3d | ImageType::Tex3dArray(_) => {
+            if !config.sparse_residency_image3d_support {
+                missing.push("PhysicalDeviceFeatures::sparse_residency_image3_d".to_owned());
+            }
+        }
+        //NOTE: Vulkan has no residency feature for 1D images.
+        ImageType::Tex1d | ImageType::Tex1dArray(_) => {
+            missing.push("sparse residency for 1D images".to_owned());
+        }
+    }
+    missing
+}
+
 macro_rules! check_feature {
     ($vkf:ident, $name:ident, $missing:ident, $any_needed:ident) => {
         if $vkf.$name == 0 {
@@ -73,6 +102,9 @@ pub struct Rmg {
     pub ctx: CtxRmg,
 
     config: Config,
+
+    ///Index of the next recording, see [Recorder::submission].
+    pub(crate) next_submission: u64,
 }
 
 impl Rmg {
@@ -80,6 +112,73 @@ impl Rmg {
         &self.config
     }
 
+    ///Enables or disables split barriers for all following recordings, see [Config::split_barriers].
+    ///
+    /// If enabled, a transition between two tasks on the same graphics or compute track is recorded as a `vkCmdSetEvent2` after
+    /// the producing task, and a `vkCmdWaitEvents2` before the consuming task, if at least one independent task is recorded
+    /// in between. Otherwise a single pipeline barrier is used.
+    pub fn set_split_barriers(&mut self, enable: bool) {
+        self.config.split_barriers = enable;
+    }
+
+    ///Returns a [ShaderCompiler](marpii::resources::ShaderCompiler) that resolves `#include "shared.glsl"` to RMG's
+    /// [GLSL resource handle definitions](marpii_rmg_shared::SHARED_GLSL).
+    #[cfg(feature = "shader_compiler")]
+    pub fn shader_compiler() -> marpii::resources::ShaderCompiler {
+        marpii::resources::ShaderCompiler::new()
+            .with_virtual_file("shared.glsl", marpii_rmg_shared::SHARED_GLSL)
+    }
+
+    ///Creates a [PipelineLibraryCache](marpii::resources::PipelineLibraryCache) for pipelines that use the bindless layout.
+    /// Build the variants with a [GraphicsPipelineBuilder](marpii::resources::GraphicsPipelineBuilder) that lists the color and depth
+    /// attachments.
+    ///
+    /// Returns `None` if `VK_EXT_graphics_pipeline_library` is not supported, see [Config::graphics_pipeline_library_support].
+    pub fn pipeline_library_cache(
+        &self,
+        link_time_optimization: bool,
+    ) -> Option<marpii::resources::PipelineLibraryCache> {
+        if !self.config.graphics_pipeline_library_support {
+            return None;
+        }
+        Some(marpii::resources::PipelineLibraryCache::new(
+            &self.ctx.device,
+            self.resources.bindless_layout(),
+            link_time_optimization,
+        ))
+    }
+
+    ///Creates a [ShaderObject](marpii::resources::ShaderObject) for `stage` that uses the bindless layout. `next_stage` lists the stages that may
+    /// follow this one, for instance `FRAGMENT` for a vertex shader. Use the objects via [`new_compute_pass`](Self::new_compute_pass)
+    /// or [`new_raster_shaders`](Self::new_raster_shaders).
+    ///
+    /// Fails if `VK_EXT_shader_object` is not supported (see [Config::shader_object_support]), or if the stage does not fit the bindless layout.
+    pub fn shader_object(
+        &self,
+        stage: &ShaderStage,
+        next_stage: vk::ShaderStageFlags,
+    ) -> Result<Arc<ShaderObject>, RmgError> {
+        if !self.config.shader_object_support {
+            return Err(RmgError::MissingFeatures(vec![
+                "PhysicalDeviceShaderObjectFeaturesEXT::shader_object".to_owned(),
+            ]));
+        }
+        self.resources
+            .validate_shader_stage(stage)
+            .map_err(MarpiiError::from)?;
+
+        let bindless = &self.resources.bindless;
+        let shader = ShaderObject::new(
+            &self.ctx.device,
+            stage,
+            next_stage,
+            &bindless.set_layouts(),
+            &[bindless.push_constant_range()],
+        )
+        .map_err(MarpiiError::from)?;
+        Ok(Arc::new(shader))
+    }
+
     fn check_features(context: &Ctx<Allocator>) -> Result<(), RmgError> {
         //Right now we are hardcoding all needed features.
 
@@ -287,7 +386,36 @@ impl Rmg {
         //query context for features
         Self::check_features(&context)?;
 
-        let config = Config::new_for_device(&context.instance, &context.device.physical_device);
+        let mut config = Config::new_for_device(&context.instance, &context.device.physical_device);
+        //Headless devices don't enable the presentation extensions, even if they are supported.
+        config.hdr_metadata_support &= context
+            .device
+            .extension_enabled_cstr(marpii::ash::ext::hdr_metadata::NAME);
+        config.present_wait_support &= context
+            .device
+            .extension_enabled_cstr(marpii::ash::khr::present_wait::NAME);
+        config.display_timing_support &= context
+            .device
+            .extension_enabled_cstr(marpii::ash::google::display_timing::NAME);
+        //Custom contexts might not enable the extensions a supported feature depends on.
+        config.shader_object_support &= context
+            .device
+            .extension_enabled_cstr(marpii::ash::ext::shader_object::NAME);
+        config.graphics_pipeline_library_support &= context
+            .device
+            .extension_enabled_cstr(marpii::ash::ext::graphics_pipeline_library::NAME)
+            && context
+                .device
+                .extension_enabled_cstr(marpii::ash::khr::pipeline_library::NAME);
+        //Sparse support must also be enabled as core feature.
+        let enabled = &context.device.enabled_features;
+        config.sparse_binding_support &= enabled.sparse_binding == vk::TRUE;
+        config.sparse_residency_image2d_support &= enabled.sparse_residency_image2_d == vk::TRUE;
+        config.sparse_residency_image3d_support &= enabled.sparse_residency_image3_d == vk::TRUE;
+        config.sparse_residency_buffer_support &= enabled.sparse_residency_buffer == vk::TRUE;
+        //Queries fall back to imprecise occlusion and no statistics if the features are not enabled.
+        config.pipeline_statistics_query_support &= enabled.pipeline_statistics_query == vk::TRUE;
+        config.occlusion_query_precise_support &= enabled.occlusion_query_precise == vk::TRUE;
 
         //TODO: make the iterator return an error. Currently if track creation fails, everything fails
         let tracks = context.device.queues.iter().fold(
@@ -318,6 +446,7 @@ impl Rmg {
             tracks: Tracks(tracks),
             ctx: context,
             config,
+            next_submission: 0,
         })
     }
 
@@ -334,11 +463,28 @@ impl Rmg {
         &mut self,
         description: ImgDesc,
         name: Option<&str>,
+    ) -> Result<ImageHandle, RmgError> {
+        self.new_image_uninitialized_with_memory(description, MemoryUsage::GpuOnly, name)
+    }
+
+    ///Same as [`new_image_uninitialized`](Self::new_image_uninitialized), but lets you choose how the image's memory is allocated.
+    /// For instance a dedicated allocation with high priority for big render targets, or [`MemoryUsage::GpuLazy`] for transient MSAA attachments.
+    ///
+    /// Transient attachments (`TRANSIENT_ATTACHMENT` usage) are allowed to have neither `SAMPLED` nor `STORAGE` usage. They can't be bound to the
+    /// bindless descriptor set in that case.
+    pub fn new_image_uninitialized_with_memory(
+        &mut self,
+        description: ImgDesc,
+        memory: impl Into<AllocationDesc>,
+        name: Option<&str>,
     ) -> Result<ImageHandle, RmgError> {
         //patch usage bits
 
         if !description.usage.contains(vk::ImageUsageFlags::SAMPLED)
             && !description.usage.contains(vk::ImageUsageFlags::STORAGE)
+            && !description
+                .usage
+                .contains(vk::ImageUsageFlags::TRANSIENT_ATTACHMENT)
         {
             return Err(RmgError::from(ResourceError::ImageNoUsageFlags));
         }
@@ -355,7 +501,7 @@ impl Rmg {
                 &self.ctx.device,
                 &self.ctx.allocator,
                 description,
-                MemoryUsage::GpuOnly, //always cpu only, everything else is handled by passes directly
+                memory,
                 name,
             )
             .map_err(MarpiiError::from)?,
@@ -374,10 +520,63 @@ impl Rmg {
         Ok(self.resources.add_image(image)?)
     }
 
+    ///Creates a sparse, partially resident image. No memory is bound initially. Use the returned residency helper to commit pages,
+    /// and execute the resulting binds via a [`BindSparse`](crate::helper::bind_sparse::BindSparse) task.
+    ///
+    /// Note that sampling non-resident pages is only defined if the device reports `residencyNonResidentStrict`.
+    ///
+    /// Fails if `sparseBinding` or the residency feature of the image type is not supported, see [Config::sparse_residency_image2d_support].
+    pub fn new_sparse_image(
+        &mut self,
+        description: ImgDesc,
+        name: Option<&str>,
+    ) -> Result<(ImageHandle, SparseImageResidency<Allocator>), RmgError> {
+        if !description.usage.contains(vk::ImageUsageFlags::SAMPLED)
+            && !description.usage.contains(vk::ImageUsageFlags::STORAGE)
+        {
+            return Err(RmgError::from(ResourceError::ImageNoUsageFlags));
+        }
+
+        let missing = missing_sparse_image_features(&self.config, &description.img_type);
+        if !missing.is_empty() {
+            return Err(RmgError::MissingFeatures(missing));
+        }
+
+        let image =
+            Arc::new(Image::new_sparse(&self.ctx.device, description).map_err(MarpiiError::from)?);
+
+        #[cfg(feature = "debug_marker")]
+        if let Some(dbg) = self.ctx.device.get_debugger()
+            && let Ok(dbg_name) = std::ffi::CString::new(name.unwrap_or("Sparse Image"))
+            && let Err(e) = dbg.name_object(image.inner, &dbg_name)
+        {
+            #[cfg(feature = "logging")]
+            log::error!("Could not name image: {}", e);
+        }
+        #[cfg(not(feature = "debug_marker"))]
+        let _ = name;
+
+        let residency =
+            SparseImageResidency::new(&self.ctx.device, &self.ctx.allocator, image.clone())
+                .map_err(MarpiiError::from)?;
+        Ok((self.resources.add_image(image)?, residency))
+    }
+
     ///Creates a buffer that holds `n`-times data of type `T`. Where `n = buffer.size / size_of::<T>()`.
     pub fn new_buffer_uninitialized<T: 'static>(
+        &mut self,
+        description: BufDesc,
+        name: Option<&str>,
+    ) -> Result<BufferHandle<T>, RmgError> {
+        self.new_buffer_uninitialized_with_memory(description, MemoryUsage::GpuOnly, name)
+    }
+
+    ///Same as [`new_buffer_uninitialized`](Self::new_buffer_uninitialized), but lets you choose how the buffer's memory is allocated.
+    /// For instance [`MemoryUsage::CpuToGpuDeviceLocal`] for data that is written by the host every frame.
+    pub fn new_buffer_uninitialized_with_memory<T: 'static>(
         &mut self,
         mut description: BufDesc,
+        memory: impl Into<AllocationDesc>,
         name: Option<&str>,
     ) -> Result<BufferHandle<T>, RmgError> {
         #[cfg(feature = "debug_marker")]
@@ -392,7 +591,7 @@ impl Rmg {
                 &self.ctx.device,
                 &self.ctx.allocator,
                 description,
-                MemoryUsage::GpuOnly,
+                memory,
                 name,
             )
             .map_err(MarpiiError::from)?,
@@ -431,6 +630,8 @@ impl Rmg {
     }
 
     ///Imports the buffer with the given state. Returns an error if a given `queue_family` index has no internal `TrackId`.
+    ///
+    /// Use `vk::QUEUE_FAMILY_EXTERNAL` as `queue_family` for buffers that are shared with an external party, see [`import_image`](Self::import_image).
     pub fn import_buffer<T: 'static>(
         &mut self,
         buffer: Arc<Buffer>,
@@ -442,6 +643,11 @@ impl Rmg {
     }
 
     ///Imports the image with the given state. Returns an error if a given `queue_family` index has no internal `TrackId`.
+    ///
+    /// Images that are shared with another process or API (see [`Image::import_fd`](marpii::resources::Image::import_fd)) are imported with
+    /// `vk::QUEUE_FAMILY_EXTERNAL` as `queue_family`, and `layout` set to the layout the external party released the image in. The image is then acquired
+    /// from the external queue family by the first task that uses it. Use [`ResourceRegistry::release_to_external`](crate::ResourceRegistry::release_to_external)
+    /// to hand it back.
     pub fn import_image(
         &mut self,
         image: Arc<Image>,
@@ -473,6 +679,13 @@ impl Rmg {
         Recorder::new(self)
     }
 
+    ///Returns the queue family of the track that executes tasks with the given [`queue_flags`](crate::Task::queue_flags).
+    pub fn queue_family_for_usage(&self, usage: vk::QueueFlags) -> Option<u32> {
+        self.tracks
+            .track_for_usage(usage)
+            .map(|id| self.trackid_to_queue_idx(id))
+    }
+
     pub(crate) fn queue_idx_to_trackid(&self, idx: u32) -> Option<TrackId> {
         for t in self.tracks.0.iter() {
             if t.1.queue_idx == idx {
@@ -498,6 +711,26 @@ impl Rmg {
         Ok(())
     }
 
+    ///Returns the command pool statistics of each track, identified by the track's queue flags. Each track recycles its command pools
+    /// once the execution that used them finished.
+    pub fn command_pool_statistics(&self) -> Vec<(vk::QueueFlags, CommandPoolStatistics)> {
+        self.tracks
+            .0
+            .iter()
+            .map(|(id, track)| (id.0, track.command_pool_statistics()))
+            .collect()
+    }
+
+    ///Takes the results of all queries that tasks requested via [ResourceRegistry::request_occlusion_query](crate::ResourceRegistry::request_occlusion_query)
+    /// and [ResourceRegistry::request_pipeline_statistics](crate::ResourceRegistry::request_pipeline_statistics), and whose execution finished
+    /// since the last call.
+    ///
+    /// Results are collected when recording starts (see [record](Self::record)), or while [waiting for idle](Self::wait_for_idle). Each track
+    /// keeps at most 1024 results, older ones are dropped.
+    pub fn take_query_results(&mut self) -> Vec<TaskQueryResult> {
+        self.tracks.take_query_results()
+    }
+
     /// Appends all known timings from the last execution.
     /// Note that, depending on how heavy the workload is, some timings might not (yet) be available.
     ///
@@ -523,3 +756,27 @@ impl Drop for Rmg {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparse_image_features() {
+        let mut config = Config::default();
+        assert_eq!(
+            missing_sparse_image_features(&config, &ImageType::Tex2d).len(),
+            2
+        );
+
+        config.sparse_binding_support = true;
+        config.sparse_residency_image2d_support = true;
+        assert!(missing_sparse_image_features(&config, &ImageType::Tex2dArray(4)).is_empty());
+        assert!(missing_sparse_image_features(&config, &ImageType::Cube).is_empty());
+        assert_eq!(
+            missing_sparse_image_features(&config, &ImageType::Tex3d),
+            vec!["PhysicalDeviceFeatures::sparse_residency_image3_d".to_owned()]
+        );
+        assert!(!missing_sparse_image_features(&config, &ImageType::Tex1d).is_empty());
+    }
+}
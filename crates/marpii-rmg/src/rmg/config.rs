@@ -92,16 +92,90 @@ pub struct Config {
 
     ///limits defined by variouse used extension
     pub limit: PhysicalDeviceLimitsExtended,
+
+    ///Whether `VK_EXT_memory_priority` is supported. If so, the extension is enabled and memory priorities
+    /// set via [`AllocationDesc`](marpii::allocator::AllocationDesc) are respected.
+    pub memory_priority_support: bool,
+
+    ///Whether `VK_EXT_graphics_pipeline_library` is supported. If so, the extension is enabled and
+    /// [`Rmg::pipeline_library_cache`](crate::Rmg::pipeline_library_cache) can be used. [`Rmg::new`](crate::Rmg::new) clears the flag
+    /// if the context did not enable `VK_EXT_graphics_pipeline_library` and `VK_KHR_pipeline_library`.
+    pub graphics_pipeline_library_support: bool,
+
+    ///Whether `VK_EXT_shader_object` is supported. If so, the extension is enabled and compute and raster passes
+    /// can be created from [shader objects](marpii::resources::ShaderObject), see [`Rmg::shader_object`](crate::Rmg::shader_object).
+    /// [`Rmg::new`](crate::Rmg::new) clears the flag if the context did not enable the extension.
+    pub shader_object_support: bool,
+
+    ///Whether `VK_EXT_hdr_metadata` is supported. If so, and RMG was created for a window, the extension is enabled and
+    /// HDR metadata can be set on swapchains, see [`Swapchain::set_hdr_metadata`](marpii::swapchain::Swapchain::set_hdr_metadata).
+    pub hdr_metadata_support: bool,
+
+    ///Whether `VK_KHR_present_id` and `VK_KHR_present_wait` are supported. If so, and RMG was created for a window, both
+    /// extensions are enabled and swapchains can wait for specific presents, see [`Swapchain::wait_for_present`](marpii::swapchain::Swapchain::wait_for_present).
+    pub present_wait_support: bool,
+
+    ///Whether `VK_GOOGLE_display_timing` is supported. If so, and RMG was created for a window, the extension is enabled
+    /// and presentation timings can be queried, see [`Swapchain::past_presentation_timing`](marpii::swapchain::Swapchain::past_presentation_timing).
+    pub display_timing_support: bool,
+
+    ///Whether the `pipeline_statistics_query` feature is supported. If so, it is enabled and tasks can request
+    /// pipeline statistics, see [`ResourceRegistry::request_pipeline_statistics`](crate::ResourceRegistry::request_pipeline_statistics).
+    /// [`Rmg::new`](crate::Rmg::new) clears the flag if the context did not enable the feature.
+    pub pipeline_statistics_query_support: bool,
+
+    ///Whether the `occlusion_query_precise` feature is supported. If so, it is enabled and occlusion queries report
+    /// exact sample counts, see [`ResourceRegistry::request_occlusion_query`](crate::ResourceRegistry::request_occlusion_query).
+    /// [`Rmg::new`](crate::Rmg::new) clears the flag if the context did not enable the feature.
+    pub occlusion_query_precise_support: bool,
+
+    ///Whether the `sparseBinding` feature is supported. If so, it is enabled. Needed by all sparse resources.
+    ///
+    /// [`Rmg::new`](crate::Rmg::new) clears this and the `sparse_residency_*` flags if the context did not enable the feature.
+    pub sparse_binding_support: bool,
+
+    ///Whether the `sparseResidencyImage2D` feature is supported. If so, it is enabled and partially resident 2D images can be created,
+    /// see [`Rmg::new_sparse_image`](crate::Rmg::new_sparse_image).
+    pub sparse_residency_image2d_support: bool,
+
+    ///Whether the `sparseResidencyImage3D` feature is supported. If so, it is enabled and partially resident 3D images can be created.
+    pub sparse_residency_image3d_support: bool,
+
+    ///Whether the `sparseResidencyBuffer` feature is supported. If so, it is enabled and partially resident buffers can be created.
+    pub sparse_residency_buffer_support: bool,
+
+    ///Size in bytes of the upload ring buffer that is used for staging data by tasks. If 0, each upload
+    /// allocates its own staging buffer.
+    pub upload_ring_size: u64,
+
+    ///Whether barriers between dependent tasks on the same track may be split into an event signal right after the producing task
+    /// and an event wait right before the consuming task. Tasks recorded in between can then overlap with the producer.
+    /// Disabled by default, see [`Rmg::set_split_barriers`](crate::Rmg::set_split_barriers).
+    pub split_barriers: bool,
 }
 
 impl Config {
+    ///Default size of the upload ring buffer (32MiB).
+    pub const DEFAULT_UPLOAD_RING_SIZE: u64 = 32 * 1024 * 1024;
+
     ///Initializes the [Config] for a given physical device
     pub fn new_for_device(instance: &Instance, physical_device: &vk::PhysicalDevice) -> Self {
-        let mut conf = Config::default();
+        let mut conf = Config {
+            upload_ring_size: Self::DEFAULT_UPLOAD_RING_SIZE,
+            ..Default::default()
+        };
         conf.load_limits(instance, physical_device);
         conf.check_enable_rt_support(instance, physical_device);
         conf.check_enable_unified_image_layout(instance, physical_device);
         conf.check_atomics(instance, physical_device);
+        conf.check_memory_priority(instance, physical_device);
+        conf.check_graphics_pipeline_library(instance, physical_device);
+        conf.check_shader_object(instance, physical_device);
+        conf.check_hdr_metadata(instance, physical_device);
+        conf.check_present_wait(instance, physical_device);
+        conf.check_display_timing(instance, physical_device);
+        conf.check_queries(instance, physical_device);
+        conf.check_sparse(instance, physical_device);
         conf
     }
 
@@ -221,4 +295,125 @@ impl Config {
             atomic_image,
         }
     }
+
+    pub(crate) fn check_memory_priority(
+        &mut self,
+        instance: &Instance,
+        physical_device: &vk::PhysicalDevice,
+    ) {
+        let f_priority = instance
+            .get_feature::<vk::PhysicalDeviceMemoryPriorityFeaturesEXT<'_>>(physical_device);
+        self.memory_priority_support = f_priority.memory_priority == vk::TRUE;
+    }
+
+    pub(crate) fn check_graphics_pipeline_library(
+        &mut self,
+        instance: &Instance,
+        physical_device: &vk::PhysicalDevice,
+    ) {
+        let f_pipelib = instance
+            .get_feature::<vk::PhysicalDeviceGraphicsPipelineLibraryFeaturesEXT<'_>>(
+                physical_device,
+            );
+        self.graphics_pipeline_library_support = f_pipelib.graphics_pipeline_library == vk::TRUE;
+    }
+
+    pub(crate) fn check_shader_object(
+        &mut self,
+        instance: &Instance,
+        physical_device: &vk::PhysicalDevice,
+    ) {
+        let f_shader_object =
+            instance.get_feature::<vk::PhysicalDeviceShaderObjectFeaturesEXT<'_>>(physical_device);
+        self.shader_object_support = f_shader_object.shader_object == vk::TRUE;
+    }
+
+    pub(crate) fn check_hdr_metadata(
+        &mut self,
+        instance: &Instance,
+        physical_device: &vk::PhysicalDevice,
+    ) {
+        //NOTE: The extension has no feature struct, so check the extension list directly.
+        self.hdr_metadata_support = extension_supported(
+            instance,
+            physical_device,
+            marpii::ash::ext::hdr_metadata::NAME,
+        );
+    }
+
+    pub(crate) fn check_present_wait(
+        &mut self,
+        instance: &Instance,
+        physical_device: &vk::PhysicalDevice,
+    ) {
+        let f_present_id =
+            instance.get_feature::<vk::PhysicalDevicePresentIdFeaturesKHR<'_>>(physical_device);
+        let f_present_wait =
+            instance.get_feature::<vk::PhysicalDevicePresentWaitFeaturesKHR<'_>>(physical_device);
+        self.present_wait_support =
+            f_present_id.present_id == vk::TRUE && f_present_wait.present_wait == vk::TRUE;
+    }
+
+    pub(crate) fn check_display_timing(
+        &mut self,
+        instance: &Instance,
+        physical_device: &vk::PhysicalDevice,
+    ) {
+        //NOTE: The extension has no feature struct, so check the extension list directly.
+        self.display_timing_support = extension_supported(
+            instance,
+            physical_device,
+            marpii::ash::google::display_timing::NAME,
+        );
+    }
+
+    pub(crate) fn check_queries(
+        &mut self,
+        instance: &Instance,
+        physical_device: &vk::PhysicalDevice,
+    ) {
+        let features = unsafe {
+            instance
+                .inner
+                .get_physical_device_features(*physical_device)
+        };
+        self.pipeline_statistics_query_support = features.pipeline_statistics_query == vk::TRUE;
+        self.occlusion_query_precise_support = features.occlusion_query_precise == vk::TRUE;
+    }
+
+    ///Checks sparse binding and residency support. Residency is only reported if binding is supported as well.
+    pub(crate) fn check_sparse(
+        &mut self,
+        instance: &Instance,
+        physical_device: &vk::PhysicalDevice,
+    ) {
+        let features = unsafe {
+            instance
+                .inner
+                .get_physical_device_features(*physical_device)
+        };
+        self.sparse_binding_support = features.sparse_binding == vk::TRUE;
+        self.sparse_residency_image2d_support =
+            self.sparse_binding_support && features.sparse_residency_image2_d == vk::TRUE;
+        self.sparse_residency_image3d_support =
+            self.sparse_binding_support && features.sparse_residency_image3_d == vk::TRUE;
+        self.sparse_residency_buffer_support =
+            self.sparse_binding_support && features.sparse_residency_buffer == vk::TRUE;
+    }
+}
+
+fn extension_supported(
+    instance: &Instance,
+    physical_device: &vk::PhysicalDevice,
+    name: &std::ffi::CStr,
+) -> bool {
+    let extensions = unsafe {
+        instance
+            .inner
+            .enumerate_device_extension_properties(*physical_device)
+    }
+    .unwrap_or_default();
+    extensions
+        .iter()
+        .any(|ext| ext.extension_name_as_c_str() == Ok(name))
 }
@@ -16,11 +16,27 @@ impl Rmg {
     ///
     /// 1. The correct extensions are loaded
     /// 2. A graphics-capable device is selected.
+    ///
+    /// Use it to create additional surfaces for multi-window applications. Fails if the graphics queue can not present
+    /// to the surface.
     pub fn create_surface<W: HasWindowHandle + HasDisplayHandle>(
         &self,
         window: &W,
     ) -> Result<Surface, MarpiiError> {
-        Surface::new(&self.ctx.instance, window).map_err(|e| MarpiiError::from(e))
+        let surface = Surface::new(&self.ctx.instance, window).map_err(|e| MarpiiError::from(e))?;
+        if let Some(queue) = self
+            .ctx
+            .device
+            .first_queue_for_attribute(true, false, false)
+            && !surface
+                .supports_queue_family(&self.ctx.device.physical_device, queue.family_index)?
+        {
+            return Err(MarpiiError::Other(format!(
+                "Graphics queue family {} can not present to the surface",
+                queue.family_index
+            )));
+        }
+        Ok(surface)
     }
 
     ///Handles the creation and initialization of a _as-powerful-as-possible_ RMG.
@@ -84,7 +100,15 @@ impl Rmg {
                         .shader_int64(true)
                         .shader_float64(true)
                         //Robust access
-                        .robust_buffer_access(true);
+                        .robust_buffer_access(true)
+                        //Queries requested by tasks
+                        .pipeline_statistics_query(config.pipeline_statistics_query_support)
+                        .occlusion_query_precise(config.occlusion_query_precise_support)
+                        //Sparse resources
+                        .sparse_binding(config.sparse_binding_support)
+                        .sparse_residency_image2_d(config.sparse_residency_image2d_support)
+                        .sparse_residency_image3_d(config.sparse_residency_image3d_support)
+                        .sparse_residency_buffer(config.sparse_residency_buffer_support);
                 })
                 .with_feature(
                     vk::PhysicalDeviceVulkan12Features::default()
@@ -170,6 +194,75 @@ impl Rmg {
                 db
             };
 
+            db = if config.memory_priority_support {
+                log::info!("Enable MemoryPriority support");
+                db.with_extensions(marpii::ash::ext::memory_priority::NAME)
+                    .with_feature(
+                        vk::PhysicalDeviceMemoryPriorityFeaturesEXT::default()
+                            .memory_priority(true),
+                    )
+            } else {
+                db
+            };
+
+            db = if config.graphics_pipeline_library_support {
+                log::info!("Enable GraphicsPipelineLibrary support");
+                //NOTE: pipeline_library is already enabled for ray tracing
+                let db = if config.rt_support {
+                    db
+                } else {
+                    db.with_extensions(marpii::ash::khr::pipeline_library::NAME)
+                };
+                db.with_extensions(marpii::ash::ext::graphics_pipeline_library::NAME)
+                    .with_feature(
+                        vk::PhysicalDeviceGraphicsPipelineLibraryFeaturesEXT::default()
+                            .graphics_pipeline_library(true),
+                    )
+            } else {
+                db
+            };
+
+            db = if config.shader_object_support {
+                log::info!("Enable ShaderObject support");
+                db.with_extensions(marpii::ash::ext::shader_object::NAME)
+                    .with_feature(
+                        vk::PhysicalDeviceShaderObjectFeaturesEXT::default().shader_object(true),
+                    )
+            } else {
+                db
+            };
+
+            //The presentation extensions depend on VK_KHR_swapchain, which is only enabled for windows.
+            let presents = window.is_some();
+
+            db = if presents && config.hdr_metadata_support {
+                log::info!("Enable HdrMetadata support");
+                db.with_extensions(marpii::ash::ext::hdr_metadata::NAME)
+            } else {
+                db
+            };
+
+            db = if presents && config.present_wait_support {
+                log::info!("Enable PresentWait support");
+                db.with_extensions(marpii::ash::khr::present_id::NAME)
+                    .with_extensions(marpii::ash::khr::present_wait::NAME)
+                    .with_feature(
+                        vk::PhysicalDevicePresentIdFeaturesKHR::default().present_id(true),
+                    )
+                    .with_feature(
+                        vk::PhysicalDevicePresentWaitFeaturesKHR::default().present_wait(true),
+                    )
+            } else {
+                db
+            };
+
+            db = if presents && config.display_timing_support {
+                log::info!("Enable DisplayTiming support");
+                db.with_extensions(marpii::ash::google::display_timing::NAME)
+            } else {
+                db
+            };
+
             db = on_builder(db, &config);
 
             db
@@ -2,17 +2,23 @@ use ahash::AHashMap;
 use marpii::{
     ash::vk,
     context::Device,
-    resources::{CommandBuffer, CommandBufferAllocator, CommandPool},
-    sync::Semaphore,
-    OoS,
+    resources::{CommandPoolRecycler, CommandPoolStatistics, RecycledCommandBuffer},
+    sync::{Event, Semaphore},
 };
 
 #[cfg(feature = "timestamps")]
 use marpii::util::Timestamps;
 
-use std::{fmt::Display, sync::Arc};
+use std::{
+    fmt::Display,
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
 
-use crate::{recorder::Execution, RecordError, Rmg};
+use crate::{
+    RecordError, Rmg, TaskQueryResult,
+    recorder::{Execution, queries::QueryPoolCache},
+};
 
 #[cfg(feature = "timestamps")]
 use smallvec::{Array, SmallVec};
@@ -177,23 +183,81 @@ impl TimestampTable {
     }
 }
 
+///Device-only events of a track, used for split barriers. Handed out events return to the pool once they are dropped,
+/// which happens when the execution that used them finished.
+#[derive(Clone)]
+pub(crate) struct EventPool {
+    device: Arc<Device>,
+    idle: Arc<Mutex<Vec<Arc<Event>>>>,
+}
+
+impl EventPool {
+    pub fn new(device: &Arc<Device>) -> Self {
+        EventPool {
+            device: device.clone(),
+            idle: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    ///Returns an unsignaled event. Reuses an idle one if there is any.
+    pub fn acquire(&self) -> Result<PooledEvent, vk::Result> {
+        let idle = self.idle.lock().unwrap().pop();
+        let event = if let Some(event) = idle {
+            event
+        } else {
+            Event::new_device_only(self.device.clone())?
+        };
+        Ok(PooledEvent {
+            event,
+            idle: self.idle.clone(),
+        })
+    }
+}
+
+///Event of an [EventPool]. The execution that sets it must also [reset](Event::cmd_reset) it, since it is reused once dropped.
+pub(crate) struct PooledEvent {
+    event: Arc<Event>,
+    idle: Arc<Mutex<Vec<Arc<Event>>>>,
+}
+
+impl Deref for PooledEvent {
+    type Target = Event;
+    fn deref(&self) -> &Self::Target {
+        &self.event
+    }
+}
+
+impl Drop for PooledEvent {
+    fn drop(&mut self) {
+        self.idle.lock().unwrap().push(self.event.clone());
+    }
+}
+
 ///Execution track. Basically a `DeviceQueue` and some associated data.
 pub(crate) struct Track {
     pub(crate) queue_idx: u32,
     pub(crate) flags: vk::QueueFlags,
     pub(crate) sem: Arc<Semaphore>,
 
-    pub(crate) command_buffer_pool: OoS<CommandPool>,
+    pub(crate) command_buffer_pool: CommandPoolRecycler,
+    pub(crate) event_pool: EventPool,
+    pub(crate) query_pools: QueryPoolCache,
     pub(crate) inflight_executions: Vec<Execution>,
 
     //Latest known value that is going to be signaled eventually.
     pub(crate) latest_signaled_value: u64,
 
+    ///Query results of finished executions that were not taken yet.
+    pub(crate) query_results: Vec<TaskQueryResult>,
+
     #[cfg(feature = "timestamps")]
     pub(crate) timestamp_table: TimestampTable,
 }
 
 impl Track {
+    ///Maximum number of query results a track keeps. If they are not taken, the oldest ones are dropped.
+    pub const MAX_QUERY_RESULTS: usize = 1024;
+
     pub fn new(device: &Arc<Device>, queue_idx: u32, flags: vk::QueueFlags) -> Self {
         let sem = Semaphore::new(device, 0).expect("Could not create Track's semaphore");
 
@@ -214,16 +278,12 @@ impl Track {
             queue_idx,
             flags,
             sem,
-            command_buffer_pool: OoS::new(
-                CommandPool::new(
-                    device,
-                    queue_idx,
-                    vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
-                )
-                .expect("Failed to create command pool!"),
-            ),
+            command_buffer_pool: CommandPoolRecycler::new(device, queue_idx),
+            event_pool: EventPool::new(device),
+            query_pools: QueryPoolCache::default(),
             inflight_executions: Vec::with_capacity(10),
             latest_signaled_value: 0,
+            query_results: Vec::new(),
 
             #[cfg(feature = "timestamps")]
             timestamp_table,
@@ -233,9 +293,24 @@ impl Track {
     ///Ticks the track. Triggers internal cleanup operations
     pub fn tick_frame(&mut self) {
         let finished_till = self.sem.get_value();
-        //Drop all executions (and therefore resources like buffers etc) that have finished till now
-        self.inflight_executions
-            .retain(|exec| exec.guard.target_value >= finished_till);
+        //Drop all executions (and therefore resources like buffers etc) that have finished till now.
+        // Their query results are available now as well.
+        let query_results = &mut self.query_results;
+        self.inflight_executions.retain(|exec| {
+            let in_flight = exec.guard.target_value >= finished_till;
+            if !in_flight && let Some(queries) = &exec.queries {
+                queries.read_results(query_results);
+            }
+            in_flight
+        });
+        self.trim_query_results();
+    }
+
+    fn trim_query_results(&mut self) {
+        if self.query_results.len() > Self::MAX_QUERY_RESULTS {
+            let overflow = self.query_results.len() - Self::MAX_QUERY_RESULTS;
+            self.query_results.drain(0..overflow);
+        }
     }
 
     ///Allocates the next guard for this track.
@@ -265,19 +340,29 @@ impl Track {
         self.sem
             .wait(max, u64::MAX)
             .expect("Failed to wait for inflight execution");
-        self.inflight_executions.clear();
+        for exec in self.inflight_executions.drain(..) {
+            if let Some(queries) = &exec.queries {
+                queries.read_results(&mut self.query_results);
+            }
+        }
+        self.trim_query_results();
     }
 
-    pub fn new_command_buffer(&mut self) -> Result<CommandBuffer, RecordError> {
+    ///Returns a command buffer from the track's recycler. Its pool is reset and reused once the execution
+    /// that owns the buffer finished.
+    pub fn new_command_buffer(&mut self) -> Result<RecycledCommandBuffer, RecordError> {
         let cb = self
             .command_buffer_pool
-            .share()
-            .allocate_buffer(vk::CommandBufferLevel::PRIMARY)
+            .acquire()
             .map_err(|e| RecordError::MarpiiError(e.into()))?;
 
         Ok(cb)
     }
 
+    pub fn command_pool_statistics(&self) -> CommandPoolStatistics {
+        self.command_buffer_pool.statistics()
+    }
+
     /// Appends all known timings from the last execution.
     /// Note that, depending on how heavy the workload is, some timings might not (yet) be available.
     ///
@@ -406,12 +491,16 @@ impl Tracks {
         //To get the best track try to find a track that has only *this* usage. If none is found, add more capabilities
         // from less important to more important.
 
+        //Special capabilities (like SPARSE_BINDING) that are requested must be supported by the track,
+        // the rest is matched as before.
+        let required_caps = usage & Self::CAP_MASK;
+        let usage = usage & !Self::CAP_MASK;
         for add_on_cap in Self::CAP_PRECEDENCE.iter() {
             let target_usage = usage | *add_on_cap;
 
             for (id, _) in self.0.iter() {
                 let masked = id.0.as_raw() & !(id.0.as_raw() & Self::CAP_MASK.as_raw());
-                if masked == target_usage.as_raw() {
+                if masked == target_usage.as_raw() && id.0.contains(required_caps) {
                     #[cfg(feature = "logging")]
                     log::trace!("Using {:#?} for {:#?}", id, usage);
                     return Some(*id);
@@ -431,6 +520,15 @@ Could not find track for usage {:#?}. Following tracks are loaded:
         None
     }
 
+    ///Takes the query results of all finished executions of all tracks.
+    pub fn take_query_results(&mut self) -> Vec<TaskQueryResult> {
+        let mut results = Vec::new();
+        for track in self.0.values_mut() {
+            results.append(&mut track.query_results);
+        }
+        results
+    }
+
     /// Appends all known timings from the last execution.
     /// Note that, depending on how heavy the workload is, some timings might not (yet) be available.
     ///
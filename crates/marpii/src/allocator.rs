@@ -11,6 +11,14 @@
 mod gpu_allocator;
 
 mod unallocated;
+
+mod dedicated;
+#[cfg(unix)]
+pub(crate) use dedicated::ExternalMemory;
+pub use dedicated::{DedicatedAllocation, DedicatedTarget};
+
+mod ring_buffer;
+pub use ring_buffer::{RingAllocation, RingBuffer};
 use std::{ffi::c_void, ptr::NonNull};
 
 use ash::vk::{self, MappedMemoryRange};
@@ -25,6 +33,104 @@ pub enum MemoryUsage {
     GpuOnly,
     CpuToGpu,
     GpuToCpu,
+    ///Host visible memory that prefers to be device local (resizable BAR). Falls back to
+    /// normal host visible memory if there is no such heap. Good for data that is written by
+    /// the CPU every frame and read by the GPU a lot.
+    ///
+    /// Always uses a [dedicated](AllocationDesc::dedicated) allocation.
+    CpuToGpuDeviceLocal,
+    ///`LAZILY_ALLOCATED` memory. Only valid for images with `TRANSIENT_ATTACHMENT` usage, for instance
+    /// MSAA targets that are resolved in the same render pass. Falls back to `GpuOnly` memory if the
+    /// device has no lazily allocated memory (usually desktop GPUs).
+    ///
+    /// Always uses a [dedicated](AllocationDesc::dedicated) allocation. Is never host mappable.
+    GpuLazy,
+}
+
+impl MemoryUsage {
+    ///Returns the memory property flags the usage prefers, ordered from best to worst fit. The
+    /// last entry is the minimal requirement.
+    pub fn preferred_property_flags(&self) -> &'static [vk::MemoryPropertyFlags] {
+        use vk::MemoryPropertyFlags as F;
+        const HOST: F = F::from_raw(F::HOST_VISIBLE.as_raw() | F::HOST_COHERENT.as_raw());
+        const LAZY: F = F::from_raw(F::DEVICE_LOCAL.as_raw() | F::LAZILY_ALLOCATED.as_raw());
+        const REBAR: F = F::from_raw(HOST.as_raw() | F::DEVICE_LOCAL.as_raw());
+        const CACHED: F = F::from_raw(HOST.as_raw() | F::HOST_CACHED.as_raw());
+
+        const UNKNOWN: &[F] = &[F::empty()];
+        const GPU_ONLY: &[F] = &[F::DEVICE_LOCAL, F::empty()];
+        const GPU_LAZY: &[F] = &[LAZY, F::DEVICE_LOCAL, F::empty()];
+        const CPU_TO_GPU: &[F] = &[HOST, F::HOST_VISIBLE];
+        const CPU_TO_GPU_DEVICE_LOCAL: &[F] = &[REBAR, HOST, F::HOST_VISIBLE];
+        const GPU_TO_CPU: &[F] = &[CACHED, HOST, F::HOST_VISIBLE];
+
+        match self {
+            MemoryUsage::Unknown => UNKNOWN,
+            MemoryUsage::GpuOnly => GPU_ONLY,
+            MemoryUsage::GpuLazy => GPU_LAZY,
+            MemoryUsage::CpuToGpu => CPU_TO_GPU,
+            MemoryUsage::CpuToGpuDeviceLocal => CPU_TO_GPU_DEVICE_LOCAL,
+            MemoryUsage::GpuToCpu => GPU_TO_CPU,
+        }
+    }
+}
+
+///Describes how memory for a resource is allocated. Can be created from a [MemoryUsage] for the common case:
+///
+/// ```rust, ignore
+/// let desc: AllocationDesc = MemoryUsage::GpuOnly.into();
+/// //big render target, that should stay resident
+/// let desc = AllocationDesc::new(MemoryUsage::GpuOnly).dedicated().with_priority(1.0);
+/// ```
+///
+/// Everything but the plain usage is served by a [DedicatedAllocation] instead of the
+/// [Allocator] that is passed to [Buffer::new](crate::resources::Buffer::new) or [Image::new](crate::resources::Image::new).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AllocationDesc {
+    pub usage: MemoryUsage,
+    ///Forces a dedicated `vkDeviceMemory` for the resource.
+    pub dedicated: bool,
+    ///`VK_EXT_memory_priority` hint in the range 0.0 ..= 1.0. Ignored if the extension is not enabled.
+    pub priority: Option<f32>,
+}
+
+impl AllocationDesc {
+    pub fn new(usage: MemoryUsage) -> Self {
+        AllocationDesc {
+            usage,
+            dedicated: false,
+            priority: None,
+        }
+    }
+
+    ///Forces a dedicated allocation.
+    pub fn dedicated(mut self) -> Self {
+        self.dedicated = true;
+        self
+    }
+
+    ///Sets the memory priority. `priority` is clamped to 0.0 ..= 1.0. Note that this implies
+    /// a dedicated allocation.
+    pub fn with_priority(mut self, priority: f32) -> Self {
+        self.priority = Some(priority.clamp(0.0, 1.0));
+        self
+    }
+
+    ///Returns true if the allocation can't be served by a general purpose [Allocator].
+    pub fn needs_dedicated(&self) -> bool {
+        self.dedicated
+            || self.priority.is_some()
+            || matches!(
+                self.usage,
+                MemoryUsage::GpuLazy | MemoryUsage::CpuToGpuDeviceLocal
+            )
+    }
+}
+
+impl From<MemoryUsage> for AllocationDesc {
+    fn from(usage: MemoryUsage) -> Self {
+        AllocationDesc::new(usage)
+    }
 }
 
 ///Implemented for all managed allocations. Allows the [Image](crate::resources::Image) and [Buffer](crate::resources::Buffer) implementations to hide their allocator type.
@@ -37,6 +143,10 @@ pub trait AnonymAllocation {
     fn as_slice_mut(&mut self) -> Option<&mut [u8]>;
     ///Returns the memory properties of the allocation
     fn memory_properties(&self) -> Option<vk::MemoryPropertyFlags>;
+    ///Returns the handle types the memory was allocated to be exportable as. Empty for non-exportable memory.
+    fn exportable_handle_types(&self) -> vk::ExternalMemoryHandleTypeFlags {
+        vk::ExternalMemoryHandleTypeFlags::empty()
+    }
 }
 
 impl<A: Allocator + Send + Sync + 'static> AnonymAllocation for ManagedAllocation<A> {
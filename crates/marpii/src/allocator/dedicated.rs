@@ -0,0 +1,290 @@
+use std::{ffi::c_void, ptr::NonNull, sync::Arc};
+
+use ash::vk;
+
+use super::{AllocationDesc, AnonymAllocation};
+use crate::{context::Device, error::DeviceError};
+
+///Resource a [DedicatedAllocation] is created for.
+#[derive(Debug, Clone, Copy)]
+pub enum DedicatedTarget {
+    Buffer {
+        buffer: vk::Buffer,
+        usage: vk::BufferUsageFlags,
+    },
+    Image(vk::Image),
+}
+
+///External memory operation of a [DedicatedAllocation]. See [Buffer::new_exportable](crate::resources::Buffer::new_exportable) and
+/// [Buffer::import_fd](crate::resources::Buffer::import_fd) for the user facing API.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ExternalMemory {
+    ///Allocates memory that can be exported as `handle_type`.
+    Export(vk::ExternalMemoryHandleTypeFlags),
+    ///Imports `fd`. On success the fd is owned by the Vulkan implementation.
+    #[cfg(unix)]
+    ImportFd {
+        handle_type: vk::ExternalMemoryHandleTypeFlags,
+        fd: i32,
+    },
+}
+
+///Allocation that owns its own `vkDeviceMemory`. Is used for everything a general purpose allocator
+/// can't express, like memory priorities, lazily allocated memory or explicit memory type selection.
+///
+/// The memory is freed when the allocation is dropped. Note that the resource bound to it must be destroyed before that.
+pub struct DedicatedAllocation {
+    device: Arc<Device>,
+    memory: vk::DeviceMemory,
+    size: u64,
+    properties: vk::MemoryPropertyFlags,
+    mapped: Option<NonNull<c_void>>,
+    ///Handle types the memory can be exported as.
+    exportable: vk::ExternalMemoryHandleTypeFlags,
+}
+
+//Safety: the mapped pointer is only handed out through &self / &mut self, same as for gpu-allocator's allocations.
+unsafe impl Send for DedicatedAllocation {}
+unsafe impl Sync for DedicatedAllocation {}
+
+impl DedicatedAllocation {
+    ///Allocates memory for `target` based on `desc`. Host visible memory is mapped persistently.
+    ///
+    ///Note that the memory is not bound to `target`.
+    pub fn new(
+        device: &Arc<Device>,
+        target: DedicatedTarget,
+        desc: &AllocationDesc,
+    ) -> Result<Self, DeviceError> {
+        Self::new_external(device, target, desc, None)
+    }
+
+    pub(crate) fn new_external(
+        device: &Arc<Device>,
+        target: DedicatedTarget,
+        desc: &AllocationDesc,
+        external: Option<ExternalMemory>,
+    ) -> Result<Self, DeviceError> {
+        let mut requirements = match target {
+            DedicatedTarget::Buffer { buffer, .. } => unsafe {
+                device.inner.get_buffer_memory_requirements(buffer)
+            },
+            DedicatedTarget::Image(image) => unsafe {
+                device.inner.get_image_memory_requirements(image)
+            },
+        };
+
+        //dma-bufs might restrict the usable memory types further.
+        #[cfg(unix)]
+        if let Some(ExternalMemory::ImportFd { handle_type, fd }) = external
+            && handle_type != vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD
+        {
+            let loader =
+                ash::khr::external_memory_fd::Device::new(&device.instance.inner, &device.inner);
+            let mut fd_properties = vk::MemoryFdPropertiesKHR::default();
+            unsafe { loader.get_memory_fd_properties(handle_type, fd, &mut fd_properties)? };
+            requirements.memory_type_bits &= fd_properties.memory_type_bits;
+        }
+
+        let (type_index, properties) = Self::select_memory_type(device, &requirements, desc)?;
+
+        let mut dedicated_info = match target {
+            DedicatedTarget::Buffer { buffer, .. } => {
+                vk::MemoryDedicatedAllocateInfo::default().buffer(buffer)
+            }
+            DedicatedTarget::Image(image) => {
+                vk::MemoryDedicatedAllocateInfo::default().image(image)
+            }
+        };
+        let mut flags_info =
+            vk::MemoryAllocateFlagsInfo::default().flags(vk::MemoryAllocateFlags::DEVICE_ADDRESS);
+        let mut priority_info = vk::MemoryPriorityAllocateInfoEXT::default();
+        let mut export_info = vk::ExportMemoryAllocateInfo::default();
+        #[cfg(unix)]
+        let mut import_info = vk::ImportMemoryFdInfoKHR::default();
+
+        let mut info = vk::MemoryAllocateInfo::default()
+            .allocation_size(requirements.size)
+            .memory_type_index(type_index)
+            .push_next(&mut dedicated_info);
+
+        if let DedicatedTarget::Buffer { usage, .. } = target
+            && usage.contains(vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS)
+        {
+            info = info.push_next(&mut flags_info);
+        }
+
+        if let Some(priority) = desc.priority {
+            if device.extension_enabled_cstr(ash::ext::memory_priority::NAME) {
+                priority_info = priority_info.priority(priority);
+                info = info.push_next(&mut priority_info);
+            } else {
+                #[cfg(feature = "logging")]
+                log::warn!(
+                    "Memory priority {} ignored, VK_EXT_memory_priority is not enabled",
+                    priority
+                );
+            }
+        }
+
+        match external {
+            Some(ExternalMemory::Export(handle_types)) => {
+                export_info = export_info.handle_types(handle_types);
+                info = info.push_next(&mut export_info);
+            }
+            #[cfg(unix)]
+            Some(ExternalMemory::ImportFd { handle_type, fd }) => {
+                import_info = import_info.handle_type(handle_type).fd(fd);
+                info = info.push_next(&mut import_info);
+            }
+            None => {}
+        }
+
+        let memory = unsafe { device.inner.allocate_memory(&info, None)? };
+
+        let mapped = if properties.contains(vk::MemoryPropertyFlags::HOST_VISIBLE) {
+            match unsafe {
+                device
+                    .inner
+                    .map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())
+            } {
+                Ok(ptr) => NonNull::new(ptr),
+                Err(e) => {
+                    unsafe { device.inner.free_memory(memory, None) };
+                    return Err(e.into());
+                }
+            }
+        } else {
+            None
+        };
+
+        Ok(DedicatedAllocation {
+            device: device.clone(),
+            memory,
+            size: requirements.size,
+            properties,
+            mapped,
+            exportable: match external {
+                Some(ExternalMemory::Export(handle_types)) => handle_types,
+                _ => vk::ExternalMemoryHandleTypeFlags::empty(),
+            },
+        })
+    }
+
+    ///Selects the best fitting memory type for `desc`. Lazily allocated memory types are only considered for
+    /// [GpuLazy](super::MemoryUsage::GpuLazy).
+    fn select_memory_type(
+        device: &Arc<Device>,
+        requirements: &vk::MemoryRequirements,
+        desc: &AllocationDesc,
+    ) -> Result<(u32, vk::MemoryPropertyFlags), DeviceError> {
+        let memory_properties = unsafe {
+            device
+                .instance
+                .inner
+                .get_physical_device_memory_properties(device.physical_device)
+        };
+        let allow_lazy = desc.usage == super::MemoryUsage::GpuLazy;
+
+        let types = &memory_properties.memory_types[..memory_properties.memory_type_count as usize];
+        for preferred in desc.usage.preferred_property_flags() {
+            let candidate = types.iter().enumerate().find(|(idx, ty)| {
+                requirements.memory_type_bits & (1 << idx) != 0
+                    && ty.property_flags.contains(*preferred)
+                    && !ty
+                        .property_flags
+                        .contains(vk::MemoryPropertyFlags::PROTECTED)
+                    && (allow_lazy
+                        || !ty
+                            .property_flags
+                            .contains(vk::MemoryPropertyFlags::LAZILY_ALLOCATED))
+            });
+            if let Some((idx, ty)) = candidate {
+                return Ok((idx as u32, ty.property_flags));
+            }
+        }
+
+        #[cfg(feature = "logging")]
+        log::error!(
+            "No memory type for {:?} in type bits {:b}",
+            desc.usage,
+            requirements.memory_type_bits
+        );
+        Err(DeviceError::NoSuitableMemoryType {
+            usage: desc.usage,
+            type_bits: requirements.memory_type_bits,
+        })
+    }
+
+    pub fn memory(&self) -> vk::DeviceMemory {
+        self.memory
+    }
+
+    ///Names the memory object via the device's debugger, if there is one. Mirrors the allocation name of allocator managed memory.
+    pub(crate) fn set_name(&self, name: Option<&str>) {
+        if let Some(name) = name
+            && let Some(dbg) = self.device.get_debugger()
+            && let Ok(name) = std::ffi::CString::new(name)
+            && let Err(_e) = dbg.name_object(self.memory, &name)
+        {
+            #[cfg(feature = "logging")]
+            log::error!("Could not name dedicated allocation: {}", _e);
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl AnonymAllocation for DedicatedAllocation {
+    fn mapped_ptr(&self) -> Option<NonNull<c_void>> {
+        self.mapped
+    }
+    fn as_memory_range(&self) -> Option<vk::MappedMemoryRange<'_>> {
+        Some(vk::MappedMemoryRange {
+            memory: self.memory,
+            offset: 0,
+            size: self.size,
+            ..Default::default()
+        })
+    }
+    fn as_slice_ref(&self) -> Option<&[u8]> {
+        self.mapped.map(|ptr| unsafe {
+            core::slice::from_raw_parts(ptr.as_ptr() as *const u8, self.size as usize)
+        })
+    }
+    fn as_slice_mut(&mut self) -> Option<&mut [u8]> {
+        self.mapped.map(|ptr| unsafe {
+            core::slice::from_raw_parts_mut(ptr.as_ptr() as *mut u8, self.size as usize)
+        })
+    }
+    fn memory_properties(&self) -> Option<vk::MemoryPropertyFlags> {
+        Some(self.properties)
+    }
+    fn exportable_handle_types(&self) -> vk::ExternalMemoryHandleTypeFlags {
+        self.exportable
+    }
+}
+
+impl Drop for DedicatedAllocation {
+    fn drop(&mut self) {
+        unsafe {
+            if self.mapped.is_some() {
+                self.device.inner.unmap_memory(self.memory);
+            }
+            self.device.inner.free_memory(self.memory, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use static_assertions::assert_impl_all;
+
+    #[test]
+    fn impl_send_sync() {
+        assert_impl_all!(DedicatedAllocation: Send, Sync);
+    }
+}
@@ -8,8 +8,10 @@ use super::{Allocation, MemoryUsage};
 
 pub fn memory_usage_to_location(usage: MemoryUsage) -> MemoryLocation {
     match usage {
-        MemoryUsage::CpuToGpu => MemoryLocation::CpuToGpu,
-        MemoryUsage::GpuOnly => MemoryLocation::GpuOnly,
+        //NOTE: gpu-allocator already prefers device local, host visible memory for CpuToGpu. The *strict*
+        //      variants are served by `DedicatedAllocation`s, but if someone calls the allocator directly we fall back.
+        MemoryUsage::CpuToGpu | MemoryUsage::CpuToGpuDeviceLocal => MemoryLocation::CpuToGpu,
+        MemoryUsage::GpuOnly | MemoryUsage::GpuLazy => MemoryLocation::GpuOnly,
         MemoryUsage::GpuToCpu => MemoryLocation::GpuToCpu,
         MemoryUsage::Unknown => MemoryLocation::Unknown,
     }
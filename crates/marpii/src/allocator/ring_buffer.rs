@@ -0,0 +1,334 @@
+//! ## Ring buffer
+//!
+//! Linear sub-allocator over one big, host visible buffer. Mostly used for _per-frame_ data like
+//! staging uploads, where creating a new buffer for each upload would be wasteful.
+//!
+//! Each [RingAllocation] is an aligned sub-range of the buffer. The range stays occupied until
+//!
+//! 1. no [RingAllocation] for that range is alive anymore, and
+//! 2. the guard that was assigned via [RingBuffer::set_guard] (if any) has expired.
+//!
+//! Since the guard type is generic the ring buffer can be used with any kind of execution tracking. Freed ranges
+//! are reclaimed in allocation order whenever [RingBuffer::reclaim] is called.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use ash::vk;
+
+use crate::{
+    DeviceError,
+    allocator::{Allocator, MemoryUsage},
+    context::Device,
+    error::RingBufferError,
+    resources::{BufDesc, Buffer, BufferMapError, SharingMode},
+};
+
+///Sub-range of a [RingBuffer]. The range is kept occupied as long as any clone of the allocation is alive.
+#[derive(Clone)]
+pub struct RingAllocation {
+    ///The buffer this range is allocated on.
+    pub buffer: Arc<Buffer>,
+    ///Offset in bytes into `buffer`.
+    pub offset: u64,
+    ///Size of the range in bytes.
+    pub size: u64,
+    //Id of the region this allocation occupies. Doubles as liveness marker for the ring buffer.
+    region: Arc<u64>,
+}
+
+impl RingAllocation {
+    ///Wraps the whole `buffer` as an allocation that is not managed by any ring buffer. Useful as fallback if an allocation does not
+    /// fit into a ring.
+    pub fn dedicated(buffer: Arc<Buffer>) -> Self {
+        RingAllocation {
+            offset: 0,
+            size: buffer.desc.size,
+            buffer,
+            region: Arc::new(u64::MAX),
+        }
+    }
+
+    ///Writes `data` to the allocation, starting at `offset` bytes into the allocation.
+    pub fn write(&self, offset: u64, data: &[u8]) -> Result<(), BufferMapError> {
+        if offset + data.len() as u64 > self.size {
+            return Err(BufferMapError::OffsetTooLarge);
+        }
+        self.buffer.write((self.offset + offset) as usize, data)
+    }
+
+    ///Returns the region of the allocation as buffer-copy source.
+    pub fn as_buffer_copy(&self, dst_offset: u64) -> vk::BufferCopy2<'static> {
+        vk::BufferCopy2::default()
+            .src_offset(self.offset)
+            .dst_offset(dst_offset)
+            .size(self.size)
+    }
+
+    ///Creates a barrier that makes host writes to this allocation available to `dst_stage`/`dst_access`.
+    pub fn host_write_barrier(
+        &self,
+        dst_stage: vk::PipelineStageFlags2,
+        dst_access: vk::AccessFlags2,
+    ) -> vk::BufferMemoryBarrier2<'static> {
+        vk::BufferMemoryBarrier2::default()
+            .buffer(self.buffer.inner)
+            .offset(self.offset)
+            .size(self.size)
+            .src_access_mask(vk::AccessFlags2::HOST_WRITE)
+            .src_stage_mask(vk::PipelineStageFlags2::HOST)
+            .dst_access_mask(dst_access)
+            .dst_stage_mask(dst_stage)
+    }
+}
+
+struct RingRegion<G> {
+    id: Arc<u64>,
+    //virtual start (incl. alignment padding) and end of the region
+    start: u64,
+    end: u64,
+    guard: Option<G>,
+}
+
+impl<G> RingRegion<G> {
+    ///Returns true if no allocation references the region anymore.
+    fn is_orphaned(&self) -> bool {
+        Arc::strong_count(&self.id) <= 1
+    }
+}
+
+///Book keeping of the ring. Uses _virtual_, ever-growing offsets. The physical offset is the virtual offset
+/// modulo the capacity.
+struct RingState<G> {
+    capacity: u64,
+    head: u64,
+    tail: u64,
+    next_id: u64,
+    regions: VecDeque<RingRegion<G>>,
+}
+
+impl<G> RingState<G> {
+    fn new(capacity: u64) -> Self {
+        RingState {
+            capacity,
+            head: 0,
+            tail: 0,
+            next_id: 0,
+            regions: VecDeque::new(),
+        }
+    }
+
+    ///Returns (physical offset, region id) of the new region.
+    fn allocate(&mut self, size: u64, alignment: u64) -> Result<(u64, Arc<u64>), RingBufferError> {
+        if size == 0 || size > self.capacity {
+            return Err(RingBufferError::InvalidSize {
+                size,
+                capacity: self.capacity,
+            });
+        }
+
+        let alignment = alignment.max(1);
+        let physical = self.head % self.capacity;
+        let aligned = physical.next_multiple_of(alignment);
+        //if the region does not fit till the end of the buffer, wrap around to 0. The skipped
+        // bytes are part of the region and get reclaimed with it.
+        let start = if aligned + size > self.capacity {
+            self.head + (self.capacity - physical)
+        } else {
+            self.head + (aligned - physical)
+        };
+        let end = start + size;
+
+        if end - self.tail > self.capacity {
+            return Err(RingBufferError::OutOfMemory {
+                size,
+                available: self.available(),
+            });
+        }
+
+        let id = Arc::new(self.next_id);
+        self.next_id += 1;
+        self.regions.push_back(RingRegion {
+            id: id.clone(),
+            start: self.head,
+            end,
+            guard: None,
+        });
+        self.head = end;
+
+        Ok((start % self.capacity, id))
+    }
+
+    fn region_mut(&mut self, id: u64) -> Option<&mut RingRegion<G>> {
+        //Regions are pushed in id order, so we can search by id
+        let idx = self.regions.binary_search_by_key(&id, |r| *r.id).ok()?;
+        self.regions.get_mut(idx)
+    }
+
+    fn reclaim(&mut self, mut is_expired: impl FnMut(&G) -> bool) -> u64 {
+        let mut reclaimed = 0;
+        while let Some(front) = self.regions.front() {
+            let is_free = front.is_orphaned() && front.guard.as_ref().is_none_or(&mut is_expired);
+            if !is_free {
+                break;
+            }
+            reclaimed += front.end - front.start;
+            self.tail = front.end;
+            self.regions.pop_front();
+        }
+
+        if self.regions.is_empty() {
+            self.tail = self.head;
+        }
+
+        reclaimed
+    }
+
+    fn available(&self) -> u64 {
+        self.capacity - (self.head - self.tail)
+    }
+}
+
+///Linear ring allocator over a single host visible (`MemoryUsage::CpuToGpu`) buffer. Hands out aligned [RingAllocation]s
+/// that are reclaimed once they are not used anymore, and their guard of type `G` has expired.
+///
+/// Note that ranges are reclaimed in order. A long living allocation therefore blocks all ranges allocated after it.
+pub struct RingBuffer<G> {
+    buffer: Arc<Buffer>,
+    state: RingState<G>,
+}
+
+impl<G> RingBuffer<G> {
+    ///Creates a ring buffer of `size` bytes. The buffer is always created with `TRANSFER_SRC` usage. Use `usage` to add
+    /// additional usage flags, for instance if you want to use the ring for uniform data.
+    pub fn new<A: Allocator + Send + Sync + 'static>(
+        device: &Arc<Device>,
+        allocator: &Arc<Mutex<A>>,
+        size: u64,
+        usage: vk::BufferUsageFlags,
+        name: Option<&str>,
+    ) -> Result<Self, DeviceError> {
+        let desc = BufDesc {
+            size,
+            usage: usage | vk::BufferUsageFlags::TRANSFER_SRC,
+            sharing: SharingMode::Exclusive,
+            ..Default::default()
+        };
+        let buffer = Buffer::new(device, allocator, desc, MemoryUsage::CpuToGpu, name)?;
+
+        Ok(RingBuffer {
+            buffer: Arc::new(buffer),
+            state: RingState::new(size),
+        })
+    }
+
+    ///Allocates `size` bytes with the given `alignment`. Fails if the ring can't currently fit the allocation. In that case
+    /// you might want to [reclaim](Self::reclaim) first.
+    pub fn allocate(
+        &mut self,
+        size: u64,
+        alignment: u64,
+    ) -> Result<RingAllocation, RingBufferError> {
+        let (offset, region) = self.state.allocate(size, alignment)?;
+        Ok(RingAllocation {
+            buffer: self.buffer.clone(),
+            offset,
+            size,
+            region,
+        })
+    }
+
+    ///Allocates a range that fits `data`, and writes `data` to it.
+    pub fn allocate_for_data<T: bytemuck::Pod>(
+        &mut self,
+        data: &[T],
+        alignment: u64,
+    ) -> Result<RingAllocation, RingBufferError> {
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+        let allocation = self.allocate(bytes.len() as u64, alignment)?;
+        allocation.write(0, bytes)?;
+        Ok(allocation)
+    }
+
+    ///Sets the guard of the range occupied by `allocation`. The range won't be reclaimed until the guard expired. Overwrites any
+    /// previously set guard.
+    ///
+    /// Does nothing if `allocation` was not allocated by this ring buffer.
+    pub fn set_guard(&mut self, allocation: &RingAllocation, guard: G) {
+        if !Arc::ptr_eq(&allocation.buffer, &self.buffer) {
+            #[cfg(feature = "logging")]
+            log::warn!("Tried to guard allocation of foreign buffer");
+            return;
+        }
+        if let Some(region) = self.state.region_mut(*allocation.region) {
+            region.guard = Some(guard);
+        }
+    }
+
+    ///Reclaims all ranges, in allocation order, that are not used anymore, and for which `is_expired` returns true. Returns the number of bytes
+    /// that where reclaimed.
+    pub fn reclaim(&mut self, is_expired: impl FnMut(&G) -> bool) -> u64 {
+        self.state.reclaim(is_expired)
+    }
+
+    ///Returns true if `allocation` is located on this ring buffer.
+    pub fn owns(&self, allocation: &RingAllocation) -> bool {
+        Arc::ptr_eq(&allocation.buffer, &self.buffer)
+    }
+
+    ///Bytes that can currently be allocated (ignoring alignment).
+    pub fn available(&self) -> u64 {
+        self.state.available()
+    }
+
+    ///Size of the ring in bytes.
+    pub fn capacity(&self) -> u64 {
+        self.state.capacity
+    }
+
+    ///Number of ranges that are currently occupied.
+    pub fn allocation_count(&self) -> usize {
+        self.state.regions.len()
+    }
+
+    pub fn buffer(&self) -> &Arc<Buffer> {
+        &self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use static_assertions::assert_impl_all;
+
+    #[test]
+    fn impl_send_sync() {
+        assert_impl_all!(RingAllocation: Send, Sync);
+        assert_impl_all!(RingBuffer<u64>: Send, Sync);
+    }
+
+    #[test]
+    fn aligned_wrap_around() {
+        let mut ring = RingState::<u64>::new(256);
+        let (a, a_id) = ring.allocate(100, 16).unwrap();
+        let (b, b_id) = ring.allocate(100, 64).unwrap();
+        assert_eq!(a, 0);
+        assert_eq!(b, 128);
+        //does not fit anymore
+        assert!(ring.allocate(100, 16).is_err());
+
+        //a is still guarded
+        ring.region_mut(*a_id).unwrap().guard = Some(1);
+        drop(a_id);
+        assert_eq!(ring.reclaim(|g| *g < 1), 0);
+        assert_eq!(ring.reclaim(|g| *g < 2), 100);
+
+        //wraps around to the start
+        let (c, _c_id) = ring.allocate(100, 16).unwrap();
+        assert_eq!(c, 0);
+        drop(b_id);
+        assert_eq!(ring.regions.len(), 2);
+    }
+}
@@ -45,7 +45,10 @@ pub use physical_device::{PhyDeviceProperties, PhysicalDeviceFilter};
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 
 mod debugger;
-pub use debugger::Debugger;
+pub use debugger::{
+    CmdLabel, DebugMessage, DebugMessageCounts, DebugMessageHandler, DebugObject, Debugger,
+    LogMessageHandler, QueueLabel,
+};
 
 #[allow(unused_imports)]
 use crate::{allocator::Allocator, error::DeviceError, surface::Surface, MarpiiError};
@@ -1,13 +1,282 @@
-use std::ffi::CStr;
+use std::{
+    ffi::{CStr, CString, c_char},
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
 
+use ahash::AHashSet;
 use ash::vk::{self, Handle};
 
+///Named object that is referenced by a [DebugMessage].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DebugObject {
+    pub ty: vk::ObjectType,
+    pub handle: u64,
+    ///Name of the object, if it was named via [Debugger::name_object].
+    pub name: Option<String>,
+}
+
+///Owned copy of the `DebugUtilsMessengerCallbackDataEXT` a message was reported with.
+#[derive(Clone, Debug)]
+pub struct DebugMessage {
+    pub severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    pub ty: vk::DebugUtilsMessageTypeFlagsEXT,
+    pub id_number: i32,
+    ///The message's id. For validation messages this is the VUID, for instance `VUID-vkCmdDraw-None-08600`.
+    pub id_name: Option<String>,
+    pub message: String,
+    ///Objects the message is related to.
+    pub objects: Vec<DebugObject>,
+    ///Active queue labels, innermost label last.
+    pub queue_labels: Vec<String>,
+    ///Active command buffer labels, innermost label last.
+    pub cmd_buf_labels: Vec<String>,
+}
+
+impl DebugMessage {
+    pub fn is_error(&self) -> bool {
+        self.severity
+            .contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR)
+    }
+
+    ///Copies the callback data.
+    ///
+    /// # Safety
+    /// `data` must be a valid pointer as handed to a `PFN_vkDebugUtilsMessengerCallbackEXT`.
+    unsafe fn from_raw(
+        severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+        ty: vk::DebugUtilsMessageTypeFlagsEXT,
+        data: &vk::DebugUtilsMessengerCallbackDataEXT,
+    ) -> Self {
+        unsafe fn string(ptr: *const c_char) -> Option<String> {
+            if ptr.is_null() {
+                None
+            } else {
+                Some(
+                    unsafe { CStr::from_ptr(ptr) }
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            }
+        }
+        unsafe fn slice<'a, T>(ptr: *const T, count: u32) -> &'a [T] {
+            if ptr.is_null() || count == 0 {
+                &[]
+            } else {
+                unsafe { core::slice::from_raw_parts(ptr, count as usize) }
+            }
+        }
+
+        unsafe {
+            DebugMessage {
+                severity,
+                ty,
+                id_number: data.message_id_number,
+                id_name: string(data.p_message_id_name),
+                message: string(data.p_message).unwrap_or_default(),
+                objects: slice(data.p_objects, data.object_count)
+                    .iter()
+                    .map(|obj| DebugObject {
+                        ty: obj.object_type,
+                        handle: obj.object_handle,
+                        name: string(obj.p_object_name),
+                    })
+                    .collect(),
+                queue_labels: slice(data.p_queue_labels, data.queue_label_count)
+                    .iter()
+                    .filter_map(|l| string(l.p_label_name))
+                    .collect(),
+                cmd_buf_labels: slice(data.p_cmd_buf_labels, data.cmd_buf_label_count)
+                    .iter()
+                    .filter_map(|l| string(l.p_label_name))
+                    .collect(),
+            }
+        }
+    }
+}
+
+///Receives all messages of the debug messenger that are not muted. Set via [Debugger::set_message_handler], or
+/// [InstanceBuilder::with_debug_message_handler](crate::context::InstanceBuilder::with_debug_message_handler).
+///
+/// Note that the handler is called from whichever thread issued the Vulkan call that caused the message.
+pub trait DebugMessageHandler: Send + Sync {
+    fn handle(&self, message: &DebugMessage);
+}
+
+impl<F: Fn(&DebugMessage) + Send + Sync> DebugMessageHandler for F {
+    fn handle(&self, message: &DebugMessage) {
+        self(message)
+    }
+}
+
+///Default handler. Prints either via [println](println), or via the log crate if the `logging` feature is enabled.
+pub struct LogMessageHandler;
+
+impl DebugMessageHandler for LogMessageHandler {
+    fn handle(&self, message: &DebugMessage) {
+        let id = message.id_name.as_deref().unwrap_or("unknown id");
+
+        #[cfg(feature = "logging")]
+        {
+            let level = if message.is_error() {
+                log::Level::Error
+            } else if message
+                .severity
+                .contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING)
+            {
+                log::Level::Warn
+            } else if message
+                .severity
+                .contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO)
+            {
+                log::Level::Info
+            } else {
+                log::Level::Trace
+            };
+            log::log!(
+                level,
+                "[{}: {:?}]: {:?}",
+                message.id_number,
+                id,
+                message.message
+            );
+        }
+
+        #[cfg(not(feature = "logging"))]
+        {
+            println!(
+                "MarpDebugMsg: Level: {:?}, Type: {:?}\n",
+                message.severity, message.ty
+            );
+            println!("Id[{:?}]: {:?}", message.id_number, id);
+            println!("Msg: {:?}", message.message);
+        }
+    }
+}
+
+///Number of messages per severity. Muted messages are not counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DebugMessageCounts {
+    pub errors: u64,
+    pub warnings: u64,
+    pub infos: u64,
+    pub verbose: u64,
+}
+
+impl DebugMessageCounts {
+    pub fn total(&self) -> u64 {
+        self.errors + self.warnings + self.infos + self.verbose
+    }
+}
+
+///State the messenger callback routes messages through. Lives in a [Box] owned by the [Debugger], since its
+/// address is the callback's user data.
+pub(crate) struct MessageRouter {
+    handler: RwLock<Arc<dyn DebugMessageHandler>>,
+    muted: RwLock<AHashSet<String>>,
+    panic_on_error: AtomicBool,
+    ///First error reported while `panic_on_error` is set. Panicking in the callback would unwind through the Vulkan loader,
+    /// so the panic is deferred to [check_errors](Self::check_errors).
+    pending_error: Mutex<Option<String>>,
+    counts: [AtomicU64; 4],
+}
+
+impl MessageRouter {
+    pub(crate) fn new(handler: Arc<dyn DebugMessageHandler>) -> Self {
+        MessageRouter {
+            handler: RwLock::new(handler),
+            muted: RwLock::new(AHashSet::default()),
+            panic_on_error: AtomicBool::new(false),
+            pending_error: Mutex::new(None),
+            counts: Default::default(),
+        }
+    }
+
+    fn is_muted(&self, message: &DebugMessage) -> bool {
+        message
+            .id_name
+            .as_ref()
+            .map(|id| self.muted.read().unwrap().contains(id))
+            .unwrap_or(false)
+    }
+
+    fn count(&self, severity: vk::DebugUtilsMessageSeverityFlagsEXT) {
+        let idx = if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+            0
+        } else if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+            1
+        } else if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
+            2
+        } else {
+            3
+        };
+        self.counts[idx].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn route(&self, message: DebugMessage) {
+        if self.is_muted(&message) {
+            return;
+        }
+        self.count(message.severity);
+        //NOTE: clone the handler, so it may call `set_message_handler` itself.
+        let handler = self.handler.read().unwrap().clone();
+        handler.handle(&message);
+
+        if message.is_error() && self.panic_on_error.load(Ordering::Relaxed) {
+            self.pending_error.lock().unwrap().get_or_insert_with(|| {
+                format!(
+                    "Validation error [{}]: {}",
+                    message.id_name.as_deref().unwrap_or("unknown id"),
+                    message.message
+                )
+            });
+        }
+    }
+
+    fn check_errors(&self) {
+        if let Some(error) = self.pending_error.lock().unwrap().take() {
+            panic!("{}", error);
+        }
+    }
+}
+
+///Messenger callback that forwards all messages to the [MessageRouter] behind `p_user_data`.
+pub(crate) unsafe extern "system" fn route_debug_message(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_types: vk::DebugUtilsMessageTypeFlagsEXT,
+    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
+    p_user_data: *mut core::ffi::c_void,
+) -> vk::Bool32 {
+    if p_callback_data.is_null() || p_user_data.is_null() {
+        #[cfg(feature = "logging")]
+        log::error!("MarpDebugMsg: Got Msg, but no data!");
+        return vk::FALSE;
+    }
+
+    let (router, message) = unsafe {
+        (
+            &*(p_user_data as *const MessageRouter),
+            DebugMessage::from_raw(message_severity, message_types, &*p_callback_data),
+        )
+    };
+    router.route(message);
+    //Never abort the call that caused the message.
+    vk::FALSE
+}
+
 ///Helper that gets usually initialised by activating validation layers.
 /// Allows to use all `VK_EXT_DEBUG_UTILS` functions.
+///
+/// All messages of the debug messenger are routed to a [DebugMessageHandler], which defaults to [LogMessageHandler].
+/// Specific messages can be [muted](Self::mute_message), and the messages are [counted](Self::take_message_counts) per severity.
 pub struct Debugger {
     pub debug_instance: ash::ext::debug_utils::Instance,
     pub debug_report_loader: ash::ext::debug_utils::Device,
     pub debug_messenger: ash::vk::DebugUtilsMessengerEXT,
+    //NOTE: Must outlive the messenger. Dropped after `Drop::drop` destroyed it.
+    pub(crate) router: Box<MessageRouter>,
 }
 
 impl Debugger {
@@ -17,6 +286,152 @@ impl Debugger {
             .object_handle(handle);
         unsafe { self.debug_report_loader.set_debug_utils_object_name(&info) }
     }
+
+    ///Opens a label region named `name` in `command_buffer`, which must be recording. The region has to be closed by
+    /// [cmd_end_label](Self::cmd_end_label) in the same command buffer. Prefer [cmd_label](Self::cmd_label), which closes it when dropped.
+    pub fn cmd_begin_label(&self, command_buffer: vk::CommandBuffer, name: &str, color: [f32; 4]) {
+        let name = label_name(name);
+        let label = vk::DebugUtilsLabelEXT::default()
+            .label_name(&name)
+            .color(color);
+        unsafe {
+            self.debug_report_loader
+                .cmd_begin_debug_utils_label(command_buffer, &label)
+        };
+    }
+
+    pub fn cmd_end_label(&self, command_buffer: vk::CommandBuffer) {
+        unsafe {
+            self.debug_report_loader
+                .cmd_end_debug_utils_label(command_buffer)
+        };
+    }
+
+    ///Inserts a single label into `command_buffer`.
+    pub fn cmd_insert_label(&self, command_buffer: vk::CommandBuffer, name: &str, color: [f32; 4]) {
+        let name = label_name(name);
+        let label = vk::DebugUtilsLabelEXT::default()
+            .label_name(&name)
+            .color(color);
+        unsafe {
+            self.debug_report_loader
+                .cmd_insert_debug_utils_label(command_buffer, &label)
+        };
+    }
+
+    ///Opens a label region in `command_buffer` that is closed when the returned guard is dropped. The guard must be dropped
+    /// before recording ends.
+    pub fn cmd_label(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        name: &str,
+        color: [f32; 4],
+    ) -> CmdLabel<'_> {
+        self.cmd_begin_label(command_buffer, name, color);
+        CmdLabel {
+            debugger: self,
+            command_buffer,
+        }
+    }
+
+    ///Opens a label region on `queue`. Must be closed by [queue_end_label](Self::queue_end_label). Access to `queue` must be
+    /// externally synchronized, same as for submissions.
+    pub fn queue_begin_label(&self, queue: vk::Queue, name: &str, color: [f32; 4]) {
+        let name = label_name(name);
+        let label = vk::DebugUtilsLabelEXT::default()
+            .label_name(&name)
+            .color(color);
+        unsafe {
+            self.debug_report_loader
+                .queue_begin_debug_utils_label(queue, &label)
+        };
+    }
+
+    pub fn queue_end_label(&self, queue: vk::Queue) {
+        unsafe { self.debug_report_loader.queue_end_debug_utils_label(queue) };
+    }
+
+    pub fn queue_insert_label(&self, queue: vk::Queue, name: &str, color: [f32; 4]) {
+        let name = label_name(name);
+        let label = vk::DebugUtilsLabelEXT::default()
+            .label_name(&name)
+            .color(color);
+        unsafe {
+            self.debug_report_loader
+                .queue_insert_debug_utils_label(queue, &label)
+        };
+    }
+
+    ///Opens a label region on `queue` that is closed when the returned guard is dropped.
+    pub fn queue_label(&self, queue: vk::Queue, name: &str, color: [f32; 4]) -> QueueLabel<'_> {
+        self.queue_begin_label(queue, name, color);
+        QueueLabel {
+            debugger: self,
+            queue,
+        }
+    }
+
+    ///Replaces the handler that receives all messages.
+    pub fn set_message_handler(&self, handler: impl DebugMessageHandler + 'static) {
+        *self.router.handler.write().unwrap() = Arc::new(handler);
+    }
+
+    ///Mutes all messages with the id `id_name`, for instance `VUID-vkCmdDraw-None-08600`. Muted messages are neither
+    /// counted, nor handed to the handler.
+    pub fn mute_message(&self, id_name: impl Into<String>) {
+        self.router.muted.write().unwrap().insert(id_name.into());
+    }
+
+    pub fn unmute_message(&self, id_name: &str) {
+        self.router.muted.write().unwrap().remove(id_name);
+    }
+
+    ///If enabled, the first error message is recorded, and [check_errors](Self::check_errors) panics with it. Useful for tests
+    /// and CI, where any validation error should fail the run.
+    ///
+    /// The callback is invoked from within the Vulkan loader, where a panic can not unwind. Therefore it only records the
+    /// error, and the panic happens at the next [check_errors](Self::check_errors) call.
+    pub fn set_panic_on_error(&self, panic_on_error: bool) {
+        self.router
+            .panic_on_error
+            .store(panic_on_error, Ordering::Relaxed);
+    }
+
+    ///Panics if an error was reported while [panic on error](Self::set_panic_on_error) was enabled. RMG calls this after
+    /// each submission, call it after your own Vulkan calls otherwise.
+    pub fn check_errors(&self) {
+        self.router.check_errors();
+    }
+
+    ///Number of messages since creation, or since the last [take_message_counts](Self::take_message_counts).
+    pub fn message_counts(&self) -> DebugMessageCounts {
+        let [errors, warnings, infos, verbose] = self
+            .router
+            .counts
+            .each_ref()
+            .map(|c| c.load(Ordering::Relaxed));
+        DebugMessageCounts {
+            errors,
+            warnings,
+            infos,
+            verbose,
+        }
+    }
+
+    ///Returns the message counts and resets them. Calling this once per frame yields the messages per frame.
+    pub fn take_message_counts(&self) -> DebugMessageCounts {
+        let [errors, warnings, infos, verbose] = self
+            .router
+            .counts
+            .each_ref()
+            .map(|c| c.swap(0, Ordering::Relaxed));
+        DebugMessageCounts {
+            errors,
+            warnings,
+            infos,
+            verbose,
+        }
+    }
 }
 
 impl Drop for Debugger {
@@ -28,3 +443,149 @@ impl Drop for Debugger {
         };
     }
 }
+
+///Converts `name` into a label name. Interior nul bytes are removed.
+fn label_name(name: &str) -> CString {
+    CString::new(name).unwrap_or_else(|_| CString::new(name.replace('\0', "")).unwrap())
+}
+
+///Label region of a command buffer, opened by [Debugger::cmd_label]. Closes the region when dropped.
+pub struct CmdLabel<'a> {
+    debugger: &'a Debugger,
+    command_buffer: vk::CommandBuffer,
+}
+
+impl Drop for CmdLabel<'_> {
+    fn drop(&mut self) {
+        self.debugger.cmd_end_label(self.command_buffer);
+    }
+}
+
+///Label region of a queue, opened by [Debugger::queue_label]. Closes the region when dropped.
+pub struct QueueLabel<'a> {
+    debugger: &'a Debugger,
+    queue: vk::Queue,
+}
+
+impl Drop for QueueLabel<'_> {
+    fn drop(&mut self) {
+        self.debugger.queue_end_label(self.queue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use static_assertions::assert_impl_all;
+
+    fn message(severity: vk::DebugUtilsMessageSeverityFlagsEXT, id: &str) -> DebugMessage {
+        DebugMessage {
+            severity,
+            ty: vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
+            id_number: 0,
+            id_name: Some(id.to_owned()),
+            message: String::new(),
+            objects: Vec::new(),
+            queue_labels: Vec::new(),
+            cmd_buf_labels: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn label_names() {
+        assert_eq!(label_name("task").as_bytes(), b"task");
+        assert_eq!(label_name("ta\0sk").as_bytes(), b"task");
+    }
+
+    #[test]
+    fn impl_send_sync() {
+        assert_impl_all!(Debugger: Send, Sync);
+    }
+
+    #[test]
+    fn route_mute_and_count() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let handler = {
+            let received = received.clone();
+            move |msg: &DebugMessage| received.lock().unwrap().push(msg.id_name.clone().unwrap())
+        };
+        let router = MessageRouter::new(Arc::new(handler));
+        router
+            .muted
+            .write()
+            .unwrap()
+            .insert("VUID-muted".to_owned());
+
+        router.route(message(
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            "VUID-a",
+        ));
+        router.route(message(
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            "VUID-muted",
+        ));
+        router.route(message(
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            "VUID-b",
+        ));
+
+        assert_eq!(*received.lock().unwrap(), vec!["VUID-a", "VUID-b"]);
+        assert_eq!(router.counts[0].load(Ordering::Relaxed), 1);
+        assert_eq!(router.counts[1].load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn route_records_error() {
+        let router = MessageRouter::new(Arc::new(LogMessageHandler));
+        router.route(message(
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            "VUID-ignored",
+        ));
+        assert!(router.pending_error.lock().unwrap().is_none());
+
+        router.panic_on_error.store(true, Ordering::Relaxed);
+        router.route(message(
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            "VUID-warning",
+        ));
+        assert!(router.pending_error.lock().unwrap().is_none());
+
+        //routing does not panic, only the first error is kept
+        router.route(message(
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            "VUID-a",
+        ));
+        router.route(message(
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            "VUID-b",
+        ));
+        assert!(
+            router
+                .pending_error
+                .lock()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .contains("VUID-a")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "VUID-a")]
+    fn panic_on_error() {
+        let router = MessageRouter::new(Arc::new(LogMessageHandler));
+        router.panic_on_error.store(true, Ordering::Relaxed);
+        router.route(message(
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            "VUID-a",
+        ));
+        router.check_errors();
+    }
+
+    #[test]
+    fn check_errors_without_error() {
+        let router = MessageRouter::new(Arc::new(LogMessageHandler));
+        router.panic_on_error.store(true, Ordering::Relaxed);
+        router.check_errors();
+    }
+}
@@ -6,7 +6,10 @@ use crate::{
     util::image_usage_to_format_features,
 };
 
-use super::{Debugger, Queue, QueueBuilder};
+use super::{
+    Debugger, LogMessageHandler, Queue, QueueBuilder,
+    debugger::{MessageRouter, route_debug_message},
+};
 use std::sync::{Arc, Mutex};
 
 ///Helper that lets you setup device properties and possibly needed extensions before creating the actual
@@ -175,6 +178,8 @@ pub struct Device {
     pub queues: Vec<Queue>,
 
     pub enabled_extensions: Vec<String>,
+    ///Core features the device was created with, as passed via `DeviceCreateInfo::p_enabled_features`.
+    pub enabled_features: ash::vk::PhysicalDeviceFeatures,
 
     pub physical_device_properties: ash::vk::PhysicalDeviceProperties,
     ///Hosts the debug-utils used for validation-layer reporting and _naming_things.
@@ -218,6 +223,10 @@ impl Device {
             })
             .collect();
 
+        let enabled_features = unsafe { device_create_info.p_enabled_features.as_ref() }
+            .copied()
+            .unwrap_or_default();
+
         let physical_device_properties = unsafe {
             instance
                 .inner
@@ -245,6 +254,13 @@ impl Device {
 
         //if validation is enabled, either unwrap the debugger, of create the new one
         let debugger = if instance.validation_enabled() {
+            //NOTE: boxed, since its address is passed as user data to the callback.
+            let router = Box::new(MessageRouter::new(
+                instance
+                    .debug_message_handler
+                    .clone()
+                    .unwrap_or_else(|| Arc::new(LogMessageHandler)),
+            ));
             let (debug_instance, debug_messenger, debug_report_loader) = {
                 //create the reporter
                 let debug_instance =
@@ -264,7 +280,8 @@ impl Device {
                             | ash::vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
                             | ash::vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
                     )
-                    .pfn_user_callback(Some(crate::context::instance::vulkan_debug_callback));
+                    .pfn_user_callback(Some(route_debug_message))
+                    .user_data(router.as_ref() as *const MessageRouter as *mut core::ffi::c_void);
 
                 let messenger =
                     unsafe { debug_instance.create_debug_utils_messenger(&debug_info, None)? };
@@ -275,6 +292,7 @@ impl Device {
                 debug_instance,
                 debug_report_loader,
                 debug_messenger,
+                router,
             };
 
             Some(debugger)
@@ -287,6 +305,7 @@ impl Device {
             instance,
             physical_device,
             enabled_extensions,
+            enabled_features,
             queues,
             physical_device_properties,
             debugger,
@@ -6,89 +6,11 @@ use std::{
 };
 
 use ash::vk::{self, BaseOutStructure, TaggedStructure};
-use const_cstr::const_cstr;
 use raw_window_handle::HasDisplayHandle;
 
 use crate::error::InstanceError;
 
-use super::PhysicalDeviceFilter;
-const_cstr! {
-    UNKNOWNID = "unknown id";
-    NOMSG = "no message";
-}
-
-///The external callback print function for debugging
-pub unsafe extern "system" fn vulkan_debug_callback(
-    message_severity: ash::vk::DebugUtilsMessageSeverityFlagsEXT,
-    #[allow(unused)] message_types: ash::vk::DebugUtilsMessageTypeFlagsEXT,
-    p_callback_data: *const ash::vk::DebugUtilsMessengerCallbackDataEXT,
-    _p_user_data: *mut core::ffi::c_void,
-) -> u32 {
-    if p_callback_data.is_null() {
-        #[cfg(feature = "logging")]
-        log::error!("MarpDebugMsg: Got Msg, but no data!");
-        return 1;
-    }
-
-    //use log if the layer is enabled, otherwise use println
-    #[cfg(feature = "logging")]
-    unsafe {
-        let (id, idname) = if !(*p_callback_data).p_message_id_name.is_null() {
-            (
-                (*p_callback_data).message_id_number,
-                std::ffi::CStr::from_ptr((*p_callback_data).p_message_id_name),
-            )
-        } else {
-            (
-                (*p_callback_data).message_id_number,
-                std::ffi::CStr::from_ptr(UNKNOWNID.as_ptr()),
-            )
-        };
-
-        let msg = if !(*p_callback_data).p_message.is_null() {
-            std::ffi::CStr::from_ptr((*p_callback_data).p_message)
-        } else {
-            std::ffi::CStr::from_ptr(NOMSG.as_ptr())
-        };
-
-        if message_severity.contains(ash::vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
-            log::error!("[{}: {:?}]: {:?}", id, idname, msg);
-        } else if message_severity.contains(ash::vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
-            log::warn!("[{}: {:?}]: {:?}", id, idname, msg);
-        } else if message_severity.contains(ash::vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
-            log::info!("[{}: {:?}]: {:?}", id, idname, msg);
-        } else {
-            log::trace!("[{}: {:?}]: {:?}", id, idname, msg);
-        }
-    }
-
-    #[cfg(not(feature = "logging"))]
-    unsafe {
-        println!(
-            "MarpDebugMsg: Level: {:?}, Type: {:?}\n",
-            message_severity, message_types
-        );
-        //If there is a validation layer msg, append
-        if !(*p_callback_data).p_message_id_name.is_null() {
-            println!(
-                "Id[{:?}]: {:?}",
-                (*p_callback_data).message_id_number,
-                std::ffi::CStr::from_ptr((*p_callback_data).p_message_id_name)
-            )
-        } else {
-            println!("Id[{:?}]", (*p_callback_data).message_id_number);
-        }
-
-        //Now display message if there is any
-        if !(*p_callback_data).p_message.is_null() {
-            println!(
-                "Msg: {:?}",
-                std::ffi::CStr::from_ptr((*p_callback_data).p_message)
-            );
-        }
-    }
-    1
-}
+use super::{DebugMessageHandler, PhysicalDeviceFilter};
 
 ///Signales enabled and disabled validation layer features
 #[allow(dead_code)]
@@ -133,6 +55,8 @@ pub struct InstanceBuilder {
     pub validation_layers: Option<ValidationFeatures>,
     pub enabled_layers: Vec<CString>,
     pub enabled_extensions: Vec<CString>,
+    ///Handler of the device's [Debugger](crate::context::Debugger), if validation is enabled. Defaults to [LogMessageHandler](crate::context::LogMessageHandler).
+    pub debug_message_handler: Option<Arc<dyn DebugMessageHandler>>,
     available_layers: Vec<vk::LayerProperties>,
     available_extensions: Vec<vk::ExtensionProperties>,
 }
@@ -153,6 +77,7 @@ impl InstanceBuilder {
             validation_layers: _,
             enabled_layers,
             enabled_extensions,
+            debug_message_handler,
             available_layers: _,
             available_extensions: _,
         } = self;
@@ -207,6 +132,7 @@ impl InstanceBuilder {
             entry,
             inner: instance,
             validation_enabled: has_val_layers,
+            debug_message_handler,
             enabled_extensions,
             enabled_layers,
         }))
@@ -322,6 +248,8 @@ impl InstanceBuilder {
     }
 
     ///Enables all extensions that are needed for the surface behind `handle` to work.
+    ///
+    /// If available, `VK_EXT_swapchain_colorspace` is enabled as well, so that surfaces report their extended (and HDR) color spaces.
     pub fn for_surface(mut self, handle: &dyn HasDisplayHandle) -> Result<Self, InstanceError> {
         let required_extensions =
             ash_window::enumerate_required_extensions(handle.display_handle().unwrap().as_raw())?;
@@ -330,6 +258,10 @@ impl InstanceBuilder {
             self = self.with_extension(st)?;
         }
 
+        if self.is_extension_available_cstr(ash::ext::swapchain_colorspace::NAME) {
+            self = self.with_extension(ash::ext::swapchain_colorspace::NAME.to_owned())?;
+        }
+
         Ok(self)
     }
 
@@ -338,6 +270,16 @@ impl InstanceBuilder {
         self.validation_layers = Some(features);
         self
     }
+
+    ///Routes all validation messages to `handler` instead of printing them. Only has an effect if validation is enabled.
+    /// The handler can be exchanged later via [Debugger::set_message_handler](crate::context::Debugger::set_message_handler).
+    pub fn with_debug_message_handler(
+        mut self,
+        handler: impl DebugMessageHandler + 'static,
+    ) -> Self {
+        self.debug_message_handler = Some(Arc::new(handler));
+        self
+    }
 }
 
 ///marpii instance. Wraps the entry point as well as the created instance into one object.
@@ -350,6 +292,7 @@ pub struct Instance {
     pub entry: ash::Entry,
     pub inner: ash::Instance,
     validation_enabled: bool,
+    pub(crate) debug_message_handler: Option<Arc<dyn DebugMessageHandler>>,
     enabled_extensions: Vec<CString>,
     enabled_layers: Vec<CString>,
 }
@@ -374,6 +317,7 @@ impl Instance {
             enabled_extensions: Vec::new(),
             enabled_layers: Vec::new(),
             validation_layers: None,
+            debug_message_handler: None,
             available_layers,
             available_extensions,
         })
@@ -390,6 +334,7 @@ impl Instance {
             enabled_extensions: Vec::new(),
             enabled_layers: Vec::new(),
             validation_layers: None,
+            debug_message_handler: None,
             available_layers,
             available_extensions,
         })
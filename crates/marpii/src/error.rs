@@ -4,7 +4,7 @@ use ash::{vk, LoadingError};
 
 use thiserror::Error;
 
-use crate::resources::BufferMapError;
+use crate::{allocator::MemoryUsage, resources::BufferMapError};
 
 #[derive(Error, Debug)]
 pub enum DeviceError {
@@ -29,6 +29,15 @@ pub enum DeviceError {
     ImageExpectUsageFlag(vk::ImageUsageFlags),
     #[error("Usage flag {0:#?} must be set")]
     BufferExpectUsageFlag(vk::BufferUsageFlags),
+    #[error("No memory type suitable for {usage:?} in memory type bits {type_bits:b}")]
+    NoSuitableMemoryType { usage: MemoryUsage, type_bits: u32 },
+    #[error(
+        "Memory can not be exported as {requested:?}, exportable handle types are {exportable:?}"
+    )]
+    NotExportable {
+        requested: vk::ExternalMemoryHandleTypeFlags,
+        exportable: vk::ExternalMemoryHandleTypeFlags,
+    },
     #[error("Vulkan error: {0}")]
     VkError(#[from] vk::Result),
 }
@@ -43,6 +52,9 @@ pub enum ShaderError {
     #[cfg_attr(feature = "shader_reflection", error("Reflection error: {0}"))]
     #[cfg(feature = "shader_reflection")]
     ReflectionError(String),
+    #[cfg_attr(feature = "shader_compiler", error("Compilation failed: {0}"))]
+    #[cfg(feature = "shader_compiler")]
+    Compile(crate::resources::CompileError),
 }
 
 #[derive(Error, Debug)]
@@ -85,6 +97,53 @@ pub enum PipelineError {
     VkError(#[from] vk::Result),
     #[error("Failed to allocate pipeline")]
     Allocation,
+    #[error("Shader module has no {stage:?} entry point named \"{name}\"")]
+    MissingEntryPoint {
+        name: String,
+        stage: vk::ShaderStageFlags,
+    },
+    #[error(
+        "Shader expects a push constant block of {expected} bytes, but only {provided} bytes are provided"
+    )]
+    PushConstantSize { expected: u32, provided: u32 },
+    #[error("Push constant of {size} bytes exceeds the device limit of {limit} bytes")]
+    PushConstantLimit { size: u32, limit: u32 },
+    #[error(
+        "Descriptor set {set}, binding {binding} (\"{name}\") is declared as {found:?} in the shader, but the pipeline layout expects {expected:?}"
+    )]
+    DescriptorMismatch {
+        set: u32,
+        binding: u32,
+        name: String,
+        ///Type the layout declares for the binding, `None` if the binding is not part of the layout.
+        expected: Option<vk::DescriptorType>,
+        found: vk::DescriptorType,
+    },
+    #[error(
+        "Specialization constant {id} is set with {provided} bytes, but the shader declares it with {expected:?} bytes"
+    )]
+    SpecializationMismatch {
+        id: u32,
+        ///Size declared in the shader, `None` if the shader has no constant with that id.
+        expected: Option<u32>,
+        provided: u32,
+    },
+    #[error(
+        "Shader stage already carries specialization constants, an additional specialization info can not be applied"
+    )]
+    ConflictingSpecialization,
+    #[error("Failed to reflect shader interface: {0}")]
+    Reflection(String),
+}
+
+#[derive(Error, Debug)]
+pub enum RingBufferError {
+    #[error("Allocation of {size} bytes can not fit into ring buffer of capacity {capacity}")]
+    InvalidSize { size: u64, capacity: u64 },
+    #[error("Ring buffer can't fit {size} bytes, only {available} bytes available")]
+    OutOfMemory { size: u64, available: u64 },
+    #[error("Failed to write to ring buffer: {0}")]
+    MapError(#[from] BufferMapError),
 }
 
 #[derive(Error, Debug)]
@@ -103,6 +162,8 @@ pub enum MarpiiError {
     ShaderError(#[from] ShaderError),
     #[error("Data (pointer) MapError: {0}")]
     MapError(#[from] BufferMapError),
+    #[error("Ring buffer error: {0}")]
+    RingBufferError(#[from] RingBufferError),
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -114,7 +175,7 @@ mod test {
     use crate::{
         error::{
             CommandBufferError, DescriptorError, DeviceError, InstanceError, PipelineError,
-            ShaderError,
+            RingBufferError, ShaderError,
         },
         MarpiiError,
     };
@@ -127,6 +188,7 @@ mod test {
         assert_impl_all!(InstanceError: Send, Sync);
         assert_impl_all!(DescriptorError: Send, Sync);
         assert_impl_all!(PipelineError: Send, Sync);
+        assert_impl_all!(RingBufferError: Send, Sync);
         assert_impl_all!(MarpiiError: Send, Sync);
     }
 }
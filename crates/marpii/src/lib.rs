@@ -49,7 +49,7 @@ pub mod sync;
 mod error;
 pub use error::{
     CommandBufferError, DescriptorError, DeviceError, InstanceError, MarpiiError, PipelineError,
-    ShaderError,
+    RingBufferError, ShaderError,
 };
 
 ///The infamous utility module contains all sorts of nice-to-have functions. Stuff like type converters etc.
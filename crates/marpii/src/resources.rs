@@ -4,22 +4,51 @@ pub use image::{Image, ImageType, ImageView, ImgDesc, ImgViewDesc, SafeImageView
 mod buffer;
 pub use buffer::{BufDesc, Buffer, BufferMapError};
 
+#[cfg(unix)]
+mod external;
+
+mod sparse;
+pub use sparse::{SparseBindBatch, SparseBufferResidency, SparseImageResidency, SparsePage};
+
 mod push_constant;
 pub use push_constant::PushConstant;
 
 mod descriptor;
 #[cfg(feature = "shader_reflection")]
-pub use descriptor::shader_interface::Reflection;
+pub use descriptor::shader_interface::{
+    DescriptorCount, EntryPoint, InterfaceVariable, PushConstantBlock, ReflectedBinding,
+    Reflection, ScalarType, SpecializationConstant,
+};
 pub use descriptor::{DescriptorAllocator, DescriptorPool, DescriptorSet, DescriptorSetLayout};
 
 pub mod pipeline;
 pub use pipeline::{compute::ComputePipeline, graphics::GraphicsPipeline, PipelineLayout};
+#[doc(hidden)]
+pub use pipeline::graphics_builder::field_format;
+pub use pipeline::graphics_builder::{
+    BlendPreset, GraphicsPipelineBuilder, Vertex, VertexAttributeFormat,
+};
+pub use pipeline::library::{GraphicsPipelineLibrary, PipelineLibraryCache};
 
 mod command_buffer;
-pub use command_buffer::{CommandBuffer, CommandBufferAllocator, CommandPool};
+pub use command_buffer::{
+    CommandBuffer, CommandBufferAllocator, CommandPool, CommandPoolRecycler,
+    CommandPoolStatistics, RecycledCommandBuffer,
+};
 
 mod shader_module;
 pub use shader_module::{ShaderModule, ShaderStage};
+
+mod shader_object;
+pub use shader_object::ShaderObject;
+
+#[cfg(feature = "shader_compiler")]
+mod shader_compiler;
+#[cfg(feature = "shader_compiler")]
+pub use shader_compiler::{CompileError, ShaderCompiler, SourceLanguage};
+
+mod specialization;
+pub use specialization::Specialization;
 use smallvec::SmallVec;
 
 ///Memory usage types
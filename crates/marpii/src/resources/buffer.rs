@@ -4,7 +4,10 @@ use std::{
 };
 
 use crate::{
-    allocator::{Allocation, Allocator, AnonymAllocation, ManagedAllocation, MemoryUsage},
+    allocator::{
+        Allocation, AllocationDesc, Allocator, AnonymAllocation, DedicatedAllocation,
+        DedicatedTarget, ManagedAllocation, MemoryUsage,
+    },
     context::Device,
     error::DeviceError,
 };
@@ -169,19 +172,60 @@ impl Hash for Buffer {
 impl Buffer {
     ///Creates a buffer for `description` and the supplied creation-time information. Note that the actual resulting
     ///allocation can be bigger than specified.
+    ///
+    /// `memory` is usually just a [MemoryUsage]. Use an [AllocationDesc] for dedicated allocations or memory priorities. In that case
+    /// the memory is not allocated via `allocator`.
     pub fn new<A: Allocator + Send + Sync + 'static>(
         device: &Arc<Device>,
         allocator: &Arc<Mutex<A>>,
         description: BufDesc,
-        usage: MemoryUsage,
+        memory: impl Into<AllocationDesc>,
         name: Option<&str>,
     ) -> Result<Self, DeviceError> {
+        let memory = memory.into();
+        let usage = memory.usage;
         let mut builder = ash::vk::BufferCreateInfo::default();
 
         builder = description.set_on_builder(builder);
 
         //create buffer handle
         let buffer = unsafe { device.inner.create_buffer(&builder, None)? };
+
+        if memory.needs_dedicated() {
+            let allocation = match DedicatedAllocation::new(
+                device,
+                DedicatedTarget::Buffer {
+                    buffer,
+                    usage: description.usage,
+                },
+                &memory,
+            ) {
+                Ok(a) => a,
+                Err(e) => {
+                    unsafe { device.inner.destroy_buffer(buffer, None) };
+                    return Err(e);
+                }
+            };
+            if let Err(e) = unsafe {
+                device
+                    .inner
+                    .bind_buffer_memory(buffer, allocation.memory(), 0)
+            } {
+                //NOTE: destroy the handle before the allocation frees the memory.
+                unsafe { device.inner.destroy_buffer(buffer, None) };
+                return Err(e.into());
+            }
+            allocation.set_name(name);
+
+            return Ok(Buffer {
+                device: device.clone(),
+                allocation: Mutex::new(Box::new(allocation)),
+                usage,
+                desc: description,
+                inner: buffer,
+            });
+        }
+
         let allocation = allocator
             .lock()
             .unwrap()
@@ -280,7 +324,7 @@ impl Buffer {
     pub fn write(&self, offset: usize, data: &[u8]) -> Result<(), BufferMapError> {
         //Check that we have a chance for mapping
         match &self.usage {
-            MemoryUsage::GpuOnly | MemoryUsage::Unknown => {
+            MemoryUsage::GpuOnly | MemoryUsage::GpuLazy | MemoryUsage::Unknown => {
                 #[cfg(feature = "logging")]
                 log::error!("Tried to map buffer that has usage: {:?}", self.usage);
                 return Err(BufferMapError::NotMapable);
@@ -362,7 +406,7 @@ impl Buffer {
     ///Tries to flash the memory range. Does nothing if the memory is not host mappable
     pub fn flush_range(&self) -> Result<(), BufferMapError> {
         match &self.usage {
-            MemoryUsage::GpuOnly | MemoryUsage::Unknown => {
+            MemoryUsage::GpuOnly | MemoryUsage::GpuLazy | MemoryUsage::Unknown => {
                 #[cfg(feature = "logging")]
                 log::error!("Tried flush buffer that has usage: {:?}", self.usage);
                 return Err(BufferMapError::NotMapable);
@@ -408,7 +452,7 @@ impl Buffer {
         &'a self,
     ) -> Result<MutexGuard<'a, Box<dyn AnonymAllocation + Send + Sync>>, BufferMapError> {
         match &self.usage {
-            MemoryUsage::GpuOnly | MemoryUsage::Unknown => {
+            MemoryUsage::GpuOnly | MemoryUsage::GpuLazy | MemoryUsage::Unknown => {
                 #[cfg(feature = "logging")]
                 log::error!("Tried to map buffer that has usage: {:?}", self.usage);
                 return Err(BufferMapError::NotMapable);
@@ -1,5 +1,9 @@
-use std::sync::Arc;
+use std::{
+    sync::{Arc, Mutex},
+    thread::ThreadId,
+};
 
+use ahash::AHashMap;
 use oos::OoS;
 
 use crate::{context::Device, error::CommandBufferError};
@@ -187,3 +191,170 @@ impl Drop for CommandBuffer {
         }
     }
 }
+
+///Statistics of a [CommandPoolRecycler].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CommandPoolStatistics {
+    ///Number of pools created over the recycler's lifetime.
+    pub pools_created: u64,
+    ///Number of times an idle pool was reused instead of creating a new one.
+    pub pools_reused: u64,
+    ///Pools that are currently handed out.
+    pub pools_in_use: usize,
+    ///Pools that are reset and wait for reuse.
+    pub pools_idle: usize,
+}
+
+struct RecyclerState {
+    ///Reset pools and their command buffer, per thread that used them last.
+    idle: AHashMap<ThreadId, Vec<(CommandPool, ash::vk::CommandBuffer)>>,
+    statistics: CommandPoolStatistics,
+}
+
+///Hands out primary command buffers, each from its own `TRANSIENT` [CommandPool]. Once a [RecycledCommandBuffer] is dropped, its whole pool
+/// is reset and kept for reuse. Therefore the buffer should only be dropped after its execution finished, for instance together
+/// with the execution's other resources.
+///
+/// Since each pool is owned by a single buffer, buffers can be recorded on different threads. Idle pools are kept per thread and
+/// are reused by the thread that used them last.
+#[derive(Clone)]
+pub struct CommandPoolRecycler {
+    device: Arc<Device>,
+    queue_family: u32,
+    state: Arc<Mutex<RecyclerState>>,
+}
+
+impl CommandPoolRecycler {
+    pub fn new(device: &Arc<Device>, queue_family: u32) -> Self {
+        CommandPoolRecycler {
+            device: device.clone(),
+            queue_family,
+            state: Arc::new(Mutex::new(RecyclerState {
+                idle: AHashMap::default(),
+                statistics: CommandPoolStatistics::default(),
+            })),
+        }
+    }
+
+    ///The queue family all pools are created for.
+    pub fn queue_family(&self) -> u32 {
+        self.queue_family
+    }
+
+    ///Returns a primary command buffer in the initial state. Reuses an idle pool of the calling thread if there is one.
+    pub fn acquire(&self) -> Result<RecycledCommandBuffer, CommandBufferError> {
+        let thread = std::thread::current().id();
+        let reused = {
+            let mut state = self.state.lock().unwrap();
+            let reused = state.idle.get_mut(&thread).and_then(|idle| idle.pop());
+            if reused.is_some() {
+                state.statistics.pools_reused += 1;
+                state.statistics.pools_idle -= 1;
+            }
+            reused
+        };
+
+        let (pool, inner) = if let Some(reused) = reused {
+            reused
+        } else {
+            let pool = CommandPool::new(
+                &self.device,
+                self.queue_family,
+                ash::vk::CommandPoolCreateFlags::TRANSIENT,
+            )?;
+            let mut buffers = unsafe {
+                self.device.inner.allocate_command_buffers(
+                    &ash::vk::CommandBufferAllocateInfo::default()
+                        .command_pool(pool.inner)
+                        .command_buffer_count(1)
+                        .level(ash::vk::CommandBufferLevel::PRIMARY),
+                )?
+            };
+            if buffers.is_empty() {
+                return Err(CommandBufferError::FailedToAllocate {
+                    allocated: 0,
+                    count: 1,
+                });
+            }
+            self.state.lock().unwrap().statistics.pools_created += 1;
+            (pool, buffers.remove(0))
+        };
+
+        self.state.lock().unwrap().statistics.pools_in_use += 1;
+        Ok(RecycledCommandBuffer {
+            inner,
+            pool: Some(pool),
+            thread,
+            state: self.state.clone(),
+        })
+    }
+
+    pub fn statistics(&self) -> CommandPoolStatistics {
+        self.state.lock().unwrap().statistics
+    }
+
+    ///Destroys all idle pools. Pools that are in use are still recycled once their buffer is dropped.
+    pub fn trim(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.idle.clear();
+        state.statistics.pools_idle = 0;
+    }
+}
+
+///Command buffer handed out by a [CommandPoolRecycler]. On drop the buffer's pool is reset and returned to the recycler.
+pub struct RecycledCommandBuffer {
+    ///the raw vulkan handle
+    pub inner: ash::vk::CommandBuffer,
+    pool: Option<CommandPool>,
+    thread: ThreadId,
+    state: Arc<Mutex<RecyclerState>>,
+}
+
+impl RecycledCommandBuffer {
+    ///The pool this buffer is allocated from. The pool is exclusive to this buffer.
+    pub fn pool(&self) -> &CommandPool {
+        self.pool.as_ref().unwrap()
+    }
+}
+
+impl Drop for RecycledCommandBuffer {
+    fn drop(&mut self) {
+        let pool = self.pool.take().unwrap();
+        let mut state = self.state.lock().unwrap();
+        state.statistics.pools_in_use -= 1;
+
+        //Resetting the pool resets the buffer as well. If that fails the pool is dropped instead.
+        let reset = unsafe {
+            pool.device
+                .inner
+                .reset_command_pool(pool.inner, ash::vk::CommandPoolResetFlags::empty())
+        };
+        match reset {
+            Ok(_) => {
+                state
+                    .idle
+                    .entry(self.thread)
+                    .or_default()
+                    .push((pool, self.inner));
+                state.statistics.pools_idle += 1;
+            }
+            Err(_e) => {
+                #[cfg(feature = "logging")]
+                log::error!("Failed to reset recycled command pool: {}", _e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use static_assertions::assert_impl_all;
+
+    #[test]
+    fn impl_send_sync() {
+        assert_impl_all!(CommandPool: Send, Sync);
+        assert_impl_all!(CommandPoolRecycler: Send, Sync);
+        assert_impl_all!(RecycledCommandBuffer: Send, Sync);
+    }
+}
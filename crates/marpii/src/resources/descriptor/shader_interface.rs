@@ -1,69 +1,260 @@
+use ash::vk;
+
+use crate::{error::PipelineError, resources::Specialization};
+
+mod spirv;
+use spirv::ShaderInterface;
+pub use spirv::{
+    EntryPoint, InterfaceVariable, PushConstantBlock, ScalarType, SpecializationConstant,
+};
+
+///Number of descriptors of a reflected binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DescriptorCount {
+    Fixed(u32),
+    ///Runtime sized array, for instance the bindless arrays of `marpii-rmg`.
+    Unbounded,
+}
+
+///Single descriptor binding of a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReflectedBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub name: String,
+    pub ty: vk::DescriptorType,
+    pub count: DescriptorCount,
+}
+
 ///Reflection interface for some shader source.
 pub struct Reflection {
     inner: rspirv_reflect::Reflection,
+    interface: ShaderInterface,
 }
 
-fn descriptor_count_from_binding(binding: rspirv_reflect::BindingCount) -> u32 {
-    match binding {
-        rspirv_reflect::BindingCount::One => 1,
-        rspirv_reflect::BindingCount::StaticSized(n) => n as u32,
-        rspirv_reflect::BindingCount::Unbounded => {
-            #[cfg(feature = "logging")]
-            log::error!("Found unbound decriptor set, can't reflect correct size. Using 1 instead");
-            1
-        }
-    }
-}
-
-//TODO handle bindless gracefully. Happens if we have an unbound number of descriptors. for a binding layout.
 impl Reflection {
     pub fn new_from_code(shader_code: &[u8]) -> Result<Self, rspirv_reflect::ReflectError> {
-        Ok(Reflection {
-            inner: rspirv_reflect::Reflection::new_from_spirv(shader_code).unwrap(),
-        })
+        let inner = rspirv_reflect::Reflection::new_from_spirv(shader_code)?;
+        let interface = ShaderInterface::from_module(&inner.0);
+        Ok(Reflection { inner, interface })
     }
 
-    ///Generates binding layouts for each descriptor set. Sets the shader `stage_flags` of each binding with the supplied ones.
-    pub fn get_bindings(
+    ///Returns all descriptor bindings of the module. In contrast to [`get_bindings`](Self::get_bindings) runtime arrays are reported
+    /// as [`DescriptorCount::Unbounded`].
+    pub fn descriptor_bindings(
         &self,
-        stage_flags: ash::vk::ShaderStageFlags,
-    ) -> Result<
-        Vec<(u32, Vec<ash::vk::DescriptorSetLayoutBinding<'_>>)>,
-        rspirv_reflect::ReflectError,
-    > {
-        #[cfg(feature = "shader_reflection_verbose")]
-        log::info!("Reflection:");
-
+    ) -> Result<Vec<ReflectedBinding>, rspirv_reflect::ReflectError> {
         Ok(self
             .inner
             .get_descriptor_sets()?
             .into_iter()
-            .map(|(set_idx, descriptor_set)| {
-                #[cfg(feature = "shader_reflection_verbose")]
-                log::info!("  Set {}", set_idx);
-
-                //Generate the binding information
-                let set_bindings = descriptor_set
+            .flat_map(|(set, bindings)| {
+                bindings
                     .into_iter()
-                    .map(|(binding_id, binding)| {
-                        #[cfg(feature = "shader_reflection_verbose")]
-                        log::info!("    Binding {} = {:?}", binding_id, binding);
-
-                        ash::vk::DescriptorSetLayoutBinding {
-                            binding: binding_id,
-                            descriptor_count: descriptor_count_from_binding(binding.binding_count),
-                            descriptor_type: ash::vk::DescriptorType::from_raw(i32::from_be_bytes(
-                                binding.ty.0.to_be_bytes(),
-                            )),
-                            stage_flags,
-                            ..Default::default()
-                        }
+                    .map(move |(binding, info)| ReflectedBinding {
+                        set,
+                        binding,
+                        name: info.name,
+                        ty: vk::DescriptorType::from_raw(i32::from_be_bytes(
+                            info.ty.0.to_be_bytes(),
+                        )),
+                        count: match info.binding_count {
+                            rspirv_reflect::BindingCount::One => DescriptorCount::Fixed(1),
+                            rspirv_reflect::BindingCount::StaticSized(n) => {
+                                DescriptorCount::Fixed(n as u32)
+                            }
+                            rspirv_reflect::BindingCount::Unbounded => DescriptorCount::Unbounded,
+                        },
                     })
-                    .collect::<Vec<_>>();
-                (set_idx, set_bindings)
             })
             .collect())
     }
+
+    ///Generates binding layouts for each descriptor set. Sets the shader `stage_flags` of each binding with the supplied ones.
+    ///
+    /// Runtime sized arrays get a descriptor count of `1`. Use [`get_bindings_with_unbounded`](Self::get_bindings_with_unbounded) to choose
+    /// the count for those.
+    pub fn get_bindings(
+        &self,
+        stage_flags: vk::ShaderStageFlags,
+    ) -> Result<Vec<(u32, Vec<vk::DescriptorSetLayoutBinding<'_>>)>, rspirv_reflect::ReflectError>
+    {
+        self.get_bindings_with_unbounded(stage_flags, 1)
+    }
+
+    ///Same as [`get_bindings`](Self::get_bindings), but uses `unbounded_count` as the descriptor count of runtime sized arrays. Note that
+    /// the layout usually needs the `VARIABLE_DESCRIPTOR_COUNT` and `PARTIALLY_BOUND` binding flags for those.
+    pub fn get_bindings_with_unbounded(
+        &self,
+        stage_flags: vk::ShaderStageFlags,
+        unbounded_count: u32,
+    ) -> Result<Vec<(u32, Vec<vk::DescriptorSetLayoutBinding<'_>>)>, rspirv_reflect::ReflectError>
+    {
+        #[cfg(feature = "shader_reflection_verbose")]
+        log::info!("Reflection:");
+
+        let mut sets: Vec<(u32, Vec<vk::DescriptorSetLayoutBinding<'_>>)> = Vec::new();
+        for binding in self.descriptor_bindings()? {
+            #[cfg(feature = "shader_reflection_verbose")]
+            log::info!(
+                "  Set {} Binding {} = {:?}",
+                binding.set,
+                binding.binding,
+                binding
+            );
+
+            let layout_binding = vk::DescriptorSetLayoutBinding {
+                binding: binding.binding,
+                descriptor_count: match binding.count {
+                    DescriptorCount::Fixed(n) => n,
+                    DescriptorCount::Unbounded => unbounded_count,
+                },
+                descriptor_type: binding.ty,
+                stage_flags,
+                ..Default::default()
+            };
+            if let Some((_, set)) = sets.iter_mut().find(|(idx, _)| *idx == binding.set) {
+                set.push(layout_binding);
+            } else {
+                sets.push((binding.set, vec![layout_binding]));
+            }
+        }
+        Ok(sets)
+    }
+
+    ///All entry points of the module.
+    pub fn entry_points(&self) -> &[EntryPoint] {
+        &self.interface.entry_points
+    }
+
+    ///Returns the entry point called `name`, if there is any.
+    pub fn entry_point(&self, name: &str) -> Option<&EntryPoint> {
+        self.interface.entry_points.iter().find(|e| e.name == name)
+    }
+
+    ///Returns the push-constant block used by `entry_point`, if there is any.
+    pub fn push_constant_block(&self, entry_point: &str) -> Option<PushConstantBlock> {
+        self.entry_point(entry_point)
+            .and_then(|entry| entry.push_constants)
+    }
+
+    ///Returns the push-constant range used by `entry_point`.
+    pub fn push_constant_range(
+        &self,
+        entry_point: &str,
+        stage_flags: vk::ShaderStageFlags,
+    ) -> Option<vk::PushConstantRange> {
+        self.push_constant_block(entry_point)
+            .map(|block| block.range(stage_flags))
+    }
+
+    ///All specialization constants of the module, sorted by their `constant_id`.
+    pub fn specialization_constants(&self) -> &[SpecializationConstant] {
+        &self.interface.specialization_constants
+    }
+
+    ///Vertex attributes of the first vertex entry point.
+    pub fn vertex_inputs(&self) -> &[InterfaceVariable] {
+        self.interface
+            .entry_points
+            .iter()
+            .find(|e| e.stage == vk::ShaderStageFlags::VERTEX)
+            .map(|e| e.inputs.as_slice())
+            .unwrap_or(&[])
+    }
+
+    ///Workgroup size (`LocalSize`) of the first compute entry point.
+    pub fn workgroup_size(&self) -> Option<[u32; 3]> {
+        self.interface
+            .entry_points
+            .iter()
+            .find(|e| e.stage == vk::ShaderStageFlags::COMPUTE)
+            .and_then(|e| e.local_size)
+    }
+
+    ///Checks that the module is compatible with a pipeline layout made from `set_layouts` (indexed by the set number) and a push constant
+    /// range of `push_constant_size` bytes starting at offset 0. Also checks that `entry_point` exists for `stage`.
+    ///
+    /// Only the descriptor types are compared, descriptor counts are not checked.
+    pub fn validate_layout(
+        &self,
+        entry_point: &str,
+        stage: vk::ShaderStageFlags,
+        set_layouts: &[&[vk::DescriptorSetLayoutBinding<'_>]],
+        push_constant_size: u32,
+    ) -> Result<(), PipelineError> {
+        //NOTE: modules without any entry point (for instance linkable libraries) can't be checked. Don't fail for those.
+        if !self.interface.entry_points.is_empty()
+            && !self
+                .interface
+                .entry_points
+                .iter()
+                .any(|e| e.name == entry_point && e.stage == stage)
+        {
+            return Err(PipelineError::MissingEntryPoint {
+                name: entry_point.to_owned(),
+                stage,
+            });
+        }
+
+        if let Some(block) = self.push_constant_block(entry_point)
+            && block.size > push_constant_size
+        {
+            return Err(PipelineError::PushConstantSize {
+                expected: block.size,
+                provided: push_constant_size,
+            });
+        }
+
+        let bindings = self
+            .descriptor_bindings()
+            .map_err(|e| PipelineError::Reflection(format!("{}", e)))?;
+        for binding in bindings {
+            let expected = set_layouts
+                .get(binding.set as usize)
+                .and_then(|set| set.iter().find(|b| b.binding == binding.binding))
+                .map(|b| b.descriptor_type);
+            if expected != Some(binding.ty) {
+                return Err(PipelineError::DescriptorMismatch {
+                    set: binding.set,
+                    binding: binding.binding,
+                    name: binding.name,
+                    expected,
+                    found: binding.ty,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    ///Checks that every constant of `specialization` is declared by the module with the same size.
+    pub fn validate_specialization(
+        &self,
+        specialization: &Specialization,
+    ) -> Result<(), PipelineError> {
+        //NOTE: see validate_layout
+        if self.interface.entry_points.is_empty() {
+            return Ok(());
+        }
+
+        for (id, size) in specialization.constants() {
+            let expected = self
+                .interface
+                .specialization_constants
+                .iter()
+                .find(|c| c.id == id)
+                .map(|c| c.ty.size());
+            if expected != Some(size) {
+                return Err(PipelineError::SpecializationMismatch {
+                    id,
+                    expected,
+                    provided: size,
+                });
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,775 @@
+//! Collects everything `rspirv_reflect` doesn't report: entry points, stage inputs/outputs, workgroup size, specialization
+//! constants and push-constant blocks. Works on the [rspirv module](Module) `rspirv_reflect` already loaded, only looks
+//! at the instructions it needs and ignores everything else.
+//!
+//! Push-constant blocks are resolved per entry point via the entry point's interface. Before SPIR-V 1.4 the interface only lists
+//! inputs and outputs, in that case the module's first push-constant block is reported for every entry point.
+
+use ahash::AHashMap;
+use ash::vk;
+use rspirv_reflect::{
+    rspirv::dr::{Instruction, Module, Operand},
+    spirv::{BuiltIn, Decoration, ExecutionMode, ExecutionModel, Op, StorageClass},
+};
+
+///First version whose entry point interfaces list all global variables, not just inputs and outputs.
+const VERSION_1_4: u32 = 0x0001_0400;
+
+///Scalar type of a specialization constant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScalarType {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+}
+
+impl ScalarType {
+    ///Size in bytes of the scalar. Note that booleans are 4 bytes (`VkBool32`) when used as a specialization constant.
+    pub fn size(&self) -> u32 {
+        match self {
+            ScalarType::Bool => 4,
+            ScalarType::Int { width, .. } | ScalarType::Float { width } => width / 8,
+        }
+    }
+}
+
+///A specialization constant of the module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecializationConstant {
+    ///The `constant_id` of the constant.
+    pub id: u32,
+    pub name: Option<String>,
+    pub ty: ScalarType,
+    ///Default value as declared in the module. Values narrower than 64 bit are stored in the lower bits.
+    pub default: u64,
+}
+
+///Input or output variable of a shader stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceVariable {
+    pub location: u32,
+    pub name: Option<String>,
+    ///Format of a single location. Matrices occupy one location per column, and are reported as one variable per column.
+    pub format: vk::Format,
+}
+
+///Push-constant block of an entry point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PushConstantBlock {
+    ///Byte offset of the first member.
+    pub offset: u32,
+    ///Size of the block in bytes, starting at 0 (not at `offset`).
+    pub size: u32,
+}
+
+impl PushConstantBlock {
+    ///Returns the range that is actually used by the block.
+    pub fn range(&self, stage_flags: vk::ShaderStageFlags) -> vk::PushConstantRange {
+        vk::PushConstantRange {
+            stage_flags,
+            offset: self.offset,
+            size: self.size - self.offset,
+        }
+    }
+}
+
+///Single entry point of a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPoint {
+    pub name: String,
+    pub stage: vk::ShaderStageFlags,
+    ///Workgroup size for compute-like stages. Reports the default value if the size is a specialization constant.
+    pub local_size: Option<[u32; 3]>,
+    ///Stage inputs, excluding built-ins, sorted by location. For vertex shaders those are the vertex attributes.
+    pub inputs: Vec<InterfaceVariable>,
+    ///Stage outputs, excluding built-ins, sorted by location. For fragment shaders those are the color attachments.
+    pub outputs: Vec<InterfaceVariable>,
+    ///Push-constant block used by the entry point.
+    pub push_constants: Option<PushConstantBlock>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Type {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Array { element: u32, length: u32 },
+    RuntimeArray,
+    Struct,
+    Pointer { pointee: u32 },
+}
+
+///Everything parsed from the module.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ShaderInterface {
+    pub entry_points: Vec<EntryPoint>,
+    pub specialization_constants: Vec<SpecializationConstant>,
+}
+
+#[derive(Default)]
+struct Parser {
+    version: u32,
+    names: AHashMap<u32, String>,
+    types: AHashMap<u32, Type>,
+    struct_members: AHashMap<u32, Vec<u32>>,
+    constants: AHashMap<u32, (u32, u64)>,
+    spec_constants: Vec<(u32, u32, u64)>,
+    composites: AHashMap<u32, Vec<u32>>,
+    variables: Vec<(u32, u32, StorageClass)>,
+    decorations: AHashMap<(u32, Decoration), Vec<u32>>,
+    member_decorations: AHashMap<(u32, u32, Decoration), Vec<u32>>,
+    entry_points: Vec<(ExecutionModel, u32, String, Vec<u32>)>,
+    local_size: AHashMap<u32, [u32; 3]>,
+    local_size_id: AHashMap<u32, [u32; 3]>,
+}
+
+///Value of a single word operand. Built-ins are reported by their number.
+fn word(operand: &Operand) -> Option<u32> {
+    match operand {
+        Operand::IdRef(id) => Some(*id),
+        Operand::LiteralBit32(value) => Some(*value),
+        Operand::BuiltIn(builtin) => Some(*builtin as u32),
+        _ => None,
+    }
+}
+
+fn words(operands: &[Operand]) -> Vec<u32> {
+    operands.iter().filter_map(word).collect()
+}
+
+fn execution_model_to_stage(model: ExecutionModel) -> vk::ShaderStageFlags {
+    match model {
+        ExecutionModel::Vertex => vk::ShaderStageFlags::VERTEX,
+        ExecutionModel::TessellationControl => vk::ShaderStageFlags::TESSELLATION_CONTROL,
+        ExecutionModel::TessellationEvaluation => vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+        ExecutionModel::Geometry => vk::ShaderStageFlags::GEOMETRY,
+        ExecutionModel::Fragment => vk::ShaderStageFlags::FRAGMENT,
+        ExecutionModel::GLCompute => vk::ShaderStageFlags::COMPUTE,
+        ExecutionModel::TaskNV => vk::ShaderStageFlags::TASK_NV,
+        ExecutionModel::MeshNV => vk::ShaderStageFlags::MESH_NV,
+        ExecutionModel::RayGenerationKHR => vk::ShaderStageFlags::RAYGEN_KHR,
+        ExecutionModel::IntersectionKHR => vk::ShaderStageFlags::INTERSECTION_KHR,
+        ExecutionModel::AnyHitKHR => vk::ShaderStageFlags::ANY_HIT_KHR,
+        ExecutionModel::ClosestHitKHR => vk::ShaderStageFlags::CLOSEST_HIT_KHR,
+        ExecutionModel::MissKHR => vk::ShaderStageFlags::MISS_KHR,
+        ExecutionModel::CallableKHR => vk::ShaderStageFlags::CALLABLE_KHR,
+        ExecutionModel::TaskEXT => vk::ShaderStageFlags::TASK_EXT,
+        ExecutionModel::MeshEXT => vk::ShaderStageFlags::MESH_EXT,
+        _ => vk::ShaderStageFlags::empty(),
+    }
+}
+
+impl Parser {
+    fn from_module(module: &Module) -> Self {
+        let mut parser = Parser {
+            version: module.header.as_ref().map(|h| h.version).unwrap_or(0),
+            ..Default::default()
+        };
+        for inst in module
+            .debug_names
+            .iter()
+            .chain(&module.entry_points)
+            .chain(&module.execution_modes)
+            .chain(&module.annotations)
+            .chain(&module.types_global_values)
+        {
+            parser.parse_instruction(inst);
+        }
+        parser
+    }
+
+    fn decoration(&self, id: u32, decoration: Decoration) -> Option<&[u32]> {
+        self.decorations
+            .get(&(id, decoration))
+            .map(|lits| lits.as_slice())
+    }
+
+    fn member_decoration(&self, id: u32, member: u32, decoration: Decoration) -> Option<u32> {
+        self.member_decorations
+            .get(&(id, member, decoration))
+            .and_then(|lits| lits.first().copied())
+    }
+
+    fn parse_instruction(&mut self, inst: &Instruction) {
+        let ops = inst.operands.as_slice();
+        let result = inst.result_id.unwrap_or(0);
+        match (inst.class.opcode, ops) {
+            (Op::Name, [Operand::IdRef(id), Operand::LiteralString(name), ..]) => {
+                self.names.insert(*id, name.clone());
+            }
+            (
+                Op::EntryPoint,
+                [
+                    Operand::ExecutionModel(model),
+                    Operand::IdRef(id),
+                    Operand::LiteralString(name),
+                    interface @ ..,
+                ],
+            ) => {
+                self.entry_points
+                    .push((*model, *id, name.clone(), words(interface)));
+            }
+            (
+                Op::ExecutionMode | Op::ExecutionModeId,
+                [
+                    Operand::IdRef(entry),
+                    Operand::ExecutionMode(mode),
+                    size @ ..,
+                ],
+            ) => {
+                if let [x, y, z] = words(size)[..] {
+                    match mode {
+                        ExecutionMode::LocalSize => {
+                            self.local_size.insert(*entry, [x, y, z]);
+                        }
+                        ExecutionMode::LocalSizeId => {
+                            self.local_size_id.insert(*entry, [x, y, z]);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            (Op::TypeBool, _) => {
+                self.types.insert(result, Type::Bool);
+            }
+            (Op::TypeInt, [Operand::LiteralBit32(width), Operand::LiteralBit32(signed)]) => {
+                self.types.insert(
+                    result,
+                    Type::Int {
+                        width: *width,
+                        signed: *signed != 0,
+                    },
+                );
+            }
+            (Op::TypeFloat, [Operand::LiteralBit32(width), ..]) => {
+                self.types.insert(result, Type::Float { width: *width });
+            }
+            (Op::TypeVector, [Operand::IdRef(component), Operand::LiteralBit32(count)]) => {
+                self.types.insert(
+                    result,
+                    Type::Vector {
+                        component: *component,
+                        count: *count,
+                    },
+                );
+            }
+            (Op::TypeMatrix, [Operand::IdRef(column), Operand::LiteralBit32(count)]) => {
+                self.types.insert(
+                    result,
+                    Type::Matrix {
+                        column: *column,
+                        count: *count,
+                    },
+                );
+            }
+            (Op::TypeArray, [Operand::IdRef(element), Operand::IdRef(length)]) => {
+                let length = self.constants.get(length).map(|c| c.1).unwrap_or(0) as u32;
+                self.types.insert(
+                    result,
+                    Type::Array {
+                        element: *element,
+                        length,
+                    },
+                );
+            }
+            (Op::TypeRuntimeArray, _) => {
+                self.types.insert(result, Type::RuntimeArray);
+            }
+            (Op::TypeStruct, members) => {
+                self.types.insert(result, Type::Struct);
+                self.struct_members.insert(result, words(members));
+            }
+            (Op::TypePointer, [Operand::StorageClass(_), Operand::IdRef(pointee)]) => {
+                self.types
+                    .insert(result, Type::Pointer { pointee: *pointee });
+            }
+            (Op::Constant | Op::SpecConstant, [value, ..]) => {
+                let value = match value {
+                    Operand::LiteralBit32(value) => u64::from(*value),
+                    Operand::LiteralBit64(value) => *value,
+                    _ => return,
+                };
+                let ty = inst.result_type.unwrap_or(0);
+                self.constants.insert(result, (ty, value));
+                if inst.class.opcode == Op::SpecConstant {
+                    self.spec_constants.push((result, ty, value));
+                }
+            }
+            (
+                Op::ConstantTrue | Op::ConstantFalse | Op::SpecConstantTrue | Op::SpecConstantFalse,
+                _,
+            ) => {
+                let value =
+                    matches!(inst.class.opcode, Op::ConstantTrue | Op::SpecConstantTrue) as u64;
+                let ty = inst.result_type.unwrap_or(0);
+                self.constants.insert(result, (ty, value));
+                if matches!(
+                    inst.class.opcode,
+                    Op::SpecConstantTrue | Op::SpecConstantFalse
+                ) {
+                    self.spec_constants.push((result, ty, value));
+                }
+            }
+            (Op::ConstantComposite | Op::SpecConstantComposite, constituents) => {
+                self.composites.insert(result, words(constituents));
+            }
+            (Op::Variable, [Operand::StorageClass(storage), ..]) => {
+                self.variables
+                    .push((result, inst.result_type.unwrap_or(0), *storage));
+            }
+            (
+                Op::Decorate,
+                [
+                    Operand::IdRef(id),
+                    Operand::Decoration(decoration),
+                    literals @ ..,
+                ],
+            ) => {
+                self.decorations.insert((*id, *decoration), words(literals));
+            }
+            (
+                Op::MemberDecorate,
+                [
+                    Operand::IdRef(id),
+                    Operand::LiteralBit32(member),
+                    Operand::Decoration(decoration),
+                    literals @ ..,
+                ],
+            ) => {
+                self.member_decorations
+                    .insert((*id, *member, *decoration), words(literals));
+            }
+            _ => {}
+        }
+    }
+
+    ///Base alignment of `ty` in bytes, following the std430 rules used for push constants.
+    fn type_alignment(&self, ty: u32) -> u32 {
+        match self.types.get(&ty) {
+            Some(Type::Bool) => 4,
+            Some(Type::Int { width, .. }) | Some(Type::Float { width }) => width / 8,
+            //NOTE: three component vectors are aligned like four component vectors.
+            Some(Type::Vector { component, count }) => {
+                self.type_alignment(*component) * if *count == 2 { 2 } else { 4 }
+            }
+            Some(Type::Matrix { column, .. }) => self.type_alignment(*column),
+            Some(Type::Array { element, .. }) => self.type_alignment(*element),
+            Some(Type::Struct) => self
+                .struct_members
+                .get(&ty)
+                .into_iter()
+                .flatten()
+                .map(|member| self.type_alignment(*member))
+                .max()
+                .unwrap_or(1),
+            //NOTE: only physical storage buffer pointers (64 bit) can be part of a block.
+            Some(Type::Pointer { .. }) => 8,
+            Some(Type::RuntimeArray) | None => 1,
+        }
+    }
+
+    ///Size of `ty` in bytes, respecting explicit layout decorations. Runtime arrays have a size of 0.
+    ///
+    /// Structs end at their last member (by offset), rounded up to the struct's alignment.
+    fn type_size(&self, ty: u32, matrix_stride: Option<u32>) -> u32 {
+        match self.types.get(&ty) {
+            Some(Type::Bool) => 4,
+            Some(Type::Int { width, .. }) | Some(Type::Float { width }) => width / 8,
+            Some(Type::Vector { component, count }) => self.type_size(*component, None) * count,
+            Some(Type::Matrix { column, count }) => {
+                matrix_stride.unwrap_or_else(|| self.type_size(*column, None)) * count
+            }
+            Some(Type::Array { element, length }) => {
+                let stride = self
+                    .decoration(ty, Decoration::ArrayStride)
+                    .and_then(|lits| lits.first().copied())
+                    .unwrap_or_else(|| self.type_size(*element, matrix_stride));
+                stride * length
+            }
+            Some(Type::Struct) => {
+                let members = self.struct_members.get(&ty).map(|m| m.as_slice());
+                let mut end = 0;
+                let mut packed = 0;
+                for (idx, member) in members.unwrap_or(&[]).iter().enumerate() {
+                    let stride = self.member_decoration(ty, idx as u32, Decoration::MatrixStride);
+                    let size = self.type_size(*member, stride);
+                    let offset = self
+                        .member_decoration(ty, idx as u32, Decoration::Offset)
+                        .unwrap_or(packed);
+                    packed = offset + size;
+                    end = end.max(offset + size);
+                }
+                end.next_multiple_of(self.type_alignment(ty))
+            }
+            //NOTE: only physical storage buffer pointers (64 bit) can be part of a block.
+            Some(Type::Pointer { .. }) => 8,
+            Some(Type::RuntimeArray) | None => 0,
+        }
+    }
+
+    fn scalar_type(&self, ty: u32) -> Option<ScalarType> {
+        match self.types.get(&ty)? {
+            Type::Bool => Some(ScalarType::Bool),
+            Type::Int { width, signed } => Some(ScalarType::Int {
+                width: *width,
+                signed: *signed,
+            }),
+            Type::Float { width } => Some(ScalarType::Float { width: *width }),
+            _ => None,
+        }
+    }
+
+    ///Format of a scalar or vector type as used for vertex attributes.
+    fn format(&self, ty: u32) -> vk::Format {
+        let (scalar, count) = match self.types.get(&ty) {
+            Some(Type::Vector { component, count }) => (self.scalar_type(*component), *count),
+            _ => (self.scalar_type(ty), 1),
+        };
+        use vk::Format as F;
+        let table: [F; 4] = match scalar {
+            Some(ScalarType::Float { width: 16 }) => [
+                F::R16_SFLOAT,
+                F::R16G16_SFLOAT,
+                F::R16G16B16_SFLOAT,
+                F::R16G16B16A16_SFLOAT,
+            ],
+            Some(ScalarType::Float { width: 32 }) => [
+                F::R32_SFLOAT,
+                F::R32G32_SFLOAT,
+                F::R32G32B32_SFLOAT,
+                F::R32G32B32A32_SFLOAT,
+            ],
+            Some(ScalarType::Float { width: 64 }) => [
+                F::R64_SFLOAT,
+                F::R64G64_SFLOAT,
+                F::R64G64B64_SFLOAT,
+                F::R64G64B64A64_SFLOAT,
+            ],
+            Some(ScalarType::Int {
+                width: 32,
+                signed: true,
+            }) => [
+                F::R32_SINT,
+                F::R32G32_SINT,
+                F::R32G32B32_SINT,
+                F::R32G32B32A32_SINT,
+            ],
+            Some(ScalarType::Int {
+                width: 32,
+                signed: false,
+            }) => [
+                F::R32_UINT,
+                F::R32G32_UINT,
+                F::R32G32B32_UINT,
+                F::R32G32B32A32_UINT,
+            ],
+            Some(ScalarType::Int {
+                width: 64,
+                signed: false,
+            }) => [
+                F::R64_UINT,
+                F::R64G64_UINT,
+                F::R64G64B64_UINT,
+                F::R64G64B64A64_UINT,
+            ],
+            Some(ScalarType::Int {
+                width: 64,
+                signed: true,
+            }) => [
+                F::R64_SINT,
+                F::R64G64_SINT,
+                F::R64G64B64_SINT,
+                F::R64G64B64A64_SINT,
+            ],
+            _ => return F::UNDEFINED,
+        };
+        table
+            .get(count.saturating_sub(1) as usize)
+            .copied()
+            .unwrap_or(F::UNDEFINED)
+    }
+
+    ///Collects all non-builtin variables of `storage` in `interface`.
+    fn interface_variables(
+        &self,
+        interface: &[u32],
+        storage: StorageClass,
+    ) -> Vec<InterfaceVariable> {
+        let mut variables = Vec::new();
+        for (id, ptr_ty, var_storage) in &self.variables {
+            if *var_storage != storage || !interface.contains(id) {
+                continue;
+            }
+            if self.decoration(*id, Decoration::BuiltIn).is_some() {
+                continue;
+            }
+            let Some(location) = self
+                .decoration(*id, Decoration::Location)
+                .and_then(|lits| lits.first().copied())
+            else {
+                continue;
+            };
+            let Some(Type::Pointer { pointee, .. }) = self.types.get(ptr_ty) else {
+                continue;
+            };
+
+            //unwrap arrays and matrices into one variable per location
+            let (ty, count) = match self.types.get(pointee) {
+                Some(Type::Matrix { column, count }) => (*column, *count),
+                Some(Type::Array { element, length }) => (*element, *length),
+                _ => (*pointee, 1),
+            };
+            let format = self.format(ty);
+            for offset in 0..count {
+                variables.push(InterfaceVariable {
+                    location: location + offset,
+                    name: self.names.get(id).cloned(),
+                    format,
+                });
+            }
+        }
+        variables.sort_by_key(|v| v.location);
+        variables
+    }
+
+    fn constant_value(&self, id: u32) -> Option<u32> {
+        self.constants.get(&id).map(|c| c.1 as u32)
+    }
+
+    fn local_size(&self, entry: u32) -> Option<[u32; 3]> {
+        //The WorkgroupSize built-in overrides any execution mode.
+        let builtin = self.composites.iter().find_map(|(id, constituents)| {
+            if self.decoration(*id, Decoration::BuiltIn) == Some(&[BuiltIn::WorkgroupSize as u32]) {
+                Some(constituents)
+            } else {
+                None
+            }
+        });
+        if let Some(constituents) = builtin
+            && let [x, y, z] = constituents.as_slice()
+        {
+            return Some([
+                self.constant_value(*x)?,
+                self.constant_value(*y)?,
+                self.constant_value(*z)?,
+            ]);
+        }
+
+        if let Some(size) = self.local_size.get(&entry) {
+            return Some(*size);
+        }
+
+        let [x, y, z] = self.local_size_id.get(&entry)?;
+        Some([
+            self.constant_value(*x)?,
+            self.constant_value(*y)?,
+            self.constant_value(*z)?,
+        ])
+    }
+
+    ///Push-constant block of a push-constant variable of type `ptr_ty`.
+    fn push_constant_block(&self, ptr_ty: u32) -> Option<PushConstantBlock> {
+        let Some(Type::Pointer { pointee, .. }) = self.types.get(&ptr_ty) else {
+            return None;
+        };
+        let members = self.struct_members.get(pointee)?;
+        let offset = (0..members.len() as u32)
+            .filter_map(|idx| self.member_decoration(*pointee, idx, Decoration::Offset))
+            .min()
+            .unwrap_or(0);
+        Some(PushConstantBlock {
+            offset,
+            size: self.type_size(*pointee, None),
+        })
+    }
+
+    ///Push-constant block used by an entry point with `interface`. There can only be one push-constant block per entry point.
+    fn entry_push_constants(&self, interface: &[u32]) -> Option<PushConstantBlock> {
+        let mut push_variables = self
+            .variables
+            .iter()
+            .filter(|(_id, _ptr_ty, storage)| *storage == StorageClass::PushConstant);
+        if self.version >= VERSION_1_4 {
+            push_variables
+                .find(|(id, _ptr_ty, _storage)| interface.contains(id))
+                .and_then(|(_id, ptr_ty, _storage)| self.push_constant_block(*ptr_ty))
+        } else {
+            //NOTE: the interface does not list push constants, so we can't tell which block belongs to the entry point.
+            push_variables
+                .next()
+                .and_then(|(_id, ptr_ty, _storage)| self.push_constant_block(*ptr_ty))
+        }
+    }
+
+    fn finish(self) -> ShaderInterface {
+        let entry_points = self
+            .entry_points
+            .iter()
+            .map(|(model, id, name, interface)| EntryPoint {
+                name: name.clone(),
+                stage: execution_model_to_stage(*model),
+                local_size: self.local_size(*id),
+                inputs: self.interface_variables(interface, StorageClass::Input),
+                outputs: self.interface_variables(interface, StorageClass::Output),
+                push_constants: self.entry_push_constants(interface),
+            })
+            .collect();
+
+        let mut specialization_constants = self
+            .spec_constants
+            .iter()
+            .filter_map(|(id, ty, default)| {
+                let constant_id = self
+                    .decoration(*id, Decoration::SpecId)
+                    .and_then(|lits| lits.first().copied())?;
+                Some(SpecializationConstant {
+                    id: constant_id,
+                    name: self.names.get(id).cloned(),
+                    ty: self.scalar_type(*ty)?,
+                    default: *default,
+                })
+            })
+            .collect::<Vec<_>>();
+        specialization_constants.sort_by_key(|c| c.id);
+
+        ShaderInterface {
+            entry_points,
+            specialization_constants,
+        }
+    }
+}
+
+impl ShaderInterface {
+    ///Collects the interface of `module`.
+    pub fn from_module(module: &Module) -> Self {
+        Parser::from_module(module).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rspirv_reflect::rspirv::dr::Builder;
+
+    fn offset(builder: &mut Builder, structure: u32, member: u32, offset: u32) {
+        builder.member_decorate(
+            structure,
+            member,
+            Decoration::Offset,
+            [Operand::LiteralBit32(offset)],
+        );
+    }
+
+    #[test]
+    fn parse_compute_interface() {
+        let mut builder = Builder::new();
+        builder.set_version(1, 3);
+        let main = builder.id();
+        builder.entry_point(ExecutionModel::GLCompute, main, "main", []);
+        builder.execution_mode(main, ExecutionMode::LocalSize, [8, 4, 1]);
+        let float = builder.type_float(32);
+        let vec4 = builder.type_vector(float, 4);
+        let uint = builder.type_int(32, 0);
+        let block = builder.type_struct([vec4, uint]);
+        offset(&mut builder, block, 0, 0);
+        offset(&mut builder, block, 1, 16);
+        let ptr = builder.type_pointer(None, StorageClass::PushConstant, block);
+        let constant = builder.spec_constant_bit32(uint, 42);
+        builder.decorate(constant, Decoration::SpecId, [Operand::LiteralBit32(3)]);
+        builder.variable(ptr, None, StorageClass::PushConstant, None);
+
+        let interface = ShaderInterface::from_module(&builder.module());
+
+        assert_eq!(interface.entry_points.len(), 1);
+        assert_eq!(interface.entry_points[0].name, "main");
+        assert_eq!(
+            interface.entry_points[0].stage,
+            vk::ShaderStageFlags::COMPUTE
+        );
+        assert_eq!(interface.entry_points[0].local_size, Some([8, 4, 1]));
+        //vec4 + uint end at 20 bytes, rounded up to the vec4 alignment.
+        assert_eq!(
+            interface.entry_points[0].push_constants,
+            Some(PushConstantBlock {
+                offset: 0,
+                size: 32
+            })
+        );
+        assert_eq!(
+            interface.specialization_constants,
+            vec![SpecializationConstant {
+                id: 3,
+                name: None,
+                ty: ScalarType::Int {
+                    width: 32,
+                    signed: false
+                },
+                default: 42
+            }]
+        );
+    }
+
+    #[test]
+    fn push_constants_per_entry_point() {
+        let mut builder = Builder::new();
+        //SPIR-V 1.4, the interfaces list the push constant variables
+        builder.set_version(1, 4);
+        let float = builder.type_float(32);
+        let vec4 = builder.type_vector(float, 4);
+        let small = builder.type_struct([float]);
+        let big = builder.type_struct([vec4, float]);
+        offset(&mut builder, small, 0, 0);
+        offset(&mut builder, big, 0, 0);
+        offset(&mut builder, big, 1, 16);
+        let small_ptr = builder.type_pointer(None, StorageClass::PushConstant, small);
+        let big_ptr = builder.type_pointer(None, StorageClass::PushConstant, big);
+        let small_var = builder.variable(small_ptr, None, StorageClass::PushConstant, None);
+        let big_var = builder.variable(big_ptr, None, StorageClass::PushConstant, None);
+        let (vert, frag) = (builder.id(), builder.id());
+        builder.entry_point(ExecutionModel::Vertex, vert, "vert", [small_var]);
+        builder.entry_point(ExecutionModel::Fragment, frag, "frag", [big_var]);
+
+        let interface = ShaderInterface::from_module(&builder.module());
+        assert_eq!(interface.entry_points.len(), 2);
+        assert_eq!(
+            interface.entry_points[0].push_constants,
+            Some(PushConstantBlock { offset: 0, size: 4 })
+        );
+        assert_eq!(
+            interface.entry_points[1].push_constants,
+            Some(PushConstantBlock {
+                offset: 0,
+                size: 32
+            })
+        );
+    }
+
+    #[test]
+    fn struct_size_is_aligned() {
+        let mut builder = Builder::new();
+        let float = builder.type_float(32);
+        let vec3 = builder.type_vector(float, 3);
+        let ulong = builder.type_int(64, 0);
+        //vec3 followed by a float fits into 16 bytes
+        let packed = builder.type_struct([vec3, float]);
+        offset(&mut builder, packed, 0, 0);
+        offset(&mut builder, packed, 1, 12);
+
+        //members declared out of offset order, the struct ends at the member with the highest offset
+        let unordered = builder.type_struct([float, ulong]);
+        offset(&mut builder, unordered, 0, 8);
+        offset(&mut builder, unordered, 1, 0);
+
+        //u64 followed by a float is padded to the u64 alignment
+        let padded = builder.type_struct([ulong, float]);
+        offset(&mut builder, padded, 0, 0);
+        offset(&mut builder, padded, 1, 8);
+
+        let parser = Parser::from_module(&builder.module());
+        assert_eq!(parser.type_size(packed, None), 16);
+        assert_eq!(parser.type_size(unordered, None), 16);
+        assert_eq!(parser.type_size(padded, None), 16);
+    }
+}
@@ -0,0 +1,339 @@
+//! External memory support for [Buffer]s and [Image]s.
+//!
+//! Resources created via `new_exportable` can be shared with other processes or APIs (for instance a video encoder or a compositor)
+//! through a file descriptor. Foreign memory can be imported via `import_fd`. Supported handle types are
+//! `OPAQUE_FD` (needs `VK_KHR_external_memory_fd`) and `DMA_BUF_EXT` (additionally needs `VK_EXT_external_memory_dma_buf`).
+//!
+//! Note that dma-buf images are only supported with `LINEAR` tiling at the moment, since DRM format modifiers are not handled.
+//!
+//! Ownership of the memory is transferred via queue family ownership transfers from/to `vk::QUEUE_FAMILY_EXTERNAL`.
+//! Synchronisation with the other party is usually done with exported or imported semaphores. See [Semaphore](crate::sync::Semaphore).
+
+use std::{
+    os::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd},
+    sync::{Arc, Mutex},
+};
+
+use ash::vk;
+
+use super::{BufDesc, Buffer, Image, ImgDesc};
+use crate::{
+    allocator::{
+        AllocationDesc, AnonymAllocation, DedicatedAllocation, DedicatedTarget, ExternalMemory,
+    },
+    context::Device,
+    error::DeviceError,
+};
+
+///Checks that all extensions needed for `handle_type` are enabled.
+fn check_external_memory_support(
+    device: &Arc<Device>,
+    handle_type: vk::ExternalMemoryHandleTypeFlags,
+) -> Result<(), DeviceError> {
+    if !device.extension_enabled_cstr(ash::khr::external_memory_fd::NAME) {
+        return Err(DeviceError::UnsupportedExtension(
+            ash::khr::external_memory_fd::NAME
+                .to_string_lossy()
+                .to_string(),
+        ));
+    }
+    if handle_type.contains(vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT)
+        && !device.extension_enabled_cstr(ash::ext::external_memory_dma_buf::NAME)
+    {
+        return Err(DeviceError::UnsupportedExtension(
+            ash::ext::external_memory_dma_buf::NAME
+                .to_string_lossy()
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+///Exports the memory of `allocation` as `handle_type`.
+fn export_memory_fd(
+    device: &Arc<Device>,
+    allocation: &(dyn AnonymAllocation + Send + Sync),
+    handle_type: vk::ExternalMemoryHandleTypeFlags,
+) -> Result<OwnedFd, DeviceError> {
+    check_external_memory_support(device, handle_type)?;
+    let exportable = allocation.exportable_handle_types();
+    if handle_type.is_empty() || !exportable.contains(handle_type) {
+        #[cfg(feature = "logging")]
+        log::error!(
+            "Memory is not exportable as {:?}, only as {:?}",
+            handle_type,
+            exportable
+        );
+        return Err(DeviceError::NotExportable {
+            requested: handle_type,
+            exportable,
+        });
+    }
+    let memory = allocation
+        .as_memory_range()
+        .map(|range| range.memory)
+        .ok_or(DeviceError::VkError(
+            vk::Result::ERROR_INVALID_EXTERNAL_HANDLE,
+        ))?;
+
+    let loader = ash::khr::external_memory_fd::Device::new(&device.instance.inner, &device.inner);
+    let fd = unsafe {
+        loader.get_memory_fd(
+            &vk::MemoryGetFdInfoKHR::default()
+                .memory(memory)
+                .handle_type(handle_type),
+        )?
+    };
+    //Safety: The fd was just created by the implementation and is owned by us.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+impl Buffer {
+    ///Creates a buffer whose memory can be exported as `handle_types`, see [export_fd](Self::export_fd). The buffer always uses a
+    /// dedicated allocation.
+    pub fn new_exportable(
+        device: &Arc<Device>,
+        description: BufDesc,
+        memory: impl Into<AllocationDesc>,
+        handle_types: vk::ExternalMemoryHandleTypeFlags,
+        name: Option<&str>,
+    ) -> Result<Self, DeviceError> {
+        check_external_memory_support(device, handle_types)?;
+        Self::new_external(
+            device,
+            description,
+            memory.into(),
+            handle_types,
+            ExternalMemory::Export(handle_types),
+            name,
+        )
+    }
+
+    ///Imports `fd` of type `handle_type` as the memory of a new buffer described by `description`. On success the Vulkan implementation takes
+    /// ownership of `fd`, otherwise it is closed.
+    pub fn import_fd(
+        device: &Arc<Device>,
+        description: BufDesc,
+        memory: impl Into<AllocationDesc>,
+        fd: OwnedFd,
+        handle_type: vk::ExternalMemoryHandleTypeFlags,
+        name: Option<&str>,
+    ) -> Result<Self, DeviceError> {
+        check_external_memory_support(device, handle_type)?;
+        let buffer = Self::new_external(
+            device,
+            description,
+            memory.into(),
+            handle_type,
+            ExternalMemory::ImportFd {
+                handle_type,
+                fd: fd.as_raw_fd(),
+            },
+            name,
+        )?;
+        //import succeeded, the fd is now owned by the implementation.
+        let _ = fd.into_raw_fd();
+        Ok(buffer)
+    }
+
+    ///Returns the handle types the buffer's memory can be exported as. Empty, unless the buffer was created via [new_exportable](Self::new_exportable).
+    pub fn exportable_handle_types(&self) -> vk::ExternalMemoryHandleTypeFlags {
+        self.allocation
+            .lock()
+            .map(|allocation| allocation.exportable_handle_types())
+            .unwrap_or(vk::ExternalMemoryHandleTypeFlags::empty())
+    }
+
+    ///Exports the buffer's memory as `handle_type`. The buffer must have been created via [new_exportable](Self::new_exportable)
+    /// with `handle_type`, otherwise [NotExportable](DeviceError::NotExportable) is returned.
+    pub fn export_fd(
+        &self,
+        handle_type: vk::ExternalMemoryHandleTypeFlags,
+    ) -> Result<OwnedFd, DeviceError> {
+        let allocation = self
+            .allocation
+            .lock()
+            .map_err(|_| DeviceError::VkError(vk::Result::ERROR_UNKNOWN))?;
+        export_memory_fd(&self.device, allocation.as_ref(), handle_type)
+    }
+
+    fn new_external(
+        device: &Arc<Device>,
+        description: BufDesc,
+        memory: AllocationDesc,
+        handle_types: vk::ExternalMemoryHandleTypeFlags,
+        external: ExternalMemory,
+        #[allow(unused_variables)] name: Option<&str>,
+    ) -> Result<Self, DeviceError> {
+        let mut external_info =
+            vk::ExternalMemoryBufferCreateInfo::default().handle_types(handle_types);
+        let builder = description
+            .set_on_builder(vk::BufferCreateInfo::default())
+            .push_next(&mut external_info);
+        let buffer = unsafe { device.inner.create_buffer(&builder, None)? };
+
+        let allocation = match DedicatedAllocation::new_external(
+            device,
+            DedicatedTarget::Buffer {
+                buffer,
+                usage: description.usage,
+            },
+            &memory,
+            Some(external),
+        ) {
+            Ok(a) => a,
+            Err(e) => {
+                #[cfg(feature = "logging")]
+                log::error!("Failed to allocate external memory for {:?}: {}", name, e);
+                unsafe { device.inner.destroy_buffer(buffer, None) };
+                return Err(e);
+            }
+        };
+        if let Err(e) = unsafe {
+            device
+                .inner
+                .bind_buffer_memory(buffer, allocation.memory(), 0)
+        } {
+            //NOTE: destroy the handle before the allocation frees the memory.
+            unsafe { device.inner.destroy_buffer(buffer, None) };
+            return Err(e.into());
+        }
+        allocation.set_name(name);
+
+        Ok(Buffer {
+            device: device.clone(),
+            allocation: Mutex::new(Box::new(allocation)),
+            usage: memory.usage,
+            desc: description,
+            inner: buffer,
+        })
+    }
+}
+
+impl Image {
+    ///Creates an image whose memory can be exported as `handle_types`, see [export_fd](Self::export_fd). The image always uses a
+    /// dedicated allocation.
+    pub fn new_exportable(
+        device: &Arc<Device>,
+        description: ImgDesc,
+        memory: impl Into<AllocationDesc>,
+        handle_types: vk::ExternalMemoryHandleTypeFlags,
+        name: Option<&str>,
+    ) -> Result<Self, DeviceError> {
+        check_external_memory_support(device, handle_types)?;
+        Self::new_external(
+            device,
+            description,
+            memory.into(),
+            handle_types,
+            ExternalMemory::Export(handle_types),
+            name,
+        )
+    }
+
+    ///Imports `fd` of type `handle_type` as the memory of a new image described by `description`. On success the Vulkan implementation takes
+    /// ownership of `fd`, otherwise it is closed.
+    ///
+    /// The image is created in the `UNDEFINED` layout. If the content should be preserved, transition from the layout the other party
+    /// released the image in instead.
+    pub fn import_fd(
+        device: &Arc<Device>,
+        description: ImgDesc,
+        memory: impl Into<AllocationDesc>,
+        fd: OwnedFd,
+        handle_type: vk::ExternalMemoryHandleTypeFlags,
+        name: Option<&str>,
+    ) -> Result<Self, DeviceError> {
+        check_external_memory_support(device, handle_type)?;
+        let image = Self::new_external(
+            device,
+            description,
+            memory.into(),
+            handle_type,
+            ExternalMemory::ImportFd {
+                handle_type,
+                fd: fd.as_raw_fd(),
+            },
+            name,
+        )?;
+        //import succeeded, the fd is now owned by the implementation.
+        let _ = fd.into_raw_fd();
+        Ok(image)
+    }
+
+    ///Returns the handle types the image's memory can be exported as. Empty, unless the image was created via [new_exportable](Self::new_exportable).
+    pub fn exportable_handle_types(&self) -> vk::ExternalMemoryHandleTypeFlags {
+        self.allocation.exportable_handle_types()
+    }
+
+    ///Exports the image's memory as `handle_type`. The image must have been created via [new_exportable](Self::new_exportable)
+    /// with `handle_type`, otherwise [NotExportable](DeviceError::NotExportable) is returned.
+    pub fn export_fd(
+        &self,
+        handle_type: vk::ExternalMemoryHandleTypeFlags,
+    ) -> Result<OwnedFd, DeviceError> {
+        export_memory_fd(&self.device, self.allocation.as_ref(), handle_type)
+    }
+
+    fn new_external(
+        device: &Arc<Device>,
+        description: ImgDesc,
+        memory: AllocationDesc,
+        handle_types: vk::ExternalMemoryHandleTypeFlags,
+        external: ExternalMemory,
+        #[allow(unused_variables)] name: Option<&str>,
+    ) -> Result<Self, DeviceError> {
+        if handle_types.contains(vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT)
+            && description.tiling != vk::ImageTiling::LINEAR
+        {
+            #[cfg(feature = "logging")]
+            log::error!("dma-buf images must use LINEAR tiling");
+            return Err(DeviceError::UnsupportedFeature(
+                "dma-buf image with non-linear tiling".to_owned(),
+            ));
+        }
+
+        let mut external_info =
+            vk::ExternalMemoryImageCreateInfo::default().handle_types(handle_types);
+        let builder = description
+            .set_on_builder(
+                vk::ImageCreateInfo::default().initial_layout(vk::ImageLayout::UNDEFINED),
+            )
+            .push_next(&mut external_info);
+        let image = unsafe { device.inner.create_image(&builder, None)? };
+
+        let allocation = match DedicatedAllocation::new_external(
+            device,
+            DedicatedTarget::Image(image),
+            &memory,
+            Some(external),
+        ) {
+            Ok(a) => a,
+            Err(e) => {
+                #[cfg(feature = "logging")]
+                log::error!("Failed to allocate external memory for {:?}: {}", name, e);
+                unsafe { device.inner.destroy_image(image, None) };
+                return Err(e);
+            }
+        };
+        if let Err(e) = unsafe {
+            device
+                .inner
+                .bind_image_memory(image, allocation.memory(), 0)
+        } {
+            //NOTE: destroy the handle before the allocation frees the memory.
+            unsafe { device.inner.destroy_image(image, None) };
+            return Err(e.into());
+        }
+        allocation.set_name(name);
+
+        Ok(Image {
+            allocation: Box::new(allocation),
+            desc: description,
+            inner: image,
+            device: device.clone(),
+            usage: memory.usage,
+            do_not_destroy: false,
+        })
+    }
+}
@@ -2,7 +2,10 @@ use ash::vk::{self, SamplerCreateInfo};
 use oos::OoS;
 
 use crate::{
-    allocator::{Allocation, Allocator, AnonymAllocation, ManagedAllocation, MemoryUsage},
+    allocator::{
+        Allocation, AllocationDesc, Allocator, AnonymAllocation, DedicatedAllocation,
+        DedicatedTarget, ManagedAllocation, MemoryUsage,
+    },
     context::Device,
     error::DeviceError,
     resources::SharingMode,
@@ -98,6 +101,7 @@ impl From<ImageType> for ash::vk::ImageType {
 ///Describes all static parameters of an image view. The easiest way is to create the view description via a
 /// helper function on an image. This fills in all parameters with default value. Those can then be changed base don the needed
 /// usage. Usually only the subresource range is changed.
+#[derive(Debug, Clone, Copy)]
 pub struct ImgViewDesc {
     pub view_type: ash::vk::ImageViewType,
     pub format: ash::vk::Format,
@@ -105,6 +109,19 @@ pub struct ImgViewDesc {
     pub range: ash::vk::ImageSubresourceRange,
 }
 
+//NOTE: implemented by hand, since ash's `ComponentMapping` and `ImageSubresourceRange` are neither `Eq` nor `Hash`.
+impl PartialEq for ImgViewDesc {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_tuple() == other.as_tuple()
+    }
+}
+impl Eq for ImgViewDesc {}
+impl Hash for ImgViewDesc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_tuple().hash(state)
+    }
+}
+
 impl ImgViewDesc {
     ///Overwrites all fields (that apply) of `build` with the data in `self`
     pub fn set_on_builder<'a>(
@@ -121,6 +138,52 @@ impl ImgViewDesc {
         self.range.aspect_mask |= aspect_flag;
         self
     }
+
+    ///Restricts the view to `count` mip levels, starting at `base`.
+    pub fn with_mip_range(mut self, base: u32, count: u32) -> Self {
+        self.range.base_mip_level = base;
+        self.range.level_count = count;
+        self
+    }
+
+    ///Restricts the view to `count` array layers, starting at `base`. Note that you might need to
+    /// change the view type as well, for instance when viewing a single layer of an array image.
+    pub fn with_layer_range(mut self, base: u32, count: u32) -> Self {
+        self.range.base_array_layer = base;
+        self.range.layer_count = count;
+        self
+    }
+
+    ///Changes the view's format. The image must be created with `MUTABLE_FORMAT` if the format differs from the image's format.
+    pub fn with_format(mut self, format: ash::vk::Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_view_type(mut self, view_type: ash::vk::ImageViewType) -> Self {
+        self.view_type = view_type;
+        self
+    }
+
+    fn as_tuple(&self) -> ([i32; 6], [u32; 5]) {
+        (
+            [
+                self.view_type.as_raw(),
+                self.format.as_raw(),
+                self.component_mapping.r.as_raw(),
+                self.component_mapping.g.as_raw(),
+                self.component_mapping.b.as_raw(),
+                self.component_mapping.a.as_raw(),
+            ],
+            [
+                self.range.aspect_mask.as_raw(),
+                self.range.base_mip_level,
+                self.range.level_count,
+                self.range.base_array_layer,
+                self.range.layer_count,
+            ],
+        )
+    }
 }
 
 ///[ash::vk::ImageView](ash::vk::ImageView) wrapper that safes its description data, source image and destroys itself when not in use anymore.
@@ -430,13 +493,18 @@ impl Image {
     ///
     ///
     /// Note that the image is just created with an initial "Undefined" layout.
+    ///
+    /// `memory` is usually just a [MemoryUsage]. Use an [AllocationDesc] for dedicated allocations, memory priorities or
+    /// lazily allocated memory. In that case the memory is not allocated via `allocator`.
     pub fn new<A: Allocator + Send + Sync + 'static>(
         device: &Arc<Device>,
         allocator: &Arc<Mutex<A>>,
         description: ImgDesc,
-        memory_usage: MemoryUsage,
+        memory: impl Into<AllocationDesc>,
         name: Option<&str>,
     ) -> Result<Self, DeviceError> {
+        let memory = memory.into();
+        let memory_usage = memory.usage;
         //per definition the image layout is undefined when creating an image.
         let initial_layout = ash::vk::ImageLayout::UNDEFINED;
 
@@ -447,6 +515,36 @@ impl Image {
         //Time to create the image handle
         let image = unsafe { device.inner.create_image(&builder, None)? };
 
+        if memory.needs_dedicated() {
+            let allocation =
+                match DedicatedAllocation::new(device, DedicatedTarget::Image(image), &memory) {
+                    Ok(a) => a,
+                    Err(e) => {
+                        unsafe { device.inner.destroy_image(image, None) };
+                        return Err(e);
+                    }
+                };
+            if let Err(e) = unsafe {
+                device
+                    .inner
+                    .bind_image_memory(image, allocation.memory(), 0)
+            } {
+                //NOTE: destroy the handle before the allocation frees the memory.
+                unsafe { device.inner.destroy_image(image, None) };
+                return Err(e.into());
+            }
+            allocation.set_name(name);
+
+            return Ok(Image {
+                allocation: Box::new(allocation),
+                desc: description,
+                inner: image,
+                device: device.clone(),
+                usage: memory_usage,
+                do_not_destroy: false,
+            });
+        }
+
         //if we got the image successfuly, retrieve allocation information and ask the allocator for an allocation fitting the image
         let allocation = allocator
             .lock()
@@ -5,6 +5,8 @@ use super::{DescriptorSetLayout, PushConstant};
 
 pub(crate) mod compute;
 pub mod graphics;
+pub(crate) mod graphics_builder;
+pub(crate) mod library;
 
 pub struct PipelineLayout {
     pub device: Arc<Device>,
@@ -55,6 +57,7 @@ mod tests {
     use super::*;
     use compute::ComputePipeline;
     use graphics::GraphicsPipeline;
+    use library::GraphicsPipelineLibrary;
     use static_assertions::assert_impl_all;
 
     #[test]
@@ -62,5 +65,6 @@ mod tests {
         assert_impl_all!(PipelineLayout: Send, Sync);
         assert_impl_all!(ComputePipeline: Send, Sync);
         assert_impl_all!(GraphicsPipeline: Send, Sync);
+        assert_impl_all!(GraphicsPipelineLibrary: Send, Sync);
     }
 }
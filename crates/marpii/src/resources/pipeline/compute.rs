@@ -13,16 +13,34 @@ pub struct ComputePipeline {
     pub device: Arc<Device>,
     pub pipeline: ash::vk::Pipeline,
     pub layout: OoS<PipelineLayout>,
+    ///Push constant size the shader stage expects, if known through reflection.
+    pub push_constant_size: Option<u32>,
 }
 
 impl ComputePipeline {
+    ///Creates the pipeline for `stage`. If no `specialization_info` is given, the stage's own
+    /// [specialization](ShaderStage::specialization) is used.
+    ///
+    /// Fails with [PipelineError::ConflictingSpecialization] if both are set, since only one of them can be applied.
     pub fn new<'a>(
         device: &Arc<Device>,
         stage: &'a ShaderStage,
         specialization_info: Option<&'a ash::vk::SpecializationInfo>,
         layout: impl Into<OoS<PipelineLayout>> + 'static,
     ) -> Result<Self, PipelineError> {
+        if specialization_info.is_some() && !stage.specialization.is_empty() {
+            return Err(PipelineError::ConflictingSpecialization);
+        }
+
         let layout = layout.into();
+        //fall back to the stage's own specialization
+        let map_entries = stage.specialization.map_entries();
+        let stage_specialization = stage.specialization.as_info(&map_entries);
+        let specialization_info = specialization_info.or(if stage.specialization.is_empty() {
+            None
+        } else {
+            Some(&stage_specialization)
+        });
         let create_info = ash::vk::ComputePipelineCreateInfo::default()
             .stage(stage.as_create_info(specialization_info))
             .layout(layout.layout);
@@ -50,6 +68,7 @@ impl ComputePipeline {
             device: device.clone(),
             pipeline,
             layout,
+            push_constant_size: stage.push_constant_size(),
         })
     }
 }
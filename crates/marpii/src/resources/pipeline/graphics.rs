@@ -43,6 +43,8 @@ pub struct GraphicsPipeline {
     /// if not using dynamic-rendering, this is some renderpass which is kept alive.
     //TODO: Change to marpii renderpass if such a thing is created at some point.
     pub renderpass: Option<RenderPass>,
+    ///Biggest push constant size any of the shader stages expects, if known through reflection.
+    pub push_constant_size: Option<u32>,
 }
 
 impl GraphicsPipeline {
@@ -82,11 +84,14 @@ impl GraphicsPipeline {
             pipeline,
             layout,
             renderpass: Some(renderpass),
+            push_constant_size: None,
         })
     }
 
     ///Creates a new `DynamicRendering` pipeline where the attachment images
     /// are defined through the order in `color_formats` and `depth_format`.
+    ///
+    /// Each stage is specialized with its own [specialization](ShaderStage::specialization).
     pub fn new_dynamic_pipeline(
         device: &Arc<Device>,
         create_info: ash::vk::GraphicsPipelineCreateInfo<'_>,
@@ -109,47 +114,110 @@ impl GraphicsPipeline {
             })
             .color_attachment_formats(color_formats);
 
-        let stages = shader_stages
-            .iter()
-            .map(|s| s.as_create_info(None))
-            .collect::<Vec<_>>();
-
-        //make renderpass nullptr
+        //make renderpass nullptr and push dynamic extension
         let create_info = create_info
-            .stages(&stages)
             .render_pass(vk::RenderPass::null())
-            .layout(layout.layout)
-            //now push dynamic extension
             .push_next(&mut pipline_rendering_create_info);
 
-        let mut pipelines = unsafe {
-            match device.inner.create_graphics_pipelines(
-                ash::vk::PipelineCache::null(),
-                core::slice::from_ref(&create_info),
-                None,
-            ) {
-                Ok(p) => p,
-                Err((_plines, err)) => {
-                    return Err(err.into());
-                }
-            }
-        };
+        Self::new_with_stages(device, create_info, layout, shader_stages, None)
+    }
 
-        if pipelines.len() != 1 {
-            return Err(PipelineError::Allocation);
-        }
+    ///Creates a pipeline for `subpass` of `renderpass`. In contrast to [new](Self::new) the shader stages are set
+    /// from `shader_stages`, each specialized with its own [specialization](ShaderStage::specialization).
+    pub fn new_with_render_pass(
+        device: &Arc<Device>,
+        create_info: ash::vk::GraphicsPipelineCreateInfo<'_>,
+        layout: impl Into<OoS<PipelineLayout>>,
+        shader_stages: &[ShaderStage],
+        renderpass: RenderPass,
+        subpass: u32,
+    ) -> Result<Self, PipelineError> {
+        let create_info = create_info.render_pass(renderpass.inner).subpass(subpass);
+        Self::new_with_stages(
+            device,
+            create_info,
+            layout.into(),
+            shader_stages,
+            Some(renderpass),
+        )
+    }
 
-        let pipeline = pipelines.remove(0);
+    fn new_with_stages(
+        device: &Arc<Device>,
+        create_info: ash::vk::GraphicsPipelineCreateInfo<'_>,
+        layout: OoS<PipelineLayout>,
+        shader_stages: &[ShaderStage],
+        renderpass: Option<RenderPass>,
+    ) -> Result<Self, PipelineError> {
+        let pipeline = create_pipeline(
+            device,
+            create_info.layout(layout.layout),
+            &shader_stages.iter().collect::<Vec<_>>(),
+        )?;
 
         Ok(GraphicsPipeline {
             device: device.clone(),
             pipeline,
             layout,
-            renderpass: None,
+            renderpass,
+            push_constant_size: shader_stages
+                .iter()
+                .filter_map(|s| s.push_constant_size())
+                .max(),
         })
     }
 }
 
+///Creates a single graphics pipeline from `create_info` with `shader_stages`, each specialized with its own
+/// [specialization](ShaderStage::specialization).
+pub(crate) fn create_pipeline(
+    device: &Arc<Device>,
+    create_info: ash::vk::GraphicsPipelineCreateInfo<'_>,
+    shader_stages: &[&ShaderStage],
+) -> Result<vk::Pipeline, PipelineError> {
+    let map_entries = shader_stages
+        .iter()
+        .map(|s| s.specialization.map_entries())
+        .collect::<Vec<_>>();
+    let specialization_infos = shader_stages
+        .iter()
+        .zip(map_entries.iter())
+        .map(|(s, entries)| s.specialization.as_info(entries))
+        .collect::<Vec<_>>();
+    let stages = shader_stages
+        .iter()
+        .zip(specialization_infos.iter())
+        .map(|(s, info)| {
+            s.as_create_info(if s.specialization.is_empty() {
+                None
+            } else {
+                Some(info)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let create_info = create_info.stages(&stages);
+
+    let mut pipelines = unsafe {
+        match device.inner.create_graphics_pipelines(
+            ash::vk::PipelineCache::null(),
+            core::slice::from_ref(&create_info),
+            None,
+        ) {
+            Ok(p) => p,
+            Err((_plines, err)) => {
+                return Err(err.into());
+            }
+        }
+    };
+
+    if pipelines.len() != 1 {
+        return Err(PipelineError::Allocation);
+    }
+
+    Ok(pipelines.remove(0))
+}
+
 impl Drop for GraphicsPipeline {
     fn drop(&mut self) {
         unsafe { self.device.inner.destroy_pipeline(self.pipeline, None) }
@@ -0,0 +1,630 @@
+use std::sync::Arc;
+
+use super::{
+    PipelineLayout,
+    graphics::{GraphicsPipeline, RenderPass},
+    library::{GraphicsPipelineLibrary, LibraryKey, LibraryPartState, library_part_of_stage},
+};
+use crate::ash::vk;
+use crate::context::Device;
+use crate::error::PipelineError;
+use crate::resources::ShaderStage;
+use oos::OoS;
+use smallvec::SmallVec;
+
+///Vulkan format of a single vertex attribute type.
+pub trait VertexAttributeFormat {
+    const FORMAT: vk::Format;
+}
+
+macro_rules! impl_attribute_format {
+    ($($ty:ty => $format:ident),* $(,)?) => {
+        $(impl VertexAttributeFormat for $ty {
+            const FORMAT: vk::Format = vk::Format::$format;
+        })*
+    };
+}
+
+impl_attribute_format!(
+    f32 => R32_SFLOAT,
+    [f32; 2] => R32G32_SFLOAT,
+    [f32; 3] => R32G32B32_SFLOAT,
+    [f32; 4] => R32G32B32A32_SFLOAT,
+    u32 => R32_UINT,
+    [u32; 2] => R32G32_UINT,
+    [u32; 3] => R32G32B32_UINT,
+    [u32; 4] => R32G32B32A32_UINT,
+    i32 => R32_SINT,
+    [i32; 2] => R32G32_SINT,
+    [i32; 3] => R32G32B32_SINT,
+    [i32; 4] => R32G32B32A32_SINT,
+    [u16; 2] => R16G16_UINT,
+    [u16; 4] => R16G16B16A16_UINT,
+    //NOTE: byte vectors are usually colors, therefore they are normalized.
+    [u8; 4] => R8G8B8A8_UNORM,
+);
+
+///Vertex type that can be read from a vertex buffer. Usually implemented via [impl_vertex](crate::impl_vertex) for a
+/// `#[repr(C)]` struct.
+pub trait Vertex: Copy + 'static {
+    ///Format and byte offset of each attribute, in location order.
+    fn attributes() -> Vec<(vk::Format, u32)>;
+}
+
+///Returns the format of the field `field` points to. Used by [impl_vertex](crate::impl_vertex).
+#[doc(hidden)]
+pub fn field_format<V, F: VertexAttributeFormat>(_field: fn(&V) -> &F) -> vk::Format {
+    F::FORMAT
+}
+
+///Implements [Vertex](crate::resources::Vertex) for a `#[repr(C)]` struct. The listed fields become the attributes at location
+/// 0, 1, 2... in the given order. Each field type must implement [VertexAttributeFormat](crate::resources::VertexAttributeFormat).
+///
+/// ```ignore
+/// #[repr(C)]
+/// #[derive(Clone, Copy)]
+/// struct MyVertex {
+///     position: [f32; 3],
+///     uv: [f32; 2],
+///     color: [u8; 4],
+/// }
+///
+/// marpii::impl_vertex!(MyVertex { position, uv, color });
+/// ```
+#[macro_export]
+macro_rules! impl_vertex {
+    ($ty:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::resources::Vertex for $ty {
+            fn attributes() -> Vec<($crate::ash::vk::Format, u32)> {
+                vec![$(
+                    (
+                        $crate::resources::field_format(|v: &$ty| &v.$field),
+                        core::mem::offset_of!($ty, $field) as u32,
+                    )
+                ),*]
+            }
+        }
+    };
+}
+
+///Common color blend configurations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendPreset {
+    ///No blending, the fragment overwrites the attachment.
+    Opaque,
+    ///`src * src_alpha + dst * (1 - src_alpha)`.
+    AlphaBlend,
+    ///`src + dst * (1 - src_alpha)`, for colors that are already multiplied by their alpha.
+    PremultipliedAlpha,
+    ///`src + dst`.
+    Additive,
+}
+
+impl BlendPreset {
+    pub fn attachment_state(&self) -> vk::PipelineColorBlendAttachmentState {
+        let (src, dst, src_alpha, dst_alpha) = match self {
+            BlendPreset::Opaque => {
+                return vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(false)
+                    .color_write_mask(vk::ColorComponentFlags::RGBA);
+            }
+            BlendPreset::AlphaBlend => (
+                vk::BlendFactor::SRC_ALPHA,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ZERO,
+            ),
+            BlendPreset::PremultipliedAlpha => (
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            ),
+            BlendPreset::Additive => (
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE,
+                vk::BlendFactor::ONE,
+            ),
+        };
+
+        vk::PipelineColorBlendAttachmentState::default()
+            .src_color_blend_factor(src)
+            .dst_color_blend_factor(dst)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(src_alpha)
+            .dst_alpha_blend_factor(dst_alpha)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .color_write_mask(vk::ColorComponentFlags::RGBA)
+            .blend_enable(true)
+    }
+}
+
+///Builder for [GraphicsPipeline]s that sets sane defaults for all fixed function state.
+///
+/// By default the pipeline draws filled triangle lists without vertex input and culling, uses a single sample, and
+/// has dynamic viewport and scissor state (one each). Depth testing (`LESS`, with writes) is enabled if a depth attachment is set.
+/// Pipelines built [for a render pass](Self::build_for_render_pass) always enable the configured depth test, since the subpass
+/// decides whether there is a depth attachment.
+///
+/// ```ignore
+/// let pipeline = GraphicsPipelineBuilder::new()
+///     .with_stage(vertex_stage)
+///     .with_stage(fragment_stage)
+///     .with_vertex_buffer::<MyVertex>(vk::VertexInputRate::VERTEX)
+///     .with_color_attachment(vk::Format::R8G8B8A8_UNORM, BlendPreset::AlphaBlend)
+///     .with_depth_attachment(vk::Format::D32_SFLOAT)
+///     .with_cull_mode(vk::CullModeFlags::BACK)
+///     .build(&device, layout)?;
+/// ```
+pub struct GraphicsPipelineBuilder {
+    stages: Vec<ShaderStage>,
+    vertex_bindings: SmallVec<[vk::VertexInputBindingDescription; 2]>,
+    vertex_attributes: SmallVec<[vk::VertexInputAttributeDescription; 8]>,
+    topology: vk::PrimitiveTopology,
+    primitive_restart: bool,
+    polygon_mode: vk::PolygonMode,
+    cull_mode: vk::CullModeFlags,
+    front_face: vk::FrontFace,
+    line_width: f32,
+    samples: vk::SampleCountFlags,
+    color_formats: SmallVec<[vk::Format; 4]>,
+    color_blend: SmallVec<[vk::PipelineColorBlendAttachmentState; 4]>,
+    depth_format: Option<vk::Format>,
+    ///Compare operation and depth write, `None` if depth testing is disabled.
+    depth_test: Option<(vk::CompareOp, bool)>,
+    dynamic_states: SmallVec<[vk::DynamicState; 4]>,
+}
+
+impl Default for GraphicsPipelineBuilder {
+    fn default() -> Self {
+        GraphicsPipelineBuilder {
+            stages: Vec::new(),
+            vertex_bindings: SmallVec::new(),
+            vertex_attributes: SmallVec::new(),
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            primitive_restart: false,
+            polygon_mode: vk::PolygonMode::FILL,
+            cull_mode: vk::CullModeFlags::NONE,
+            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+            line_width: 1.0,
+            samples: vk::SampleCountFlags::TYPE_1,
+            color_formats: SmallVec::new(),
+            color_blend: SmallVec::new(),
+            depth_format: None,
+            depth_test: Some((vk::CompareOp::LESS, true)),
+            dynamic_states: smallvec::smallvec![
+                vk::DynamicState::VIEWPORT,
+                vk::DynamicState::SCISSOR
+            ],
+        }
+    }
+}
+
+impl GraphicsPipelineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///Adds a shader stage. Each stage is specialized with its own [specialization](ShaderStage::specialization).
+    pub fn with_stage(mut self, stage: ShaderStage) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    ///Adds a vertex buffer binding of `V`s. The binding index is the number of previously added vertex buffers. The attributes
+    /// of `V` continue the locations of previously added vertex buffers.
+    pub fn with_vertex_buffer<V: Vertex>(mut self, input_rate: vk::VertexInputRate) -> Self {
+        let binding = self.vertex_bindings.len() as u32;
+        self.vertex_bindings.push(
+            vk::VertexInputBindingDescription::default()
+                .binding(binding)
+                .stride(std::mem::size_of::<V>() as u32)
+                .input_rate(input_rate),
+        );
+        for (format, offset) in V::attributes() {
+            let location = self.vertex_attributes.len() as u32;
+            self.vertex_attributes.push(
+                vk::VertexInputAttributeDescription::default()
+                    .location(location)
+                    .binding(binding)
+                    .format(format)
+                    .offset(offset),
+            );
+        }
+        self
+    }
+
+    pub fn with_topology(mut self, topology: vk::PrimitiveTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_primitive_restart(mut self, enable: bool) -> Self {
+        self.primitive_restart = enable;
+        self
+    }
+
+    pub fn with_polygon_mode(mut self, polygon_mode: vk::PolygonMode) -> Self {
+        self.polygon_mode = polygon_mode;
+        self
+    }
+
+    pub fn with_cull_mode(mut self, cull_mode: vk::CullModeFlags) -> Self {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn with_front_face(mut self, front_face: vk::FrontFace) -> Self {
+        self.front_face = front_face;
+        self
+    }
+
+    ///Sets the line width. Note that widths other than 1.0 need the `wideLines` feature.
+    pub fn with_line_width(mut self, width: f32) -> Self {
+        self.line_width = width;
+        self
+    }
+
+    pub fn with_samples(mut self, samples: vk::SampleCountFlags) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    ///Adds a color attachment at the next index that is blended according to `blend`.
+    pub fn with_color_attachment(mut self, format: vk::Format, blend: BlendPreset) -> Self {
+        self.color_formats.push(format);
+        self.color_blend.push(blend.attachment_state());
+        self
+    }
+
+    ///Same as [with_color_attachment](Self::with_color_attachment), but with a custom blend state.
+    pub fn with_color_attachment_blend(
+        mut self,
+        format: vk::Format,
+        blend: vk::PipelineColorBlendAttachmentState,
+    ) -> Self {
+        self.color_formats.push(format);
+        self.color_blend.push(blend);
+        self
+    }
+
+    ///Sets the depth (/stencil) attachment format.
+    pub fn with_depth_attachment(mut self, format: vk::Format) -> Self {
+        self.depth_format = Some(format);
+        self
+    }
+
+    ///Sets the depth test that is used if a depth attachment is set, or the pipeline is built for a render pass.
+    pub fn with_depth_test(mut self, compare_op: vk::CompareOp, write: bool) -> Self {
+        self.depth_test = Some((compare_op, write));
+        self
+    }
+
+    ///Disables the depth test, even if a depth attachment is set. Use it for render pass pipelines that should not depth test.
+    pub fn without_depth_test(mut self) -> Self {
+        self.depth_test = None;
+        self
+    }
+
+    ///Adds `state` to the dynamic states.
+    pub fn with_dynamic_state(mut self, state: vk::DynamicState) -> Self {
+        if !self.dynamic_states.contains(&state) {
+            self.dynamic_states.push(state);
+        }
+        self
+    }
+
+    ///Removes `state` from the dynamic states. Note that a static viewport or scissor has to be set via
+    /// [build_with](Self::build_with) in that case.
+    pub fn without_dynamic_state(mut self, state: vk::DynamicState) -> Self {
+        self.dynamic_states.retain(|s| *s != state);
+        self
+    }
+
+    pub fn color_formats(&self) -> &[vk::Format] {
+        &self.color_formats
+    }
+
+    pub fn depth_format(&self) -> Option<vk::Format> {
+        self.depth_format
+    }
+
+    ///Builds a `DynamicRendering` pipeline for the configured attachments.
+    pub fn build(
+        &self,
+        device: &Arc<Device>,
+        layout: impl Into<OoS<PipelineLayout>>,
+    ) -> Result<GraphicsPipeline, PipelineError> {
+        self.build_with(device, layout, |ci| ci)
+    }
+
+    ///Same as [build](Self::build), but lets `configure` change the final create info, for instance to set state the
+    /// builder doesn't cover.
+    pub fn build_with(
+        &self,
+        device: &Arc<Device>,
+        layout: impl Into<OoS<PipelineLayout>>,
+        configure: impl FnOnce(vk::GraphicsPipelineCreateInfo<'_>) -> vk::GraphicsPipelineCreateInfo<'_>,
+    ) -> Result<GraphicsPipeline, PipelineError> {
+        self.with_create_info(self.depth_format.is_some(), |create_info| {
+            GraphicsPipeline::new_dynamic_pipeline(
+                device,
+                configure(create_info),
+                layout,
+                &self.stages,
+                &self.color_formats,
+                self.depth_format,
+            )
+        })
+    }
+
+    ///Builds the pipeline for `subpass` of `renderpass`. The attachment formats are taken from the render pass, but the
+    /// color attachments must still be added to configure their blending.
+    ///
+    /// The depth attachment is taken from the subpass as well, so [with_depth_attachment](Self::with_depth_attachment) is not
+    /// needed. The configured depth test (`LESS` with writes by default) is always enabled. Use
+    /// [without_depth_test](Self::without_depth_test) if the subpass has a depth attachment that should not be tested against.
+    pub fn build_for_render_pass(
+        &self,
+        device: &Arc<Device>,
+        layout: impl Into<OoS<PipelineLayout>>,
+        renderpass: RenderPass,
+        subpass: u32,
+    ) -> Result<GraphicsPipeline, PipelineError> {
+        self.with_create_info(true, |create_info| {
+            GraphicsPipeline::new_with_render_pass(
+                device,
+                create_info,
+                layout,
+                &self.stages,
+                renderpass,
+                subpass,
+            )
+        })
+    }
+
+    ///Builds a [pipeline library](GraphicsPipelineLibrary) that contains the `parts` of this pipeline. Only the shader stages
+    /// that belong to `parts` are compiled. The libraries can be [linked](GraphicsPipelineLibrary::link) into a complete pipeline.
+    pub fn build_library(
+        &self,
+        device: &Arc<Device>,
+        layout: impl Into<OoS<PipelineLayout>>,
+        parts: vk::GraphicsPipelineLibraryFlagsEXT,
+    ) -> Result<GraphicsPipelineLibrary, PipelineError> {
+        let stages = self
+            .stages
+            .iter()
+            .filter(|s| parts.intersects(library_part_of_stage(s.stage)))
+            .collect::<Vec<_>>();
+        self.with_create_info(self.depth_format.is_some(), |create_info| {
+            GraphicsPipelineLibrary::new(
+                device,
+                create_info,
+                layout,
+                &stages,
+                parts,
+                &self.color_formats,
+                self.depth_format,
+            )
+        })
+    }
+
+    ///Collects all state that influences the library `part`. Used to deduplicate libraries in the [PipelineLibraryCache](super::library::PipelineLibraryCache).
+    ///
+    /// Shader stages are identified by their module handle, entry point and specialization.
+    pub(crate) fn library_key(&self, part: vk::GraphicsPipelineLibraryFlagsEXT) -> LibraryKey {
+        let stages = self
+            .stages
+            .iter()
+            .filter(|s| part.intersects(library_part_of_stage(s.stage)))
+            .map(|s| {
+                (
+                    *s.inner(),
+                    s.stage,
+                    s.entry_name.clone(),
+                    s.specialization.clone(),
+                )
+            })
+            .collect();
+
+        let state = match part {
+            vk::GraphicsPipelineLibraryFlagsEXT::VERTEX_INPUT_INTERFACE => {
+                LibraryPartState::VertexInput {
+                    bindings: self
+                        .vertex_bindings
+                        .iter()
+                        .map(|b| (b.binding, b.stride, b.input_rate))
+                        .collect(),
+                    attributes: self
+                        .vertex_attributes
+                        .iter()
+                        .map(|a| (a.location, a.binding, a.format, a.offset))
+                        .collect(),
+                    topology: self.topology,
+                    primitive_restart: self.primitive_restart,
+                }
+            }
+            vk::GraphicsPipelineLibraryFlagsEXT::PRE_RASTERIZATION_SHADERS => {
+                LibraryPartState::PreRasterization {
+                    polygon_mode: self.polygon_mode,
+                    cull_mode: self.cull_mode,
+                    front_face: self.front_face,
+                    line_width: self.line_width.to_bits(),
+                }
+            }
+            vk::GraphicsPipelineLibraryFlagsEXT::FRAGMENT_SHADER => {
+                LibraryPartState::FragmentShader {
+                    samples: self.samples,
+                    depth_format: self.depth_format,
+                    depth_test: self.depth_test,
+                }
+            }
+            _ => LibraryPartState::FragmentOutput {
+                samples: self.samples,
+                depth_format: self.depth_format,
+                color_attachments: self
+                    .color_formats
+                    .iter()
+                    .zip(self.color_blend.iter())
+                    .map(|(format, blend)| {
+                        (
+                            *format,
+                            (
+                                blend.blend_enable,
+                                blend.src_color_blend_factor,
+                                blend.dst_color_blend_factor,
+                                blend.color_blend_op,
+                                blend.src_alpha_blend_factor,
+                                blend.dst_alpha_blend_factor,
+                                blend.alpha_blend_op,
+                                blend.color_write_mask,
+                            ),
+                        )
+                    })
+                    .collect(),
+            },
+        };
+
+        LibraryKey {
+            part,
+            dynamic_states: self.dynamic_states.clone(),
+            stages,
+            state,
+        }
+    }
+
+    ///Returns the depth/stencil state. Depth testing is only enabled if `depth_attachment` is true.
+    fn depth_stencil_state(
+        &self,
+        depth_attachment: bool,
+    ) -> vk::PipelineDepthStencilStateCreateInfo<'static> {
+        match (depth_attachment, self.depth_test) {
+            (true, Some((compare_op, write))) => vk::PipelineDepthStencilStateCreateInfo::default()
+                .depth_compare_op(compare_op)
+                .depth_write_enable(write)
+                .depth_test_enable(true)
+                .depth_bounds_test_enable(false)
+                .stencil_test_enable(false),
+            _ => vk::PipelineDepthStencilStateCreateInfo::default(),
+        }
+    }
+
+    ///Calls `f` with the create info of all fixed function state. `depth_attachment` signals whether the pipeline renders
+    /// to a depth attachment, see [depth_stencil_state](Self::depth_stencil_state).
+    fn with_create_info<R>(
+        &self,
+        depth_attachment: bool,
+        f: impl FnOnce(vk::GraphicsPipelineCreateInfo<'_>) -> R,
+    ) -> R {
+        let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_binding_descriptions(&self.vertex_bindings)
+            .vertex_attribute_descriptions(&self.vertex_attributes);
+
+        let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::default()
+            .primitive_restart_enable(self.primitive_restart)
+            .topology(self.topology);
+
+        let rasterization_state = vk::PipelineRasterizationStateCreateInfo::default()
+            .cull_mode(self.cull_mode)
+            .depth_bias_enable(false)
+            .depth_clamp_enable(false)
+            .front_face(self.front_face)
+            .polygon_mode(self.polygon_mode)
+            .line_width(self.line_width);
+
+        let multisample_state =
+            vk::PipelineMultisampleStateCreateInfo::default().rasterization_samples(self.samples);
+
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo::default()
+            .blend_constants([0.0; 4])
+            .attachments(&self.color_blend);
+
+        let depth_stencil_state = self.depth_stencil_state(depth_attachment);
+
+        let dynamic_state =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&self.dynamic_states);
+
+        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+
+        f(vk::GraphicsPipelineCreateInfo::default()
+            .vertex_input_state(&vertex_input_state)
+            .input_assembly_state(&input_assembly_state)
+            .rasterization_state(&rasterization_state)
+            .multisample_state(&multisample_state)
+            .color_blend_state(&color_blend_state)
+            .depth_stencil_state(&depth_stencil_state)
+            .dynamic_state(&dynamic_state)
+            .viewport_state(&viewport_state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct TestVertex {
+        position: [f32; 3],
+        uv: [f32; 2],
+        color: [u8; 4],
+    }
+
+    crate::impl_vertex!(TestVertex {
+        position,
+        uv,
+        color
+    });
+
+    #[test]
+    fn vertex_attributes() {
+        assert_eq!(
+            TestVertex::attributes().as_slice(),
+            &[
+                (vk::Format::R32G32B32_SFLOAT, 0),
+                (vk::Format::R32G32_SFLOAT, 12),
+                (vk::Format::R8G8B8A8_UNORM, 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn vertex_locations_continue_across_bindings() {
+        let builder = GraphicsPipelineBuilder::new()
+            .with_vertex_buffer::<TestVertex>(vk::VertexInputRate::VERTEX)
+            .with_vertex_buffer::<TestVertex>(vk::VertexInputRate::INSTANCE);
+        assert_eq!(builder.vertex_bindings.len(), 2);
+        assert_eq!(builder.vertex_bindings[1].stride, 24);
+        assert_eq!(builder.vertex_attributes[3].location, 3);
+        assert_eq!(builder.vertex_attributes[3].binding, 1);
+    }
+
+    #[test]
+    fn depth_test_follows_attachment() {
+        let builder = GraphicsPipelineBuilder::new();
+        assert_eq!(
+            builder.depth_stencil_state(false).depth_test_enable,
+            vk::FALSE
+        );
+
+        //render pass pipelines always pass `true`
+        let state = builder.depth_stencil_state(true);
+        assert_eq!(state.depth_test_enable, vk::TRUE);
+        assert_eq!(state.depth_write_enable, vk::TRUE);
+        assert_eq!(state.depth_compare_op, vk::CompareOp::LESS);
+
+        let builder = builder.with_depth_test(vk::CompareOp::GREATER_OR_EQUAL, false);
+        let state = builder.depth_stencil_state(true);
+        assert_eq!(state.depth_write_enable, vk::FALSE);
+        assert_eq!(state.depth_compare_op, vk::CompareOp::GREATER_OR_EQUAL);
+
+        let builder = builder.without_depth_test();
+        assert_eq!(
+            builder.depth_stencil_state(true).depth_test_enable,
+            vk::FALSE
+        );
+    }
+}
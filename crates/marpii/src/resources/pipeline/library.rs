@@ -0,0 +1,346 @@
+use std::{ffi::CString, sync::Arc};
+
+use super::{
+    PipelineLayout,
+    graphics::{GraphicsPipeline, create_pipeline},
+    graphics_builder::GraphicsPipelineBuilder,
+};
+use crate::ash::vk;
+use crate::context::Device;
+use crate::error::PipelineError;
+use crate::resources::{ShaderStage, Specialization};
+use ahash::AHashMap;
+use oos::OoS;
+use smallvec::SmallVec;
+
+///All four parts of a graphics pipeline, in the order they are linked.
+const LIBRARY_PARTS: [vk::GraphicsPipelineLibraryFlagsEXT; 4] = [
+    vk::GraphicsPipelineLibraryFlagsEXT::VERTEX_INPUT_INTERFACE,
+    vk::GraphicsPipelineLibraryFlagsEXT::PRE_RASTERIZATION_SHADERS,
+    vk::GraphicsPipelineLibraryFlagsEXT::FRAGMENT_SHADER,
+    vk::GraphicsPipelineLibraryFlagsEXT::FRAGMENT_OUTPUT_INTERFACE,
+];
+
+///Returns the library part a shader `stage` is compiled in.
+pub(crate) fn library_part_of_stage(
+    stage: vk::ShaderStageFlags,
+) -> vk::GraphicsPipelineLibraryFlagsEXT {
+    if stage == vk::ShaderStageFlags::FRAGMENT {
+        vk::GraphicsPipelineLibraryFlagsEXT::FRAGMENT_SHADER
+    } else {
+        vk::GraphicsPipelineLibraryFlagsEXT::PRE_RASTERIZATION_SHADERS
+    }
+}
+
+///Identifies a library part by all state that influences it, see [GraphicsPipelineBuilder::library_key].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct LibraryKey {
+    pub(crate) part: vk::GraphicsPipelineLibraryFlagsEXT,
+    pub(crate) dynamic_states: SmallVec<[vk::DynamicState; 4]>,
+    ///`(module, stage, entry point, specialization)` of each shader stage that is compiled into the part.
+    pub(crate) stages: SmallVec<
+        [(
+            vk::ShaderModule,
+            vk::ShaderStageFlags,
+            CString,
+            Specialization,
+        ); 2],
+    >,
+    pub(crate) state: LibraryPartState,
+}
+
+///Fixed function state of a single library part.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum LibraryPartState {
+    VertexInput {
+        ///`(binding, stride, input_rate)` of each vertex buffer.
+        bindings: SmallVec<[(u32, u32, vk::VertexInputRate); 2]>,
+        ///`(location, binding, format, offset)` of each attribute.
+        attributes: SmallVec<[(u32, u32, vk::Format, u32); 8]>,
+        topology: vk::PrimitiveTopology,
+        primitive_restart: bool,
+    },
+    PreRasterization {
+        polygon_mode: vk::PolygonMode,
+        cull_mode: vk::CullModeFlags,
+        front_face: vk::FrontFace,
+        ///Bits of the `f32` line width.
+        line_width: u32,
+    },
+    FragmentShader {
+        samples: vk::SampleCountFlags,
+        depth_format: Option<vk::Format>,
+        depth_test: Option<(vk::CompareOp, bool)>,
+    },
+    FragmentOutput {
+        samples: vk::SampleCountFlags,
+        depth_format: Option<vk::Format>,
+        ///Format and blend state (`blend_enable`, color factors and op, alpha factors and op, write mask) of each color attachment.
+        color_attachments: SmallVec<[(vk::Format, BlendState); 4]>,
+    },
+}
+
+type BlendState = (
+    vk::Bool32,
+    vk::BlendFactor,
+    vk::BlendFactor,
+    vk::BlendOp,
+    vk::BlendFactor,
+    vk::BlendFactor,
+    vk::BlendOp,
+    vk::ColorComponentFlags,
+);
+
+///Part of a graphics pipeline, created via `VK_EXT_graphics_pipeline_library`. Usually built with
+/// [GraphicsPipelineBuilder::build_library] and linked into a [GraphicsPipeline] via [link](Self::link).
+///
+/// Libraries are created with `RETAIN_LINK_TIME_OPTIMIZATION_INFO`, so they can be linked with and without link time optimization.
+pub struct GraphicsPipelineLibrary {
+    pub device: Arc<Device>,
+    pub pipeline: vk::Pipeline,
+    pub layout: OoS<PipelineLayout>,
+    ///The parts of the pipeline this library contains.
+    pub parts: vk::GraphicsPipelineLibraryFlagsEXT,
+    ///Biggest push constant size any of the shader stages expects, if known through reflection.
+    pub push_constant_size: Option<u32>,
+}
+
+impl GraphicsPipelineLibrary {
+    ///Creates a library of `parts` from `create_info`. State that does not belong to `parts` is ignored, the same is true for
+    /// the attachment formats if neither the fragment shader nor fragment output part is created.
+    ///
+    /// Panics if `VK_EXT_graphics_pipeline_library` is not enabled.
+    pub fn new(
+        device: &Arc<Device>,
+        create_info: vk::GraphicsPipelineCreateInfo<'_>,
+        layout: impl Into<OoS<PipelineLayout>>,
+        shader_stages: &[&ShaderStage],
+        parts: vk::GraphicsPipelineLibraryFlagsEXT,
+        color_formats: &[vk::Format],
+        depth_format: Option<vk::Format>,
+    ) -> Result<Self, PipelineError> {
+        assert!(
+            device.extension_enabled_cstr(ash::ext::graphics_pipeline_library::NAME),
+            "GraphicsPipelineLibraryEXT extension not activated!"
+        );
+        let layout = layout.into();
+
+        let mut library_create_info =
+            vk::GraphicsPipelineLibraryCreateInfoEXT::default().flags(parts);
+        let mut pipline_rendering_create_info = vk::PipelineRenderingCreateInfo::default()
+            .depth_attachment_format(depth_format.unwrap_or(vk::Format::UNDEFINED))
+            .color_attachment_formats(color_formats);
+
+        let create_info = create_info
+            .flags(
+                vk::PipelineCreateFlags::LIBRARY_KHR
+                    | vk::PipelineCreateFlags::RETAIN_LINK_TIME_OPTIMIZATION_INFO_EXT,
+            )
+            .render_pass(vk::RenderPass::null())
+            .layout(layout.layout)
+            .push_next(&mut library_create_info)
+            .push_next(&mut pipline_rendering_create_info);
+
+        let pipeline = create_pipeline(device, create_info, shader_stages)?;
+
+        Ok(GraphicsPipelineLibrary {
+            device: device.clone(),
+            pipeline,
+            layout,
+            parts,
+            push_constant_size: shader_stages
+                .iter()
+                .filter_map(|s| s.push_constant_size())
+                .max(),
+        })
+    }
+
+    ///Links `libraries` into a complete pipeline. Together they must contain all four parts exactly once.
+    ///
+    /// If `link_time_optimization` is set, the driver may optimize across the parts. This is slower to link, but yields a
+    /// faster pipeline. A common pattern is to link without optimization first, and replace the pipeline with an optimized one later.
+    pub fn link(
+        device: &Arc<Device>,
+        libraries: &[&GraphicsPipelineLibrary],
+        layout: impl Into<OoS<PipelineLayout>>,
+        link_time_optimization: bool,
+    ) -> Result<GraphicsPipeline, PipelineError> {
+        let layout = layout.into();
+        let handles = libraries.iter().map(|l| l.pipeline).collect::<Vec<_>>();
+        let mut library_info = vk::PipelineLibraryCreateInfoKHR::default().libraries(&handles);
+
+        let create_info = vk::GraphicsPipelineCreateInfo::default()
+            .flags(if link_time_optimization {
+                vk::PipelineCreateFlags::LINK_TIME_OPTIMIZATION_EXT
+            } else {
+                vk::PipelineCreateFlags::empty()
+            })
+            .layout(layout.layout)
+            .push_next(&mut library_info);
+
+        let pipeline = create_pipeline(device, create_info, &[])?;
+
+        Ok(GraphicsPipeline {
+            device: device.clone(),
+            pipeline,
+            layout,
+            renderpass: None,
+            push_constant_size: libraries.iter().filter_map(|l| l.push_constant_size).max(),
+        })
+    }
+}
+
+impl Drop for GraphicsPipelineLibrary {
+    fn drop(&mut self) {
+        unsafe { self.device.inner.destroy_pipeline(self.pipeline, None) }
+    }
+}
+
+///Caches the [libraries](GraphicsPipelineLibrary) of the pipelines it creates. Pipelines that share parts with an already
+/// created pipeline (for instance the same vertex shader, but a different fragment shader or blend state) only compile the parts
+/// that differ, and are then linked.
+///
+/// Shader stages are identified by their module handle. Call [clear](Self::clear) if a module is destroyed while the cache
+/// is still in use, since a new module might reuse the handle.
+pub struct PipelineLibraryCache {
+    device: Arc<Device>,
+    layout: Arc<PipelineLayout>,
+    link_time_optimization: bool,
+    libraries: AHashMap<LibraryKey, Arc<GraphicsPipelineLibrary>>,
+    pipelines: AHashMap<[LibraryKey; 4], Arc<GraphicsPipeline>>,
+}
+
+impl PipelineLibraryCache {
+    ///Creates the cache. All libraries and pipelines use `layout`. See [GraphicsPipelineLibrary::link] for `link_time_optimization`.
+    pub fn new(
+        device: &Arc<Device>,
+        layout: Arc<PipelineLayout>,
+        link_time_optimization: bool,
+    ) -> Self {
+        PipelineLibraryCache {
+            device: device.clone(),
+            layout,
+            link_time_optimization,
+            libraries: AHashMap::default(),
+            pipelines: AHashMap::default(),
+        }
+    }
+
+    ///Returns the pipeline described by `builder`. Missing libraries are built and linked, existing ones are reused.
+    pub fn get_or_create(
+        &mut self,
+        builder: &GraphicsPipelineBuilder,
+    ) -> Result<Arc<GraphicsPipeline>, PipelineError> {
+        let keys = LIBRARY_PARTS.map(|part| builder.library_key(part));
+        if let Some(pipeline) = self.pipelines.get(&keys) {
+            return Ok(pipeline.clone());
+        }
+
+        let mut libraries = Vec::with_capacity(LIBRARY_PARTS.len());
+        for (part, key) in LIBRARY_PARTS.iter().zip(keys.iter()) {
+            let library = if let Some(library) = self.libraries.get(key) {
+                library.clone()
+            } else {
+                #[cfg(feature = "logging")]
+                log::trace!("Building pipeline library {:?}", part);
+                let library = Arc::new(builder.build_library(
+                    &self.device,
+                    OoS::new_shared(self.layout.clone()),
+                    *part,
+                )?);
+                self.libraries.insert(key.clone(), library.clone());
+                library
+            };
+            libraries.push(library);
+        }
+
+        let pipeline = Arc::new(GraphicsPipelineLibrary::link(
+            &self.device,
+            &libraries.iter().map(|l| l.as_ref()).collect::<Vec<_>>(),
+            OoS::new_shared(self.layout.clone()),
+            self.link_time_optimization,
+        )?);
+        self.pipelines.insert(keys, pipeline.clone());
+        Ok(pipeline)
+    }
+
+    ///Number of cached libraries over all parts.
+    pub fn library_count(&self) -> usize {
+        self.libraries.len()
+    }
+
+    ///Number of cached, linked pipelines.
+    pub fn pipeline_count(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    ///Drops all cached libraries and pipelines. Pipelines that are still in use elsewhere stay alive.
+    pub fn clear(&mut self) {
+        self.libraries.clear();
+        self.pipelines.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::BlendPreset;
+
+    #[test]
+    fn stage_parts() {
+        assert_eq!(
+            library_part_of_stage(vk::ShaderStageFlags::VERTEX),
+            vk::GraphicsPipelineLibraryFlagsEXT::PRE_RASTERIZATION_SHADERS
+        );
+        assert_eq!(
+            library_part_of_stage(vk::ShaderStageFlags::FRAGMENT),
+            vk::GraphicsPipelineLibraryFlagsEXT::FRAGMENT_SHADER
+        );
+    }
+
+    #[test]
+    fn library_key_per_part() {
+        let base = GraphicsPipelineBuilder::new()
+            .with_color_attachment(vk::Format::R8G8B8A8_UNORM, BlendPreset::AlphaBlend);
+        let keys = LIBRARY_PARTS.map(|part| base.library_key(part));
+        //identical state yields identical keys
+        assert_eq!(keys, LIBRARY_PARTS.map(|part| base.library_key(part)));
+        //keys of different parts never collide
+        for (a, b) in keys.iter().zip(keys.iter().skip(1)) {
+            assert_ne!(a, b);
+        }
+
+        //only the output interface depends on the blend state
+        let blended = GraphicsPipelineBuilder::new()
+            .with_color_attachment(vk::Format::R8G8B8A8_UNORM, BlendPreset::Additive);
+        let blended_keys = LIBRARY_PARTS.map(|part| blended.library_key(part));
+        assert_eq!(keys[..3], blended_keys[..3]);
+        assert_ne!(keys[3], blended_keys[3]);
+
+        //only the pre rasterization part depends on culling
+        let culled = GraphicsPipelineBuilder::new()
+            .with_color_attachment(vk::Format::R8G8B8A8_UNORM, BlendPreset::AlphaBlend)
+            .with_cull_mode(vk::CullModeFlags::BACK);
+        let culled_keys = LIBRARY_PARTS.map(|part| culled.library_key(part));
+        assert_eq!(keys[0], culled_keys[0]);
+        assert_ne!(keys[1], culled_keys[1]);
+        assert_eq!(keys[2..], culled_keys[2..]);
+    }
+
+    #[test]
+    fn library_key_depth_state() {
+        let base = GraphicsPipelineBuilder::new().with_depth_attachment(vk::Format::D32_SFLOAT);
+        let part = vk::GraphicsPipelineLibraryFlagsEXT::FRAGMENT_SHADER;
+        assert_ne!(
+            base.library_key(part),
+            GraphicsPipelineBuilder::new().library_key(part)
+        );
+        assert_ne!(
+            base.library_key(part),
+            GraphicsPipelineBuilder::new()
+                .with_depth_attachment(vk::Format::D32_SFLOAT)
+                .without_depth_test()
+                .library_key(part)
+        );
+    }
+}
@@ -0,0 +1,449 @@
+//! Runtime compilation of GLSL and WGSL sources to SPIR-V, based on [naga](https://github.com/gfx-rs/wgpu/tree/trunk/naga).
+//!
+//! GLSL sources are preprocessed before they are handed to naga. `#include "file"` and `#include <file>` directives are
+//! resolved relative to the including file, then in the compiler's include directories and finally in its
+//! [virtual files](ShaderCompiler::with_virtual_file). Errors are reported with the file and line they originate from, even if
+//! that is an included file.
+//!
+//! ```ignore
+//! let compiler = ShaderCompiler::new()
+//!     .with_virtual_file("shared.glsl", marpii_rmg_shared::SHARED_GLSL)
+//!     .with_define("WORKGROUP_SIZE", "64");
+//! let module = ShaderModule::new_from_source_file(&device, &compiler, "shaders/simulation.comp")?;
+//! ```
+//!
+//! Note that naga does not implement all of GLSL. Most notably runtime sized arrays of images and samplers (bindless
+//! descriptors) can't be expressed in its GLSL frontend.
+
+use crate::error::ShaderError;
+use ahash::AHashMap;
+use ash::vk;
+use std::path::{Path, PathBuf};
+
+///Includes nested deeper than this are assumed to be recursive.
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+///Language of a shader source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceLanguage {
+    ///GLSL source of a single `stage`. Supported are `VERTEX`, `FRAGMENT` and `COMPUTE`.
+    Glsl {
+        stage: vk::ShaderStageFlags,
+    },
+    Wgsl,
+}
+
+impl SourceLanguage {
+    ///Guesses the language based on the file extension. Follows `glslangValidator`'s convention of `.vert`, `.frag` and `.comp`
+    ///for GLSL, and `.wgsl` for WGSL.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let stage = match path.as_ref().extension()?.to_str()? {
+            "vert" => vk::ShaderStageFlags::VERTEX,
+            "frag" => vk::ShaderStageFlags::FRAGMENT,
+            "comp" => vk::ShaderStageFlags::COMPUTE,
+            "wgsl" => return Some(SourceLanguage::Wgsl),
+            _ => return None,
+        };
+        Some(SourceLanguage::Glsl { stage })
+    }
+}
+
+///A compilation error, located in the (possibly included) file it originates from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileError {
+    ///Path or virtual name of the file.
+    pub file: String,
+    ///1-based line, `None` if the error has no location, for instance when SPIR-V generation failed.
+    pub line: Option<u32>,
+    ///1-based column in bytes.
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+impl std::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "{}:{}:{}: {}", self.file, line, column, self.message)
+            }
+            (Some(line), None) => write!(f, "{}:{}: {}", self.file, line, self.message),
+            _ => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+///Source after include expansion. Keeps track of where each line came from.
+struct Expanded {
+    source: String,
+    ///`(file index, 1-based line)` for each line in `source`.
+    lines: Vec<(usize, u32)>,
+    files: Vec<String>,
+}
+
+impl Expanded {
+    fn file_index(&mut self, name: &str) -> usize {
+        if let Some(idx) = self.files.iter().position(|f| f == name) {
+            idx
+        } else {
+            self.files.push(name.to_owned());
+            self.files.len() - 1
+        }
+    }
+
+    ///Maps a location in the expanded source back to its origin.
+    fn error(&self, location: Option<naga::SourceLocation>, message: String) -> CompileError {
+        match location.and_then(|loc| {
+            self.lines
+                .get(loc.line_number.checked_sub(1)? as usize)
+                .map(|origin| (origin, loc.line_position))
+        }) {
+            Some(((file, line), column)) => CompileError {
+                file: self.files[*file].clone(),
+                line: Some(*line),
+                column: Some(column),
+                message,
+            },
+            None => CompileError {
+                file: self.files[0].clone(),
+                line: None,
+                column: None,
+                message,
+            },
+        }
+    }
+}
+
+///Compiles GLSL and WGSL sources to SPIR-V. See the [module level](self) documentation for the include resolution.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderCompiler {
+    include_dirs: Vec<PathBuf>,
+    virtual_files: AHashMap<String, String>,
+    defines: Vec<(String, String)>,
+}
+
+impl ShaderCompiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///Adds a directory that is searched for included files.
+    pub fn with_include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.include_dirs.push(dir.into());
+        self
+    }
+
+    ///Registers an in-memory file that can be included by `name`, for instance RMG's `shared.glsl`. Files on disk take precedence.
+    pub fn with_virtual_file(mut self, name: impl Into<String>, source: impl Into<String>) -> Self {
+        self.virtual_files.insert(name.into(), source.into());
+        self
+    }
+
+    ///Adds a `#define name value` to all GLSL sources.
+    pub fn with_define(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.defines.push((name.into(), value.into()));
+        self
+    }
+
+    ///Compiles the file at `path`. The language is derived from the file extension, see [SourceLanguage::from_path].
+    pub fn compile_file(&self, path: impl AsRef<Path>) -> Result<Vec<u32>, ShaderError> {
+        let path = path.as_ref();
+        let language = SourceLanguage::from_path(path).ok_or_else(|| {
+            ShaderError::Compile(CompileError {
+                file: path.display().to_string(),
+                line: None,
+                column: None,
+                message: "could not derive shader language from the file extension".to_owned(),
+            })
+        })?;
+        let source = std::fs::read_to_string(path)?;
+        self.compile_impl(
+            &source,
+            &path.display().to_string(),
+            path.parent(),
+            language,
+        )
+    }
+
+    ///Compiles `source`. `name` is used in error messages. Relative includes are resolved against the include directories
+    ///and virtual files only.
+    pub fn compile(
+        &self,
+        source: &str,
+        name: &str,
+        language: SourceLanguage,
+    ) -> Result<Vec<u32>, ShaderError> {
+        self.compile_impl(source, name, None, language)
+    }
+
+    fn compile_impl(
+        &self,
+        source: &str,
+        name: &str,
+        dir: Option<&Path>,
+        language: SourceLanguage,
+    ) -> Result<Vec<u32>, ShaderError> {
+        #[cfg(feature = "logging")]
+        log::trace!("Compiling shader {name}");
+
+        let mut expanded = Expanded {
+            source: String::with_capacity(source.len()),
+            lines: Vec::new(),
+            files: Vec::new(),
+        };
+
+        let module = match language {
+            SourceLanguage::Glsl { stage } => {
+                let naga_stage = match stage {
+                    vk::ShaderStageFlags::VERTEX => naga::ShaderStage::Vertex,
+                    vk::ShaderStageFlags::FRAGMENT => naga::ShaderStage::Fragment,
+                    vk::ShaderStageFlags::COMPUTE => naga::ShaderStage::Compute,
+                    _ => {
+                        return Err(ShaderError::Compile(CompileError {
+                            file: name.to_owned(),
+                            line: None,
+                            column: None,
+                            message: format!("unsupported GLSL stage {stage:?}"),
+                        }));
+                    }
+                };
+                self.expand(source, name, dir, &mut expanded, 0)?;
+
+                let mut options = naga::front::glsl::Options::from(naga_stage);
+                options.defines.extend(self.defines.iter().cloned());
+                naga::front::glsl::Frontend::default()
+                    .parse(&options, &expanded.source)
+                    .map_err(|errors| {
+                        #[cfg(feature = "logging")]
+                        for error in errors.errors.iter().skip(1) {
+                            log::error!(
+                                "{}",
+                                expanded.error(error.location(&expanded.source), error.to_string())
+                            );
+                        }
+
+                        ShaderError::Compile(match errors.errors.first() {
+                            Some(error) => {
+                                expanded.error(error.location(&expanded.source), error.to_string())
+                            }
+                            None => CompileError {
+                                file: name.to_owned(),
+                                line: None,
+                                column: None,
+                                message: "GLSL parsing failed without reporting an error"
+                                    .to_owned(),
+                            },
+                        })
+                    })?
+            }
+            SourceLanguage::Wgsl => {
+                //WGSL has no preprocessor, so the source maps 1:1.
+                let idx = expanded.file_index(name);
+                expanded.source.push_str(source);
+                expanded.lines = (1..=source.lines().count() as u32)
+                    .map(|line| (idx, line))
+                    .collect();
+                naga::front::wgsl::parse_str(source).map_err(|e| {
+                    ShaderError::Compile(expanded.error(e.location(source), e.message().to_owned()))
+                })?
+            }
+        };
+
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .map_err(|e| {
+            ShaderError::Compile(
+                expanded.error(e.location(&expanded.source), e.as_inner().to_string()),
+            )
+        })?;
+
+        naga::back::spv::write_vec(&module, &info, &naga::back::spv::Options::default(), None)
+            .map_err(|e| ShaderError::Compile(expanded.error(None, e.to_string())))
+    }
+
+    ///Appends `source` to `out`, replacing `#include` directives with the included file.
+    fn expand(
+        &self,
+        source: &str,
+        name: &str,
+        dir: Option<&Path>,
+        out: &mut Expanded,
+        depth: usize,
+    ) -> Result<(), ShaderError> {
+        let file = out.file_index(name);
+        for (idx, line) in source.lines().enumerate() {
+            let line_number = idx as u32 + 1;
+            let Some(include) = line.trim_start().strip_prefix("#include") else {
+                out.source.push_str(line);
+                out.source.push('\n');
+                out.lines.push((file, line_number));
+                continue;
+            };
+
+            let include_error = |message: String| {
+                ShaderError::Compile(CompileError {
+                    file: name.to_owned(),
+                    line: Some(line_number),
+                    column: None,
+                    message,
+                })
+            };
+
+            let include = include.trim();
+            let Some(target) = include
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .or_else(|| include.strip_prefix('<').and_then(|s| s.strip_suffix('>')))
+            else {
+                return Err(include_error(format!("malformed include {include}")));
+            };
+
+            if depth >= MAX_INCLUDE_DEPTH {
+                return Err(include_error(format!(
+                    "include depth exceeds {MAX_INCLUDE_DEPTH} while including \"{target}\", is the include recursive?"
+                )));
+            }
+
+            let on_disk = dir
+                .map(|dir| dir.join(target))
+                .into_iter()
+                .chain(self.include_dirs.iter().map(|dir| dir.join(target)))
+                .find(|path| path.is_file());
+
+            if let Some(path) = on_disk {
+                let included = std::fs::read_to_string(&path)?;
+                self.expand(
+                    &included,
+                    &path.display().to_string(),
+                    path.parent(),
+                    out,
+                    depth + 1,
+                )?;
+            } else if let Some(included) = self.virtual_files.get(target) {
+                self.expand(included, target, None, out, depth + 1)?;
+            } else {
+                return Err(include_error(format!(
+                    "could not resolve include \"{target}\""
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(compiler: &ShaderCompiler, source: &str) -> Result<Expanded, ShaderError> {
+        let mut expanded = Expanded {
+            source: String::new(),
+            lines: Vec::new(),
+            files: Vec::new(),
+        };
+        compiler.expand(source, "main.comp", None, &mut expanded, 0)?;
+        Ok(expanded)
+    }
+
+    #[test]
+    fn includes_virtual_files() {
+        let compiler = ShaderCompiler::new().with_virtual_file("shared.glsl", "uint a;\nuint b;");
+        let expanded = expand(
+            &compiler,
+            "#version 460\n#include \"shared.glsl\"\nvoid main(){}",
+        )
+        .unwrap();
+        assert_eq!(
+            expanded.source,
+            "#version 460\nuint a;\nuint b;\nvoid main(){}\n"
+        );
+        assert_eq!(expanded.lines, vec![(0, 1), (1, 1), (1, 2), (0, 3)]);
+    }
+
+    #[test]
+    fn maps_lines_to_included_file() {
+        let compiler = ShaderCompiler::new().with_virtual_file("shared.glsl", "uint a;\nuint b;");
+        let expanded = expand(&compiler, "#include <shared.glsl>\nvoid main(){}").unwrap();
+        let error = expanded.error(
+            Some(naga::SourceLocation {
+                line_number: 2,
+                line_position: 6,
+                offset: 0,
+                length: 0,
+            }),
+            "test".to_owned(),
+        );
+        assert_eq!(error.file, "shared.glsl");
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.column, Some(6));
+
+        let error = expanded.error(
+            Some(naga::SourceLocation {
+                line_number: 3,
+                line_position: 1,
+                offset: 0,
+                length: 0,
+            }),
+            "test".to_owned(),
+        );
+        assert_eq!(error.file, "main.comp");
+        assert_eq!(error.line, Some(2));
+    }
+
+    #[test]
+    fn missing_include_reports_line() {
+        let compiler = ShaderCompiler::new();
+        match expand(&compiler, "#version 460\n#include \"missing.glsl\"") {
+            Err(ShaderError::Compile(error)) => {
+                assert_eq!(error.file, "main.comp");
+                assert_eq!(error.line, Some(2));
+            }
+            _ => panic!("expected missing include error"),
+        }
+    }
+
+    #[test]
+    fn recursive_include_fails() {
+        let compiler = ShaderCompiler::new().with_virtual_file("a.glsl", "#include \"a.glsl\"");
+        assert!(matches!(
+            expand(&compiler, "#include \"a.glsl\""),
+            Err(ShaderError::Compile(_))
+        ));
+    }
+
+    #[test]
+    fn compiles_glsl_compute() {
+        let compiler = ShaderCompiler::new()
+            .with_virtual_file("shared.glsl", "#define SIZE 8")
+            .with_define("UNUSED", "1");
+        let code = compiler
+            .compile(
+                "#version 450\n#include \"shared.glsl\"\nlayout(local_size_x = SIZE) in;\nvoid main(){}",
+                "main.comp",
+                SourceLanguage::Glsl {
+                    stage: vk::ShaderStageFlags::COMPUTE,
+                },
+            )
+            .unwrap();
+        assert_eq!(code[0], 0x0723_0203);
+    }
+
+    #[test]
+    fn reports_wgsl_error_line() {
+        let compiler = ShaderCompiler::new();
+        match compiler.compile(
+            "@compute @workgroup_size(1)\nfn main() {\n    let a: u32 = ;\n}",
+            "main.wgsl",
+            SourceLanguage::Wgsl,
+        ) {
+            Err(ShaderError::Compile(error)) => {
+                assert_eq!(error.file, "main.wgsl");
+                assert_eq!(error.line, Some(3));
+            }
+            _ => panic!("expected compile error"),
+        }
+    }
+}
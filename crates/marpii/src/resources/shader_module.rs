@@ -3,6 +3,7 @@ use oos::OoS;
 #[cfg(feature = "shader_reflection")]
 use super::Reflection;
 
+use super::Specialization;
 use crate::{context::Device, error::ShaderError};
 use std::{ffi::CString, path::Path, sync::Arc};
 
@@ -10,6 +11,8 @@ use std::{ffi::CString, path::Path, sync::Arc};
 pub struct ShaderModule {
     pub device: Arc<Device>,
     pub inner: ash::vk::ShaderModule,
+    ///SPIR-V code the module was created from. Kept, since [ShaderObject](super::ShaderObject)s are created from code instead of modules.
+    pub code: Vec<u32>,
     ///saves the descriptor interface of this module where each bindings `shader_stage` is marked as `ALL`.
     /// for best performance those might be optimised by the user.
     #[cfg(feature = "shader_reflection")]
@@ -37,6 +40,31 @@ impl ShaderModule {
         Self::new(device, &words)
     }
 
+    ///Compiles the GLSL or WGSL file at `path` and creates the module from it. The language is derived from the file extension,
+    ///see [SourceLanguage::from_path](super::SourceLanguage::from_path).
+    #[cfg(feature = "shader_compiler")]
+    pub fn new_from_source_file(
+        device: &Arc<Device>,
+        compiler: &super::ShaderCompiler,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, ShaderError> {
+        let code = compiler.compile_file(path)?;
+        Self::new(device, &code)
+    }
+
+    ///Compiles `source` and creates the module from it. `name` is used in error messages.
+    #[cfg(feature = "shader_compiler")]
+    pub fn new_from_source(
+        device: &Arc<Device>,
+        compiler: &super::ShaderCompiler,
+        source: &str,
+        name: &str,
+        language: super::SourceLanguage,
+    ) -> Result<Self, ShaderError> {
+        let code = compiler.compile(source, name, language)?;
+        Self::new(device, &code)
+    }
+
     pub fn new(device: &Arc<Device>, code: &[u32]) -> Result<Self, ShaderError> {
         #[cfg(feature = "logging")]
         log::trace!("Shader Module new");
@@ -65,6 +93,7 @@ impl ShaderModule {
         Ok(ShaderModule {
             device: device.clone(),
             inner: module,
+            code: code.to_vec(),
             #[cfg(feature = "shader_reflection")]
             reflection,
         })
@@ -115,6 +144,7 @@ impl ShaderModule {
             module: OoS::new(self),
             stage,
             entry_name: CString::new(entry_name.into()).unwrap(),
+            specialization: Specialization::new(),
         }
     }
 }
@@ -131,6 +161,8 @@ pub struct ShaderStage {
     module: OoS<ShaderModule>,
     pub stage: ash::vk::ShaderStageFlags,
     pub entry_name: CString,
+    ///Specialization constants applied when a pipeline is created from this stage.
+    pub specialization: Specialization,
 }
 
 impl ShaderStage {
@@ -144,6 +176,7 @@ impl ShaderStage {
             module,
             stage,
             entry_name: CString::new(entry_name).unwrap(),
+            specialization: Specialization::new(),
         }
     }
 
@@ -154,6 +187,30 @@ impl ShaderStage {
         &self.module.inner
     }
 
+    ///Size in bytes of the push constant block the stage's entry point uses. Always `None` if the entry point uses no
+    /// push constant block, or if the `shader_reflection` feature is disabled.
+    pub fn push_constant_size(&self) -> Option<u32> {
+        #[cfg(feature = "shader_reflection")]
+        {
+            self.module
+                .reflection
+                .push_constant_block(self.entry_name.to_str().unwrap_or_default())
+                .map(|b| b.size)
+        }
+        #[cfg(not(feature = "shader_reflection"))]
+        {
+            None
+        }
+    }
+
+    ///Sets the specialization constants used for pipelines created from this stage.
+    pub fn with_specialization(mut self, specialization: Specialization) -> Self {
+        self.specialization = specialization;
+        self
+    }
+
+    ///Creates the create info for this stage. Note that the stage's [specialization](Self::specialization) is not applied, since
+    /// the [SpecializationInfo](ash::vk::SpecializationInfo) has to outlive the create info. Pass it via `specialization_info` instead.
     pub fn as_create_info<'a>(
         &'a self,
         specialization_info: Option<&'a ash::vk::SpecializationInfo>,
@@ -176,6 +233,7 @@ impl ShaderStage {
             module: self.module.share(),
             stage: self.stage,
             entry_name: self.entry_name.clone(),
+            specialization: self.specialization.clone(),
         }
     }
 }
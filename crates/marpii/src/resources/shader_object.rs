@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use crate::ash::vk;
+use crate::context::Device;
+use crate::error::PipelineError;
+use crate::resources::ShaderStage;
+
+///Single shader stage, created via `VK_EXT_shader_object`. Contrary to a pipeline, a shader object contains no fixed function state.
+/// It is bound via `vkCmdBindShadersEXT`, and all state is set dynamically while recording.
+///
+/// The descriptor set layouts and push constant ranges given at creation must match the pipeline layout used for
+/// binding descriptors and pushing constants.
+pub struct ShaderObject {
+    pub device: Arc<Device>,
+    pub loader: ash::ext::shader_object::Device,
+    pub inner: vk::ShaderEXT,
+    pub stage: vk::ShaderStageFlags,
+    ///Push constant size the shader stage expects, if known through reflection.
+    pub push_constant_size: Option<u32>,
+}
+
+impl ShaderObject {
+    ///Creates an unlinked shader object for `stage`. `next_stage` lists the stages that may follow this one, for instance `FRAGMENT` for a
+    /// vertex shader. The stage's [specialization](ShaderStage::specialization) is applied.
+    ///
+    /// Panics if `VK_EXT_shader_object` is not enabled.
+    pub fn new(
+        device: &Arc<Device>,
+        stage: &ShaderStage,
+        next_stage: vk::ShaderStageFlags,
+        set_layouts: &[vk::DescriptorSetLayout],
+        push_constant_ranges: &[vk::PushConstantRange],
+    ) -> Result<Self, PipelineError> {
+        let mut objects = Self::create(
+            device,
+            &[(stage, next_stage)],
+            vk::ShaderCreateFlagsEXT::empty(),
+            set_layouts,
+            push_constant_ranges,
+        )?;
+        Ok(objects.remove(0))
+    }
+
+    ///Creates linked shader objects for `stages`, which must be bound together. Linking lets the driver optimize across the stages,
+    /// similar to a pipeline. Each stage's next stage is the stage that follows it in `stages`.
+    ///
+    /// Panics if `VK_EXT_shader_object` is not enabled.
+    pub fn new_linked(
+        device: &Arc<Device>,
+        stages: &[&ShaderStage],
+        set_layouts: &[vk::DescriptorSetLayout],
+        push_constant_ranges: &[vk::PushConstantRange],
+    ) -> Result<Vec<Self>, PipelineError> {
+        let stages = stages
+            .iter()
+            .enumerate()
+            .map(|(idx, stage)| {
+                let next = stages
+                    .get(idx + 1)
+                    .map(|s| s.stage)
+                    .unwrap_or(vk::ShaderStageFlags::empty());
+                (*stage, next)
+            })
+            .collect::<Vec<_>>();
+        Self::create(
+            device,
+            &stages,
+            vk::ShaderCreateFlagsEXT::LINK_STAGE,
+            set_layouts,
+            push_constant_ranges,
+        )
+    }
+
+    fn create(
+        device: &Arc<Device>,
+        stages: &[(&ShaderStage, vk::ShaderStageFlags)],
+        flags: vk::ShaderCreateFlagsEXT,
+        set_layouts: &[vk::DescriptorSetLayout],
+        push_constant_ranges: &[vk::PushConstantRange],
+    ) -> Result<Vec<Self>, PipelineError> {
+        assert!(
+            device.extension_enabled_cstr(ash::ext::shader_object::NAME),
+            "ShaderObjectEXT extension not activated!"
+        );
+        let loader = ash::ext::shader_object::Device::new(&device.instance.inner, &device.inner);
+
+        //NOTE: the specialization infos have to outlive the create infos.
+        let map_entries = stages
+            .iter()
+            .map(|(stage, _)| stage.specialization.map_entries())
+            .collect::<Vec<_>>();
+        let specialization_infos = stages
+            .iter()
+            .zip(map_entries.iter())
+            .map(|((stage, _), entries)| stage.specialization.as_info(entries))
+            .collect::<Vec<_>>();
+
+        let create_infos = stages
+            .iter()
+            .zip(specialization_infos.iter())
+            .map(|((stage, next_stage), specialization)| {
+                let code = &stage.module().code;
+                //Reinterpreting the SPIR-V words as bytes is safe, since u8 has no alignment requirement.
+                let code = unsafe {
+                    core::slice::from_raw_parts(
+                        code.as_ptr() as *const u8,
+                        std::mem::size_of_val(code.as_slice()),
+                    )
+                };
+                let info = vk::ShaderCreateInfoEXT::default()
+                    .flags(flags)
+                    .stage(stage.stage)
+                    .next_stage(*next_stage)
+                    .code_type(vk::ShaderCodeTypeEXT::SPIRV)
+                    .code(code)
+                    .name(stage.entry_name.as_c_str())
+                    .set_layouts(set_layouts)
+                    .push_constant_ranges(push_constant_ranges);
+                if stage.specialization.is_empty() {
+                    info
+                } else {
+                    info.specialization_info(specialization)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let shaders = match unsafe { loader.create_shaders(&create_infos, None) } {
+            Ok(shaders) => shaders,
+            Err((shaders, err)) => {
+                for shader in shaders.into_iter().filter(|s| *s != vk::ShaderEXT::null()) {
+                    unsafe { loader.destroy_shader(shader, None) };
+                }
+                return Err(err.into());
+            }
+        };
+
+        Ok(shaders
+            .into_iter()
+            .zip(stages.iter())
+            .map(|(inner, (stage, _))| ShaderObject {
+                device: device.clone(),
+                loader: loader.clone(),
+                inner,
+                stage: stage.stage,
+                push_constant_size: stage.push_constant_size(),
+            })
+            .collect())
+    }
+}
+
+impl Drop for ShaderObject {
+    fn drop(&mut self) {
+        unsafe { self.loader.destroy_shader(self.inner, None) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use static_assertions::assert_impl_all;
+
+    #[test]
+    fn impl_send_sync() {
+        assert_impl_all!(ShaderObject: Send, Sync);
+    }
+}
@@ -0,0 +1,747 @@
+//! Sparse resources.
+//!
+//! Sparse images and buffers are created without any memory bound. Memory is bound page-wise later via `vkQueueBindSparse` on a queue that
+//! supports `SPARSE_BINDING`. This allows for instance virtual texturing, where only the visible part of a huge texture is resident.
+//!
+//! The residency helpers ([SparseImageResidency] and [SparseBufferResidency]) keep track of which pages are resident, allocate the memory
+//! for them and produce a [SparseBindBatch] that has to be executed on a sparse binding queue. Note that the bind is only valid once the batch was
+//! executed. Decommitted memory is moved into the batch and must be kept alive until the batch has finished executing.
+
+use std::{
+    any::Any,
+    sync::{Arc, Mutex},
+};
+
+use ahash::AHashMap;
+use ash::vk;
+
+use super::{BufDesc, Buffer, Image, ImgDesc};
+use crate::{
+    allocator::{Allocation, Allocator, ManagedAllocation, MemoryUsage, UnmanagedAllocation},
+    context::Device,
+    error::DeviceError,
+};
+
+impl Image {
+    ///Creates a sparse, partially resident image. Adds `SPARSE_BINDING` and `SPARSE_RESIDENCY` to the description's create flags.
+    /// No memory is bound. Use a [SparseImageResidency] to manage the image's memory.
+    ///
+    /// Note that the device must support the `sparseBinding` and the `sparseResidencyImage*` feature that fits the image type.
+    pub fn new_sparse(device: &Arc<Device>, mut description: ImgDesc) -> Result<Self, DeviceError> {
+        description.create_flags |=
+            vk::ImageCreateFlags::SPARSE_BINDING | vk::ImageCreateFlags::SPARSE_RESIDENCY;
+
+        let builder = description.set_on_builder(
+            vk::ImageCreateInfo::default().initial_layout(vk::ImageLayout::UNDEFINED),
+        );
+        let image = unsafe { device.inner.create_image(&builder, None)? };
+
+        Ok(Image {
+            //Safety: The memory is managed by the residency helper, the allocation is never used.
+            allocation: Box::new(unsafe { UnmanagedAllocation::new() }),
+            desc: description,
+            inner: image,
+            device: device.clone(),
+            usage: MemoryUsage::GpuOnly,
+            do_not_destroy: false,
+        })
+    }
+}
+
+impl Buffer {
+    ///Creates a sparse, partially resident buffer. Adds `SPARSE_BINDING` and `SPARSE_RESIDENCY` to the description's create flags.
+    /// No memory is bound. Use a [SparseBufferResidency] to manage the buffer's memory.
+    pub fn new_sparse(device: &Arc<Device>, mut description: BufDesc) -> Result<Self, DeviceError> {
+        description.create_flags |=
+            vk::BufferCreateFlags::SPARSE_BINDING | vk::BufferCreateFlags::SPARSE_RESIDENCY;
+
+        let builder = description.set_on_builder(vk::BufferCreateInfo::default());
+        let buffer = unsafe { device.inner.create_buffer(&builder, None)? };
+
+        Ok(Buffer {
+            device: device.clone(),
+            //Safety: The memory is managed by the residency helper, the allocation is never used.
+            allocation: Mutex::new(Box::new(unsafe { UnmanagedAllocation::new() })),
+            usage: MemoryUsage::GpuOnly,
+            desc: description,
+            inner: buffer,
+        })
+    }
+}
+
+///Collection of sparse memory binds that are executed in one `vkQueueBindSparse` call.
+#[derive(Default)]
+pub struct SparseBindBatch {
+    pub buffer_binds: Vec<(vk::Buffer, Vec<vk::SparseMemoryBind>)>,
+    pub image_opaque_binds: Vec<(vk::Image, Vec<vk::SparseMemoryBind>)>,
+    pub image_binds: Vec<(vk::Image, Vec<vk::SparseImageMemoryBind>)>,
+    ///Memory that was unbound by this batch. Must be kept alive until the batch has executed.
+    retired: Vec<Box<dyn Any + Send + Sync>>,
+}
+
+impl SparseBindBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///True if there is no bind in this batch.
+    pub fn is_empty(&self) -> bool {
+        self.buffer_binds.is_empty()
+            && self.image_opaque_binds.is_empty()
+            && self.image_binds.is_empty()
+    }
+
+    ///Moves all binds of `other` into `self`.
+    pub fn append(&mut self, mut other: SparseBindBatch) {
+        self.buffer_binds.append(&mut other.buffer_binds);
+        self.image_opaque_binds
+            .append(&mut other.image_opaque_binds);
+        self.image_binds.append(&mut other.image_binds);
+        self.retired.append(&mut other.retired);
+    }
+
+    ///Takes all memory that was unbound by this batch. Keep it alive until the batch has executed.
+    pub fn take_retired(&mut self) -> Vec<Box<dyn Any + Send + Sync>> {
+        std::mem::take(&mut self.retired)
+    }
+
+    ///Builds the [vk::BindSparseInfo] for this batch and passes it to `f`. Semaphores can be added in `f` before submitting.
+    pub fn with_bind_info<R>(&self, f: impl FnOnce(vk::BindSparseInfo<'_>) -> R) -> R {
+        let buffer_binds = self
+            .buffer_binds
+            .iter()
+            .map(|(buffer, binds)| {
+                vk::SparseBufferMemoryBindInfo::default()
+                    .buffer(*buffer)
+                    .binds(binds)
+            })
+            .collect::<Vec<_>>();
+        let image_opaque_binds = self
+            .image_opaque_binds
+            .iter()
+            .map(|(image, binds)| {
+                vk::SparseImageOpaqueMemoryBindInfo::default()
+                    .image(*image)
+                    .binds(binds)
+            })
+            .collect::<Vec<_>>();
+        let image_binds = self
+            .image_binds
+            .iter()
+            .map(|(image, binds)| {
+                vk::SparseImageMemoryBindInfo::default()
+                    .image(*image)
+                    .binds(binds)
+            })
+            .collect::<Vec<_>>();
+
+        f(vk::BindSparseInfo::default()
+            .buffer_binds(&buffer_binds)
+            .image_opaque_binds(&image_opaque_binds)
+            .image_binds(&image_binds))
+    }
+}
+
+///Single page of a sparse image. Coordinates are in pages, not texels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SparsePage {
+    pub mip_level: u32,
+    pub array_layer: u32,
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+///Extent of `mip_level` for an image of `extent`.
+fn mip_extent(extent: vk::Extent3D, mip_level: u32) -> vk::Extent3D {
+    vk::Extent3D {
+        width: (extent.width >> mip_level).max(1),
+        height: (extent.height >> mip_level).max(1),
+        depth: (extent.depth >> mip_level).max(1),
+    }
+}
+
+///Number of pages of size `granularity` that are needed to cover `extent`.
+fn page_count(extent: vk::Extent3D, granularity: vk::Extent3D) -> vk::Extent3D {
+    vk::Extent3D {
+        width: extent.width.div_ceil(granularity.width),
+        height: extent.height.div_ceil(granularity.height),
+        depth: extent.depth.div_ceil(granularity.depth),
+    }
+}
+
+///Returns the page range `start..end` per axis that intersects the texel region `offset..offset+extent`, clamped to `count` pages.
+fn page_range(
+    granularity: vk::Extent3D,
+    count: vk::Extent3D,
+    offset: vk::Offset3D,
+    extent: vk::Extent3D,
+) -> ([u32; 3], [u32; 3]) {
+    let start = [
+        offset.x.max(0) as u32 / granularity.width,
+        offset.y.max(0) as u32 / granularity.height,
+        offset.z.max(0) as u32 / granularity.depth,
+    ];
+    let end = [
+        (offset.x.max(0) as u32 + extent.width)
+            .div_ceil(granularity.width)
+            .min(count.width),
+        (offset.y.max(0) as u32 + extent.height)
+            .div_ceil(granularity.height)
+            .min(count.height),
+        (offset.z.max(0) as u32 + extent.depth)
+            .div_ceil(granularity.depth)
+            .min(count.depth),
+    ];
+    (start, end)
+}
+
+///Texel region of `page` in a mip level of `mip_extent`. Pages at the border of the image are cut to the image extent.
+fn page_region(
+    granularity: vk::Extent3D,
+    mip_extent: vk::Extent3D,
+    page: &SparsePage,
+) -> (vk::Offset3D, vk::Extent3D) {
+    let offset = vk::Offset3D {
+        x: (page.x * granularity.width) as i32,
+        y: (page.y * granularity.height) as i32,
+        z: (page.z * granularity.depth) as i32,
+    };
+    let extent = vk::Extent3D {
+        width: granularity.width.min(mip_extent.width - offset.x as u32),
+        height: granularity.height.min(mip_extent.height - offset.y as u32),
+        depth: granularity.depth.min(mip_extent.depth - offset.z as u32),
+    };
+    (offset, extent)
+}
+
+///Indices of the pages of size `page_size` that intersect the byte range `offset..offset+size` of a buffer of `buffer_size` bytes.
+fn buffer_page_range(
+    buffer_size: u64,
+    page_size: u64,
+    offset: u64,
+    size: u64,
+) -> std::ops::Range<u64> {
+    let page_count = buffer_size.div_ceil(page_size);
+    let start = offset / page_size;
+    let end = (offset + size).div_ceil(page_size).min(page_count);
+    start..end
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MipTail {
+    first_lod: u32,
+    size: u64,
+    offset: u64,
+    stride: u64,
+    single: bool,
+}
+
+///Page table of a sparse image. Allocates memory for each committed page via the allocator `A`.
+///
+/// The mip levels starting at the mip tail can't be committed page-wise, use [commit_mip_tail](Self::commit_mip_tail) for those.
+pub struct SparseImageResidency<A: Allocator + Send + Sync + 'static> {
+    device: Arc<Device>,
+    allocator: Arc<Mutex<A>>,
+    image: Arc<Image>,
+    aspect: vk::ImageAspectFlags,
+    granularity: vk::Extent3D,
+    mip_tail: Option<MipTail>,
+    page_requirements: vk::MemoryRequirements,
+    pages: AHashMap<SparsePage, ManagedAllocation<A>>,
+    mip_tail_memory: Vec<ManagedAllocation<A>>,
+}
+
+impl<A: Allocator + Send + Sync + 'static> SparseImageResidency<A> {
+    ///Creates the page table for `image`, which must be created via [Image::new_sparse].
+    pub fn new(
+        device: &Arc<Device>,
+        allocator: &Arc<Mutex<A>>,
+        image: Arc<Image>,
+    ) -> Result<Self, DeviceError> {
+        let sparse_requirements = unsafe {
+            device
+                .inner
+                .get_image_sparse_memory_requirements(image.inner)
+        };
+        let requirements = unsafe { device.inner.get_image_memory_requirements(image.inner) };
+
+        //NOTE: metadata aspects are not handled. Use the first non-metadata aspect
+        let Some(sparse) = sparse_requirements.iter().find(|req| {
+            !req.format_properties
+                .aspect_mask
+                .contains(vk::ImageAspectFlags::METADATA)
+        }) else {
+            #[cfg(feature = "logging")]
+            log::error!("Image has no sparse memory requirements, was it created sparse?");
+            return Err(DeviceError::UnsupportedFeature(
+                "sparse residency for image".to_owned(),
+            ));
+        };
+
+        let mip_tail = if sparse.image_mip_tail_first_lod < image.desc.mip_levels {
+            Some(MipTail {
+                first_lod: sparse.image_mip_tail_first_lod,
+                size: sparse.image_mip_tail_size,
+                offset: sparse.image_mip_tail_offset,
+                stride: sparse.image_mip_tail_stride,
+                single: sparse
+                    .format_properties
+                    .flags
+                    .contains(vk::SparseImageFormatFlags::SINGLE_MIPTAIL),
+            })
+        } else {
+            None
+        };
+
+        Ok(SparseImageResidency {
+            device: device.clone(),
+            allocator: allocator.clone(),
+            aspect: sparse.format_properties.aspect_mask,
+            granularity: sparse.format_properties.image_granularity,
+            mip_tail,
+            page_requirements: vk::MemoryRequirements {
+                size: requirements.alignment,
+                alignment: requirements.alignment,
+                memory_type_bits: requirements.memory_type_bits,
+            },
+            pages: AHashMap::default(),
+            mip_tail_memory: Vec::new(),
+            image,
+        })
+    }
+
+    pub fn image(&self) -> &Arc<Image> {
+        &self.image
+    }
+
+    ///Extent of a single page in texels.
+    pub fn page_extent(&self) -> vk::Extent3D {
+        self.granularity
+    }
+
+    ///Size of a single page in bytes.
+    pub fn page_size(&self) -> u64 {
+        self.page_requirements.size
+    }
+
+    ///First mip level that is part of the mip tail, if there is any.
+    pub fn mip_tail_first_lod(&self) -> Option<u32> {
+        self.mip_tail.map(|t| t.first_lod)
+    }
+
+    fn mip_extent(&self, mip_level: u32) -> vk::Extent3D {
+        mip_extent(self.image.extent_3d(), mip_level)
+    }
+
+    ///Number of pages in each direction for `mip_level`.
+    pub fn page_count(&self, mip_level: u32) -> vk::Extent3D {
+        page_count(self.mip_extent(mip_level), self.granularity)
+    }
+
+    ///Returns all pages that intersect the texel region `offset..offset+extent` of `mip_level` and `array_layer`.
+    pub fn pages_for_region(
+        &self,
+        mip_level: u32,
+        array_layer: u32,
+        offset: vk::Offset3D,
+        extent: vk::Extent3D,
+    ) -> impl Iterator<Item = SparsePage> + use<A> {
+        let (start, end) = page_range(self.granularity, self.page_count(mip_level), offset, extent);
+        (start[2]..end[2]).flat_map(move |z| {
+            (start[1]..end[1]).flat_map(move |y| {
+                (start[0]..end[0]).map(move |x| SparsePage {
+                    mip_level,
+                    array_layer,
+                    x,
+                    y,
+                    z,
+                })
+            })
+        })
+    }
+
+    pub fn is_resident(&self, page: &SparsePage) -> bool {
+        self.pages.contains_key(page)
+    }
+
+    ///Number of currently resident pages (not including the mip tail).
+    pub fn resident_page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    fn is_valid_page(&self, page: &SparsePage) -> bool {
+        let count = self.page_count(page.mip_level);
+        page.mip_level
+            < self
+                .mip_tail_first_lod()
+                .unwrap_or(self.image.desc.mip_levels)
+            && page.array_layer < self.image.desc.img_type.layer_count()
+            && page.x < count.width
+            && page.y < count.height
+            && page.z < count.depth
+    }
+
+    fn page_bind(
+        &self,
+        page: &SparsePage,
+        memory: vk::DeviceMemory,
+        memory_offset: u64,
+    ) -> vk::SparseImageMemoryBind {
+        let (offset, extent) = page_region(self.granularity, self.mip_extent(page.mip_level), page);
+
+        vk::SparseImageMemoryBind {
+            subresource: vk::ImageSubresource {
+                aspect_mask: self.aspect,
+                mip_level: page.mip_level,
+                array_layer: page.array_layer,
+            },
+            offset,
+            extent,
+            memory,
+            memory_offset,
+            flags: vk::SparseMemoryBindFlags::empty(),
+        }
+    }
+
+    fn allocate(
+        &self,
+        requirements: vk::MemoryRequirements,
+    ) -> Result<ManagedAllocation<A>, DeviceError> {
+        let allocation = self
+            .allocator
+            .lock()
+            .unwrap()
+            .allocate(
+                Some("sparse page"),
+                requirements,
+                MemoryUsage::GpuOnly,
+                false,
+            )
+            .map_err(|e| DeviceError::GpuAllocatorError(Box::new(e)))?;
+        Ok(ManagedAllocation {
+            allocator: self.allocator.clone(),
+            device: self.device.clone(),
+            allocation: Some(allocation),
+        })
+    }
+
+    ///Allocates memory for all `pages` that are not yet resident. Pages that are not valid for the image (or part of the mip tail) are ignored.
+    ///
+    /// The pages are resident once the returned batch has been executed.
+    pub fn commit(
+        &mut self,
+        pages: impl IntoIterator<Item = SparsePage>,
+    ) -> Result<SparseBindBatch, DeviceError> {
+        let mut binds = Vec::new();
+        for page in pages {
+            if self.pages.contains_key(&page) || !self.is_valid_page(&page) {
+                continue;
+            }
+            let allocation = self.allocate(self.page_requirements)?;
+            let (memory, offset) = allocation
+                .allocation
+                .as_ref()
+                .map(|a| (a.memory(), a.offset()))
+                .unwrap();
+            binds.push(self.page_bind(&page, memory, offset));
+            self.pages.insert(page, allocation);
+        }
+
+        let mut batch = SparseBindBatch::new();
+        if !binds.is_empty() {
+            batch.image_binds.push((self.image.inner, binds));
+        }
+        Ok(batch)
+    }
+
+    ///Unbinds all resident `pages`. The memory is moved into the returned batch, since it must be kept alive until the unbind has been executed.
+    pub fn decommit(&mut self, pages: impl IntoIterator<Item = SparsePage>) -> SparseBindBatch {
+        let mut batch = SparseBindBatch::new();
+        let mut binds = Vec::new();
+        for page in pages {
+            if let Some(allocation) = self.pages.remove(&page) {
+                binds.push(self.page_bind(&page, vk::DeviceMemory::null(), 0));
+                batch.retired.push(Box::new(allocation));
+            }
+        }
+
+        if !binds.is_empty() {
+            batch.image_binds.push((self.image.inner, binds));
+        }
+        batch
+    }
+
+    ///Commits the mip tail. Does nothing if the image has no mip tail, or it is already resident.
+    pub fn commit_mip_tail(&mut self) -> Result<SparseBindBatch, DeviceError> {
+        let mut batch = SparseBindBatch::new();
+        let Some(tail) = self.mip_tail else {
+            return Ok(batch);
+        };
+        if !self.mip_tail_memory.is_empty() {
+            return Ok(batch);
+        }
+
+        let tail_count = if tail.single {
+            1
+        } else {
+            self.image.desc.img_type.layer_count()
+        };
+
+        let mut binds = Vec::with_capacity(tail_count as usize);
+        for layer in 0..tail_count {
+            let allocation = self.allocate(vk::MemoryRequirements {
+                size: tail.size,
+                ..self.page_requirements
+            })?;
+            let (memory, memory_offset) = allocation
+                .allocation
+                .as_ref()
+                .map(|a| (a.memory(), a.offset()))
+                .unwrap();
+            binds.push(vk::SparseMemoryBind {
+                resource_offset: tail.offset + layer as u64 * tail.stride,
+                size: tail.size,
+                memory,
+                memory_offset,
+                flags: vk::SparseMemoryBindFlags::empty(),
+            });
+            self.mip_tail_memory.push(allocation);
+        }
+
+        batch.image_opaque_binds.push((self.image.inner, binds));
+        Ok(batch)
+    }
+}
+
+///Page table of a sparse buffer. Pages have the size of the buffer's memory alignment.
+pub struct SparseBufferResidency<A: Allocator + Send + Sync + 'static> {
+    device: Arc<Device>,
+    allocator: Arc<Mutex<A>>,
+    buffer: Arc<Buffer>,
+    page_requirements: vk::MemoryRequirements,
+    pages: AHashMap<u64, ManagedAllocation<A>>,
+}
+
+impl<A: Allocator + Send + Sync + 'static> SparseBufferResidency<A> {
+    ///Creates the page table for `buffer`, which must be created via [Buffer::new_sparse].
+    pub fn new(device: &Arc<Device>, allocator: &Arc<Mutex<A>>, buffer: Arc<Buffer>) -> Self {
+        let requirements = unsafe { device.inner.get_buffer_memory_requirements(buffer.inner) };
+        SparseBufferResidency {
+            device: device.clone(),
+            allocator: allocator.clone(),
+            buffer,
+            page_requirements: vk::MemoryRequirements {
+                size: requirements.alignment,
+                alignment: requirements.alignment,
+                memory_type_bits: requirements.memory_type_bits,
+            },
+            pages: AHashMap::default(),
+        }
+    }
+
+    pub fn buffer(&self) -> &Arc<Buffer> {
+        &self.buffer
+    }
+
+    ///Size of a single page in bytes.
+    pub fn page_size(&self) -> u64 {
+        self.page_requirements.size
+    }
+
+    ///Returns the indices of all pages that intersect the byte range `offset..offset+size`.
+    pub fn pages_for_range(&self, offset: u64, size: u64) -> std::ops::Range<u64> {
+        buffer_page_range(self.buffer.desc.size, self.page_size(), offset, size)
+    }
+
+    pub fn is_resident(&self, page: u64) -> bool {
+        self.pages.contains_key(&page)
+    }
+
+    ///Allocates memory for all `pages` that are not yet resident.
+    pub fn commit(
+        &mut self,
+        pages: impl IntoIterator<Item = u64>,
+    ) -> Result<SparseBindBatch, DeviceError> {
+        let page_count = self.buffer.desc.size.div_ceil(self.page_size());
+        let mut binds = Vec::new();
+        for page in pages {
+            if self.pages.contains_key(&page) || page >= page_count {
+                continue;
+            }
+            let allocation = self
+                .allocator
+                .lock()
+                .unwrap()
+                .allocate(
+                    Some("sparse page"),
+                    self.page_requirements,
+                    MemoryUsage::GpuOnly,
+                    true,
+                )
+                .map_err(|e| DeviceError::GpuAllocatorError(Box::new(e)))?;
+            binds.push(vk::SparseMemoryBind {
+                resource_offset: page * self.page_size(),
+                size: self.page_size(),
+                memory: allocation.memory(),
+                memory_offset: allocation.offset(),
+                flags: vk::SparseMemoryBindFlags::empty(),
+            });
+            self.pages.insert(
+                page,
+                ManagedAllocation {
+                    allocator: self.allocator.clone(),
+                    device: self.device.clone(),
+                    allocation: Some(allocation),
+                },
+            );
+        }
+
+        let mut batch = SparseBindBatch::new();
+        if !binds.is_empty() {
+            batch.buffer_binds.push((self.buffer.inner, binds));
+        }
+        Ok(batch)
+    }
+
+    ///Unbinds all resident `pages`. The memory is moved into the returned batch.
+    pub fn decommit(&mut self, pages: impl IntoIterator<Item = u64>) -> SparseBindBatch {
+        let mut batch = SparseBindBatch::new();
+        let mut binds = Vec::new();
+        for page in pages {
+            if let Some(allocation) = self.pages.remove(&page) {
+                binds.push(vk::SparseMemoryBind {
+                    resource_offset: page * self.page_size(),
+                    size: self.page_size(),
+                    memory: vk::DeviceMemory::null(),
+                    memory_offset: 0,
+                    flags: vk::SparseMemoryBindFlags::empty(),
+                });
+                batch.retired.push(Box::new(allocation));
+            }
+        }
+        if !binds.is_empty() {
+            batch.buffer_binds.push((self.buffer.inner, binds));
+        }
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use static_assertions::assert_impl_all;
+
+    #[test]
+    fn impl_send_sync() {
+        assert_impl_all!(SparseBindBatch: Send, Sync);
+    }
+
+    const GRANULARITY: vk::Extent3D = vk::Extent3D {
+        width: 128,
+        height: 64,
+        depth: 1,
+    };
+
+    fn extent(width: u32, height: u32, depth: u32) -> vk::Extent3D {
+        vk::Extent3D {
+            width,
+            height,
+            depth,
+        }
+    }
+
+    #[test]
+    fn page_count_rounds_up() {
+        let base = extent(1000, 500, 1);
+        assert_eq!(page_count(base, GRANULARITY), extent(8, 8, 1));
+        assert_eq!(mip_extent(base, 3), extent(125, 62, 1));
+        assert_eq!(
+            page_count(mip_extent(base, 3), GRANULARITY),
+            extent(1, 1, 1)
+        );
+        //Mips never shrink below one texel
+        assert_eq!(mip_extent(base, 12), extent(1, 1, 1));
+    }
+
+    #[test]
+    fn region_pages() {
+        let count = extent(8, 8, 1);
+        //Region inside of a single page
+        let (start, end) = page_range(
+            GRANULARITY,
+            count,
+            vk::Offset3D { x: 10, y: 10, z: 0 },
+            extent(10, 10, 1),
+        );
+        assert_eq!((start, end), ([0, 0, 0], [1, 1, 1]));
+
+        //Region that straddles page borders
+        let (start, end) = page_range(
+            GRANULARITY,
+            count,
+            vk::Offset3D {
+                x: 120,
+                y: 60,
+                z: 0,
+            },
+            extent(10, 10, 1),
+        );
+        assert_eq!((start, end), ([0, 0, 0], [2, 2, 1]));
+
+        //Region exceeding the image is clamped, negative offsets start at 0
+        let (start, end) = page_range(
+            GRANULARITY,
+            count,
+            vk::Offset3D {
+                x: -50,
+                y: 400,
+                z: 0,
+            },
+            extent(5000, 5000, 1),
+        );
+        assert_eq!((start, end), ([0, 6, 0], [8, 8, 1]));
+    }
+
+    #[test]
+    fn border_pages_are_cut() {
+        let mip = extent(1000, 500, 1);
+        let page = SparsePage {
+            mip_level: 0,
+            array_layer: 0,
+            x: 7,
+            y: 7,
+            z: 0,
+        };
+        let (offset, region) = page_region(GRANULARITY, mip, &page);
+        assert_eq!(
+            offset,
+            vk::Offset3D {
+                x: 896,
+                y: 448,
+                z: 0
+            }
+        );
+        assert_eq!(region, extent(104, 52, 1));
+
+        let inner = SparsePage { x: 1, y: 2, ..page };
+        let (offset, region) = page_region(GRANULARITY, mip, &inner);
+        assert_eq!(
+            offset,
+            vk::Offset3D {
+                x: 128,
+                y: 128,
+                z: 0
+            }
+        );
+        assert_eq!(region, GRANULARITY);
+    }
+
+    #[test]
+    fn buffer_pages() {
+        assert_eq!(buffer_page_range(1000, 256, 0, 1), 0..1);
+        assert_eq!(buffer_page_range(1000, 256, 255, 2), 0..2);
+        assert_eq!(buffer_page_range(1000, 256, 512, 10_000), 2..4);
+        assert!(buffer_page_range(1000, 256, 2000, 10).is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+use ash::vk;
+
+///Typed builder for the specialization constants of a [ShaderStage](super::ShaderStage).
+///
+/// Each value is stored with the `constant_id` it specializes. Setting an id twice replaces the former value.
+/// Values are kept sorted by their id, regardless of the order they are set in.
+///
+/// ```ignore
+/// let stage = module.into_shader_stage(vk::ShaderStageFlags::COMPUTE, "main").with_specialization(
+///     Specialization::new()
+///         .with(0, 64u32) //workgroup size
+///         .with_bool(1, true), //feature toggle
+/// );
+/// ```
+///
+/// Equality and hashing consider the ids and the data. Since values are sorted, two specializations that
+/// set the same values in a different order are equal.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Specialization {
+    ///`(constant_id, offset, size)` of each value in `data`, sorted by `constant_id`. `data` is packed in
+    /// the same order.
+    entries: Vec<(u32, u32, u32)>,
+    data: Vec<u8>,
+}
+
+impl Specialization {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///Sets the constant `id` to `value`.
+    pub fn with<T: bytemuck::Pod>(mut self, id: u32, value: T) -> Self {
+        self.set(id, value);
+        self
+    }
+
+    ///Sets the boolean constant `id`. Booleans are passed as `VkBool32`.
+    pub fn with_bool(self, id: u32, value: bool) -> Self {
+        self.with(id, if value { vk::TRUE } else { vk::FALSE })
+    }
+
+    ///Sets the constant `id` to `value`, see [with](Self::with).
+    pub fn set<T: bytemuck::Pod>(&mut self, id: u32, value: T) {
+        let bytes = bytemuck::bytes_of(&value);
+        let new_size = bytes.len() as u32;
+        let (idx, offset, old_size) =
+            match self.entries.binary_search_by_key(&id, |(eid, _, _)| *eid) {
+                Ok(idx) => {
+                    let (_, offset, size) = self.entries[idx];
+                    self.entries[idx].2 = new_size;
+                    (idx, offset, size)
+                }
+                Err(idx) => {
+                    let offset = self
+                        .entries
+                        .get(idx)
+                        .map(|(_, offset, _)| *offset)
+                        .unwrap_or(self.data.len() as u32);
+                    self.entries.insert(idx, (id, offset, new_size));
+                    (idx, offset, 0)
+                }
+            };
+
+        self.data.splice(
+            offset as usize..(offset + old_size) as usize,
+            bytes.iter().copied(),
+        );
+        //move all following values by the size difference
+        for entry in self.entries[idx + 1..].iter_mut() {
+            entry.1 = entry.1 + new_size - old_size;
+        }
+    }
+
+    ///Removes the constant `id`, so the module's default value is used.
+    pub fn remove(&mut self, id: u32) {
+        if let Ok(idx) = self.entries.binary_search_by_key(&id, |(eid, _, _)| *eid) {
+            let (_, offset, size) = self.entries.remove(idx);
+            self.data.drain(offset as usize..(offset + size) as usize);
+            for entry in self.entries[idx..].iter_mut() {
+                entry.1 -= size;
+            }
+        }
+    }
+
+    ///Returns the raw bytes of constant `id`, if it is set.
+    pub fn get(&self, id: u32) -> Option<&[u8]> {
+        self.entries
+            .iter()
+            .find(|(eid, _, _)| *eid == id)
+            .map(|(_, offset, size)| &self.data[*offset as usize..(offset + size) as usize])
+    }
+
+    ///Iterates all set constants as `(constant_id, size)`.
+    pub fn constants(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.entries.iter().map(|(id, _, size)| (*id, *size))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    ///Returns the map entries describing the data.
+    pub fn map_entries(&self) -> Vec<vk::SpecializationMapEntry> {
+        self.entries
+            .iter()
+            .map(|(id, offset, size)| vk::SpecializationMapEntry {
+                constant_id: *id,
+                offset: *offset,
+                size: *size as usize,
+            })
+            .collect()
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    ///Creates the info for pipeline creation. `map_entries` must be the result of [map_entries](Self::map_entries).
+    pub fn as_info<'a>(
+        &'a self,
+        map_entries: &'a [vk::SpecializationMapEntry],
+    ) -> vk::SpecializationInfo<'a> {
+        vk::SpecializationInfo::default()
+            .map_entries(map_entries)
+            .data(&self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use static_assertions::assert_impl_all;
+
+    #[test]
+    fn impl_send_sync() {
+        assert_impl_all!(Specialization: Send, Sync);
+    }
+
+    #[test]
+    fn replace_and_remove() {
+        let mut spec = Specialization::new()
+            .with(0, 64u32)
+            .with(1, 1.0f64)
+            .with_bool(2, true);
+        assert_eq!(spec.data().len(), 16);
+
+        spec.set(0, 32u32);
+        assert_eq!(spec.get(0), Some(32u32.to_ne_bytes().as_slice()));
+
+        //size change moves the following values
+        spec.set(0, 8u16);
+        assert_eq!(spec.data().len(), 14);
+        assert_eq!(spec.get(1), Some(1.0f64.to_ne_bytes().as_slice()));
+        assert_eq!(spec.get(0), Some(8u16.to_ne_bytes().as_slice()));
+
+        spec.remove(1);
+        assert_eq!(spec.data().len(), 6);
+        assert_eq!(spec.get(2), Some(vk::TRUE.to_ne_bytes().as_slice()));
+        assert_eq!(spec.constants().count(), 2);
+    }
+
+    #[test]
+    fn order_independent() {
+        let a = Specialization::new()
+            .with(3, 1.0f64)
+            .with(0, 64u32)
+            .with_bool(1, true);
+        let b = Specialization::new()
+            .with_bool(1, true)
+            .with(0, 64u32)
+            .with(3, 1.0f64);
+        assert_eq!(a, b);
+        assert_eq!(a.constants().collect::<Vec<_>>(), [(0, 4), (1, 4), (3, 8)]);
+        assert_eq!(
+            a.map_entries().iter().map(|e| e.offset).collect::<Vec<_>>(),
+            [0, 4, 8]
+        );
+
+        let hash = |s: &Specialization| {
+            use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+            BuildHasherDefault::<DefaultHasher>::default().hash_one(s)
+        };
+        assert_eq!(hash(&a), hash(&b));
+    }
+}
@@ -70,6 +70,21 @@ impl Surface {
         })
     }
 
+    ///Returns true if queues of `queue_family` can present to this surface.
+    pub fn supports_queue_family(
+        &self,
+        physical_device: &ash::vk::PhysicalDevice,
+        queue_family: u32,
+    ) -> Result<bool, InstanceError> {
+        Ok(unsafe {
+            self.surface_loader.get_physical_device_surface_support(
+                *physical_device,
+                queue_family,
+                self.surface,
+            )?
+        })
+    }
+
     ///Tries to read the current surface extent. This can fail on some platforms (like Linux+Wayland).
     /// Note that this can be different than the swapchain extent, for instace right after a resize.
     pub fn get_current_extent(&self, physical_device: &vk::PhysicalDevice) -> Option<vk::Extent2D> {
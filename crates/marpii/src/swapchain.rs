@@ -1,6 +1,10 @@
 use std::{
     marker::PhantomData,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
 };
 
 use ash::vk::{self, SwapchainCreateInfoKHR};
@@ -16,6 +20,97 @@ use crate::{
     sync::BinarySemaphore,
 };
 
+mod frame_limiter;
+pub use frame_limiter::FrameLimiter;
+
+///Transfer function (EOTF) the presentation engine applies to the swapchain images, as implied by the swapchain's
+/// color space. Shaders writing to the swapchain (or to images that are blitted to it) must encode their output accordingly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferFunction {
+    ///sRGB encoded output. `_SRGB` formats encode in hardware, all others must be encoded by the shader.
+    Srgb,
+    ///Linear output, for instance for the extended sRGB linear color space (scRGB). Values may exceed 1.0.
+    Linear,
+    ///SMPTE ST 2084 perceptual quantizer, used by HDR10 and Dolby Vision.
+    St2084,
+    ///Hybrid log-gamma.
+    Hlg,
+    ///Any other color space, for instance pass-through or non-sRGB gamma curves.
+    Other(vk::ColorSpaceKHR),
+}
+
+impl TransferFunction {
+    pub fn from_color_space(color_space: vk::ColorSpaceKHR) -> Self {
+        match color_space {
+            vk::ColorSpaceKHR::SRGB_NONLINEAR
+            | vk::ColorSpaceKHR::EXTENDED_SRGB_NONLINEAR_EXT
+            | vk::ColorSpaceKHR::DISPLAY_P3_NONLINEAR_EXT => TransferFunction::Srgb,
+            vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT
+            | vk::ColorSpaceKHR::DISPLAY_P3_LINEAR_EXT
+            | vk::ColorSpaceKHR::BT709_LINEAR_EXT
+            | vk::ColorSpaceKHR::BT2020_LINEAR_EXT
+            | vk::ColorSpaceKHR::ADOBERGB_LINEAR_EXT => TransferFunction::Linear,
+            vk::ColorSpaceKHR::HDR10_ST2084_EXT | vk::ColorSpaceKHR::DOLBYVISION_EXT => {
+                TransferFunction::St2084
+            }
+            vk::ColorSpaceKHR::HDR10_HLG_EXT => TransferFunction::Hlg,
+            other => TransferFunction::Other(other),
+        }
+    }
+
+    ///Returns true if shaders must apply the sRGB encoding themselves when writing to an image of `format`. This is the case
+    /// for sRGB encoded color spaces, if `format` is not an `_SRGB` format.
+    pub fn needs_srgb_encoding(&self, format: vk::Format) -> bool {
+        *self == TransferFunction::Srgb && !crate::util::is_srgb(&format)
+    }
+
+    ///Returns true for the HDR transfer functions [St2084](Self::St2084) and [Hlg](Self::Hlg).
+    pub fn is_hdr(&self) -> bool {
+        matches!(self, TransferFunction::St2084 | TransferFunction::Hlg)
+    }
+}
+
+///Mastering display and content light levels, submitted via `VK_EXT_hdr_metadata`. Chromaticities are CIE 1931 xy
+/// coordinates, luminances are in nits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HdrMetadata {
+    pub display_primary_red: [f32; 2],
+    pub display_primary_green: [f32; 2],
+    pub display_primary_blue: [f32; 2],
+    pub white_point: [f32; 2],
+    pub max_luminance: f32,
+    pub min_luminance: f32,
+    pub max_content_light_level: f32,
+    pub max_frame_average_light_level: f32,
+}
+
+impl HdrMetadata {
+    ///Rec. 2020 primaries with a D65 white point, mastered for 1000 nits.
+    pub const HDR10: Self = HdrMetadata {
+        display_primary_red: [0.708, 0.292],
+        display_primary_green: [0.170, 0.797],
+        display_primary_blue: [0.131, 0.046],
+        white_point: [0.3127, 0.3290],
+        max_luminance: 1000.0,
+        min_luminance: 0.001,
+        max_content_light_level: 1000.0,
+        max_frame_average_light_level: 400.0,
+    };
+
+    pub fn to_vk(&self) -> vk::HdrMetadataEXT<'static> {
+        let xy = |[x, y]: [f32; 2]| vk::XYColorEXT { x, y };
+        vk::HdrMetadataEXT::default()
+            .display_primary_red(xy(self.display_primary_red))
+            .display_primary_green(xy(self.display_primary_green))
+            .display_primary_blue(xy(self.display_primary_blue))
+            .white_point(xy(self.white_point))
+            .max_luminance(self.max_luminance)
+            .min_luminance(self.min_luminance)
+            .max_content_light_level(self.max_content_light_level)
+            .max_frame_average_light_level(self.max_frame_average_light_level)
+    }
+}
+
 ///All info needed to create a swapchain
 pub struct RecreateInfo {
     pub format: ash::vk::SurfaceFormatKHR,
@@ -74,6 +169,10 @@ pub struct SwapchainBuilder {
     pub create_info: RecreateInfo,
 
     pub format_preference: Vec<ash::vk::SurfaceFormatKHR>,
+    ///Color spaces in order of preference. If empty, the first supported format of `format_preference` is chosen regardless of its color space.
+    pub color_space_preference: Vec<ash::vk::ColorSpaceKHR>,
+    ///HDR metadata that is set on the swapchain after creation. Needs `VK_EXT_hdr_metadata` to be enabled.
+    pub hdr_metadata: Option<HdrMetadata>,
     pub present_mode_preference: Vec<ash::vk::PresentModeKHR>,
     pub extent_preference: Option<vk::Extent2D>,
 }
@@ -138,11 +237,51 @@ impl SwapchainBuilder {
 
         let format = self.get_first_supported_format();
         let present_mode = self.get_first_supported_present_mode();
+        let image_count = self.get_supported_image_count();
         let mut recreate_info = self.create_info;
         recreate_info.format = format;
         recreate_info.present_mode = present_mode;
+        recreate_info.image_count = image_count;
 
-        Ok(Swapchain {
+        let hdr_loader = if self
+            .device
+            .extension_enabled_cstr(ash::ext::hdr_metadata::NAME)
+        {
+            Some(ash::ext::hdr_metadata::Device::new(
+                &self.device.instance.inner,
+                &self.device.inner,
+            ))
+        } else {
+            None
+        };
+
+        let present_wait_loader = if self
+            .device
+            .extension_enabled_cstr(ash::khr::present_id::NAME)
+            && self
+                .device
+                .extension_enabled_cstr(ash::khr::present_wait::NAME)
+        {
+            Some(ash::khr::present_wait::Device::new(
+                &self.device.instance.inner,
+                &self.device.inner,
+            ))
+        } else {
+            None
+        };
+        let display_timing_loader = if self
+            .device
+            .extension_enabled_cstr(ash::google::display_timing::NAME)
+        {
+            Some(ash::google::display_timing::Device::new(
+                &self.device.instance.inner,
+                &self.device.inner,
+            ))
+        } else {
+            None
+        };
+
+        let mut swapchain = Swapchain {
             surface: self.surface,
             images,
             device: self.device,
@@ -152,14 +291,47 @@ impl SwapchainBuilder {
             loader: swapchain_loader,
             swapchain,
             recreate_info,
-        })
+            hdr_loader,
+            hdr_metadata: None,
+            present_wait_loader,
+            display_timing_loader,
+            present_id: AtomicU64::new(0),
+            first_present_id: 1,
+        };
+
+        if let Some(metadata) = self.hdr_metadata {
+            swapchain.set_hdr_metadata(metadata)?;
+        }
+
+        Ok(swapchain)
     }
 
+    ///Selects the surface format. For each entry of `color_space_preference`, the first format of `format_preference` that is
+    /// supported in that color space is chosen. If none matches, or no color space preference is set, the first supported
+    /// format of `format_preference` is used, and lastly the first supported format.
     pub fn get_first_supported_format(&self) -> ash::vk::SurfaceFormatKHR {
         let mut supported = self
             .surface
             .get_formats(self.device.physical_device)
             .unwrap();
+        for color_space in self.color_space_preference.iter() {
+            if let Some(prefered) = self
+                .format_preference
+                .iter()
+                .chain(supported.iter())
+                .find(|f| f.color_space == *color_space && supported.contains(f))
+            {
+                return *prefered;
+            }
+        }
+        if !self.color_space_preference.is_empty() {
+            #[cfg(feature = "logging")]
+            log::warn!(
+                "None of the color spaces {:?} is supported by the surface",
+                self.color_space_preference
+            );
+        }
+
         for prefered in self.format_preference.iter() {
             if supported.contains(prefered) {
                 return *prefered;
@@ -185,6 +357,15 @@ impl SwapchainBuilder {
         supported.remove(0)
     }
 
+    ///Returns the requested image count, clamped to the surface's capabilities.
+    pub fn get_supported_image_count(&self) -> u32 {
+        let capabilities = self
+            .surface
+            .get_capabilities(&self.device.physical_device)
+            .unwrap();
+        clamp_image_count(self.create_info.image_count, &capabilities)
+    }
+
     pub fn get_supported_image_extent(&self) -> ash::vk::Extent2D {
         let supported = self
             .surface
@@ -237,7 +418,7 @@ impl SwapchainBuilder {
 
         let mut builder = SwapchainCreateInfoKHR::default()
             .surface(self.surface.surface)
-            .min_image_count(self.create_info.image_count)
+            .min_image_count(self.get_supported_image_count())
             .image_format(format.format)
             .image_color_space(format.color_space)
             .image_extent(image_extent)
@@ -266,9 +447,10 @@ impl SwapchainBuilder {
 
     ///Tries to order present mode preferences to use FIFO_RELAXED or FIFO
     pub fn with_vsync(mut self) -> Self {
-        if let Ok(at) = self
+        if let Some(at) = self
             .present_mode_preference
-            .binary_search(&ash::vk::PresentModeKHR::FIFO_RELAXED)
+            .iter()
+            .position(|m| *m == ash::vk::PresentModeKHR::FIFO_RELAXED)
         {
             let e = self.present_mode_preference.remove(at);
             self.present_mode_preference.insert(0, e);
@@ -279,9 +461,10 @@ impl SwapchainBuilder {
 
     ///Tries to prefere immediate presentation.
     pub fn with_immediate_present(mut self) -> Self {
-        if let Ok(at) = self
+        if let Some(at) = self
             .present_mode_preference
-            .binary_search(&ash::vk::PresentModeKHR::IMMEDIATE)
+            .iter()
+            .position(|m| *m == ash::vk::PresentModeKHR::IMMEDIATE)
         {
             let e = self.present_mode_preference.remove(at);
             self.present_mode_preference.insert(0, e);
@@ -290,6 +473,35 @@ impl SwapchainBuilder {
         self
     }
 
+    ///Sets the color space preference, see [get_first_supported_format](Self::get_first_supported_format). Color spaces
+    /// other than `SRGB_NONLINEAR` are only reported if `VK_EXT_swapchain_colorspace` is enabled on the instance.
+    pub fn with_color_spaces(mut self, color_spaces: &[vk::ColorSpaceKHR]) -> Self {
+        self.color_space_preference = color_spaces.to_vec();
+        self
+    }
+
+    ///Prefers HDR10 (ST 2084), then extended sRGB linear, and finally sRGB nonlinear output. Check [Swapchain::transfer_function]
+    /// for the chosen one.
+    pub fn with_hdr(self) -> Self {
+        self.with_color_spaces(&[
+            vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+            vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+            vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        ])
+    }
+
+    ///Sets HDR metadata that is submitted once the swapchain is created. Needs `VK_EXT_hdr_metadata` to be enabled.
+    pub fn with_hdr_metadata(mut self, metadata: HdrMetadata) -> Self {
+        self.hdr_metadata = Some(metadata);
+        self
+    }
+
+    ///Sets the minimum number of swapchain images. The count is clamped to the surface's capabilities when building.
+    pub fn with_image_count(mut self, image_count: u32) -> Self {
+        self.create_info.image_count = image_count;
+        self
+    }
+
     pub fn with_extent(mut self, extent: vk::Extent2D) -> Self {
         self.extent_preference = Some(extent);
         self
@@ -358,6 +570,25 @@ pub struct Swapchain {
     ///present_finished semaphores
     next_semaphore: usize,
     recreate_info: RecreateInfo,
+    hdr_loader: Option<ash::ext::hdr_metadata::Device>,
+    ///Metadata that is re-submitted whenever the swapchain is recreated.
+    hdr_metadata: Option<HdrMetadata>,
+    present_wait_loader: Option<ash::khr::present_wait::Device>,
+    display_timing_loader: Option<ash::google::display_timing::Device>,
+    ///Id of the last present operation.
+    present_id: AtomicU64,
+    ///First present id of the current swapchain handle. Earlier ids were presented by an old handle.
+    first_present_id: u64,
+}
+
+fn clamp_image_count(image_count: u32, capabilities: &vk::SurfaceCapabilitiesKHR) -> u32 {
+    let count = image_count.max(capabilities.min_image_count);
+    //NOTE: max_image_count of 0 means there is no limit.
+    if capabilities.max_image_count > 0 {
+        count.min(capabilities.max_image_count)
+    } else {
+        count
+    }
 }
 
 impl Swapchain {
@@ -381,6 +612,8 @@ impl Swapchain {
             surface,
             device: device.clone(),
             format_preference: formats,
+            color_space_preference: Vec::new(),
+            hdr_metadata: None,
             present_mode_preference: present_modes,
             extent_preference: None,
             create_info: RecreateInfo {
@@ -408,6 +641,162 @@ impl Swapchain {
         })
     }
 
+    ///Returns the format and color space the swapchain images are created with.
+    pub fn surface_format(&self) -> vk::SurfaceFormatKHR {
+        self.recreate_info.format
+    }
+
+    ///Returns the transfer function implied by the swapchain's color space.
+    pub fn transfer_function(&self) -> TransferFunction {
+        TransferFunction::from_color_space(self.recreate_info.format.color_space)
+    }
+
+    ///Submits HDR `metadata` for this swapchain. The metadata is kept and re-submitted when the swapchain is recreated.
+    ///
+    /// Fails if `VK_EXT_hdr_metadata` is not enabled on the device.
+    pub fn set_hdr_metadata(&mut self, metadata: HdrMetadata) -> Result<(), DeviceError> {
+        let Some(loader) = &self.hdr_loader else {
+            return Err(DeviceError::UnsupportedExtension(
+                ash::ext::hdr_metadata::NAME.to_string_lossy().to_string(),
+            ));
+        };
+        unsafe { loader.set_hdr_metadata(&[self.swapchain], &[metadata.to_vk()]) };
+        self.hdr_metadata = Some(metadata);
+        Ok(())
+    }
+
+    ///Returns the present mode the swapchain currently uses.
+    pub fn present_mode(&self) -> vk::PresentModeKHR {
+        self.recreate_info.present_mode
+    }
+
+    ///Returns all present modes the surface supports.
+    pub fn supported_present_modes(&self) -> Result<Vec<vk::PresentModeKHR>, DeviceError> {
+        Ok(unsafe {
+            self.surface
+                .surface_loader
+                .get_physical_device_surface_present_modes(
+                    self.device.physical_device,
+                    self.surface.surface,
+                )?
+        })
+    }
+
+    ///Switches to `present_mode` by recreating the swapchain with its current extent. Fails if the surface does not support `present_mode`.
+    pub fn set_present_mode(
+        &mut self,
+        present_mode: vk::PresentModeKHR,
+    ) -> Result<(), DeviceError> {
+        if !self.supported_present_modes()?.contains(&present_mode) {
+            return Err(DeviceError::UnsupportedFeature(format!(
+                "present mode {:?}",
+                present_mode
+            )));
+        }
+        if present_mode == self.recreate_info.present_mode {
+            return Ok(());
+        }
+        self.recreate_info.present_mode = present_mode;
+        self.recreate(self.recreate_info.extent)
+    }
+
+    ///Returns the minimum image count the swapchain was created with. Note that the implementation might create more images.
+    pub fn image_count(&self) -> u32 {
+        self.recreate_info.image_count
+    }
+
+    ///Sets the minimum image count by recreating the swapchain with its current extent. The count is clamped to the surface's
+    /// capabilities, the count that is used is returned.
+    pub fn set_image_count(&mut self, image_count: u32) -> Result<u32, DeviceError> {
+        let capabilities = unsafe {
+            self.surface
+                .surface_loader
+                .get_physical_device_surface_capabilities(
+                    self.device.physical_device,
+                    self.surface.surface,
+                )?
+        };
+        let image_count = clamp_image_count(image_count, &capabilities);
+        if image_count != self.recreate_info.image_count {
+            self.recreate_info.image_count = image_count;
+            self.recreate(self.recreate_info.extent)?;
+        }
+        Ok(image_count)
+    }
+
+    ///Returns true if `VK_KHR_present_id` and `VK_KHR_present_wait` are enabled, and [wait_for_present](Self::wait_for_present) can be used.
+    pub fn supports_present_wait(&self) -> bool {
+        self.present_wait_loader.is_some()
+    }
+
+    ///Returns the id of the last present operation. Ids start at 1 and increase with each [present_image](Self::present_image).
+    pub fn last_present_id(&self) -> u64 {
+        self.present_id.load(Ordering::Acquire)
+    }
+
+    ///Waits until the present operation with `present_id` has been displayed. Returns false if `timeout` was reached before.
+    ///
+    /// Presents of a swapchain that was replaced by [recreate](Self::recreate) are treated as finished. Fails if
+    /// present wait is not supported, see [supports_present_wait](Self::supports_present_wait).
+    pub fn wait_for_present(
+        &self,
+        present_id: u64,
+        timeout: Duration,
+    ) -> Result<bool, DeviceError> {
+        let Some(loader) = &self.present_wait_loader else {
+            return Err(DeviceError::UnsupportedExtension(
+                ash::khr::present_wait::NAME.to_string_lossy().to_string(),
+            ));
+        };
+        if present_id < self.first_present_id {
+            return Ok(true);
+        }
+        let timeout = u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX);
+        match unsafe { loader.wait_for_present(self.swapchain, present_id, timeout) } {
+            Ok(()) => Ok(true),
+            Err(vk::Result::TIMEOUT) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    ///Returns true if `VK_GOOGLE_display_timing` is enabled, and presentation timings can be queried.
+    pub fn supports_display_timing(&self) -> bool {
+        self.display_timing_loader.is_some()
+    }
+
+    ///Returns the timings of all presents that completed since the last call. The `present_id` of each timing is the
+    /// (truncated) [present id](Self::last_present_id) of the present operation.
+    pub fn past_presentation_timing(
+        &self,
+    ) -> Result<Vec<vk::PastPresentationTimingGOOGLE>, DeviceError> {
+        let Some(loader) = &self.display_timing_loader else {
+            return Err(DeviceError::UnsupportedExtension(
+                ash::google::display_timing::NAME
+                    .to_string_lossy()
+                    .to_string(),
+            ));
+        };
+        Ok(unsafe { loader.get_past_presentation_timing(self.swapchain)? })
+    }
+
+    ///Returns the display's refresh cycle duration.
+    pub fn refresh_cycle_duration(&self) -> Result<Duration, DeviceError> {
+        let Some(loader) = &self.display_timing_loader else {
+            return Err(DeviceError::UnsupportedExtension(
+                ash::google::display_timing::NAME
+                    .to_string_lossy()
+                    .to_string(),
+            ));
+        };
+        let duration = unsafe { loader.get_refresh_cycle_duration(self.swapchain)? };
+        Ok(Duration::from_nanos(duration.refresh_duration))
+    }
+
+    ///Returns the HDR metadata that was set last, if any.
+    pub fn hdr_metadata(&self) -> Option<&HdrMetadata> {
+        self.hdr_metadata.as_ref()
+    }
+
     ///Retrieves the next image that should be written to. Note that all required information (acquire semaphore and)
     /// a semaphore to be signalled when finished presenting is included in that image.
     pub fn acquire_next_image(&mut self) -> Result<SwapchainImage, DeviceError> {
@@ -466,6 +855,7 @@ impl Swapchain {
 
         //overwrite swapchain ptr
         self.swapchain = new_sc;
+        self.first_present_id = *self.present_id.get_mut() + 1;
 
         //Now overwrite inner swapchain images with new ones. The old ones should be dropped once
         //they have no references anymore.
@@ -514,6 +904,11 @@ impl Swapchain {
                 .push(Arc::new(BinarySemaphore::new(&device)?));
         }
 
+        //The metadata is set per swapchain handle, so resubmit it for the new one.
+        if let (Some(loader), Some(metadata)) = (&self.hdr_loader, &self.hdr_metadata) {
+            unsafe { loader.set_hdr_metadata(&[self.swapchain], &[metadata.to_vk()]) };
+        }
+
         #[cfg(feature = "logging")]
         log::info!("Recreating swapchain for {:?}", extent);
 
@@ -528,27 +923,85 @@ impl Swapchain {
         image: SwapchainImage,
         queue: &ash::vk::Queue,
     ) -> ash::prelude::VkResult<()> {
-        let present_info = ash::vk::PresentInfoKHR::default()
-            .swapchains(core::slice::from_ref(&self.swapchain))
-            .image_indices(core::slice::from_ref(&image.index))
-            .wait_semaphores(core::slice::from_ref(&image.sem_present.inner));
-
-        match unsafe { self.loader.queue_present(*queue, &present_info) } {
-            Ok(b) => {
-                if b {
+        Self::present_batch(queue, vec![(self, image)])
+            .pop()
+            .unwrap()
+    }
+
+    ///Presents the images of several swapchains with a single present command, for instance to flip all windows of an
+    /// application at once. All swapchains must be created for the same device. Returns the result of each present, in order of `images`.
+    /// Like [present_image](Self::present_image), a suboptimal present is reported as `Err(SUBOPTIMAL_KHR)`.
+    pub fn present_batch(
+        queue: &ash::vk::Queue,
+        images: Vec<(&Swapchain, SwapchainImage)>,
+    ) -> Vec<ash::prelude::VkResult<()>> {
+        let Some((first, _)) = images.first() else {
+            return Vec::new();
+        };
+        //NOTE: Whether present ids and times can be used depends on the device, so it is the same for all swapchains.
+        let use_present_id = first.present_wait_loader.is_some();
+        let use_present_times = first.display_timing_loader.is_some();
+
+        let swapchains = images
+            .iter()
+            .map(|(sc, _)| sc.swapchain)
+            .collect::<Vec<_>>();
+        let indices = images.iter().map(|(_, img)| img.index).collect::<Vec<_>>();
+        let wait_semaphores = images
+            .iter()
+            .map(|(_, img)| img.sem_present.inner)
+            .collect::<Vec<_>>();
+        let present_ids = images
+            .iter()
+            .map(|(sc, _)| sc.present_id.fetch_add(1, Ordering::AcqRel) + 1)
+            .collect::<Vec<_>>();
+        let present_times = present_ids
+            .iter()
+            .map(|id| vk::PresentTimeGOOGLE {
+                present_id: *id as u32,
+                desired_present_time: 0,
+            })
+            .collect::<Vec<_>>();
+        let mut results = vec![vk::Result::SUCCESS; images.len()];
+
+        let mut present_id_info = vk::PresentIdKHR::default().present_ids(&present_ids);
+        let mut present_times_info = vk::PresentTimesInfoGOOGLE::default().times(&present_times);
+        let mut present_info = ash::vk::PresentInfoKHR::default()
+            .swapchains(&swapchains)
+            .image_indices(&indices)
+            .wait_semaphores(&wait_semaphores)
+            .results(&mut results);
+        if use_present_id {
+            present_info = present_info.push_next(&mut present_id_info);
+        }
+        if use_present_times {
+            present_info = present_info.push_next(&mut present_times_info);
+        }
+
+        let call_result = unsafe { first.loader.queue_present(*queue, &present_info) };
+        //If the call failed, but no swapchain reported an error, the error applies to all of them.
+        if let Err(e) = call_result
+            && results.iter().all(|r| *r == vk::Result::SUCCESS)
+        {
+            results.fill(e);
+        }
+
+        results
+            .into_iter()
+            .map(|r| match r {
+                vk::Result::SUCCESS => Ok(()),
+                vk::Result::SUBOPTIMAL_KHR => {
                     #[cfg(feature = "logging")]
                     log::warn!("Suboptimal image on present. returning error");
-                    Err(ash::vk::Result::SUBOPTIMAL_KHR)
-                } else {
-                    Ok(()) //all is right
+                    Err(r)
                 }
-            }
-            Err(e) => {
-                #[cfg(feature = "logging")]
-                log::error!("Error while presenting image: {}", e);
-                Err(e)
-            }
-        }
+                e => {
+                    #[cfg(feature = "logging")]
+                    log::error!("Error while presenting image: {}", e);
+                    Err(e)
+                }
+            })
+            .collect()
     }
 }
 
@@ -569,4 +1022,43 @@ mod tests {
     fn impl_send_sync() {
         assert_impl_all!(Arc<Swapchain>: Send, Sync);
     }
+
+    #[test]
+    fn image_count_clamping() {
+        let capabilities = vk::SurfaceCapabilitiesKHR {
+            min_image_count: 2,
+            max_image_count: 4,
+            ..Default::default()
+        };
+        assert_eq!(clamp_image_count(1, &capabilities), 2);
+        assert_eq!(clamp_image_count(3, &capabilities), 3);
+        assert_eq!(clamp_image_count(8, &capabilities), 4);
+
+        let unlimited = vk::SurfaceCapabilitiesKHR {
+            min_image_count: 2,
+            max_image_count: 0,
+            ..Default::default()
+        };
+        assert_eq!(clamp_image_count(8, &unlimited), 8);
+    }
+
+    #[test]
+    fn transfer_functions() {
+        let srgb = TransferFunction::from_color_space(vk::ColorSpaceKHR::SRGB_NONLINEAR);
+        assert_eq!(srgb, TransferFunction::Srgb);
+        assert!(srgb.needs_srgb_encoding(vk::Format::B8G8R8A8_UNORM));
+        assert!(!srgb.needs_srgb_encoding(vk::Format::B8G8R8A8_SRGB));
+
+        let linear =
+            TransferFunction::from_color_space(vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT);
+        assert_eq!(linear, TransferFunction::Linear);
+        assert!(!linear.needs_srgb_encoding(vk::Format::R16G16B16A16_SFLOAT));
+
+        let pq = TransferFunction::from_color_space(vk::ColorSpaceKHR::HDR10_ST2084_EXT);
+        assert!(pq.is_hdr());
+        assert_eq!(
+            TransferFunction::from_color_space(vk::ColorSpaceKHR::PASS_THROUGH_EXT),
+            TransferFunction::Other(vk::ColorSpaceKHR::PASS_THROUGH_EXT)
+        );
+    }
 }
@@ -0,0 +1,141 @@
+use std::time::{Duration, Instant};
+
+use super::Swapchain;
+
+///Timeout of a single present wait. Bounds the time a frame can be blocked if the presentation engine stalls, for instance
+/// while the window is minimized.
+const PRESENT_WAIT_TIMEOUT: Duration = Duration::from_millis(100);
+
+///Paces frame recording based on the actual present progress of a [Swapchain], and optionally a target frame rate.
+///
+/// Call [wait](Self::wait) before recording a frame. If the swapchain [supports present wait](Swapchain::supports_present_wait),
+/// the limiter waits until at most `max_frame_latency` presents are still in flight. This keeps input latency low, since
+/// recording starts as late as possible. If a target frame rate is set, the limiter additionally sleeps until the next frame slot.
+#[derive(Clone, Debug)]
+pub struct FrameLimiter {
+    frame_time: Option<Duration>,
+    max_frame_latency: u64,
+    next_frame: Option<Instant>,
+}
+
+impl Default for FrameLimiter {
+    fn default() -> Self {
+        FrameLimiter {
+            frame_time: None,
+            max_frame_latency: 1,
+            next_frame: None,
+        }
+    }
+}
+
+impl FrameLimiter {
+    ///Creates a limiter without target frame rate, that allows a single present in flight.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_target_fps(mut self, fps: f32) -> Self {
+        self.set_target_fps(Some(fps));
+        self
+    }
+
+    ///Sets the number of presents that may be in flight when [wait](Self::wait) returns. Values below 1 are clamped to 1.
+    pub fn with_max_frame_latency(mut self, max_frame_latency: u64) -> Self {
+        self.max_frame_latency = max_frame_latency.max(1);
+        self
+    }
+
+    ///Sets the target frame rate. `None`, or a non-positive rate, removes the limit.
+    pub fn set_target_fps(&mut self, fps: Option<f32>) {
+        self.frame_time = fps
+            .filter(|fps| *fps > 0.0 && fps.is_finite())
+            .map(|fps| Duration::from_secs_f32(1.0 / fps));
+        self.next_frame = None;
+    }
+
+    ///Returns the target frame time, if any.
+    pub fn frame_time(&self) -> Option<Duration> {
+        self.frame_time
+    }
+
+    ///Returns the time until the next frame slot starts at `now`. Zero if there is no target frame rate, or the slot already started.
+    pub fn time_until_next_frame(&self, now: Instant) -> Duration {
+        self.next_frame
+            .map(|next| next.saturating_duration_since(now))
+            .unwrap_or(Duration::ZERO)
+    }
+
+    ///Schedules the frame starting at `now`. Returns the time the frame has to wait for its slot.
+    fn schedule(&mut self, now: Instant) -> Duration {
+        let Some(frame_time) = self.frame_time else {
+            return Duration::ZERO;
+        };
+        let wait = self.time_until_next_frame(now);
+        //If we are late, reschedule from now instead of trying to catch up with a burst of frames.
+        let start = self.next_frame.map(|next| next.max(now)).unwrap_or(now);
+        self.next_frame = Some(start + frame_time);
+        wait
+    }
+
+    ///Blocks until the next frame should be recorded for `swapchain`. Returns the time that was spent waiting.
+    pub fn wait(&mut self, swapchain: &Swapchain) -> Duration {
+        let start = Instant::now();
+
+        if swapchain.supports_present_wait() {
+            let last = swapchain.last_present_id();
+            if last > self.max_frame_latency
+                && let Err(_e) =
+                    swapchain.wait_for_present(last - self.max_frame_latency, PRESENT_WAIT_TIMEOUT)
+            {
+                #[cfg(feature = "logging")]
+                log::warn!("Failed to wait for present: {}", _e);
+            }
+        }
+
+        let wait = self.schedule(Instant::now());
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+
+        start.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlimited() {
+        let mut limiter = FrameLimiter::new();
+        let now = Instant::now();
+        assert_eq!(limiter.schedule(now), Duration::ZERO);
+        assert_eq!(limiter.schedule(now), Duration::ZERO);
+        assert_eq!(limiter.time_until_next_frame(now), Duration::ZERO);
+    }
+
+    #[test]
+    fn pacing() {
+        let mut limiter = FrameLimiter::new().with_target_fps(100.0);
+        let frame_time = limiter.frame_time().unwrap();
+        let now = Instant::now();
+        //first frame starts immediately
+        assert_eq!(limiter.schedule(now), Duration::ZERO);
+        //second frame waits for its slot
+        let early = now + frame_time / 4;
+        assert_eq!(limiter.schedule(early), frame_time - frame_time / 4);
+        //a late frame does not wait, and the next slot is scheduled from the late start
+        let late = now + frame_time * 5;
+        assert_eq!(limiter.schedule(late), Duration::ZERO);
+        assert_eq!(limiter.time_until_next_frame(late), frame_time);
+    }
+
+    #[test]
+    fn invalid_fps() {
+        let mut limiter = FrameLimiter::new();
+        limiter.set_target_fps(Some(0.0));
+        assert!(limiter.frame_time().is_none());
+        limiter.set_target_fps(Some(f32::INFINITY));
+        assert!(limiter.frame_time().is_none());
+    }
+}
@@ -36,6 +36,8 @@
 use crate::context::Device;
 use ash::{self, vk};
 use std::fmt::Debug;
+#[cfg(unix)]
+use std::os::fd::{FromRawFd, IntoRawFd, OwnedFd};
 use std::sync::Arc;
 
 ///Single [TimelineSemaphore](https://www.khronos.org/blog/vulkan-timeline-semaphores). This is the preffered type of semaphores these days. However, for compatibility reasons (mostly with the swapchain) [BinarySemaphore]s exist as well.
@@ -62,6 +64,53 @@ impl Semaphore {
         }))
     }
 
+    ///Creates a timeline semaphore that can be exported as `handle_types` (usually `OPAQUE_FD`). Needs `VK_KHR_external_semaphore_fd`.
+    #[cfg(unix)]
+    pub fn new_exportable(
+        device: &Arc<Device>,
+        initial_value: u64,
+        handle_types: vk::ExternalSemaphoreHandleTypeFlags,
+    ) -> Result<Arc<Self>, ash::vk::Result> {
+        let mut timeline_ci = ash::vk::SemaphoreTypeCreateInfo::default()
+            .semaphore_type(ash::vk::SemaphoreType::TIMELINE)
+            .initial_value(initial_value);
+        let mut export_ci = vk::ExportSemaphoreCreateInfo::default().handle_types(handle_types);
+
+        let semaphore = unsafe {
+            let ci = ash::vk::SemaphoreCreateInfo::default()
+                .push_next(&mut timeline_ci)
+                .push_next(&mut export_ci);
+
+            device.inner.create_semaphore(&ci, None)?
+        };
+
+        Ok(Arc::new(Semaphore {
+            inner: semaphore,
+            device: device.clone(),
+        }))
+    }
+
+    ///Exports the semaphore as `handle_type`. The semaphore must be created via [new_exportable](Semaphore::new_exportable).
+    #[cfg(unix)]
+    pub fn export_fd(
+        &self,
+        handle_type: vk::ExternalSemaphoreHandleTypeFlags,
+    ) -> Result<OwnedFd, vk::Result> {
+        export_semaphore_fd(&self.device, self.inner, handle_type)
+    }
+
+    ///Imports the foreign semaphore payload `fd` into this semaphore. If `temporary` is set, the payload is only used until the next
+    /// wait operation. On success the Vulkan implementation takes ownership of `fd`.
+    #[cfg(unix)]
+    pub fn import_fd(
+        &self,
+        fd: OwnedFd,
+        handle_type: vk::ExternalSemaphoreHandleTypeFlags,
+        temporary: bool,
+    ) -> Result<(), vk::Result> {
+        import_semaphore_fd(&self.device, self.inner, fd, handle_type, temporary)
+    }
+
     ///Returns the current value of the semaphore. Note that this can change at any time if the semaphore is in use on
     /// the device.
     ///
@@ -178,6 +227,112 @@ impl BinarySemaphore {
             device: device.clone(),
         })
     }
+
+    ///Creates a binary semaphore that can be exported as `handle_types` (usually `SYNC_FD` or `OPAQUE_FD`). Needs `VK_KHR_external_semaphore_fd`.
+    #[cfg(unix)]
+    pub fn new_exportable(
+        device: &Arc<Device>,
+        handle_types: vk::ExternalSemaphoreHandleTypeFlags,
+    ) -> Result<Self, vk::Result> {
+        let mut ci = ash::vk::SemaphoreTypeCreateInfo::default()
+            .semaphore_type(ash::vk::SemaphoreType::BINARY);
+        let mut export_ci = vk::ExportSemaphoreCreateInfo::default().handle_types(handle_types);
+
+        let semaphore = unsafe {
+            let ci = ash::vk::SemaphoreCreateInfo::default()
+                .push_next(&mut ci)
+                .push_next(&mut export_ci);
+
+            device.inner.create_semaphore(&ci, None)?
+        };
+
+        Ok(BinarySemaphore {
+            inner: semaphore,
+            device: device.clone(),
+        })
+    }
+
+    ///Exports the semaphore as `handle_type`. Note that exporting a `SYNC_FD` requires a pending signal operation, and resets the semaphore.
+    #[cfg(unix)]
+    pub fn export_fd(
+        &self,
+        handle_type: vk::ExternalSemaphoreHandleTypeFlags,
+    ) -> Result<OwnedFd, vk::Result> {
+        export_semaphore_fd(&self.device, self.inner, handle_type)
+    }
+
+    ///Imports the foreign semaphore payload `fd`. `SYNC_FD` payloads must be imported `temporary`. On success the Vulkan implementation
+    /// takes ownership of `fd`.
+    #[cfg(unix)]
+    pub fn import_fd(
+        &self,
+        fd: OwnedFd,
+        handle_type: vk::ExternalSemaphoreHandleTypeFlags,
+        temporary: bool,
+    ) -> Result<(), vk::Result> {
+        import_semaphore_fd(&self.device, self.inner, fd, handle_type, temporary)
+    }
+}
+
+#[cfg(unix)]
+fn external_semaphore_loader(
+    device: &Arc<Device>,
+) -> Result<ash::khr::external_semaphore_fd::Device, vk::Result> {
+    if !device.extension_enabled_cstr(ash::khr::external_semaphore_fd::NAME) {
+        #[cfg(feature = "logging")]
+        log::error!("VK_KHR_external_semaphore_fd is not enabled");
+        return Err(vk::Result::ERROR_EXTENSION_NOT_PRESENT);
+    }
+    Ok(ash::khr::external_semaphore_fd::Device::new(
+        &device.instance.inner,
+        &device.inner,
+    ))
+}
+
+#[cfg(unix)]
+fn export_semaphore_fd(
+    device: &Arc<Device>,
+    semaphore: vk::Semaphore,
+    handle_type: vk::ExternalSemaphoreHandleTypeFlags,
+) -> Result<OwnedFd, vk::Result> {
+    let loader = external_semaphore_loader(device)?;
+    let fd = unsafe {
+        loader.get_semaphore_fd(
+            &vk::SemaphoreGetFdInfoKHR::default()
+                .semaphore(semaphore)
+                .handle_type(handle_type),
+        )?
+    };
+    //Safety: The fd was just created by the implementation and is owned by us.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+#[cfg(unix)]
+fn import_semaphore_fd(
+    device: &Arc<Device>,
+    semaphore: vk::Semaphore,
+    fd: OwnedFd,
+    handle_type: vk::ExternalSemaphoreHandleTypeFlags,
+    temporary: bool,
+) -> Result<(), vk::Result> {
+    let loader = external_semaphore_loader(device)?;
+    let flags = if temporary {
+        vk::SemaphoreImportFlags::TEMPORARY
+    } else {
+        vk::SemaphoreImportFlags::empty()
+    };
+    let raw_fd = fd.into_raw_fd();
+    let info = vk::ImportSemaphoreFdInfoKHR::default()
+        .semaphore(semaphore)
+        .handle_type(handle_type)
+        .flags(flags)
+        .fd(raw_fd);
+    if let Err(e) = unsafe { loader.import_semaphore_fd(&info) } {
+        //import failed, we still own the fd
+        drop(unsafe { OwnedFd::from_raw_fd(raw_fd) });
+        return Err(e);
+    }
+    Ok(())
 }
 
 impl Drop for BinarySemaphore {
@@ -287,6 +442,86 @@ impl Event {
 
         Ok(Arc::new(Event { event, device }))
     }
+
+    ///Creates an event that is only set, reset and waited on from command buffers. Host access via [set_event](Self::set_event),
+    /// [reset_event](Self::reset_event) and [status](Self::status) is not allowed, which lets the driver use a cheaper implementation.
+    pub fn new_device_only(device: Arc<Device>) -> Result<Arc<Self>, ash::vk::Result> {
+        let ci = ash::vk::EventCreateInfo::default().flags(ash::vk::EventCreateFlags::DEVICE_ONLY);
+
+        let event = unsafe { device.inner.create_event(&ci, None)? };
+
+        Ok(Arc::new(Event { event, device }))
+    }
+
+    ///Records a signal operation of the event into `command_buffer`. The event is signaled once all work described by
+    /// the source scope of `dependency_info` has finished.
+    ///
+    /// The same `dependency_info` must be passed to the [wait](Self::cmd_wait) that waits for this event.
+    pub fn cmd_set(
+        &self,
+        command_buffer: ash::vk::CommandBuffer,
+        dependency_info: &ash::vk::DependencyInfo,
+    ) {
+        unsafe {
+            self.device
+                .inner
+                .cmd_set_event2(command_buffer, self.event, dependency_info)
+        }
+    }
+
+    ///Records a reset operation of the event into `command_buffer`, that is executed after all work of `stage` has finished.
+    pub fn cmd_reset(
+        &self,
+        command_buffer: ash::vk::CommandBuffer,
+        stage: ash::vk::PipelineStageFlags2,
+    ) {
+        unsafe {
+            self.device
+                .inner
+                .cmd_reset_event2(command_buffer, self.event, stage)
+        }
+    }
+
+    ///Records a wait operation for this event into `command_buffer`. `dependency_info` must match the one used in [cmd_set](Self::cmd_set).
+    pub fn cmd_wait(
+        &self,
+        command_buffer: ash::vk::CommandBuffer,
+        dependency_info: &ash::vk::DependencyInfo,
+    ) {
+        Self::cmd_wait_all(
+            &self.device,
+            command_buffer,
+            &[self.event],
+            std::slice::from_ref(dependency_info),
+        );
+    }
+
+    ///Records a single wait operation for several `events` into `command_buffer`. `dependency_infos[i]` must match the
+    /// dependency info the event `events[i]` was [set](Self::cmd_set) with.
+    ///
+    /// # Panics
+    ///
+    /// If `events` and `dependency_infos` have different lengths.
+    pub fn cmd_wait_all(
+        device: &Device,
+        command_buffer: ash::vk::CommandBuffer,
+        events: &[ash::vk::Event],
+        dependency_infos: &[ash::vk::DependencyInfo],
+    ) {
+        assert_eq!(
+            events.len(),
+            dependency_infos.len(),
+            "Each event needs exactly one dependency info"
+        );
+        if events.is_empty() {
+            return;
+        }
+        unsafe {
+            device
+                .inner
+                .cmd_wait_events2(command_buffer, events, dependency_infos)
+        }
+    }
     ///Sets the event into the "waiting" status.
     pub fn set_event(&self) -> Result<(), EventError> {
         unsafe {
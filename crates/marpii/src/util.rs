@@ -3,6 +3,12 @@ mod timestamp;
 pub use timestamp::{Timestamp, Timestamps};
 mod query_pool;
 pub use query_pool::QueryPool;
+mod pipeline_statistics;
+pub use pipeline_statistics::PipelineStatistics;
+mod format;
+pub use format::{
+    Compression, FormatInfo, NumericType, mip_extent, srgb_counterpart, unorm_counterpart,
+};
 
 ///Converts a [Extent3D](ash::vk::Extent3D) to an offset. Needed for instance to convert
 /// an image's extent to the offset parameter for image-blit or copy operations.
@@ -178,59 +184,31 @@ pub enum FormatType {
 
 impl FormatType {
     pub fn parse(format: &vk::Format) -> Self {
-        match *format {
-            vk::Format::R16_SFLOAT
-            | vk::Format::R16G16_SFLOAT
-            | vk::Format::R16G16B16_SFLOAT
-            | vk::Format::R16G16B16A16_SFLOAT => FormatType::F16,
-            vk::Format::D32_SFLOAT
-            | vk::Format::R32_SFLOAT
-            | vk::Format::R32G32_SFLOAT
-            | vk::Format::R32G32B32_SFLOAT
-            | vk::Format::R32G32B32A32_SFLOAT => FormatType::F32,
-
-            vk::Format::R64_SFLOAT
-            | vk::Format::R64G64_SFLOAT
-            | vk::Format::R64G64B64_SFLOAT
-            | vk::Format::R64G64B64A64_SFLOAT => FormatType::F64,
-            vk::Format::R8_UINT
-            | vk::Format::R8G8_UINT
-            | vk::Format::B8G8R8_UINT
-            | vk::Format::R8G8B8_UINT
-            | vk::Format::A8B8G8R8_UINT_PACK32
-            | vk::Format::B8G8R8A8_UINT
-            | vk::Format::R8G8B8A8_UINT => FormatType::U8,
-            vk::Format::R16_UINT
-            | vk::Format::R16G16_UINT
-            | vk::Format::R16G16B16_UINT
-            | vk::Format::R16G16B16A16_UINT => FormatType::U16,
-            vk::Format::R32_UINT
-            | vk::Format::R32G32_UINT
-            | vk::Format::R32G32B32_UINT
-            | vk::Format::R32G32B32A32_UINT => FormatType::U32,
-            vk::Format::R64_UINT
-            | vk::Format::R64G64_UINT
-            | vk::Format::R64G64B64_UINT
-            | vk::Format::R64G64B64A64_UINT => FormatType::U64,
-            vk::Format::R8_SINT
-            | vk::Format::R8G8_SINT
-            | vk::Format::B8G8R8_SINT
-            | vk::Format::R8G8B8_SINT
-            | vk::Format::A8B8G8R8_SINT_PACK32
-            | vk::Format::B8G8R8A8_SINT
-            | vk::Format::R8G8B8A8_SINT => FormatType::I8,
-            vk::Format::R16_SINT
-            | vk::Format::R16G16_SINT
-            | vk::Format::R16G16B16_SINT
-            | vk::Format::R16G16B16A16_SINT => FormatType::I16,
-            vk::Format::R32_SINT
-            | vk::Format::R32G32_SINT
-            | vk::Format::R32G32B32_SINT
-            | vk::Format::R32G32B32A32_SINT => FormatType::I32,
-            vk::Format::R64_SINT
-            | vk::Format::R64G64_SINT
-            | vk::Format::R64G64B64_SINT
-            | vk::Format::R64G64B64A64_SINT => FormatType::I64,
+        let Some(info) = FormatInfo::of(*format) else {
+            return FormatType::Other;
+        };
+        //Only formats whose components all have the same size translate to a formatless type.
+        let bits = info.component_bits[0];
+        if info.is_compressed()
+            || info.is_multi_planar()
+            || info.component_bits[..info.component_count as usize]
+                .iter()
+                .any(|b| *b != bits)
+        {
+            return FormatType::Other;
+        }
+        match (info.numeric_type, bits) {
+            (NumericType::Sfloat, 16) => FormatType::F16,
+            (NumericType::Sfloat, 32) => FormatType::F32,
+            (NumericType::Sfloat, 64) => FormatType::F64,
+            (NumericType::Uint, 8) => FormatType::U8,
+            (NumericType::Uint, 16) => FormatType::U16,
+            (NumericType::Uint, 32) => FormatType::U32,
+            (NumericType::Uint, 64) => FormatType::U64,
+            (NumericType::Sint, 8) => FormatType::I8,
+            (NumericType::Sint, 16) => FormatType::I16,
+            (NumericType::Sint, 32) => FormatType::I32,
+            (NumericType::Sint, 64) => FormatType::I64,
             _ => FormatType::Other,
         }
     }
@@ -238,8 +216,7 @@ impl FormatType {
 
 ///Parsed extended set of format properties. Allows you to querry runtime information
 pub struct FormatProperties {
-    ///If known, contains the number of byte per pixel. Note that this is only parsed for *core*
-    /// formats
+    ///Contains the number of byte per pixel, if the format has a per-pixel size. See [byte_per_pixel].
     pub byte_per_pixel: Option<u8>,
     pub is_srgb: bool,
     ///FormatType defines the datatype per channel of that format.
@@ -267,111 +244,17 @@ impl FormatProperties {
 /// for instance, given a buffer and the target images width the height could be calculated.
 ///
 /// # Note
-/// The function is only implemented for core formats. Otherwise None is returned and an error is printed to the logs.
+/// Block compressed, 4:2:2 and multi-planar formats have no per-pixel size. For those `None` is returned and an error is printed to the logs.
+/// Use [FormatInfo] to calculate their sizes.
 pub fn byte_per_pixel(format: &vk::Format) -> Option<u8> {
-    match *format {
-        vk::Format::R4G4_UNORM_PACK8 => Some(1),
-        vk::Format::R4G4B4A4_UNORM_PACK16
-        | vk::Format::R5G6B5_UNORM_PACK16
-        | vk::Format::R5G5B5A1_UNORM_PACK16
-        | vk::Format::A1R5G5B5_UNORM_PACK16 => Some(2),
-
-        vk::Format::R8_UNORM
-        | vk::Format::R8_SNORM
-        | vk::Format::R8_USCALED
-        | vk::Format::R8_SSCALED
-        | vk::Format::R8_UINT
-        | vk::Format::R8_SINT
-        | vk::Format::R8_SRGB => Some(1),
-        vk::Format::R8G8_UNORM
-        | vk::Format::R8G8_SNORM
-        | vk::Format::R8G8_USCALED
-        | vk::Format::R8G8_SSCALED
-        | vk::Format::R8G8_UINT
-        | vk::Format::R8G8_SINT
-        | vk::Format::R8G8_SRGB => Some(2),
-        vk::Format::R8G8B8_UNORM
-        | vk::Format::R8G8B8_SNORM
-        | vk::Format::R8G8B8_USCALED
-        | vk::Format::R8G8B8_SSCALED
-        | vk::Format::R8G8B8_UINT
-        | vk::Format::R8G8B8_SINT
-        | vk::Format::R8G8B8_SRGB
-        | vk::Format::B8G8R8_UNORM
-        | vk::Format::B8G8R8_SNORM
-        | vk::Format::B8G8R8_USCALED
-        | vk::Format::B8G8R8_SSCALED
-        | vk::Format::B8G8R8_UINT
-        | vk::Format::B8G8R8_SINT
-        | vk::Format::B8G8R8_SRGB => Some(3),
-
-        vk::Format::R8G8B8A8_UNORM
-        | vk::Format::R8G8B8A8_SNORM
-        | vk::Format::R8G8B8A8_USCALED
-        | vk::Format::R8G8B8A8_SSCALED
-        | vk::Format::R8G8B8A8_UINT
-        | vk::Format::R8G8B8A8_SINT
-        | vk::Format::R8G8B8A8_SRGB
-        | vk::Format::B8G8R8A8_UNORM
-        | vk::Format::B8G8R8A8_SNORM
-        | vk::Format::B8G8R8A8_USCALED
-        | vk::Format::B8G8R8A8_SSCALED
-        | vk::Format::B8G8R8A8_UINT
-        | vk::Format::B8G8R8A8_SINT
-        | vk::Format::B8G8R8A8_SRGB => Some(4),
-
-        vk::Format::A2R10G10B10_UNORM_PACK32 => Some(4),
-        vk::Format::A2R10G10B10_SNORM_PACK32 => Some(4),
-        vk::Format::A2R10G10B10_USCALED_PACK32 => Some(4),
-        vk::Format::A2R10G10B10_SSCALED_PACK32 => Some(4),
-        vk::Format::A2R10G10B10_UINT_PACK32 => Some(4),
-        vk::Format::A2R10G10B10_SINT_PACK32 => Some(4),
-        vk::Format::A2B10G10R10_UNORM_PACK32 => Some(4),
-        vk::Format::A2B10G10R10_SNORM_PACK32 => Some(4),
-        vk::Format::A2B10G10R10_USCALED_PACK32 => Some(4),
-        vk::Format::A2B10G10R10_SSCALED_PACK32 => Some(4),
-        vk::Format::A2B10G10R10_UINT_PACK32 => Some(4),
-        vk::Format::A2B10G10R10_SINT_PACK32 => Some(4),
-
-        vk::Format::R16_UNORM
-        | vk::Format::R16_SNORM
-        | vk::Format::R16_USCALED
-        | vk::Format::R16_SSCALED
-        | vk::Format::R16_UINT
-        | vk::Format::R16_SINT
-        | vk::Format::R16_SFLOAT => Some(2),
-        vk::Format::R16G16_UNORM
-        | vk::Format::R16G16_SNORM
-        | vk::Format::R16G16_USCALED
-        | vk::Format::R16G16_SSCALED
-        | vk::Format::R16G16_UINT
-        | vk::Format::R16G16_SINT
-        | vk::Format::R16G16_SFLOAT => Some(4),
-        vk::Format::R16G16B16_UNORM
-        | vk::Format::R16G16B16_SNORM
-        | vk::Format::R16G16B16_USCALED
-        | vk::Format::R16G16B16_SSCALED
-        | vk::Format::R16G16B16_UINT
-        | vk::Format::R16G16B16_SINT
-        | vk::Format::R16G16B16_SFLOAT => Some(6),
-        vk::Format::R16G16B16A16_UNORM
-        | vk::Format::R16G16B16A16_SNORM
-        | vk::Format::R16G16B16A16_USCALED
-        | vk::Format::R16G16B16A16_SSCALED
-        | vk::Format::R16G16B16A16_UINT
-        | vk::Format::R16G16B16A16_SINT
-        | vk::Format::R16G16B16A16_SFLOAT => Some(8),
-
-        vk::Format::R32_UINT | vk::Format::R32_SINT | vk::Format::R32_SFLOAT => Some(4),
-        vk::Format::R32G32_UINT | vk::Format::R32G32_SINT | vk::Format::R32G32_SFLOAT => Some(8),
-        vk::Format::R32G32B32_UINT | vk::Format::R32G32B32_SINT | vk::Format::R32G32B32_SFLOAT => {
-            Some(12)
+    match FormatInfo::of(*format) {
+        Some(info)
+            if info.block_extent.width == 1
+                && info.block_extent.height == 1
+                && !info.is_multi_planar() =>
+        {
+            Some(info.block_size as u8)
         }
-        vk::Format::R32G32B32A32_UINT
-        | vk::Format::R32G32B32A32_SINT
-        | vk::Format::R32G32B32A32_SFLOAT => Some(16),
-
-        vk::Format::E5B9G9R9_UFLOAT_PACK32 => Some(4),
         _ => {
             #[cfg(feature = "logging")]
             log::error!("Format {:#?} is not supported by byte_per_pixel()", format);
@@ -380,54 +263,13 @@ pub fn byte_per_pixel(format: &vk::Format) -> Option<u8> {
     }
 }
 
-///Returns true if `format` is an `_SRGB` suffixed format. This is, simillar to [byte_per_pixel] only implemented for core formats. If unsure, have a look at the implementation.
+///Returns true if `format` is an `_SRGB` suffixed format.
 pub fn is_srgb(format: &vk::Format) -> bool {
-    matches!(
-        *format,
-        vk::Format::R8_SRGB
-            | vk::Format::BC2_SRGB_BLOCK
-            | vk::Format::BC3_SRGB_BLOCK
-            | vk::Format::BC7_SRGB_BLOCK
-            | vk::Format::R8G8_SRGB
-            | vk::Format::ASTC_4X4_SRGB_BLOCK
-            | vk::Format::ASTC_5X5_SRGB_BLOCK
-            | vk::Format::ASTC_6X6_SRGB_BLOCK
-            | vk::Format::ASTC_8X5_SRGB_BLOCK
-            | vk::Format::ASTC_8X6_SRGB_BLOCK
-            | vk::Format::ASTC_8X8_SRGB_BLOCK
-            | vk::Format::ASTC_10X5_SRGB_BLOCK
-            | vk::Format::ASTC_10X6_SRGB_BLOCK
-            | vk::Format::ASTC_10X8_SRGB_BLOCK
-            | vk::Format::ASTC_10X10_SRGB_BLOCK
-            | vk::Format::ASTC_12X10_SRGB_BLOCK
-            | vk::Format::ASTC_12X12_SRGB_BLOCK
-            | vk::Format::B8G8R8_SRGB
-            | vk::Format::R8G8B8_SRGB
-            | vk::Format::BC1_RGB_SRGB_BLOCK
-            | vk::Format::A8B8G8R8_SRGB_PACK32
-            | vk::Format::B8G8R8A8_SRGB
-            | vk::Format::BC1_RGBA_SRGB_BLOCK
-            | vk::Format::R8G8B8A8_SRGB
-            | vk::Format::ETC2_R8G8B8_SRGB_BLOCK
-            | vk::Format::PVRTC1_2BPP_SRGB_BLOCK_IMG
-            | vk::Format::PVRTC1_4BPP_SRGB_BLOCK_IMG
-            | vk::Format::PVRTC2_2BPP_SRGB_BLOCK_IMG
-            | vk::Format::PVRTC2_4BPP_SRGB_BLOCK_IMG
-            | vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK
-            | vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK
-    )
+    FormatInfo::of(*format).is_some_and(|info| info.is_srgb())
 }
 
 ///Returns true if this format contains the 'D' property, i.e. is one of the dedicated
 /// depth formats
 pub fn is_depth(format: &vk::Format) -> bool {
-    matches!(
-        *format,
-        vk::Format::D16_UNORM
-            | vk::Format::D16_UNORM_S8_UINT
-            | vk::Format::D24_UNORM_S8_UINT
-            | vk::Format::D32_SFLOAT
-            | vk::Format::D32_SFLOAT_S8_UINT
-            | vk::Format::X8_D24_UNORM_PACK32
-    )
+    FormatInfo::of(*format).is_some_and(|info| info.is_depth())
 }
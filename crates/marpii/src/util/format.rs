@@ -0,0 +1,781 @@
+use ash::vk;
+
+///How the components of a format are interpreted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NumericType {
+    Unorm,
+    Snorm,
+    Uscaled,
+    Sscaled,
+    Uint,
+    Sint,
+    Ufloat,
+    Sfloat,
+    ///Unsigned normalized with sRGB non-linear encoding of the color components.
+    Srgb,
+    ///Signed fixed-point with 5 fractional bits, used by `R16G16_S10_5_NV`.
+    Sfixed5,
+}
+
+///Block compression scheme of a compressed format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Compression {
+    Bc,
+    Etc2,
+    Eac,
+    Astc,
+    Pvrtc,
+}
+
+///Static description of a [vk::Format]. Query it via [FormatInfo::of].
+///
+/// Sizes follow the _texel block_ definition of the Vulkan specification: Uncompressed formats have a block extent of 1x1x1,
+/// compressed formats store `block_size` bytes for each `block_extent` region of texels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FormatInfo {
+    ///Texel extent of a single block.
+    pub block_extent: vk::Extent3D,
+    ///Bytes per block. For multi-planar formats this is the size of a single texel of the first plane.
+    pub block_size: u32,
+    pub component_count: u8,
+    ///Bits per component in the order of the format's name, for instance B, G, R, A for `B8G8R8A8_UNORM`. Padding bits are not counted,
+    /// unused components are 0. Compressed formats report 0 for all components.
+    pub component_bits: [u8; 4],
+    ///Numeric type of the components. For combined depth-stencil formats, the type of the depth component.
+    pub numeric_type: NumericType,
+    pub aspect: vk::ImageAspectFlags,
+    pub compression: Option<Compression>,
+    ///Number of planes of multi-planar (YCbCr) formats, 1 otherwise.
+    pub plane_count: u8,
+    ///Horizontal and vertical subsampling of the chroma components of YCbCr formats, `[1, 1]` otherwise.
+    pub chroma_subsampling: [u8; 2],
+}
+
+const fn color(component_bits: [u8; 4], numeric_type: NumericType, block_size: u32) -> FormatInfo {
+    let mut component_count = 0;
+    while component_count < 4 && component_bits[component_count] != 0 {
+        component_count += 1;
+    }
+    FormatInfo {
+        block_extent: vk::Extent3D {
+            width: 1,
+            height: 1,
+            depth: 1,
+        },
+        block_size,
+        component_count: component_count as u8,
+        component_bits,
+        numeric_type,
+        aspect: vk::ImageAspectFlags::COLOR,
+        compression: None,
+        plane_count: 1,
+        chroma_subsampling: [1, 1],
+    }
+}
+
+const DEPTH: vk::ImageAspectFlags = vk::ImageAspectFlags::DEPTH;
+const STENCIL: vk::ImageAspectFlags = vk::ImageAspectFlags::STENCIL;
+const DEPTH_STENCIL: vk::ImageAspectFlags =
+    vk::ImageAspectFlags::from_raw(DEPTH.as_raw() | STENCIL.as_raw());
+
+const fn depth_stencil(
+    component_bits: [u8; 4],
+    numeric_type: NumericType,
+    aspect: vk::ImageAspectFlags,
+    block_size: u32,
+) -> FormatInfo {
+    FormatInfo {
+        aspect,
+        ..color(component_bits, numeric_type, block_size)
+    }
+}
+
+const fn compressed(
+    compression: Compression,
+    block_extent: [u32; 2],
+    block_size: u32,
+    component_count: u8,
+    numeric_type: NumericType,
+) -> FormatInfo {
+    FormatInfo {
+        block_extent: vk::Extent3D {
+            width: block_extent[0],
+            height: block_extent[1],
+            depth: 1,
+        },
+        component_count,
+        compression: Some(compression),
+        ..color([0; 4], numeric_type, block_size)
+    }
+}
+
+///Single plane 4:2:2 format that stores two texels (G, B, G, R) per block.
+const fn packed_422(bits: u8, block_size: u32) -> FormatInfo {
+    FormatInfo {
+        block_extent: vk::Extent3D {
+            width: 2,
+            height: 1,
+            depth: 1,
+        },
+        chroma_subsampling: [2, 1],
+        ..color([bits; 4], NumericType::Unorm, block_size)
+    }
+}
+
+const fn multi_planar(
+    plane_count: u8,
+    bits: u8,
+    chroma_subsampling: [u8; 2],
+    block_size: u32,
+) -> FormatInfo {
+    FormatInfo {
+        plane_count,
+        chroma_subsampling,
+        ..color([bits, bits, bits, 0], NumericType::Unorm, block_size)
+    }
+}
+
+impl FormatInfo {
+    ///Returns the description of `format`, or `None` for `UNDEFINED` and formats unknown to marpii.
+    pub const fn of(format: vk::Format) -> Option<Self> {
+        let info = match format {
+            vk::Format::R4G4_UNORM_PACK8 => color([4, 4, 0, 0], NumericType::Unorm, 1),
+            vk::Format::R4G4B4A4_UNORM_PACK16 => color([4, 4, 4, 4], NumericType::Unorm, 2),
+            vk::Format::B4G4R4A4_UNORM_PACK16 => color([4, 4, 4, 4], NumericType::Unorm, 2),
+            vk::Format::R5G6B5_UNORM_PACK16 => color([5, 6, 5, 0], NumericType::Unorm, 2),
+            vk::Format::B5G6R5_UNORM_PACK16 => color([5, 6, 5, 0], NumericType::Unorm, 2),
+            vk::Format::R5G5B5A1_UNORM_PACK16 => color([5, 5, 5, 1], NumericType::Unorm, 2),
+            vk::Format::B5G5R5A1_UNORM_PACK16 => color([5, 5, 5, 1], NumericType::Unorm, 2),
+            vk::Format::A1R5G5B5_UNORM_PACK16 => color([1, 5, 5, 5], NumericType::Unorm, 2),
+            vk::Format::R8_UNORM => color([8, 0, 0, 0], NumericType::Unorm, 1),
+            vk::Format::R8_SNORM => color([8, 0, 0, 0], NumericType::Snorm, 1),
+            vk::Format::R8_USCALED => color([8, 0, 0, 0], NumericType::Uscaled, 1),
+            vk::Format::R8_SSCALED => color([8, 0, 0, 0], NumericType::Sscaled, 1),
+            vk::Format::R8_UINT => color([8, 0, 0, 0], NumericType::Uint, 1),
+            vk::Format::R8_SINT => color([8, 0, 0, 0], NumericType::Sint, 1),
+            vk::Format::R8_SRGB => color([8, 0, 0, 0], NumericType::Srgb, 1),
+            vk::Format::R8G8_UNORM => color([8, 8, 0, 0], NumericType::Unorm, 2),
+            vk::Format::R8G8_SNORM => color([8, 8, 0, 0], NumericType::Snorm, 2),
+            vk::Format::R8G8_USCALED => color([8, 8, 0, 0], NumericType::Uscaled, 2),
+            vk::Format::R8G8_SSCALED => color([8, 8, 0, 0], NumericType::Sscaled, 2),
+            vk::Format::R8G8_UINT => color([8, 8, 0, 0], NumericType::Uint, 2),
+            vk::Format::R8G8_SINT => color([8, 8, 0, 0], NumericType::Sint, 2),
+            vk::Format::R8G8_SRGB => color([8, 8, 0, 0], NumericType::Srgb, 2),
+            vk::Format::R8G8B8_UNORM => color([8, 8, 8, 0], NumericType::Unorm, 3),
+            vk::Format::R8G8B8_SNORM => color([8, 8, 8, 0], NumericType::Snorm, 3),
+            vk::Format::R8G8B8_USCALED => color([8, 8, 8, 0], NumericType::Uscaled, 3),
+            vk::Format::R8G8B8_SSCALED => color([8, 8, 8, 0], NumericType::Sscaled, 3),
+            vk::Format::R8G8B8_UINT => color([8, 8, 8, 0], NumericType::Uint, 3),
+            vk::Format::R8G8B8_SINT => color([8, 8, 8, 0], NumericType::Sint, 3),
+            vk::Format::R8G8B8_SRGB => color([8, 8, 8, 0], NumericType::Srgb, 3),
+            vk::Format::B8G8R8_UNORM => color([8, 8, 8, 0], NumericType::Unorm, 3),
+            vk::Format::B8G8R8_SNORM => color([8, 8, 8, 0], NumericType::Snorm, 3),
+            vk::Format::B8G8R8_USCALED => color([8, 8, 8, 0], NumericType::Uscaled, 3),
+            vk::Format::B8G8R8_SSCALED => color([8, 8, 8, 0], NumericType::Sscaled, 3),
+            vk::Format::B8G8R8_UINT => color([8, 8, 8, 0], NumericType::Uint, 3),
+            vk::Format::B8G8R8_SINT => color([8, 8, 8, 0], NumericType::Sint, 3),
+            vk::Format::B8G8R8_SRGB => color([8, 8, 8, 0], NumericType::Srgb, 3),
+            vk::Format::R8G8B8A8_UNORM => color([8, 8, 8, 8], NumericType::Unorm, 4),
+            vk::Format::R8G8B8A8_SNORM => color([8, 8, 8, 8], NumericType::Snorm, 4),
+            vk::Format::R8G8B8A8_USCALED => color([8, 8, 8, 8], NumericType::Uscaled, 4),
+            vk::Format::R8G8B8A8_SSCALED => color([8, 8, 8, 8], NumericType::Sscaled, 4),
+            vk::Format::R8G8B8A8_UINT => color([8, 8, 8, 8], NumericType::Uint, 4),
+            vk::Format::R8G8B8A8_SINT => color([8, 8, 8, 8], NumericType::Sint, 4),
+            vk::Format::R8G8B8A8_SRGB => color([8, 8, 8, 8], NumericType::Srgb, 4),
+            vk::Format::B8G8R8A8_UNORM => color([8, 8, 8, 8], NumericType::Unorm, 4),
+            vk::Format::B8G8R8A8_SNORM => color([8, 8, 8, 8], NumericType::Snorm, 4),
+            vk::Format::B8G8R8A8_USCALED => color([8, 8, 8, 8], NumericType::Uscaled, 4),
+            vk::Format::B8G8R8A8_SSCALED => color([8, 8, 8, 8], NumericType::Sscaled, 4),
+            vk::Format::B8G8R8A8_UINT => color([8, 8, 8, 8], NumericType::Uint, 4),
+            vk::Format::B8G8R8A8_SINT => color([8, 8, 8, 8], NumericType::Sint, 4),
+            vk::Format::B8G8R8A8_SRGB => color([8, 8, 8, 8], NumericType::Srgb, 4),
+            vk::Format::A8B8G8R8_UNORM_PACK32 => color([8, 8, 8, 8], NumericType::Unorm, 4),
+            vk::Format::A8B8G8R8_SNORM_PACK32 => color([8, 8, 8, 8], NumericType::Snorm, 4),
+            vk::Format::A8B8G8R8_USCALED_PACK32 => color([8, 8, 8, 8], NumericType::Uscaled, 4),
+            vk::Format::A8B8G8R8_SSCALED_PACK32 => color([8, 8, 8, 8], NumericType::Sscaled, 4),
+            vk::Format::A8B8G8R8_UINT_PACK32 => color([8, 8, 8, 8], NumericType::Uint, 4),
+            vk::Format::A8B8G8R8_SINT_PACK32 => color([8, 8, 8, 8], NumericType::Sint, 4),
+            vk::Format::A8B8G8R8_SRGB_PACK32 => color([8, 8, 8, 8], NumericType::Srgb, 4),
+            vk::Format::A2R10G10B10_UNORM_PACK32 => color([2, 10, 10, 10], NumericType::Unorm, 4),
+            vk::Format::A2R10G10B10_SNORM_PACK32 => color([2, 10, 10, 10], NumericType::Snorm, 4),
+            vk::Format::A2R10G10B10_USCALED_PACK32 => {
+                color([2, 10, 10, 10], NumericType::Uscaled, 4)
+            }
+            vk::Format::A2R10G10B10_SSCALED_PACK32 => {
+                color([2, 10, 10, 10], NumericType::Sscaled, 4)
+            }
+            vk::Format::A2R10G10B10_UINT_PACK32 => color([2, 10, 10, 10], NumericType::Uint, 4),
+            vk::Format::A2R10G10B10_SINT_PACK32 => color([2, 10, 10, 10], NumericType::Sint, 4),
+            vk::Format::A2B10G10R10_UNORM_PACK32 => color([2, 10, 10, 10], NumericType::Unorm, 4),
+            vk::Format::A2B10G10R10_SNORM_PACK32 => color([2, 10, 10, 10], NumericType::Snorm, 4),
+            vk::Format::A2B10G10R10_USCALED_PACK32 => {
+                color([2, 10, 10, 10], NumericType::Uscaled, 4)
+            }
+            vk::Format::A2B10G10R10_SSCALED_PACK32 => {
+                color([2, 10, 10, 10], NumericType::Sscaled, 4)
+            }
+            vk::Format::A2B10G10R10_UINT_PACK32 => color([2, 10, 10, 10], NumericType::Uint, 4),
+            vk::Format::A2B10G10R10_SINT_PACK32 => color([2, 10, 10, 10], NumericType::Sint, 4),
+            vk::Format::R16_UNORM => color([16, 0, 0, 0], NumericType::Unorm, 2),
+            vk::Format::R16_SNORM => color([16, 0, 0, 0], NumericType::Snorm, 2),
+            vk::Format::R16_USCALED => color([16, 0, 0, 0], NumericType::Uscaled, 2),
+            vk::Format::R16_SSCALED => color([16, 0, 0, 0], NumericType::Sscaled, 2),
+            vk::Format::R16_UINT => color([16, 0, 0, 0], NumericType::Uint, 2),
+            vk::Format::R16_SINT => color([16, 0, 0, 0], NumericType::Sint, 2),
+            vk::Format::R16_SFLOAT => color([16, 0, 0, 0], NumericType::Sfloat, 2),
+            vk::Format::R16G16_UNORM => color([16, 16, 0, 0], NumericType::Unorm, 4),
+            vk::Format::R16G16_SNORM => color([16, 16, 0, 0], NumericType::Snorm, 4),
+            vk::Format::R16G16_USCALED => color([16, 16, 0, 0], NumericType::Uscaled, 4),
+            vk::Format::R16G16_SSCALED => color([16, 16, 0, 0], NumericType::Sscaled, 4),
+            vk::Format::R16G16_UINT => color([16, 16, 0, 0], NumericType::Uint, 4),
+            vk::Format::R16G16_SINT => color([16, 16, 0, 0], NumericType::Sint, 4),
+            vk::Format::R16G16_SFLOAT => color([16, 16, 0, 0], NumericType::Sfloat, 4),
+            vk::Format::R16G16B16_UNORM => color([16, 16, 16, 0], NumericType::Unorm, 6),
+            vk::Format::R16G16B16_SNORM => color([16, 16, 16, 0], NumericType::Snorm, 6),
+            vk::Format::R16G16B16_USCALED => color([16, 16, 16, 0], NumericType::Uscaled, 6),
+            vk::Format::R16G16B16_SSCALED => color([16, 16, 16, 0], NumericType::Sscaled, 6),
+            vk::Format::R16G16B16_UINT => color([16, 16, 16, 0], NumericType::Uint, 6),
+            vk::Format::R16G16B16_SINT => color([16, 16, 16, 0], NumericType::Sint, 6),
+            vk::Format::R16G16B16_SFLOAT => color([16, 16, 16, 0], NumericType::Sfloat, 6),
+            vk::Format::R16G16B16A16_UNORM => color([16, 16, 16, 16], NumericType::Unorm, 8),
+            vk::Format::R16G16B16A16_SNORM => color([16, 16, 16, 16], NumericType::Snorm, 8),
+            vk::Format::R16G16B16A16_USCALED => color([16, 16, 16, 16], NumericType::Uscaled, 8),
+            vk::Format::R16G16B16A16_SSCALED => color([16, 16, 16, 16], NumericType::Sscaled, 8),
+            vk::Format::R16G16B16A16_UINT => color([16, 16, 16, 16], NumericType::Uint, 8),
+            vk::Format::R16G16B16A16_SINT => color([16, 16, 16, 16], NumericType::Sint, 8),
+            vk::Format::R16G16B16A16_SFLOAT => color([16, 16, 16, 16], NumericType::Sfloat, 8),
+            vk::Format::R32_UINT => color([32, 0, 0, 0], NumericType::Uint, 4),
+            vk::Format::R32_SINT => color([32, 0, 0, 0], NumericType::Sint, 4),
+            vk::Format::R32_SFLOAT => color([32, 0, 0, 0], NumericType::Sfloat, 4),
+            vk::Format::R32G32_UINT => color([32, 32, 0, 0], NumericType::Uint, 8),
+            vk::Format::R32G32_SINT => color([32, 32, 0, 0], NumericType::Sint, 8),
+            vk::Format::R32G32_SFLOAT => color([32, 32, 0, 0], NumericType::Sfloat, 8),
+            vk::Format::R32G32B32_UINT => color([32, 32, 32, 0], NumericType::Uint, 12),
+            vk::Format::R32G32B32_SINT => color([32, 32, 32, 0], NumericType::Sint, 12),
+            vk::Format::R32G32B32_SFLOAT => color([32, 32, 32, 0], NumericType::Sfloat, 12),
+            vk::Format::R32G32B32A32_UINT => color([32, 32, 32, 32], NumericType::Uint, 16),
+            vk::Format::R32G32B32A32_SINT => color([32, 32, 32, 32], NumericType::Sint, 16),
+            vk::Format::R32G32B32A32_SFLOAT => color([32, 32, 32, 32], NumericType::Sfloat, 16),
+            vk::Format::R64_UINT => color([64, 0, 0, 0], NumericType::Uint, 8),
+            vk::Format::R64_SINT => color([64, 0, 0, 0], NumericType::Sint, 8),
+            vk::Format::R64_SFLOAT => color([64, 0, 0, 0], NumericType::Sfloat, 8),
+            vk::Format::R64G64_UINT => color([64, 64, 0, 0], NumericType::Uint, 16),
+            vk::Format::R64G64_SINT => color([64, 64, 0, 0], NumericType::Sint, 16),
+            vk::Format::R64G64_SFLOAT => color([64, 64, 0, 0], NumericType::Sfloat, 16),
+            vk::Format::R64G64B64_UINT => color([64, 64, 64, 0], NumericType::Uint, 24),
+            vk::Format::R64G64B64_SINT => color([64, 64, 64, 0], NumericType::Sint, 24),
+            vk::Format::R64G64B64_SFLOAT => color([64, 64, 64, 0], NumericType::Sfloat, 24),
+            vk::Format::R64G64B64A64_UINT => color([64, 64, 64, 64], NumericType::Uint, 32),
+            vk::Format::R64G64B64A64_SINT => color([64, 64, 64, 64], NumericType::Sint, 32),
+            vk::Format::R64G64B64A64_SFLOAT => color([64, 64, 64, 64], NumericType::Sfloat, 32),
+            vk::Format::B10G11R11_UFLOAT_PACK32 => color([10, 11, 11, 0], NumericType::Ufloat, 4),
+            vk::Format::E5B9G9R9_UFLOAT_PACK32 => color([9, 9, 9, 0], NumericType::Ufloat, 4),
+            vk::Format::D16_UNORM => depth_stencil([16, 0, 0, 0], NumericType::Unorm, DEPTH, 2),
+            vk::Format::X8_D24_UNORM_PACK32 => {
+                depth_stencil([24, 0, 0, 0], NumericType::Unorm, DEPTH, 4)
+            }
+            vk::Format::D32_SFLOAT => depth_stencil([32, 0, 0, 0], NumericType::Sfloat, DEPTH, 4),
+            vk::Format::S8_UINT => depth_stencil([8, 0, 0, 0], NumericType::Uint, STENCIL, 1),
+            vk::Format::D16_UNORM_S8_UINT => {
+                depth_stencil([16, 8, 0, 0], NumericType::Unorm, DEPTH_STENCIL, 3)
+            }
+            vk::Format::D24_UNORM_S8_UINT => {
+                depth_stencil([24, 8, 0, 0], NumericType::Unorm, DEPTH_STENCIL, 4)
+            }
+            vk::Format::D32_SFLOAT_S8_UINT => {
+                depth_stencil([32, 8, 0, 0], NumericType::Sfloat, DEPTH_STENCIL, 5)
+            }
+            vk::Format::BC1_RGB_UNORM_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 8, 3, NumericType::Unorm)
+            }
+            vk::Format::BC1_RGB_SRGB_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 8, 3, NumericType::Srgb)
+            }
+            vk::Format::BC1_RGBA_UNORM_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 8, 4, NumericType::Unorm)
+            }
+            vk::Format::BC1_RGBA_SRGB_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 8, 4, NumericType::Srgb)
+            }
+            vk::Format::BC2_UNORM_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::BC2_SRGB_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::BC3_UNORM_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::BC3_SRGB_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::BC4_UNORM_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 8, 1, NumericType::Unorm)
+            }
+            vk::Format::BC4_SNORM_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 8, 1, NumericType::Snorm)
+            }
+            vk::Format::BC5_UNORM_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 16, 2, NumericType::Unorm)
+            }
+            vk::Format::BC5_SNORM_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 16, 2, NumericType::Snorm)
+            }
+            vk::Format::BC6H_UFLOAT_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 16, 3, NumericType::Ufloat)
+            }
+            vk::Format::BC6H_SFLOAT_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 16, 3, NumericType::Sfloat)
+            }
+            vk::Format::BC7_UNORM_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::BC7_SRGB_BLOCK => {
+                compressed(Compression::Bc, [4, 4], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ETC2_R8G8B8_UNORM_BLOCK => {
+                compressed(Compression::Etc2, [4, 4], 8, 3, NumericType::Unorm)
+            }
+            vk::Format::ETC2_R8G8B8_SRGB_BLOCK => {
+                compressed(Compression::Etc2, [4, 4], 8, 3, NumericType::Srgb)
+            }
+            vk::Format::ETC2_R8G8B8A1_UNORM_BLOCK => {
+                compressed(Compression::Etc2, [4, 4], 8, 4, NumericType::Unorm)
+            }
+            vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK => {
+                compressed(Compression::Etc2, [4, 4], 8, 4, NumericType::Srgb)
+            }
+            vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK => {
+                compressed(Compression::Etc2, [4, 4], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK => {
+                compressed(Compression::Etc2, [4, 4], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::EAC_R11_UNORM_BLOCK => {
+                compressed(Compression::Eac, [4, 4], 8, 1, NumericType::Unorm)
+            }
+            vk::Format::EAC_R11_SNORM_BLOCK => {
+                compressed(Compression::Eac, [4, 4], 8, 1, NumericType::Snorm)
+            }
+            vk::Format::EAC_R11G11_UNORM_BLOCK => {
+                compressed(Compression::Eac, [4, 4], 16, 2, NumericType::Unorm)
+            }
+            vk::Format::EAC_R11G11_SNORM_BLOCK => {
+                compressed(Compression::Eac, [4, 4], 16, 2, NumericType::Snorm)
+            }
+            vk::Format::ASTC_4X4_UNORM_BLOCK => {
+                compressed(Compression::Astc, [4, 4], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_4X4_SRGB_BLOCK => {
+                compressed(Compression::Astc, [4, 4], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_5X4_UNORM_BLOCK => {
+                compressed(Compression::Astc, [5, 4], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_5X4_SRGB_BLOCK => {
+                compressed(Compression::Astc, [5, 4], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_5X5_UNORM_BLOCK => {
+                compressed(Compression::Astc, [5, 5], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_5X5_SRGB_BLOCK => {
+                compressed(Compression::Astc, [5, 5], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_6X5_UNORM_BLOCK => {
+                compressed(Compression::Astc, [6, 5], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_6X5_SRGB_BLOCK => {
+                compressed(Compression::Astc, [6, 5], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_6X6_UNORM_BLOCK => {
+                compressed(Compression::Astc, [6, 6], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_6X6_SRGB_BLOCK => {
+                compressed(Compression::Astc, [6, 6], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_8X5_UNORM_BLOCK => {
+                compressed(Compression::Astc, [8, 5], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_8X5_SRGB_BLOCK => {
+                compressed(Compression::Astc, [8, 5], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_8X6_UNORM_BLOCK => {
+                compressed(Compression::Astc, [8, 6], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_8X6_SRGB_BLOCK => {
+                compressed(Compression::Astc, [8, 6], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_8X8_UNORM_BLOCK => {
+                compressed(Compression::Astc, [8, 8], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_8X8_SRGB_BLOCK => {
+                compressed(Compression::Astc, [8, 8], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_10X5_UNORM_BLOCK => {
+                compressed(Compression::Astc, [10, 5], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_10X5_SRGB_BLOCK => {
+                compressed(Compression::Astc, [10, 5], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_10X6_UNORM_BLOCK => {
+                compressed(Compression::Astc, [10, 6], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_10X6_SRGB_BLOCK => {
+                compressed(Compression::Astc, [10, 6], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_10X8_UNORM_BLOCK => {
+                compressed(Compression::Astc, [10, 8], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_10X8_SRGB_BLOCK => {
+                compressed(Compression::Astc, [10, 8], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_10X10_UNORM_BLOCK => {
+                compressed(Compression::Astc, [10, 10], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_10X10_SRGB_BLOCK => {
+                compressed(Compression::Astc, [10, 10], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_12X10_UNORM_BLOCK => {
+                compressed(Compression::Astc, [12, 10], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_12X10_SRGB_BLOCK => {
+                compressed(Compression::Astc, [12, 10], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::ASTC_12X12_UNORM_BLOCK => {
+                compressed(Compression::Astc, [12, 12], 16, 4, NumericType::Unorm)
+            }
+            vk::Format::ASTC_12X12_SRGB_BLOCK => {
+                compressed(Compression::Astc, [12, 12], 16, 4, NumericType::Srgb)
+            }
+            vk::Format::PVRTC1_2BPP_UNORM_BLOCK_IMG => {
+                compressed(Compression::Pvrtc, [8, 4], 8, 4, NumericType::Unorm)
+            }
+            vk::Format::PVRTC1_4BPP_UNORM_BLOCK_IMG => {
+                compressed(Compression::Pvrtc, [4, 4], 8, 4, NumericType::Unorm)
+            }
+            vk::Format::PVRTC2_2BPP_UNORM_BLOCK_IMG => {
+                compressed(Compression::Pvrtc, [8, 4], 8, 4, NumericType::Unorm)
+            }
+            vk::Format::PVRTC2_4BPP_UNORM_BLOCK_IMG => {
+                compressed(Compression::Pvrtc, [4, 4], 8, 4, NumericType::Unorm)
+            }
+            vk::Format::PVRTC1_2BPP_SRGB_BLOCK_IMG => {
+                compressed(Compression::Pvrtc, [8, 4], 8, 4, NumericType::Srgb)
+            }
+            vk::Format::PVRTC1_4BPP_SRGB_BLOCK_IMG => {
+                compressed(Compression::Pvrtc, [4, 4], 8, 4, NumericType::Srgb)
+            }
+            vk::Format::PVRTC2_2BPP_SRGB_BLOCK_IMG => {
+                compressed(Compression::Pvrtc, [8, 4], 8, 4, NumericType::Srgb)
+            }
+            vk::Format::PVRTC2_4BPP_SRGB_BLOCK_IMG => {
+                compressed(Compression::Pvrtc, [4, 4], 8, 4, NumericType::Srgb)
+            }
+            vk::Format::R16G16_S10_5_NV => color([16, 16, 0, 0], NumericType::Sfixed5, 4),
+            vk::Format::A1B5G5R5_UNORM_PACK16_KHR => color([1, 5, 5, 5], NumericType::Unorm, 2),
+            vk::Format::A8_UNORM_KHR => color([8, 0, 0, 0], NumericType::Unorm, 1),
+            vk::Format::G8B8G8R8_422_UNORM => packed_422(8, 4),
+            vk::Format::B8G8R8G8_422_UNORM => packed_422(8, 4),
+            vk::Format::G8_B8_R8_3PLANE_420_UNORM => multi_planar(3, 8, [2, 2], 1),
+            vk::Format::G8_B8R8_2PLANE_420_UNORM => multi_planar(2, 8, [2, 2], 1),
+            vk::Format::G8_B8_R8_3PLANE_422_UNORM => multi_planar(3, 8, [2, 1], 1),
+            vk::Format::G8_B8R8_2PLANE_422_UNORM => multi_planar(2, 8, [2, 1], 1),
+            vk::Format::G8_B8_R8_3PLANE_444_UNORM => multi_planar(3, 8, [1, 1], 1),
+            vk::Format::R10X6_UNORM_PACK16 => color([10, 0, 0, 0], NumericType::Unorm, 2),
+            vk::Format::R10X6G10X6_UNORM_2PACK16 => color([10, 10, 0, 0], NumericType::Unorm, 4),
+            vk::Format::R10X6G10X6B10X6A10X6_UNORM_4PACK16 => {
+                color([10, 10, 10, 10], NumericType::Unorm, 8)
+            }
+            vk::Format::G10X6B10X6G10X6R10X6_422_UNORM_4PACK16 => packed_422(10, 8),
+            vk::Format::B10X6G10X6R10X6G10X6_422_UNORM_4PACK16 => packed_422(10, 8),
+            vk::Format::G10X6_B10X6_R10X6_3PLANE_420_UNORM_3PACK16 => {
+                multi_planar(3, 10, [2, 2], 2)
+            }
+            vk::Format::G10X6_B10X6R10X6_2PLANE_420_UNORM_3PACK16 => multi_planar(2, 10, [2, 2], 2),
+            vk::Format::G10X6_B10X6_R10X6_3PLANE_422_UNORM_3PACK16 => {
+                multi_planar(3, 10, [2, 1], 2)
+            }
+            vk::Format::G10X6_B10X6R10X6_2PLANE_422_UNORM_3PACK16 => multi_planar(2, 10, [2, 1], 2),
+            vk::Format::G10X6_B10X6_R10X6_3PLANE_444_UNORM_3PACK16 => {
+                multi_planar(3, 10, [1, 1], 2)
+            }
+            vk::Format::R12X4_UNORM_PACK16 => color([12, 0, 0, 0], NumericType::Unorm, 2),
+            vk::Format::R12X4G12X4_UNORM_2PACK16 => color([12, 12, 0, 0], NumericType::Unorm, 4),
+            vk::Format::R12X4G12X4B12X4A12X4_UNORM_4PACK16 => {
+                color([12, 12, 12, 12], NumericType::Unorm, 8)
+            }
+            vk::Format::G12X4B12X4G12X4R12X4_422_UNORM_4PACK16 => packed_422(12, 8),
+            vk::Format::B12X4G12X4R12X4G12X4_422_UNORM_4PACK16 => packed_422(12, 8),
+            vk::Format::G12X4_B12X4_R12X4_3PLANE_420_UNORM_3PACK16 => {
+                multi_planar(3, 12, [2, 2], 2)
+            }
+            vk::Format::G12X4_B12X4R12X4_2PLANE_420_UNORM_3PACK16 => multi_planar(2, 12, [2, 2], 2),
+            vk::Format::G12X4_B12X4_R12X4_3PLANE_422_UNORM_3PACK16 => {
+                multi_planar(3, 12, [2, 1], 2)
+            }
+            vk::Format::G12X4_B12X4R12X4_2PLANE_422_UNORM_3PACK16 => multi_planar(2, 12, [2, 1], 2),
+            vk::Format::G12X4_B12X4_R12X4_3PLANE_444_UNORM_3PACK16 => {
+                multi_planar(3, 12, [1, 1], 2)
+            }
+            vk::Format::G16B16G16R16_422_UNORM => packed_422(16, 8),
+            vk::Format::B16G16R16G16_422_UNORM => packed_422(16, 8),
+            vk::Format::G16_B16_R16_3PLANE_420_UNORM => multi_planar(3, 16, [2, 2], 2),
+            vk::Format::G16_B16R16_2PLANE_420_UNORM => multi_planar(2, 16, [2, 2], 2),
+            vk::Format::G16_B16_R16_3PLANE_422_UNORM => multi_planar(3, 16, [2, 1], 2),
+            vk::Format::G16_B16R16_2PLANE_422_UNORM => multi_planar(2, 16, [2, 1], 2),
+            vk::Format::G16_B16_R16_3PLANE_444_UNORM => multi_planar(3, 16, [1, 1], 2),
+            vk::Format::G8_B8R8_2PLANE_444_UNORM => multi_planar(2, 8, [1, 1], 1),
+            vk::Format::G10X6_B10X6R10X6_2PLANE_444_UNORM_3PACK16 => multi_planar(2, 10, [1, 1], 2),
+            vk::Format::G12X4_B12X4R12X4_2PLANE_444_UNORM_3PACK16 => multi_planar(2, 12, [1, 1], 2),
+            vk::Format::G16_B16R16_2PLANE_444_UNORM => multi_planar(2, 16, [1, 1], 2),
+            vk::Format::A4R4G4B4_UNORM_PACK16 => color([4, 4, 4, 4], NumericType::Unorm, 2),
+            vk::Format::A4B4G4R4_UNORM_PACK16 => color([4, 4, 4, 4], NumericType::Unorm, 2),
+            vk::Format::ASTC_4X4_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [4, 4], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_5X4_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [5, 4], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_5X5_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [5, 5], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_6X5_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [6, 5], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_6X6_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [6, 6], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_8X5_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [8, 5], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_8X6_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [8, 6], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_8X8_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [8, 8], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_10X5_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [10, 5], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_10X6_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [10, 6], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_10X8_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [10, 8], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_10X10_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [10, 10], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_12X10_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [12, 10], 16, 4, NumericType::Sfloat)
+            }
+            vk::Format::ASTC_12X12_SFLOAT_BLOCK => {
+                compressed(Compression::Astc, [12, 12], 16, 4, NumericType::Sfloat)
+            }
+            _ => return None,
+        };
+        Some(info)
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compression.is_some()
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        self.numeric_type == NumericType::Srgb
+    }
+
+    pub fn is_depth(&self) -> bool {
+        self.aspect.contains(vk::ImageAspectFlags::DEPTH)
+    }
+
+    pub fn is_stencil(&self) -> bool {
+        self.aspect.contains(vk::ImageAspectFlags::STENCIL)
+    }
+
+    pub fn is_multi_planar(&self) -> bool {
+        self.plane_count > 1
+    }
+
+    ///Number of blocks needed to cover `extent`.
+    pub fn block_count(&self, extent: vk::Extent3D) -> vk::Extent3D {
+        vk::Extent3D {
+            width: extent.width.div_ceil(self.block_extent.width),
+            height: extent.height.div_ceil(self.block_extent.height),
+            depth: extent.depth.div_ceil(self.block_extent.depth),
+        }
+    }
+
+    ///Bytes of a tightly packed region (or single mip level and layer) of `extent`. This is the size a buffer needs for a copy of
+    /// `extent` with `buffer_row_length` and `buffer_image_height` set to 0.
+    ///
+    /// For multi-planar formats the size of all planes is returned, the chroma planes are subsampled by `chroma_subsampling`.
+    pub fn region_size(&self, extent: vk::Extent3D) -> u64 {
+        let blocks = self.block_count(extent);
+        let luma = blocks.width as u64 * blocks.height as u64 * blocks.depth as u64;
+        if self.is_multi_planar() {
+            //Either two single-component chroma planes, or one two-component plane. Both hold two components per chroma texel.
+            let chroma = extent.width.div_ceil(self.chroma_subsampling[0] as u32) as u64
+                * extent.height.div_ceil(self.chroma_subsampling[1] as u32) as u64
+                * extent.depth as u64;
+            (luma + 2 * chroma) * self.block_size as u64
+        } else {
+            luma * self.block_size as u64
+        }
+    }
+
+    ///Bytes of `mip_levels` tightly packed mip levels of `extent`, each containing `layers` layers.
+    pub fn mip_chain_size(&self, extent: vk::Extent3D, mip_levels: u32, layers: u32) -> u64 {
+        (0..mip_levels)
+            .map(|level| self.region_size(mip_extent(extent, level)) * layers as u64)
+            .sum()
+    }
+}
+
+///Extent of `mip_level` of an image with the given base `extent`.
+pub fn mip_extent(extent: vk::Extent3D, mip_level: u32) -> vk::Extent3D {
+    vk::Extent3D {
+        width: (extent.width >> mip_level).max(1),
+        height: (extent.height >> mip_level).max(1),
+        depth: (extent.depth >> mip_level).max(1),
+    }
+}
+
+///Returns the sRGB counterpart of a UNORM `format`, for instance `R8G8B8A8_SRGB` for `R8G8B8A8_UNORM`. Returns `None` if there is none.
+pub const fn srgb_counterpart(format: vk::Format) -> Option<vk::Format> {
+    match format {
+        vk::Format::R8_UNORM => Some(vk::Format::R8_SRGB),
+        vk::Format::R8G8_UNORM => Some(vk::Format::R8G8_SRGB),
+        vk::Format::R8G8B8_UNORM => Some(vk::Format::R8G8B8_SRGB),
+        vk::Format::B8G8R8_UNORM => Some(vk::Format::B8G8R8_SRGB),
+        vk::Format::R8G8B8A8_UNORM => Some(vk::Format::R8G8B8A8_SRGB),
+        vk::Format::B8G8R8A8_UNORM => Some(vk::Format::B8G8R8A8_SRGB),
+        vk::Format::A8B8G8R8_UNORM_PACK32 => Some(vk::Format::A8B8G8R8_SRGB_PACK32),
+        vk::Format::BC1_RGB_UNORM_BLOCK => Some(vk::Format::BC1_RGB_SRGB_BLOCK),
+        vk::Format::BC1_RGBA_UNORM_BLOCK => Some(vk::Format::BC1_RGBA_SRGB_BLOCK),
+        vk::Format::BC2_UNORM_BLOCK => Some(vk::Format::BC2_SRGB_BLOCK),
+        vk::Format::BC3_UNORM_BLOCK => Some(vk::Format::BC3_SRGB_BLOCK),
+        vk::Format::BC7_UNORM_BLOCK => Some(vk::Format::BC7_SRGB_BLOCK),
+        vk::Format::ETC2_R8G8B8_UNORM_BLOCK => Some(vk::Format::ETC2_R8G8B8_SRGB_BLOCK),
+        vk::Format::ETC2_R8G8B8A1_UNORM_BLOCK => Some(vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK),
+        vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK => Some(vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK),
+        vk::Format::ASTC_4X4_UNORM_BLOCK => Some(vk::Format::ASTC_4X4_SRGB_BLOCK),
+        vk::Format::ASTC_5X4_UNORM_BLOCK => Some(vk::Format::ASTC_5X4_SRGB_BLOCK),
+        vk::Format::ASTC_5X5_UNORM_BLOCK => Some(vk::Format::ASTC_5X5_SRGB_BLOCK),
+        vk::Format::ASTC_6X5_UNORM_BLOCK => Some(vk::Format::ASTC_6X5_SRGB_BLOCK),
+        vk::Format::ASTC_6X6_UNORM_BLOCK => Some(vk::Format::ASTC_6X6_SRGB_BLOCK),
+        vk::Format::ASTC_8X5_UNORM_BLOCK => Some(vk::Format::ASTC_8X5_SRGB_BLOCK),
+        vk::Format::ASTC_8X6_UNORM_BLOCK => Some(vk::Format::ASTC_8X6_SRGB_BLOCK),
+        vk::Format::ASTC_8X8_UNORM_BLOCK => Some(vk::Format::ASTC_8X8_SRGB_BLOCK),
+        vk::Format::ASTC_10X5_UNORM_BLOCK => Some(vk::Format::ASTC_10X5_SRGB_BLOCK),
+        vk::Format::ASTC_10X6_UNORM_BLOCK => Some(vk::Format::ASTC_10X6_SRGB_BLOCK),
+        vk::Format::ASTC_10X8_UNORM_BLOCK => Some(vk::Format::ASTC_10X8_SRGB_BLOCK),
+        vk::Format::ASTC_10X10_UNORM_BLOCK => Some(vk::Format::ASTC_10X10_SRGB_BLOCK),
+        vk::Format::ASTC_12X10_UNORM_BLOCK => Some(vk::Format::ASTC_12X10_SRGB_BLOCK),
+        vk::Format::ASTC_12X12_UNORM_BLOCK => Some(vk::Format::ASTC_12X12_SRGB_BLOCK),
+        vk::Format::PVRTC1_2BPP_UNORM_BLOCK_IMG => Some(vk::Format::PVRTC1_2BPP_SRGB_BLOCK_IMG),
+        vk::Format::PVRTC1_4BPP_UNORM_BLOCK_IMG => Some(vk::Format::PVRTC1_4BPP_SRGB_BLOCK_IMG),
+        vk::Format::PVRTC2_2BPP_UNORM_BLOCK_IMG => Some(vk::Format::PVRTC2_2BPP_SRGB_BLOCK_IMG),
+        vk::Format::PVRTC2_4BPP_UNORM_BLOCK_IMG => Some(vk::Format::PVRTC2_4BPP_SRGB_BLOCK_IMG),
+        _ => None,
+    }
+}
+
+///Returns the UNORM counterpart of an sRGB `format`, for instance `R8G8B8A8_UNORM` for `R8G8B8A8_SRGB`. Returns `None` if there is none.
+pub const fn unorm_counterpart(format: vk::Format) -> Option<vk::Format> {
+    match format {
+        vk::Format::R8_SRGB => Some(vk::Format::R8_UNORM),
+        vk::Format::R8G8_SRGB => Some(vk::Format::R8G8_UNORM),
+        vk::Format::R8G8B8_SRGB => Some(vk::Format::R8G8B8_UNORM),
+        vk::Format::B8G8R8_SRGB => Some(vk::Format::B8G8R8_UNORM),
+        vk::Format::R8G8B8A8_SRGB => Some(vk::Format::R8G8B8A8_UNORM),
+        vk::Format::B8G8R8A8_SRGB => Some(vk::Format::B8G8R8A8_UNORM),
+        vk::Format::A8B8G8R8_SRGB_PACK32 => Some(vk::Format::A8B8G8R8_UNORM_PACK32),
+        vk::Format::BC1_RGB_SRGB_BLOCK => Some(vk::Format::BC1_RGB_UNORM_BLOCK),
+        vk::Format::BC1_RGBA_SRGB_BLOCK => Some(vk::Format::BC1_RGBA_UNORM_BLOCK),
+        vk::Format::BC2_SRGB_BLOCK => Some(vk::Format::BC2_UNORM_BLOCK),
+        vk::Format::BC3_SRGB_BLOCK => Some(vk::Format::BC3_UNORM_BLOCK),
+        vk::Format::BC7_SRGB_BLOCK => Some(vk::Format::BC7_UNORM_BLOCK),
+        vk::Format::ETC2_R8G8B8_SRGB_BLOCK => Some(vk::Format::ETC2_R8G8B8_UNORM_BLOCK),
+        vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK => Some(vk::Format::ETC2_R8G8B8A1_UNORM_BLOCK),
+        vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK => Some(vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK),
+        vk::Format::ASTC_4X4_SRGB_BLOCK => Some(vk::Format::ASTC_4X4_UNORM_BLOCK),
+        vk::Format::ASTC_5X4_SRGB_BLOCK => Some(vk::Format::ASTC_5X4_UNORM_BLOCK),
+        vk::Format::ASTC_5X5_SRGB_BLOCK => Some(vk::Format::ASTC_5X5_UNORM_BLOCK),
+        vk::Format::ASTC_6X5_SRGB_BLOCK => Some(vk::Format::ASTC_6X5_UNORM_BLOCK),
+        vk::Format::ASTC_6X6_SRGB_BLOCK => Some(vk::Format::ASTC_6X6_UNORM_BLOCK),
+        vk::Format::ASTC_8X5_SRGB_BLOCK => Some(vk::Format::ASTC_8X5_UNORM_BLOCK),
+        vk::Format::ASTC_8X6_SRGB_BLOCK => Some(vk::Format::ASTC_8X6_UNORM_BLOCK),
+        vk::Format::ASTC_8X8_SRGB_BLOCK => Some(vk::Format::ASTC_8X8_UNORM_BLOCK),
+        vk::Format::ASTC_10X5_SRGB_BLOCK => Some(vk::Format::ASTC_10X5_UNORM_BLOCK),
+        vk::Format::ASTC_10X6_SRGB_BLOCK => Some(vk::Format::ASTC_10X6_UNORM_BLOCK),
+        vk::Format::ASTC_10X8_SRGB_BLOCK => Some(vk::Format::ASTC_10X8_UNORM_BLOCK),
+        vk::Format::ASTC_10X10_SRGB_BLOCK => Some(vk::Format::ASTC_10X10_UNORM_BLOCK),
+        vk::Format::ASTC_12X10_SRGB_BLOCK => Some(vk::Format::ASTC_12X10_UNORM_BLOCK),
+        vk::Format::ASTC_12X12_SRGB_BLOCK => Some(vk::Format::ASTC_12X12_UNORM_BLOCK),
+        vk::Format::PVRTC1_2BPP_SRGB_BLOCK_IMG => Some(vk::Format::PVRTC1_2BPP_UNORM_BLOCK_IMG),
+        vk::Format::PVRTC1_4BPP_SRGB_BLOCK_IMG => Some(vk::Format::PVRTC1_4BPP_UNORM_BLOCK_IMG),
+        vk::Format::PVRTC2_2BPP_SRGB_BLOCK_IMG => Some(vk::Format::PVRTC2_2BPP_UNORM_BLOCK_IMG),
+        vk::Format::PVRTC2_4BPP_SRGB_BLOCK_IMG => Some(vk::Format::PVRTC2_4BPP_UNORM_BLOCK_IMG),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        let extent = vk::Extent3D {
+            width: 13,
+            height: 7,
+            depth: 1,
+        };
+        let rgba = FormatInfo::of(vk::Format::R8G8B8A8_UNORM).unwrap();
+        assert_eq!(rgba.region_size(extent), 13 * 7 * 4);
+        let bc1 = FormatInfo::of(vk::Format::BC1_RGB_UNORM_BLOCK).unwrap();
+        assert_eq!(bc1.region_size(extent), 4 * 2 * 8);
+        let astc = FormatInfo::of(vk::Format::ASTC_10X5_SRGB_BLOCK).unwrap();
+        assert_eq!(astc.region_size(extent), 2 * 2 * 16);
+        let nv12 = FormatInfo::of(vk::Format::G8_B8R8_2PLANE_420_UNORM).unwrap();
+        assert_eq!(nv12.region_size(extent), 13 * 7 + 2 * 7 * 4);
+        //4x4 -> 2x2 -> 1x1, each padded to a single block
+        assert_eq!(
+            bc1.mip_chain_size(
+                vk::Extent3D {
+                    width: 4,
+                    height: 4,
+                    depth: 1
+                },
+                3,
+                6
+            ),
+            3 * 6 * 8
+        );
+    }
+
+    #[test]
+    fn properties() {
+        let d24s8 = FormatInfo::of(vk::Format::D24_UNORM_S8_UINT).unwrap();
+        assert!(d24s8.is_depth() && d24s8.is_stencil());
+        assert_eq!(d24s8.component_count, 2);
+        let bgra = FormatInfo::of(vk::Format::B8G8R8A8_SRGB).unwrap();
+        assert!(bgra.is_srgb() && !bgra.is_compressed());
+        assert_eq!(bgra.component_bits, [8; 4]);
+        assert!(FormatInfo::of(vk::Format::UNDEFINED).is_none());
+    }
+
+    #[test]
+    fn counterparts() {
+        assert_eq!(
+            srgb_counterpart(vk::Format::BC7_UNORM_BLOCK),
+            Some(vk::Format::BC7_SRGB_BLOCK)
+        );
+        assert_eq!(
+            unorm_counterpart(vk::Format::ASTC_5X4_SRGB_BLOCK),
+            Some(vk::Format::ASTC_5X4_UNORM_BLOCK)
+        );
+        assert_eq!(srgb_counterpart(vk::Format::R16_UNORM), None);
+    }
+}
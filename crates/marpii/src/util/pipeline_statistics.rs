@@ -0,0 +1,120 @@
+use ash::vk;
+
+///Results of a pipeline statistics query. Only the statistics that were queried are available.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PipelineStatistics {
+    ///Queried statistics, in the order Vulkan writes them, i.e. ascending by bit.
+    values: Vec<(vk::QueryPipelineStatisticFlags, u64)>,
+}
+
+impl PipelineStatistics {
+    ///Returns the number of values a query with `flags` writes.
+    pub fn value_count(flags: vk::QueryPipelineStatisticFlags) -> usize {
+        flags.as_raw().count_ones() as usize
+    }
+
+    ///Parses the `results` of a single query with `flags`. Vulkan writes one value per set bit, ordered from the lowest to the highest bit.
+    ///
+    /// Missing values (if `results` is too short) are not reported.
+    pub fn from_results(flags: vk::QueryPipelineStatisticFlags, results: &[u64]) -> Self {
+        let values = (0..u32::BITS)
+            .map(|bit| vk::QueryPipelineStatisticFlags::from_raw(1 << bit))
+            .filter(|statistic| flags.contains(*statistic))
+            .zip(results.iter().copied())
+            .collect();
+        PipelineStatistics { values }
+    }
+
+    ///Returns only the statistics that are part of `flags`.
+    pub fn restrict(&self, flags: vk::QueryPipelineStatisticFlags) -> Self {
+        PipelineStatistics {
+            values: self
+                .values
+                .iter()
+                .filter(|(statistic, _)| flags.contains(*statistic))
+                .copied()
+                .collect(),
+        }
+    }
+
+    ///Returns all statistics that are available.
+    pub fn flags(&self) -> vk::QueryPipelineStatisticFlags {
+        self.values.iter().fold(
+            vk::QueryPipelineStatisticFlags::empty(),
+            |flags, (statistic, _)| flags | *statistic,
+        )
+    }
+
+    ///Returns the value of a single `statistic`, if it was queried.
+    pub fn get(&self, statistic: vk::QueryPipelineStatisticFlags) -> Option<u64> {
+        self.values
+            .iter()
+            .find(|(flag, _)| *flag == statistic)
+            .map(|(_, value)| *value)
+    }
+
+    ///Iterates all queried statistics and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (vk::QueryPipelineStatisticFlags, u64)> + '_ {
+        self.values.iter().copied()
+    }
+
+    pub fn input_assembly_vertices(&self) -> Option<u64> {
+        self.get(vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES)
+    }
+
+    pub fn input_assembly_primitives(&self) -> Option<u64> {
+        self.get(vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES)
+    }
+
+    pub fn vertex_shader_invocations(&self) -> Option<u64> {
+        self.get(vk::QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS)
+    }
+
+    ///Number of primitives that were processed by the clipping stage.
+    pub fn clipping_invocations(&self) -> Option<u64> {
+        self.get(vk::QueryPipelineStatisticFlags::CLIPPING_INVOCATIONS)
+    }
+
+    ///Number of primitives that were output by the clipping stage.
+    pub fn clipping_primitives(&self) -> Option<u64> {
+        self.get(vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES)
+    }
+
+    pub fn fragment_shader_invocations(&self) -> Option<u64> {
+        self.get(vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS)
+    }
+
+    pub fn compute_shader_invocations(&self) -> Option<u64> {
+        self.get(vk::QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_in_bit_order() {
+        let flags = vk::QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS
+            | vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES
+            | vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES;
+        assert_eq!(PipelineStatistics::value_count(flags), 3);
+
+        let stats = PipelineStatistics::from_results(flags, &[10, 20, 30]);
+        assert_eq!(stats.input_assembly_primitives(), Some(10));
+        assert_eq!(stats.clipping_primitives(), Some(20));
+        assert_eq!(stats.compute_shader_invocations(), Some(30));
+        assert_eq!(stats.vertex_shader_invocations(), None);
+        assert_eq!(stats.flags(), flags);
+    }
+
+    #[test]
+    fn restrict() {
+        let flags = vk::QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS
+            | vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS;
+        let stats = PipelineStatistics::from_results(flags, &[3, 4])
+            .restrict(vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS);
+        assert_eq!(stats.vertex_shader_invocations(), None);
+        assert_eq!(stats.fragment_shader_invocations(), Some(4));
+    }
+}
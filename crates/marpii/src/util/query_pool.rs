@@ -29,6 +29,61 @@ impl QueryPool {
             size,
         })
     }
+
+    ///Creates a pool of [PIPELINE_STATISTICS](vk::QueryType::PIPELINE_STATISTICS) queries, that each count all `statistics`.
+    ///
+    /// Needs the `pipeline_statistics_query` device feature.
+    pub fn new_pipeline_statistics(
+        device: &Arc<Device>,
+        size: u32,
+        statistics: vk::QueryPipelineStatisticFlags,
+    ) -> Result<Self, vk::Result> {
+        let create_info = vk::QueryPoolCreateInfo::default()
+            .query_type(vk::QueryType::PIPELINE_STATISTICS)
+            .pipeline_statistics(statistics)
+            .query_count(size);
+        let pool = unsafe { device.inner.create_query_pool(&create_info, None)? };
+
+        Ok(QueryPool {
+            pool,
+            device: device.clone(),
+            size,
+        })
+    }
+
+    ///Number of queries that can be used.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    ///Begins the `query` on `command_buffer`. Use `flags` to request [precise](vk::QueryControlFlags::PRECISE) occlusion queries.
+    pub fn begin_query(
+        &self,
+        command_buffer: &vk::CommandBuffer,
+        query: u32,
+        flags: vk::QueryControlFlags,
+    ) {
+        debug_assert!(
+            query < self.size,
+            "Query {} exceeds pool size {}",
+            query,
+            self.size
+        );
+        unsafe {
+            self.device
+                .inner
+                .cmd_begin_query(*command_buffer, self.pool, query, flags);
+        }
+    }
+
+    ///Ends the `query` on `command_buffer`.
+    pub fn end_query(&self, command_buffer: &vk::CommandBuffer, query: u32) {
+        unsafe {
+            self.device
+                .inner
+                .cmd_end_query(*command_buffer, self.pool, query);
+        }
+    }
     ///Resets the timestamp pool of `self`.
     pub fn reset(&mut self, command_buffer: &vk::CommandBuffer) -> Result<(), vk::Result> {
         unsafe {
@@ -87,6 +142,44 @@ impl QueryPool {
         }
     }
 
+    ///Reads back the results of queries that each write `values_per_query` 64bit values, starting at `first_query`. `dst` must be a multiple
+    /// of `values_per_query` long, the query count is derived from its length.
+    ///
+    /// # Note
+    ///
+    /// This operation might block if your flags contain the `QUERY_RESULT_WAIT_BIT`
+    pub fn query_results_u64_strided(
+        &self,
+        first_query: u32,
+        values_per_query: usize,
+        dst: &mut [u64],
+        flags: vk::QueryResultFlags,
+    ) -> Result<(), vk::Result> {
+        if dst.is_empty() || values_per_query == 0 {
+            return Ok(());
+        }
+        assert!(
+            dst.len() % values_per_query == 0,
+            "dst must contain a multiple of values_per_query values"
+        );
+
+        let flags = flags | vk::QueryResultFlags::TYPE_64;
+        let stride = (values_per_query * std::mem::size_of::<u64>()) as vk::DeviceSize;
+        unsafe {
+            (self.device.inner.fp_v1_0().get_query_pool_results)(
+                self.device.inner.handle(),
+                self.pool,
+                first_query,
+                (dst.len() / values_per_query) as u32,
+                std::mem::size_of_val(dst),
+                dst.as_mut_ptr().cast(),
+                stride,
+                flags,
+            )
+            .result()
+        }
+    }
+
     pub fn query_results<T>(
         &self,
         dst: &mut [T],
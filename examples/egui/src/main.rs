@@ -1,7 +1,6 @@
 //! Example that shows how to use RMG and the EGUI standard task
 //! to render a simple user interface.
 
-use marpii::util::FormatProperties;
 use marpii_rmg::Rmg;
 use marpii_rmg_tasks::winit::event::WindowEvent;
 use marpii_rmg_tasks::{EGuiWinitIntegration, SwapchainPresent, egui};
@@ -36,12 +35,8 @@ impl winit::application::ApplicationHandler for App {
         let mut egui = EGuiWinitIntegration::new(&mut rmg, &event_loop).unwrap();
         let swapchain = SwapchainPresent::new(&mut rmg, surface).unwrap();
 
-        let swapchain_properties = FormatProperties::parse(swapchain.format());
-        if swapchain_properties.is_srgb {
-            egui.set_gamma(2.2);
-        } else {
-            egui.set_gamma(1.0);
-        }
+        egui.set_output_transfer(swapchain.transfer_function(), swapchain.format())
+            .unwrap();
 
         let name = "Teddy".to_string();
         let age = 10u32;
@@ -141,7 +141,8 @@ impl ForwardPass {
                 .unwrap(),
             )
             .with_name("ForwardPass")
-            .with_push_constant::<shared::ForwardPush>()
+            .try_with_push_constant::<shared::ForwardPush>()
+            .unwrap()
             .use_image(
                 color_image.clone(),
                 ImageUsage::ColorAttachment {
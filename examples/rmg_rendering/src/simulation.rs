@@ -35,7 +35,7 @@ impl Simulation {
         let pass = rmg
             .new_compute_pass(pipeline.clone())
             //Setup the push constant
-            .with_push_constant(|rmg| shared::SimPush {
+            .try_with_push_constant(|rmg| shared::SimPush {
                 sim_buffer: rmg.resource_handle(sim_buffer.clone()).unwrap(),
                 img_handle: rmg.resource_handle(feedback_image.clone()).unwrap(),
                 is_init: 0,
@@ -43,7 +43,7 @@ impl Simulation {
                 img_height: 64,
                 img_width: 64,
                 pad: [0; 2],
-            })
+            })?
             .use_buffer(sim_buffer.clone(), BufferUsage::ReadWrite)
             .use_image(feedback_image.clone(), ImageUsage::StorageWrite)
             .direct_dispatch_size([Self::dispatch_count(), 1, 1])